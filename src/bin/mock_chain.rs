@@ -0,0 +1,98 @@
+use alloy_primitives::Address;
+use clap::Parser;
+use snapchain::connectors::onchain_events::mock_chain::{MockChain, MockLog};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+/// Serves a scripted chain over JSON-RPC, usable as both the l1 and l2 rpc url of a testnet
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[arg(long, default_value = "0.0.0.0:8545")]
+    addr: SocketAddr,
+
+    #[arg(long, default_value = "10")]
+    chain_id: u64,
+
+    #[arg(long, default_value = "108864739")]
+    first_block_number: u64,
+
+    /// Delay between mined blocks (e.g. "2s")
+    #[arg(long, value_parser = parse_duration, default_value = "2s")]
+    block_time: Duration,
+
+    /// Register fids 1 through num_fids, with one unit of storage each, in the first mined block
+    #[arg(long, default_value = "0")]
+    num_fids: u64,
+
+    /// Custody address for the registered fids
+    #[arg(long, default_value = "0x0000000000000000000000000000000000000001")]
+    custody_address: String,
+
+    /// Ens names to resolve, as name=address
+    #[arg(long)]
+    ens_name: Vec<String>,
+
+    /// Addresses that accept any EIP-1271 signature
+    #[arg(long)]
+    contract_signer: Vec<String>,
+
+    /// Reorg the chain every this many blocks. 0 disables reorgs
+    #[arg(long, default_value = "0")]
+    reorg_interval: u64,
+
+    #[arg(long, default_value = "1")]
+    reorg_depth: u64,
+}
+
+fn parse_duration(arg: &str) -> Result<Duration, String> {
+    humantime::parse_duration(arg).map_err(|e| e.to_string())
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+    let args = Args::parse();
+
+    let chain = MockChain::new(args.chain_id, args.first_block_number, 1_700_000_000);
+    for ens_name in &args.ens_name {
+        let (name, address) = ens_name
+            .split_once('=')
+            .expect("ens names must be formatted as name=address");
+        chain.register_ens_name(name, Address::from_str(address).unwrap());
+    }
+    for signer in &args.contract_signer {
+        chain.add_contract_signer(Address::from_str(signer).unwrap());
+    }
+    let custody_address = Address::from_str(&args.custody_address).unwrap();
+    chain.mine_block(
+        (1..=args.num_fids)
+            .flat_map(|fid| {
+                vec![
+                    MockLog::id_register(fid, custody_address, Address::ZERO),
+                    MockLog::storage_rent(fid, custody_address, 1),
+                ]
+            })
+            .collect(),
+    );
+
+    let listener = TcpListener::bind(args.addr).await.unwrap();
+    println!("Mock chain listening on {}", args.addr);
+    tokio::spawn(chain.clone().serve(listener));
+
+    let mut interval = tokio::time::interval(args.block_time);
+    loop {
+        interval.tick().await;
+        let block_number = chain.mine_empty_blocks(1);
+        if args.reorg_interval > 0 && block_number % args.reorg_interval == 0 {
+            chain.reorg(args.reorg_depth);
+            let block_number = chain.mine_empty_blocks(args.reorg_depth);
+            println!(
+                "Reorged {} blocks, head is {}",
+                args.reorg_depth, block_number
+            );
+        }
+    }
+}
@@ -1,9 +1,9 @@
 use crate::{
     connectors::{self},
     consensus, mempool,
-    network::{self, http_server},
+    network::{self, api_limits, http_server, tls},
     proto::FarcasterNetwork,
-    storage,
+    storage::{self, store::node_local_state::Chain},
 };
 use clap::Parser;
 use figment::{
@@ -59,6 +59,10 @@ pub struct Config {
     pub fnames: connectors::fname::Config,
     pub onchain_events: connectors::onchain_events::Config,
     pub base_onchain_events: connectors::onchain_events::Config,
+    /** Further chains to index onchain events from, each keyed by its chain id */
+    pub chains: Vec<connectors::onchain_events::Config>,
+    pub ens_cache: connectors::onchain_events::ens_cache::Config,
+    pub solana: connectors::solana::Config,
     pub consensus: consensus::consensus::Config,
     pub gossip: network::gossip::Config,
     pub mempool: mempool::mempool::Config,
@@ -66,16 +70,26 @@ pub struct Config {
     pub rpc_auth: String,
     pub admin_rpc_auth: String,
     pub rpc_address: String,
+    /** Serve the admin service on its own address instead of alongside the public RPC */
+    pub admin_rpc_address: String,
     pub http_address: String,
     pub rocksdb_dir: String,
+    pub rocksdb: storage::db::Config,
+    pub indexes: storage::store::stores::IndexConfig,
     pub clear_db: bool,
     pub statsd: StatsdConfig,
     pub trie_branching_factor: u32,
     pub l1_rpc_url: String,
+    /** Defaults to the ENS registry on Ethereum mainnet */
+    pub l1_ens_registry_address: Option<String>,
     pub fc_network: FarcasterNetwork,
     pub read_node: bool,
+    /** Never prune blocks, shard chunks or events. Read nodes also backfill missing blocks and shard chunk headers from peers, transactions and events are not backfilled */
+    pub archive_mode: bool,
     pub pruning: PruningConfig,
     pub http_server: http_server::Config,
+    pub api_limits: api_limits::Config,
+    pub tls: tls::Config,
 }
 
 impl Default for Config {
@@ -83,25 +97,41 @@ impl Default for Config {
         Config {
             log_format: "text".to_string(),
             fnames: connectors::fname::Config::default(),
-            onchain_events: connectors::onchain_events::Config::default(),
-            base_onchain_events: connectors::onchain_events::Config::default(),
+            onchain_events: connectors::onchain_events::Config {
+                chain_id: Some(Chain::OPTIMISM.0),
+                ..Default::default()
+            },
+            base_onchain_events: connectors::onchain_events::Config {
+                chain_id: Some(Chain::BASE.0),
+                ..Default::default()
+            },
+            chains: vec![],
+            ens_cache: connectors::onchain_events::ens_cache::Config::default(),
+            solana: connectors::solana::Config::default(),
             consensus: consensus::consensus::Config::default(),
             gossip: network::gossip::Config::default(),
             mempool: mempool::mempool::Config::default(),
             rpc_auth: "".to_string(),
             admin_rpc_auth: "".to_string(),
             rpc_address: "0.0.0.0:3383".to_string(),
+            admin_rpc_address: "".to_string(),
             http_address: "0.0.0.0:3381".to_string(),
             rocksdb_dir: ".rocks".to_string(),
+            rocksdb: storage::db::Config::default(),
+            indexes: storage::store::stores::IndexConfig::default(),
             clear_db: false,
             statsd: StatsdConfig::default(),
             trie_branching_factor: 16,
             l1_rpc_url: "".to_string(),
+            l1_ens_registry_address: None,
             fc_network: FarcasterNetwork::Devnet,
             snapshot: storage::db::snapshot::Config::default(),
             read_node: false,
+            archive_mode: false,
             pruning: PruningConfig::default(),
             http_server: http_server::Config::default(),
+            api_limits: api_limits::Config::default(),
+            tls: tls::Config::default(),
         }
     }
 }
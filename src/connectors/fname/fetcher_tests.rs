@@ -0,0 +1,357 @@
+#[cfg(test)]
+mod tests {
+    use crate::connectors::fname::{Config, Fetcher, FnameRequest};
+    use crate::mempool::mempool::MempoolRequest;
+    use crate::proto::{FarcasterNetwork, FnameTransfer};
+    use crate::storage::db;
+    use crate::storage::store::engine::MempoolMessage;
+    use crate::storage::store::node_local_state::LocalStateStore;
+    use crate::storage::store::test_helper;
+    use crate::utils::factory::username_factory;
+    use alloy_signer_local::PrivateKeySigner;
+    use axum::extract::{Query, State};
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio::sync::{broadcast, mpsc};
+
+    struct TestTransfer {
+        id: u64,
+        to: u64,
+        server_signer: PrivateKeySigner,
+        user_signer: Option<PrivateKeySigner>,
+    }
+
+    fn signature(transfer: &TestTransfer, signer: &PrivateKeySigner, owner: &[u8]) -> String {
+        let signed = username_factory::create_transfer(
+            transfer.to,
+            &format!("name{}", transfer.id),
+            Some(1_700_000_000),
+            None,
+            Some(owner.to_vec()),
+            signer.clone(),
+        );
+        format!("0x{}", hex::encode(signed.proof.unwrap().signature))
+    }
+
+    fn to_json(transfer: &TestTransfer, owner: &PrivateKeySigner) -> Value {
+        let owner = owner.address().to_vec();
+        let user_signature = match &transfer.user_signer {
+            Some(user_signer) => signature(transfer, user_signer, &owner),
+            None => "0x".to_string(),
+        };
+        json!({
+            "id": transfer.id,
+            "timestamp": 1_700_000_000,
+            "username": format!("name{}", transfer.id),
+            "owner": format!("0x{}", hex::encode(&owner)),
+            "from": 0,
+            "to": transfer.to,
+            "user_signature": user_signature,
+            "server_signature": signature(transfer, &transfer.server_signer, &owner),
+        })
+    }
+
+    async fn serve_transfers(transfers: Vec<Value>) -> String {
+        let router = Router::new()
+            .route(
+                "/transfers",
+                get(
+                    |State(transfers): State<Arc<Vec<Value>>>,
+                     Query(params): Query<HashMap<String, u64>>| async move {
+                        let from_id = params.get("from_id").copied().unwrap_or(0);
+                        let transfers: Vec<Value> = transfers
+                            .iter()
+                            .filter(|transfer| transfer["id"].as_u64().unwrap() > from_id)
+                            .cloned()
+                            .collect();
+                        Json(json!({ "transfers": transfers }))
+                    },
+                ),
+            )
+            .with_state(Arc::new(transfers));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/transfers", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        url
+    }
+
+    fn fetcher(
+        cfg: Config,
+        signer: &PrivateKeySigner,
+    ) -> (Fetcher, mpsc::Receiver<MempoolRequest>, LocalStateStore) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = db::RocksDB::new(dir.path().join("a.db").to_str().unwrap());
+        db.open().unwrap();
+        let local_state_store = LocalStateStore::new(Arc::new(db));
+        let (mempool_tx, mempool_rx) = mpsc::channel(100);
+        let (_, fname_request_rx) = broadcast::channel::<FnameRequest>(10);
+        let mut fetcher = Fetcher::new(
+            cfg,
+            FarcasterNetwork::Mainnet,
+            mempool_tx,
+            test_helper::statsd_client(),
+            local_state_store.clone(),
+            fname_request_rx,
+        );
+        fetcher.signer_address = Some(signer.address());
+        (fetcher, mempool_rx, local_state_store)
+    }
+
+    fn valid_transfers(ids: &[u64], signer: &PrivateKeySigner) -> Vec<Value> {
+        ids.iter()
+            .map(|id| {
+                let transfer = TestTransfer {
+                    id: *id,
+                    to: 10 + id,
+                    server_signer: signer.clone(),
+                    user_signer: None,
+                };
+                to_json(&transfer, signer)
+            })
+            .collect()
+    }
+
+    fn submitted_ids(mempool_rx: &mut mpsc::Receiver<MempoolRequest>) -> Vec<u64> {
+        submitted_transfers(mempool_rx)
+            .iter()
+            .map(|transfer| transfer.id)
+            .collect()
+    }
+
+    fn submitted_transfers(mempool_rx: &mut mpsc::Receiver<MempoolRequest>) -> Vec<FnameTransfer> {
+        let mut transfers = vec![];
+        while let Ok(request) = mempool_rx.try_recv() {
+            if let MempoolRequest::AddMessage(MempoolMessage::ValidatorMessage(message), _, _) =
+                request
+            {
+                transfers.extend(message.fname_transfer);
+            }
+        }
+        transfers
+    }
+
+    #[tokio::test]
+    async fn test_transfers_with_bad_server_signatures_are_quarantined() {
+        let server_signer = PrivateKeySigner::random();
+        let owner = PrivateKeySigner::random();
+        let impostor = PrivateKeySigner::random();
+
+        let transfers = vec![
+            // Valid server and user signatures
+            TestTransfer {
+                id: 1,
+                to: 10,
+                server_signer: server_signer.clone(),
+                user_signer: Some(owner.clone()),
+            },
+            // Not signed by the fname server
+            TestTransfer {
+                id: 2,
+                to: 11,
+                server_signer: impostor.clone(),
+                user_signer: Some(owner.clone()),
+            },
+            // Not signed by the owner, which may be a contract wallet, so it's still submitted
+            TestTransfer {
+                id: 3,
+                to: 12,
+                server_signer: server_signer.clone(),
+                user_signer: Some(impostor.clone()),
+            },
+            // No user signature to check
+            TestTransfer {
+                id: 4,
+                to: 13,
+                server_signer: server_signer.clone(),
+                user_signer: None,
+            },
+        ];
+        let url = serve_transfers(
+            transfers
+                .iter()
+                .map(|transfer| to_json(transfer, &owner))
+                .collect(),
+        )
+        .await;
+        let (mut fetcher, mut mempool_rx, local_state_store) = fetcher(
+            Config {
+                url,
+                ..Default::default()
+            },
+            &server_signer,
+        );
+
+        fetcher.fetch().await.unwrap();
+
+        assert_eq!(
+            submitted_transfers(&mut mempool_rx)
+                .iter()
+                .map(|transfer| transfer.id)
+                .collect::<Vec<_>>(),
+            vec![1, 3, 4]
+        );
+        let quarantined = local_state_store.get_quarantined_fname_transfers().unwrap();
+        assert_eq!(
+            quarantined
+                .iter()
+                .map(|quarantined| (
+                    quarantined.transfer.as_ref().unwrap().id,
+                    quarantined.reason.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![(2, "invalid server signature: invalid signature")]
+        );
+        assert_eq!(
+            local_state_store.get_latest_fname_transfer_id().unwrap(),
+            Some(4)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gaps_are_filled_from_mirrors() {
+        let signer = PrivateKeySigner::random();
+        let url = serve_transfers(valid_transfers(&[1, 2, 4, 5], &signer)).await;
+        let mirror_url = serve_transfers(valid_transfers(&[1, 2, 3, 4, 5], &signer)).await;
+        let (mut fetcher, mut mempool_rx, local_state_store) = fetcher(
+            Config {
+                url,
+                mirror_urls: vec![mirror_url],
+                ..Default::default()
+            },
+            &signer,
+        );
+
+        fetcher.fetch().await.unwrap();
+
+        assert_eq!(submitted_ids(&mut mempool_rx), vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            local_state_store.get_latest_fname_transfer_id().unwrap(),
+            Some(5)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unfillable_gaps_are_skipped() {
+        let signer = PrivateKeySigner::random();
+        let url = serve_transfers(valid_transfers(&[1, 3], &signer)).await;
+        let (mut fetcher, mut mempool_rx, _) = fetcher(
+            Config {
+                url,
+                ..Default::default()
+            },
+            &signer,
+        );
+
+        fetcher.fetch().await.unwrap();
+
+        assert_eq!(submitted_ids(&mut mempool_rx), vec![1, 3]);
+        assert_eq!(fetcher.position, 3);
+        assert_eq!(
+            fetcher
+                .local_state_store
+                .get_missing_fname_transfers()
+                .unwrap(),
+            vec![2]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_transfers_are_recovered_later() {
+        let signer = PrivateKeySigner::random();
+        let url = serve_transfers(valid_transfers(&[1, 4, 5], &signer)).await;
+        let (mut fetcher, mut mempool_rx, local_state_store) = fetcher(
+            Config {
+                url,
+                ..Default::default()
+            },
+            &signer,
+        );
+        fetcher.fetch().await.unwrap();
+        assert_eq!(submitted_ids(&mut mempool_rx), vec![1, 4, 5]);
+        assert_eq!(
+            local_state_store.get_missing_fname_transfers().unwrap(),
+            vec![2, 3]
+        );
+
+        // A mirror that has since caught up returns the missing ids
+        fetcher.cfg.mirror_urls =
+            vec![serve_transfers(valid_transfers(&[1, 2, 3, 4, 5], &signer)).await];
+        fetcher.recover_missing_transfers().await.unwrap();
+
+        assert_eq!(submitted_ids(&mut mempool_rx), vec![2, 3]);
+        assert!(local_state_store
+            .get_missing_fname_transfers()
+            .unwrap()
+            .is_empty());
+        assert_eq!(fetcher.position, 5);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_mirror_when_primary_is_down() {
+        let signer = PrivateKeySigner::random();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/transfers", listener.local_addr().unwrap());
+        drop(listener);
+        let mirror_url = serve_transfers(valid_transfers(&[1, 2], &signer)).await;
+        let (mut fetcher, mut mempool_rx, _) = fetcher(
+            Config {
+                url: url.clone(),
+                mirror_urls: vec![mirror_url],
+                ..Default::default()
+            },
+            &signer,
+        );
+
+        fetcher.fetch().await.unwrap();
+        assert_eq!(submitted_ids(&mut mempool_rx), vec![1, 2]);
+
+        fetcher.cfg.mirror_urls = vec![];
+        assert!(fetcher.fetch().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_transfers_resubmits_range() {
+        let signer = PrivateKeySigner::random();
+        let url = serve_transfers(valid_transfers(&[1, 2, 3, 4], &signer)).await;
+        let (mut fetcher, mut mempool_rx, _) = fetcher(
+            Config {
+                url,
+                ..Default::default()
+            },
+            &signer,
+        );
+        fetcher.fetch().await.unwrap();
+        submitted_ids(&mut mempool_rx);
+
+        fetcher.retry_transfers(2, 3).await.unwrap();
+
+        assert_eq!(submitted_ids(&mut mempool_rx), vec![2, 3]);
+        assert_eq!(fetcher.position, 4);
+    }
+
+    #[tokio::test]
+    async fn test_backoff_grows_with_failures() {
+        let (mut fetcher, _, _) = fetcher(
+            Config {
+                poll_interval: Duration::from_secs(5),
+                max_backoff: Duration::from_secs(60),
+                ..Default::default()
+            },
+            &PrivateKeySigner::random(),
+        );
+        assert_eq!(fetcher.next_delay(), Duration::from_secs(5));
+
+        fetcher.consecutive_failures = 2;
+        let delay = fetcher.next_delay();
+        assert!(delay >= Duration::from_secs(10) && delay <= Duration::from_secs(20));
+
+        fetcher.consecutive_failures = 100;
+        let delay = fetcher.next_delay();
+        assert!(delay >= Duration::from_secs(30) && delay <= Duration::from_secs(60));
+    }
+}
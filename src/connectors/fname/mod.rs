@@ -1,23 +1,46 @@
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Instant;
 use thiserror::Error;
 use tokio::{
-    sync::mpsc,
+    select,
+    sync::{broadcast, mpsc},
     time::{sleep, Duration},
 };
 use tracing::{debug, error, info, warn};
 
+use crate::core::validations::verification::{
+    validate_fname_transfer, validate_fname_user_signature,
+};
 use crate::mempool::mempool::{MempoolRequest, MempoolSource};
 use crate::{
-    proto::{FnameTransfer, UserNameProof, UserNameType, ValidatorMessage},
+    proto::{
+        FarcasterNetwork, FnameTransfer, QuarantinedFnameTransfer, UserNameProof, UserNameType,
+        ValidatorMessage,
+    },
     storage::store::{engine::MempoolMessage, node_local_state::LocalStateStore},
     utils::statsd_wrapper::StatsdClientWrapper,
 };
 
+#[cfg(test)]
+mod fetcher_tests;
+
+// How often transfer ids that were missing from a gap are asked for again
+const MISSING_TRANSFERS_RETRY_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Config {
     pub start_from: Option<u64>,
     pub stop_at: Option<u64>,
     pub url: String,
+    // Queried in order when the primary url fails, and to fill gaps in its transfer ids
+    pub mirror_urls: Vec<String>,
+    #[serde(with = "humantime_serde")]
+    pub poll_interval: Duration,
+    // Upper bound for the delay between polls while every source is failing
+    #[serde(with = "humantime_serde")]
+    pub max_backoff: Duration,
     pub disable: bool,
 }
 
@@ -27,6 +50,9 @@ impl Default for Config {
             start_from: None,
             stop_at: None,
             url: "https://fnames.farcaster.xyz/transfers".to_string(),
+            mirror_urls: vec![],
+            poll_interval: Duration::from_secs(5),
+            max_backoff: Duration::from_secs(300),
             disable: false,
         }
     }
@@ -45,34 +71,22 @@ struct CurrentTransfer {
 #[derive(Deserialize, Debug, Clone)]
 struct Transfer {
     id: u64,
-
-    #[allow(dead_code)] // TODO
     timestamp: u64,
-
-    #[allow(dead_code)] // TODO
     username: String,
-
-    #[allow(dead_code)] // TODO
     owner: String,
-
-    #[allow(dead_code)] // TODO
     from: u64,
-
-    #[allow(dead_code)] // TODO
     to: u64,
-
-    #[allow(dead_code)] // TODO
     user_signature: String,
-
-    #[allow(dead_code)] // TODO
     server_signature: String,
 }
 
+#[derive(Clone, Debug)]
+pub enum FnameRequest {
+    RetryTransferIdRange { start_id: u64, stop_id: u64 },
+}
+
 #[derive(Error, Debug)]
 enum FetchError {
-    #[error("non-sequential IDs found")]
-    NonSequentialIds { position: u64, id: u64 },
-
     #[error("stop fetching")]
     Stop,
 
@@ -86,24 +100,37 @@ enum FetchError {
 pub struct Fetcher {
     position: u64,
     cfg: Config,
+    network: FarcasterNetwork,
+    // Overrides the fname server's signer, same as the engine's
+    signer_address: Option<alloy_primitives::Address>,
     mempool_tx: mpsc::Sender<MempoolRequest>,
     statsd_client: StatsdClientWrapper,
     local_state_store: LocalStateStore,
+    fname_request_rx: broadcast::Receiver<FnameRequest>,
+    consecutive_failures: u32,
+    last_missing_transfers_retry: Option<Instant>,
 }
 
 impl Fetcher {
     pub fn new(
         cfg: Config,
+        network: FarcasterNetwork,
         mempool_tx: mpsc::Sender<MempoolRequest>,
         statsd_client: StatsdClientWrapper,
         local_state_store: LocalStateStore,
+        fname_request_rx: broadcast::Receiver<FnameRequest>,
     ) -> Self {
         Fetcher {
             position: 0,
             cfg,
+            network,
+            signer_address: None,
             mempool_tx,
             statsd_client,
             local_state_store,
+            fname_request_rx,
+            consecutive_failures: 0,
+            last_missing_transfers_retry: None,
         }
     }
 
@@ -146,14 +173,286 @@ impl Fetcher {
             .gauge(format!("fnames.{}", key).as_str(), value);
     }
 
+    /**
+     * Checks the server signature, and the owner's signature when the server returned one. Errors
+     * with the reason the transfer is rejected. Owner signatures are only recovered for EOAs, so a
+     * bad one is counted and logged, but doesn't reject a transfer the server signed.
+     */
+    fn verify_transfer(
+        &self,
+        transfer: &Transfer,
+        fname_transfer: &FnameTransfer,
+    ) -> Result<(), String> {
+        if let Err(err) = validate_fname_transfer(fname_transfer, self.network, self.signer_address)
+        {
+            self.count("num_invalid_server_signatures", 1);
+            return Err(format!("invalid server signature: {}", err));
+        }
+
+        let user_signature = transfer.user_signature.trim_start_matches("0x");
+        // Deletions are signed by the previous owner rather than the owner in the proof
+        if user_signature.is_empty() || transfer.to == 0 {
+            return Ok(());
+        }
+        let result = match (hex::decode(user_signature), &fname_transfer.proof) {
+            (Ok(user_signature), Some(proof)) => {
+                validate_fname_user_signature(proof, &user_signature, self.network)
+                    .map_err(|err| format!("invalid user signature: {}", err))
+            }
+            _ => Err("malformed user signature".to_string()),
+        };
+        if let Err(reason) = result {
+            self.count("num_invalid_user_signatures", 1);
+            warn!(
+                id = fname_transfer.id,
+                to = transfer.to,
+                reason,
+                "Fname transfer has an unverified user signature"
+            );
+        }
+        Ok(())
+    }
+
+    fn quarantine_transfer(&self, fname_transfer: FnameTransfer, reason: String) {
+        warn!(
+            id = fname_transfer.id,
+            from = fname_transfer.from_fid,
+            reason,
+            "Quarantined fname transfer"
+        );
+        self.count("num_quarantined_transfers", 1);
+        if let Err(err) =
+            self.local_state_store
+                .put_quarantined_fname_transfer(&QuarantinedFnameTransfer {
+                    transfer: Some(fname_transfer),
+                    reason,
+                })
+        {
+            error!(
+                err = err.to_string(),
+                "Unable to store quarantined fname transfer"
+            );
+        }
+    }
+
+    /** The primary url followed by the mirrors, in the order they're tried */
+    fn sources(&self) -> Vec<String> {
+        std::iter::once(self.cfg.url.clone())
+            .chain(self.cfg.mirror_urls.iter().cloned())
+            .collect()
+    }
+
+    async fn get_transfers(&self, url: &str, from_id: u64) -> Result<Vec<Transfer>, FetchError> {
+        let url = format!("{}?from_id={}", url, from_id);
+        debug!(%url, "fetching transfers");
+
+        let response = reqwest::get(&url).await?.json::<TransfersData>().await?;
+        Ok(response.transfers)
+    }
+
+    /** Fetches the transfers after from_id from the first source that responds */
+    async fn fetch_page(&self, from_id: u64) -> Result<Vec<Transfer>, FetchError> {
+        let mut last_err = None;
+        for url in self.sources() {
+            match self.get_transfers(&url, from_id).await {
+                Ok(transfers) => return Ok(transfers),
+                Err(err) => {
+                    warn!(url, err = err.to_string(), "Unable to fetch transfers");
+                    self.count("num_source_errors", 1);
+                    last_err = Some(err);
+                }
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    /** Pages through a source's transfers strictly between from_id and to_id */
+    async fn get_transfer_range(
+        &self,
+        url: &str,
+        from_id: u64,
+        to_id: u64,
+    ) -> Result<Vec<Transfer>, FetchError> {
+        let mut transfers = vec![];
+        let mut cursor = from_id;
+        while cursor + 1 < to_id {
+            let page = self.get_transfers(url, cursor).await?;
+            let last_id = page.iter().map(|t| t.id).max().unwrap_or(cursor);
+            transfers.extend(page.into_iter().filter(|t| t.id > from_id && t.id < to_id));
+            if last_id <= cursor {
+                break;
+            }
+            cursor = last_id;
+        }
+        Ok(transfers)
+    }
+
+    /** Asks the sources in turn for the transfers strictly between from_id and to_id, until every id in the range is found */
+    async fn fill_gap(&self, from_id: u64, to_id: u64) -> Vec<Transfer> {
+        let mut found: BTreeMap<u64, Transfer> = BTreeMap::new();
+        for url in self.sources() {
+            match self.get_transfer_range(&url, from_id, to_id).await {
+                Ok(transfers) => {
+                    for t in transfers {
+                        found.entry(t.id).or_insert(t);
+                    }
+                    if found.len() as u64 >= to_id - from_id - 1 {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    warn!(
+                        url,
+                        err = err.to_string(),
+                        "Unable to fetch missing transfers"
+                    );
+                }
+            }
+        }
+        found.into_values().collect()
+    }
+
+    /** Persists ids in a gap that no source returned, see [Fetcher::recover_missing_transfers] */
+    fn record_missing_transfers(&self, from_id: u64, to_id: u64, found: &[Transfer]) {
+        for transfer_id in (from_id + 1)..to_id {
+            if found.iter().any(|t| t.id == transfer_id) {
+                continue;
+            }
+            self.count("num_missing_transfers", 1);
+            if let Err(err) = self
+                .local_state_store
+                .put_missing_fname_transfer(transfer_id)
+            {
+                error!(
+                    transfer_id,
+                    err = err.to_string(),
+                    "Unable to store missing fname transfer id"
+                );
+            }
+        }
+    }
+
+    /** Submits a transfer fetched after its id was recorded as missing */
+    async fn submit_missing_transfer(&self, t: &Transfer) -> Result<(), FetchError> {
+        self.submit_transfer(t).await?;
+        if let Err(err) = self.local_state_store.delete_missing_fname_transfer(t.id) {
+            error!(
+                transfer_id = t.id,
+                err = err.to_string(),
+                "Unable to clear missing fname transfer id"
+            );
+        }
+        Ok(())
+    }
+
+    /** Asks the sources again for transfer ids that were missing from earlier gaps */
+    async fn recover_missing_transfers(&mut self) -> Result<(), FetchError> {
+        let missing_ids = match self.local_state_store.get_missing_fname_transfers() {
+            Ok(missing_ids) => missing_ids,
+            Err(err) => {
+                error!(
+                    err = err.to_string(),
+                    "Unable to retrieve missing fname transfer ids"
+                );
+                return Ok(());
+            }
+        };
+        self.gauge("missing_transfers", missing_ids.len() as u64);
+
+        // Consecutive ids are asked for as one range
+        let mut ranges: Vec<(u64, u64)> = vec![];
+        for transfer_id in missing_ids {
+            match ranges.last_mut() {
+                Some((_, last_id)) if *last_id + 1 == transfer_id => *last_id = transfer_id,
+                _ => ranges.push((transfer_id, transfer_id)),
+            }
+        }
+        for (first_id, last_id) in ranges {
+            for t in self.fill_gap(first_id - 1, last_id + 1).await {
+                info!(id = t.id, "Recovered missing fname transfer");
+                self.submit_missing_transfer(&t).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /** Verifies a transfer and sends it to the mempool, or quarantines it */
+    async fn submit_transfer(&self, t: &Transfer) -> Result<(), FetchError> {
+        let owner = hex::decode(t.owner.trim_start_matches("0x"));
+        let signature = hex::decode(t.server_signature.trim_start_matches("0x"));
+
+        if owner.is_err() || signature.is_err() {
+            return Err(FetchError::InvalidFormat);
+        }
+
+        let username_proof = UserNameProof {
+            timestamp: t.timestamp,
+            name: t.username.clone().into_bytes(),
+            owner: owner.unwrap(),
+            signature: signature.unwrap(),
+            fid: t.to,
+            r#type: UserNameType::UsernameTypeFname as i32,
+        };
+        let fname_transfer = FnameTransfer {
+            id: t.id,
+            from_fid: t.from,
+            proof: Some(username_proof),
+        };
+        self.count("num_transfers", 1);
+        if let Err(reason) = self.verify_transfer(t, &fname_transfer) {
+            self.quarantine_transfer(fname_transfer, reason);
+            return Ok(());
+        }
+        if let Err(err) = self
+            .mempool_tx
+            .send(MempoolRequest::AddMessage(
+                MempoolMessage::ValidatorMessage(ValidatorMessage {
+                    on_chain_event: None,
+                    fname_transfer: Some(fname_transfer),
+                    storage_prune: None,
+                }),
+                MempoolSource::Local,
+                None,
+            ))
+            .await
+        {
+            error!(
+                from = t.from,
+                to = t.to,
+                err = err.to_string(),
+                "Unable to send fname transfer to mempool"
+            )
+        }
+        info!(
+            from = t.from,
+            fid = t.to,
+            name = t.username.clone(),
+            "Processed fname transfer"
+        );
+        Ok(())
+    }
+
+    async fn process_transfer(&mut self, t: &Transfer) -> Result<(), FetchError> {
+        if self.cfg.stop_at.is_some() && t.id >= self.cfg.stop_at.unwrap() {
+            return Err(FetchError::Stop);
+        }
+        self.position = t.id;
+        self.gauge("latest_transfer_id", t.id);
+        self.submit_transfer(t).await
+    }
+
     async fn fetch(&mut self) -> Result<(), FetchError> {
+        if self
+            .last_missing_transfers_retry
+            .is_none_or(|last_retry| last_retry.elapsed() >= MISSING_TRANSFERS_RETRY_INTERVAL)
+        {
+            self.last_missing_transfers_retry = Some(Instant::now());
+            self.recover_missing_transfers().await?;
+        }
         loop {
-            let url = format!("{}?from_id={}", self.cfg.url, self.position);
-            debug!(%url, "fetching transfers");
-
-            let response = reqwest::get(&url).await?.json::<TransfersData>().await?;
+            let transfers = self.fetch_page(self.position).await?;
 
-            let count = response.transfers.len();
+            let count = transfers.len();
 
             if count == 0 {
                 return Ok(());
@@ -162,72 +461,73 @@ impl Fetcher {
             info!(count, position = self.position, "found new transfers");
 
             let mut last_transfer_id = 0;
-            for t in response.transfers {
+            for t in transfers {
                 if t.id <= self.position {
-                    return Err(FetchError::NonSequentialIds {
-                        id: t.id,
-                        position: self.position,
-                    });
-                }
-                if self.cfg.stop_at.is_some() && t.id >= self.cfg.stop_at.unwrap() {
-                    return Err(FetchError::Stop);
-                }
-                self.position = t.id;
-
-                let owner = hex::decode(t.owner[2..].to_string());
-                let signature = hex::decode(t.server_signature[2..].to_string());
-
-                if owner.is_err() || signature.is_err() {
-                    return Err(FetchError::InvalidFormat);
+                    self.count("num_duplicate_transfers", 1);
+                    continue;
                 }
-
-                let username_proof = UserNameProof {
-                    timestamp: t.timestamp,
-                    name: t.username.clone().into_bytes(),
-                    owner: owner.unwrap(),
-                    signature: signature.unwrap(),
-                    fid: t.to,
-                    r#type: UserNameType::UsernameTypeFname as i32,
-                };
-                self.count("num_transfers", 1);
-                self.gauge("latest_transfer_id", t.id);
-                if let Err(err) = self
-                    .mempool_tx
-                    .send(MempoolRequest::AddMessage(
-                        MempoolMessage::ValidatorMessage(ValidatorMessage {
-                            on_chain_event: None,
-                            fname_transfer: Some(FnameTransfer {
-                                id: t.id,
-                                from_fid: t.from,
-                                proof: Some(username_proof),
-                            }),
-                        }),
-                        MempoolSource::Local,
-                        None,
-                    ))
-                    .await
-                {
-                    error!(
-                        from = t.from,
-                        to = t.to,
-                        err = err.to_string(),
-                        "Unable to send fname transfer to mempool"
-                    )
+                if t.id > self.position + 1 {
+                    let missing = self.fill_gap(self.position, t.id).await;
+                    warn!(
+                        position = self.position,
+                        id = t.id,
+                        found = missing.len(),
+                        "Found gap in fname transfer ids"
+                    );
+                    self.count("num_transfer_id_gaps", 1);
+                    self.record_missing_transfers(self.position, t.id, &missing);
+                    for missing in missing {
+                        self.process_transfer(&missing).await?;
+                        last_transfer_id = missing.id;
+                    }
                 }
-                info!(
-                    from = t.from,
-                    fid = t.to,
-                    name = t.username.clone(),
-                    "Processed fname transfer"
-                );
+                self.process_transfer(&t).await?;
                 last_transfer_id = t.id;
             }
             if last_transfer_id > 0 {
                 self.record_username_proof(last_transfer_id);
+            } else {
+                // Only ids we've already seen, the source has nothing new
+                return Ok(());
+            }
+        }
+    }
+
+    /** Re-fetches and resubmits the transfers with ids in [start_id, stop_id], without moving the fetcher's position */
+    pub async fn retry_transfers(&self, start_id: u64, stop_id: u64) -> Result<(), FetchError> {
+        info!(start_id, stop_id, "Retrying fname transfers");
+        let mut from_id = start_id.saturating_sub(1);
+        loop {
+            let previous_id = from_id;
+            for t in self.fetch_page(from_id).await? {
+                if t.id > stop_id {
+                    return Ok(());
+                }
+                if t.id <= from_id {
+                    continue;
+                }
+                from_id = t.id;
+                self.submit_missing_transfer(&t).await?;
+            }
+            if from_id == previous_id {
+                return Ok(());
             }
         }
     }
 
+    /** The poll interval while sources are healthy. After failures it doubles per failure up to max_backoff, with jitter so nodes don't retry in lockstep. */
+    fn next_delay(&self) -> Duration {
+        if self.consecutive_failures == 0 {
+            return self.cfg.poll_interval;
+        }
+        let backoff = self
+            .cfg
+            .poll_interval
+            .saturating_mul(2u32.saturating_pow(self.consecutive_failures.min(16)))
+            .min(self.cfg.max_backoff);
+        backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+
     async fn set_initial_position(&mut self) -> Result<(), FetchError> {
         match self.cfg.start_from {
             None => {
@@ -258,28 +558,43 @@ impl Fetcher {
         }
         info!(start_id = self.position, "Starting fname ingest");
 
+        let mut accepting_requests = true;
         loop {
-            let result = self.fetch().await;
-
-            if let Err(e) = result {
-                match e {
-                    FetchError::NonSequentialIds { id, position } => {
-                        error!(id, position, %e);
-                    }
-                    FetchError::Reqwest(request_error) => {
-                        warn!(error = %request_error, "reqwest error fetching transfers");
+            select! {
+                request = self.fname_request_rx.recv(), if accepting_requests => {
+                    match request {
+                        Ok(FnameRequest::RetryTransferIdRange { start_id, stop_id }) => {
+                            if let Err(err) = self.retry_transfers(start_id, stop_id).await {
+                                error!(start_id, stop_id, err = err.to_string(), "Unable to retry fname transfers");
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => {
+                            // Nobody can send requests anymore, keep polling without them
+                            accepting_requests = false;
+                        }
                     }
-                    FetchError::Stop => {
-                        info!(position = self.position, "stopped fetching transfers");
-                        return;
-                    }
-                    FetchError::InvalidFormat => {
-                        error!("fname server returning different format than expected");
+                }
+                _ = sleep(self.next_delay()) => {
+                    match self.fetch().await {
+                        Ok(()) => {
+                            self.consecutive_failures = 0;
+                        }
+                        Err(FetchError::Stop) => {
+                            info!(position = self.position, "stopped fetching transfers");
+                            return;
+                        }
+                        Err(FetchError::Reqwest(request_error)) => {
+                            self.consecutive_failures += 1;
+                            warn!(error = %request_error, failures = self.consecutive_failures, "reqwest error fetching transfers");
+                        }
+                        Err(FetchError::InvalidFormat) => {
+                            self.consecutive_failures += 1;
+                            error!("fname server returning different format than expected");
+                        }
                     }
                 }
             }
-
-            sleep(Duration::from_secs(5)).await;
         }
     }
 }
@@ -1,2 +1,3 @@
 pub mod fname;
 pub mod onchain_events;
+pub mod solana;
@@ -0,0 +1,135 @@
+use crate::proto::OnChainEvent;
+use alloy_primitives::FixedBytes;
+use std::collections::BTreeMap;
+
+/** Events seen in a block that hasn't reached the confirmation depth yet */
+pub struct PendingBlock {
+    pub block_hash: FixedBytes<32>,
+    pub events: Vec<OnChainEvent>,
+}
+
+/** Withholds on-chain events until their block is confirmed, keyed by block number so a reorg at any height replaces the events seen there */
+#[derive(Default)]
+pub struct PendingEvents {
+    blocks: BTreeMap<u64, PendingBlock>,
+}
+
+impl PendingEvents {
+    pub fn new() -> Self {
+        PendingEvents::default()
+    }
+
+    /** Buffers an event. Returns true if a different block had been seen at the same height, in which case its events are dropped. */
+    pub fn add(
+        &mut self,
+        block_number: u64,
+        block_hash: FixedBytes<32>,
+        event: OnChainEvent,
+    ) -> bool {
+        let mut reorged = false;
+        let block = self
+            .blocks
+            .entry(block_number)
+            .or_insert_with(|| PendingBlock {
+                block_hash,
+                events: vec![],
+            });
+        if block.block_hash != block_hash {
+            block.block_hash = block_hash;
+            block.events.clear();
+            reorged = true;
+        }
+        let is_duplicate = block.events.iter().any(|existing| {
+            existing.transaction_hash == event.transaction_hash
+                && existing.log_index == event.log_index
+        });
+        if !is_duplicate {
+            block.events.push(event);
+        }
+        reorged
+    }
+
+    /** Drops every pending block from [from] through [to]. Returns the number of events dropped. */
+    pub fn remove_range(&mut self, from: u64, to: u64) -> usize {
+        if from > to {
+            return 0;
+        }
+        let block_numbers: Vec<u64> = self.blocks.range(from..=to).map(|(n, _)| *n).collect();
+        block_numbers
+            .into_iter()
+            .filter_map(|block_number| self.blocks.remove(&block_number))
+            .map(|block| block.events.len())
+            .sum()
+    }
+
+    /** The lowest pending block at or below [up_to], if any */
+    pub fn next_confirmed(&self, up_to: u64) -> Option<(u64, FixedBytes<32>)> {
+        self.blocks
+            .range(..=up_to)
+            .next()
+            .map(|(block_number, block)| (*block_number, block.block_hash))
+    }
+
+    /** Removes a block, returning its events ordered by log index */
+    pub fn take(&mut self, block_number: u64) -> Option<PendingBlock> {
+        self.blocks.remove(&block_number).map(|mut block| {
+            block.events.sort_by_key(|event| event.log_index);
+            block
+        })
+    }
+
+    pub fn num_events(&self) -> usize {
+        self.blocks.values().map(|block| block.events.len()).sum()
+    }
+}
+
+/** A range of blocks whose logs were fetched, with the hash its last block had when they were */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FetchedRange {
+    pub start_block: u64,
+    pub stop_block: u64,
+    pub block_hash: FixedBytes<32>,
+}
+
+/** Fetched ranges that haven't been checked at the confirmation depth yet, keyed by their last block. A reorg anywhere in a range changes the hash of its last block, including at heights where no events were seen. */
+#[derive(Default)]
+pub struct FetchedRanges {
+    ranges: BTreeMap<u64, FetchedRange>,
+}
+
+impl FetchedRanges {
+    pub fn new() -> Self {
+        FetchedRanges::default()
+    }
+
+    /** Tracks a range. A range ending at the same block is merged into it, keeping the hash read last. */
+    pub fn add(&mut self, range: FetchedRange) {
+        let start_block = self
+            .ranges
+            .get(&range.stop_block)
+            .map_or(range.start_block, |existing| {
+                existing.start_block.min(range.start_block)
+            });
+        self.ranges.insert(
+            range.stop_block,
+            FetchedRange {
+                start_block,
+                ..range
+            },
+        );
+    }
+
+    /** The lowest range ending at or below [up_to], if any */
+    pub fn next_confirmed(&self, up_to: u64) -> Option<FetchedRange> {
+        self.ranges.range(..=up_to).next().map(|(_, range)| *range)
+    }
+
+    pub fn remove(&mut self, stop_block: u64) {
+        self.ranges.remove(&stop_block);
+    }
+
+    /** Drops every range ending at or after [from], returning them in block order */
+    pub fn remove_from(&mut self, from: u64) -> Vec<FetchedRange> {
+        self.ranges.split_off(&from).into_values().collect()
+    }
+}
@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use crate::connectors::onchain_events::confirmations::{
+        FetchedRange, FetchedRanges, PendingEvents,
+    };
+    use crate::proto::OnChainEvent;
+    use alloy_primitives::FixedBytes;
+
+    fn event(block_number: u32, log_index: u32) -> OnChainEvent {
+        OnChainEvent {
+            block_number,
+            log_index,
+            transaction_hash: vec![log_index as u8; 32],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_events_are_released_in_block_and_log_order() {
+        let mut pending = PendingEvents::new();
+        let hash_a = FixedBytes::from([1u8; 32]);
+        let hash_b = FixedBytes::from([2u8; 32]);
+
+        assert!(!pending.add(11, hash_b, event(11, 0)));
+        assert!(!pending.add(10, hash_a, event(10, 3)));
+        assert!(!pending.add(10, hash_a, event(10, 1)));
+        // Seeing the same log twice doesn't submit it twice
+        assert!(!pending.add(10, hash_a, event(10, 1)));
+        assert_eq!(pending.num_events(), 3);
+
+        assert_eq!(pending.next_confirmed(9), None);
+        assert_eq!(pending.next_confirmed(10), Some((10, hash_a)));
+
+        let block = pending.take(10).unwrap();
+        assert_eq!(
+            block
+                .events
+                .iter()
+                .map(|event| event.log_index)
+                .collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert_eq!(pending.next_confirmed(10), None);
+        assert_eq!(pending.next_confirmed(100), Some((11, hash_b)));
+    }
+
+    #[test]
+    fn test_reorged_block_replaces_pending_events() {
+        let mut pending = PendingEvents::new();
+        let orphaned = FixedBytes::from([1u8; 32]);
+        let canonical = FixedBytes::from([2u8; 32]);
+
+        pending.add(10, orphaned, event(10, 0));
+        pending.add(10, orphaned, event(10, 1));
+        assert!(pending.add(10, canonical, event(10, 5)));
+
+        assert_eq!(pending.next_confirmed(10), Some((10, canonical)));
+        let block = pending.take(10).unwrap();
+        assert_eq!(block.events.len(), 1);
+        assert_eq!(block.events[0].log_index, 5);
+    }
+
+    #[test]
+    fn test_range_is_removed() {
+        let mut pending = PendingEvents::new();
+        let hash = FixedBytes::from([1u8; 32]);
+        for block_number in 10..15u64 {
+            pending.add(block_number, hash, event(block_number as u32, 0));
+        }
+
+        assert_eq!(pending.remove_range(11, 13), 3);
+        assert_eq!(pending.remove_range(13, 11), 0);
+        assert_eq!(pending.next_confirmed(20), Some((10, hash)));
+        pending.take(10);
+        assert_eq!(pending.next_confirmed(20), Some((14, hash)));
+    }
+
+    #[test]
+    fn test_fetched_ranges_are_confirmed_by_their_last_block() {
+        let mut ranges = FetchedRanges::new();
+        let hash = FixedBytes::from([1u8; 32]);
+        let range = |start_block, stop_block| FetchedRange {
+            start_block,
+            stop_block,
+            block_hash: hash,
+        };
+        ranges.add(range(10, 19));
+        ranges.add(range(20, 29));
+        ranges.add(range(30, 39));
+        // Overlapping ranges ending at the same block keep the earliest start
+        ranges.add(range(15, 19));
+
+        assert_eq!(ranges.next_confirmed(18), None);
+        let range = ranges.next_confirmed(25).unwrap();
+        assert_eq!((range.start_block, range.stop_block), (10, 19));
+        ranges.remove(19);
+        assert_eq!(ranges.next_confirmed(25), None);
+
+        // Ranges ending in or after a reorged block are dropped
+        let removed = ranges.remove_from(25);
+        assert_eq!(
+            removed
+                .iter()
+                .map(|range| range.stop_block)
+                .collect::<Vec<_>>(),
+            vec![29, 39]
+        );
+        assert_eq!(ranges.next_confirmed(100), None);
+    }
+}
@@ -0,0 +1,177 @@
+use super::{Chain, ChainAPI};
+use crate::core::validations;
+use crate::core::validations::verification::VerificationAddressClaim;
+use crate::proto::{EnsResolution, VerificationAddAddressBody};
+use crate::storage::store::node_local_state::LocalStateStore;
+use crate::utils::statsd_wrapper::StatsdClientWrapper;
+use alloy_primitives::Address;
+use async_trait::async_trait;
+use foundry_common::ens::EnsError;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{error, warn};
+
+/** What to do when a name has no fresh resolution cached and the rpc call fails */
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MissPolicy {
+    // Reject, only fresh resolutions are trusted
+    Strict,
+    // Fall back to an expired resolution if there is one
+    Lenient,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Config {
+    pub enabled: bool,
+    // How long a resolution is used without asking the chain again
+    #[serde(with = "humantime_serde")]
+    pub ttl: Duration,
+    pub policy: MissPolicy,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            enabled: true,
+            ttl: Duration::from_secs(60 * 60),
+            policy: MissPolicy::Strict,
+        }
+    }
+}
+
+/** Wraps a chain client, keeping ens name resolutions in the local state store so username proofs can be validated without an rpc call per submission */
+pub struct CachedChainClient {
+    inner: Box<dyn ChainAPI>,
+    chain: Chain,
+    config: Config,
+    local_state_store: LocalStateStore,
+    statsd_client: StatsdClientWrapper,
+}
+
+impl CachedChainClient {
+    pub fn new(
+        inner: Box<dyn ChainAPI>,
+        chain: Chain,
+        config: Config,
+        local_state_store: LocalStateStore,
+        statsd_client: StatsdClientWrapper,
+    ) -> Self {
+        CachedChainClient {
+            inner,
+            chain,
+            config,
+            local_state_store,
+            statsd_client,
+        }
+    }
+
+    fn count(&self, key: &str) {
+        self.statsd_client
+            .count(&format!("ens_cache.{}_on_{}", key, self.chain), 1);
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn cached_resolution(&self, name: &str) -> Option<EnsResolution> {
+        match self
+            .local_state_store
+            .get_ens_resolution(self.chain.chain_id(), name)
+        {
+            Ok(resolution) => resolution,
+            Err(err) => {
+                error!(name, err = err.to_string(), "Unable to read ens resolution");
+                None
+            }
+        }
+    }
+
+    fn store_resolution(&self, name: &str, address: Address) {
+        let resolution = EnsResolution {
+            address: address.to_vec(),
+            resolved_at: Self::now(),
+        };
+        if let Err(err) =
+            self.local_state_store
+                .put_ens_resolution(self.chain.chain_id(), name, &resolution)
+        {
+            error!(
+                name,
+                err = err.to_string(),
+                "Unable to store ens resolution"
+            );
+        }
+    }
+
+    fn forget_resolution(&self, name: &str) {
+        if let Err(err) = self
+            .local_state_store
+            .delete_ens_resolution(self.chain.chain_id(), name)
+        {
+            error!(
+                name,
+                err = err.to_string(),
+                "Unable to delete ens resolution"
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl ChainAPI for CachedChainClient {
+    async fn resolve_ens_name(&self, name: String) -> Result<Address, EnsError> {
+        if !self.config.enabled {
+            return self.inner.resolve_ens_name(name).await;
+        }
+
+        let cached = self.cached_resolution(&name);
+        if let Some(resolution) = &cached {
+            if Self::now() < resolution.resolved_at + self.config.ttl.as_secs() {
+                self.count("hits");
+                return Ok(Address::from_slice(&resolution.address));
+            }
+        }
+        self.count("misses");
+
+        match self.inner.resolve_ens_name(name.clone()).await {
+            Ok(address) => {
+                self.store_resolution(&name, address);
+                Ok(address)
+            }
+            // The name no longer resolves, a cached address must not outlive it
+            Err(EnsError::ResolverNotFound(resolver_name)) => {
+                self.forget_resolution(&name);
+                Err(EnsError::ResolverNotFound(resolver_name))
+            }
+            Err(err) => {
+                self.count("rpc_errors");
+                match cached {
+                    Some(resolution) if self.config.policy == MissPolicy::Lenient => {
+                        warn!(
+                            name,
+                            err = err.to_string(),
+                            resolved_at = resolution.resolved_at,
+                            "Unable to resolve ens name, using expired resolution"
+                        );
+                        self.count("stale_hits");
+                        Ok(Address::from_slice(&resolution.address))
+                    }
+                    _ => Err(err),
+                }
+            }
+        }
+    }
+
+    async fn verify_contract_signature(
+        &self,
+        claim: VerificationAddressClaim,
+        body: &VerificationAddAddressBody,
+    ) -> Result<(), validations::error::ValidationError> {
+        self.inner.verify_contract_signature(claim, body).await
+    }
+}
@@ -0,0 +1,193 @@
+#[cfg(test)]
+mod tests {
+    use crate::connectors::onchain_events::ens_cache::{CachedChainClient, Config, MissPolicy};
+    use crate::connectors::onchain_events::{Chain, ChainAPI};
+    use crate::core::validations;
+    use crate::core::validations::verification::VerificationAddressClaim;
+    use crate::proto::{EnsResolution, VerificationAddAddressBody};
+    use crate::storage::db;
+    use crate::storage::store::node_local_state::LocalStateStore;
+    use crate::storage::store::test_helper;
+    use alloy_primitives::{address, Address};
+    use async_trait::async_trait;
+    use foundry_common::ens::EnsError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    const ALICE: Address = address!("0x1111111111111111111111111111111111111111");
+    const BOB: Address = address!("0x2222222222222222222222222222222222222222");
+
+    enum Resolution {
+        Address(Address),
+        NotFound,
+        RpcError,
+    }
+
+    #[derive(Clone)]
+    struct MockChain {
+        resolution: Arc<Mutex<Resolution>>,
+        num_calls: Arc<AtomicUsize>,
+    }
+
+    impl MockChain {
+        fn set(&self, resolution: Resolution) {
+            *self.resolution.lock().unwrap() = resolution;
+        }
+
+        fn num_calls(&self) -> usize {
+            self.num_calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl ChainAPI for MockChain {
+        async fn resolve_ens_name(&self, name: String) -> Result<Address, EnsError> {
+            self.num_calls.fetch_add(1, Ordering::SeqCst);
+            match *self.resolution.lock().unwrap() {
+                Resolution::Address(address) => Ok(address),
+                Resolution::NotFound => Err(EnsError::ResolverNotFound(name)),
+                Resolution::RpcError => Err(EnsError::Resolve(
+                    alloy_contract::Error::UnknownFunction("addr".to_string()),
+                )),
+            }
+        }
+
+        async fn verify_contract_signature(
+            &self,
+            _claim: VerificationAddressClaim,
+            _body: &VerificationAddAddressBody,
+        ) -> Result<(), validations::error::ValidationError> {
+            Ok(())
+        }
+    }
+
+    fn store() -> LocalStateStore {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = db::RocksDB::new(dir.path().join("a.db").to_str().unwrap());
+        db.open().unwrap();
+        LocalStateStore::new(Arc::new(db))
+    }
+
+    fn cached_client(
+        chain: Chain,
+        policy: MissPolicy,
+        local_state_store: &LocalStateStore,
+    ) -> (CachedChainClient, MockChain) {
+        let mock = MockChain {
+            resolution: Arc::new(Mutex::new(Resolution::Address(ALICE))),
+            num_calls: Arc::new(AtomicUsize::new(0)),
+        };
+        let client = CachedChainClient::new(
+            Box::new(mock.clone()),
+            chain,
+            Config {
+                policy,
+                ..Default::default()
+            },
+            local_state_store.clone(),
+            test_helper::statsd_client(),
+        );
+        (client, mock)
+    }
+
+    fn expire(local_state_store: &LocalStateStore, chain: Chain, name: &str, address: Address) {
+        local_state_store
+            .put_ens_resolution(
+                chain.chain_id(),
+                name,
+                &EnsResolution {
+                    address: address.to_vec(),
+                    resolved_at: 0,
+                },
+            )
+            .unwrap();
+    }
+
+    async fn resolve(client: &CachedChainClient, name: &str) -> Result<Address, EnsError> {
+        client.resolve_ens_name(name.to_string()).await
+    }
+
+    #[tokio::test]
+    async fn test_fresh_resolutions_are_cached() {
+        let local_state_store = store();
+        let (client, mock) =
+            cached_client(Chain::EthMainnet, MissPolicy::Strict, &local_state_store);
+
+        assert_eq!(resolve(&client, "alice.eth").await.unwrap(), ALICE);
+        mock.set(Resolution::Address(BOB));
+        assert_eq!(resolve(&client, "alice.eth").await.unwrap(), ALICE);
+        assert_eq!(mock.num_calls(), 1);
+
+        // Survives a restart
+        let (client, mock) =
+            cached_client(Chain::EthMainnet, MissPolicy::Strict, &local_state_store);
+        assert_eq!(resolve(&client, "alice.eth").await.unwrap(), ALICE);
+        assert_eq!(mock.num_calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_resolutions_are_keyed_by_chain() {
+        let local_state_store = store();
+        let (eth_client, _) =
+            cached_client(Chain::EthMainnet, MissPolicy::Strict, &local_state_store);
+        let (base_client, base_mock) =
+            cached_client(Chain::BaseMainnet, MissPolicy::Strict, &local_state_store);
+        base_mock.set(Resolution::Address(BOB));
+
+        assert_eq!(resolve(&eth_client, "alice.eth").await.unwrap(), ALICE);
+        assert_eq!(resolve(&base_client, "alice.eth").await.unwrap(), BOB);
+        assert_eq!(base_mock.num_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_expired_resolutions_are_refreshed() {
+        let local_state_store = store();
+        let (client, mock) =
+            cached_client(Chain::EthMainnet, MissPolicy::Strict, &local_state_store);
+        expire(&local_state_store, Chain::EthMainnet, "alice.eth", BOB);
+
+        assert_eq!(resolve(&client, "alice.eth").await.unwrap(), ALICE);
+        assert_eq!(mock.num_calls(), 1);
+        assert_eq!(resolve(&client, "alice.eth").await.unwrap(), ALICE);
+        assert_eq!(mock.num_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_strict_policy_rejects_expired_resolutions() {
+        let local_state_store = store();
+        let (client, mock) =
+            cached_client(Chain::EthMainnet, MissPolicy::Strict, &local_state_store);
+        expire(&local_state_store, Chain::EthMainnet, "alice.eth", ALICE);
+        mock.set(Resolution::RpcError);
+
+        assert!(resolve(&client, "alice.eth").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lenient_policy_falls_back_to_expired_resolutions() {
+        let local_state_store = store();
+        let (client, mock) =
+            cached_client(Chain::EthMainnet, MissPolicy::Lenient, &local_state_store);
+        expire(&local_state_store, Chain::EthMainnet, "alice.eth", ALICE);
+        mock.set(Resolution::RpcError);
+
+        assert_eq!(resolve(&client, "alice.eth").await.unwrap(), ALICE);
+        // Nothing to fall back to
+        assert!(resolve(&client, "bob.eth").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_names_that_stop_resolving_are_forgotten() {
+        let local_state_store = store();
+        let (client, mock) =
+            cached_client(Chain::EthMainnet, MissPolicy::Lenient, &local_state_store);
+        expire(&local_state_store, Chain::EthMainnet, "alice.eth", ALICE);
+        mock.set(Resolution::NotFound);
+
+        assert!(resolve(&client, "alice.eth").await.is_err());
+        assert!(local_state_store
+            .get_ens_resolution(Chain::EthMainnet.chain_id(), "alice.eth")
+            .unwrap()
+            .is_none());
+    }
+}
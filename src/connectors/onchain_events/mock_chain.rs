@@ -0,0 +1,486 @@
+use super::{Contract, IdRegistryAbi, KeyRegistryAbi, StorageRegistryAbi, TierRegistryAbi};
+use alloy_primitives::{keccak256, Address, Bytes, FixedBytes, LogData, U256};
+use alloy_rpc_types::{Filter, Log};
+use alloy_sol_types::{sol, SolCall, SolEvent};
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use foundry_common::ens::namehash;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tracing::{debug, error};
+
+sol! {
+    interface MockEnsRegistry {
+        function resolver(bytes32 node) external view returns (address);
+    }
+
+    interface MockEnsResolver {
+        function addr(bytes32 node) external view returns (address);
+    }
+
+    interface MockErc1271 {
+        function isValidSignature(bytes32 hash, bytes signature) external view returns (bytes4);
+    }
+}
+
+// Every name resolves through the same resolver, regardless of which registry is queried
+const MOCK_ENS_RESOLVER: Address = Address::repeat_byte(0xe5);
+const BLOCK_TIME_SECONDS: u64 = 2;
+
+/** A log to be included in a mined block, built with the constructors for the Farcaster registries */
+#[derive(Clone, Debug)]
+pub struct MockLog {
+    pub address: Address,
+    pub data: LogData,
+}
+
+impl MockLog {
+    pub fn id_register(fid: u64, to: Address, recovery: Address) -> Self {
+        MockLog {
+            address: Contract::id_registry().address,
+            data: IdRegistryAbi::Register {
+                to,
+                id: U256::from(fid),
+                recovery,
+            }
+            .encode_log_data(),
+        }
+    }
+
+    pub fn id_transfer(fid: u64, from: Address, to: Address) -> Self {
+        MockLog {
+            address: Contract::id_registry().address,
+            data: IdRegistryAbi::Transfer {
+                from,
+                to,
+                id: U256::from(fid),
+            }
+            .encode_log_data(),
+        }
+    }
+
+    pub fn key_add(fid: u64, key: Vec<u8>, metadata: Vec<u8>) -> Self {
+        MockLog {
+            address: Contract::key_registry().address,
+            data: KeyRegistryAbi::Add {
+                fid: U256::from(fid),
+                keytype: 1,
+                key: keccak256(&key),
+                keyBytes: Bytes::from(key),
+                metadatatype: 1,
+                metadata: Bytes::from(metadata),
+            }
+            .encode_log_data(),
+        }
+    }
+
+    pub fn key_remove(fid: u64, key: Vec<u8>) -> Self {
+        MockLog {
+            address: Contract::key_registry().address,
+            data: KeyRegistryAbi::Remove {
+                fid: U256::from(fid),
+                key: keccak256(&key),
+                keyBytes: Bytes::from(key),
+            }
+            .encode_log_data(),
+        }
+    }
+
+    pub fn storage_rent(fid: u64, payer: Address, units: u64) -> Self {
+        MockLog {
+            address: Contract::storage_registry().address,
+            data: StorageRegistryAbi::Rent {
+                payer,
+                fid: U256::from(fid),
+                units: U256::from(units),
+            }
+            .encode_log_data(),
+        }
+    }
+
+    pub fn tier_purchase(fid: u64, tier: u64, for_days: u64, payer: Address) -> Self {
+        MockLog {
+            address: Contract::tier_registry().address,
+            data: TierRegistryAbi::PurchasedTier {
+                fid: U256::from(fid),
+                tier: U256::from(tier),
+                forDays: U256::from(for_days),
+                payer,
+            }
+            .encode_log_data(),
+        }
+    }
+}
+
+struct MockBlock {
+    number: u64,
+    hash: FixedBytes<32>,
+    parent_hash: FixedBytes<32>,
+    timestamp: u64,
+    logs: Vec<MockLog>,
+}
+
+impl MockBlock {
+    fn rpc_logs(&self) -> Vec<Log> {
+        self.logs
+            .iter()
+            .enumerate()
+            .map(|(index, log)| Log {
+                inner: alloy_primitives::Log {
+                    address: log.address,
+                    data: log.data.clone(),
+                },
+                block_hash: Some(self.hash),
+                block_number: Some(self.number),
+                block_timestamp: None,
+                transaction_hash: Some(keccak256(
+                    [self.hash.as_slice(), &(index as u64).to_be_bytes()[..]].concat(),
+                )),
+                transaction_index: Some(index as u64),
+                log_index: Some(index as u64),
+                removed: false,
+            })
+            .collect()
+    }
+
+    fn to_json(&self) -> Value {
+        let zero = FixedBytes::<32>::ZERO;
+        json!({
+            "hash": self.hash,
+            "parentHash": self.parent_hash,
+            "sha3Uncles": zero,
+            "miner": Address::ZERO,
+            "stateRoot": zero,
+            "transactionsRoot": zero,
+            "receiptsRoot": zero,
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "difficulty": "0x0",
+            "number": format!("{:#x}", self.number),
+            "gasLimit": "0x1c9c380",
+            "gasUsed": "0x0",
+            "timestamp": format!("{:#x}", self.timestamp),
+            "extraData": "0x",
+            "mixHash": zero,
+            "nonce": "0x0000000000000000",
+            "baseFeePerGas": "0x1",
+            "uncles": [],
+            "transactions": [],
+        })
+    }
+}
+
+struct MockChainState {
+    chain_id: u64,
+    blocks: Vec<MockBlock>,
+    // Blocks dropped by a reorg stay retrievable by hash, like on a real node
+    orphaned_blocks: HashMap<FixedBytes<32>, MockBlock>,
+    num_reorgs: u64,
+    ens_names: HashMap<FixedBytes<32>, Address>,
+    contract_signers: HashSet<Address>,
+}
+
+impl MockChainState {
+    fn head(&self) -> &MockBlock {
+        self.blocks.last().unwrap()
+    }
+
+    fn block_by_number(&self, number: u64) -> Option<&MockBlock> {
+        let first = self.blocks.first()?.number;
+        self.blocks.get(number.checked_sub(first)? as usize)
+    }
+
+    fn block_by_hash(&self, hash: &FixedBytes<32>) -> Option<&MockBlock> {
+        self.blocks
+            .iter()
+            .find(|block| block.hash == *hash)
+            .or_else(|| self.orphaned_blocks.get(hash))
+    }
+
+    fn mine(&mut self, logs: Vec<MockLog>) -> u64 {
+        let parent = self.head();
+        let number = parent.number + 1;
+        let block = MockBlock {
+            number,
+            // Blocks mined after a reorg get new hashes at the same heights
+            hash: keccak256([number.to_be_bytes(), self.num_reorgs.to_be_bytes()].concat()),
+            parent_hash: parent.hash,
+            timestamp: parent.timestamp + BLOCK_TIME_SECONDS,
+            logs,
+        };
+        self.blocks.push(block);
+        number
+    }
+}
+
+/** An in-process Ethereum JSON-RPC server with scripted blocks and logs, for testing the on-chain connectors without a real provider */
+#[derive(Clone)]
+pub struct MockChain {
+    state: Arc<Mutex<MockChainState>>,
+}
+
+impl MockChain {
+    pub fn new(chain_id: u64, first_block: u64, first_block_timestamp: u64) -> Self {
+        let genesis = MockBlock {
+            number: first_block,
+            hash: keccak256([first_block.to_be_bytes(), 0u64.to_be_bytes()].concat()),
+            parent_hash: FixedBytes::ZERO,
+            timestamp: first_block_timestamp,
+            logs: vec![],
+        };
+        MockChain {
+            state: Arc::new(Mutex::new(MockChainState {
+                chain_id,
+                blocks: vec![genesis],
+                orphaned_blocks: HashMap::new(),
+                num_reorgs: 0,
+                ens_names: HashMap::new(),
+                contract_signers: HashSet::new(),
+            })),
+        }
+    }
+
+    /** Mines a block containing the given logs and returns its number */
+    pub fn mine_block(&self, logs: Vec<MockLog>) -> u64 {
+        self.state.lock().unwrap().mine(logs)
+    }
+
+    pub fn mine_empty_blocks(&self, num_blocks: u64) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        for _ in 0..num_blocks {
+            state.mine(vec![]);
+        }
+        state.head().number
+    }
+
+    /** Drops the last [depth] blocks. Blocks mined afterwards replace them with different hashes. */
+    pub fn reorg(&self, depth: u64) {
+        let mut state = self.state.lock().unwrap();
+        for _ in 0..depth {
+            if state.blocks.len() == 1 {
+                break;
+            }
+            let block = state.blocks.pop().unwrap();
+            state.orphaned_blocks.insert(block.hash, block);
+        }
+        state.num_reorgs += 1;
+    }
+
+    pub fn head(&self) -> u64 {
+        self.state.lock().unwrap().head().number
+    }
+
+    pub fn block_hash(&self, number: u64) -> Option<FixedBytes<32>> {
+        self.state
+            .lock()
+            .unwrap()
+            .block_by_number(number)
+            .map(|block| block.hash)
+    }
+
+    pub fn register_ens_name(&self, name: &str, address: Address) {
+        self.state
+            .lock()
+            .unwrap()
+            .ens_names
+            .insert(namehash(name), address);
+    }
+
+    /** Makes [address] behave like an EIP-1271 contract wallet that accepts any signature */
+    pub fn add_contract_signer(&self, address: Address) {
+        self.state.lock().unwrap().contract_signers.insert(address);
+    }
+
+    /** Serves the chain on an ephemeral local port and returns its url */
+    pub async fn start(&self) -> Result<String, std::io::Error> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        tokio::spawn(self.clone().serve(listener));
+        Ok(url)
+    }
+
+    pub async fn serve(self, listener: TcpListener) {
+        let router = Router::new()
+            .route("/", post(handle_request))
+            .with_state(self);
+        if let Err(err) = axum::serve(listener, router).await {
+            error!(err = err.to_string(), "Mock chain server stopped");
+        }
+    }
+
+    fn handle(&self, request: &Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = request
+            .get("method")
+            .and_then(|method| method.as_str())
+            .unwrap_or_default();
+        let params = request
+            .get("params")
+            .and_then(|params| params.as_array())
+            .cloned()
+            .unwrap_or_default();
+        match self.dispatch(method, &params) {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": code, "message": message},
+            }),
+        }
+    }
+
+    fn dispatch(&self, method: &str, params: &[Value]) -> Result<Value, (i64, String)> {
+        let state = self.state.lock().unwrap();
+        let invalid_params = || (-32602, format!("Invalid params for {}", method));
+        match method {
+            "eth_chainId" => Ok(json!(format!("{:#x}", state.chain_id))),
+            "eth_blockNumber" => Ok(json!(format!("{:#x}", state.head().number))),
+            "eth_getBlockByNumber" => {
+                let tag = params
+                    .first()
+                    .and_then(|tag| tag.as_str())
+                    .ok_or_else(invalid_params)?;
+                let block = match tag {
+                    "latest" | "safe" | "finalized" | "pending" => Some(state.head()),
+                    "earliest" => state.blocks.first(),
+                    number => state.block_by_number(
+                        u64::from_str_radix(number.trim_start_matches("0x"), 16)
+                            .map_err(|_| invalid_params())?,
+                    ),
+                };
+                Ok(block.map_or(Value::Null, |block| block.to_json()))
+            }
+            "eth_getBlockByHash" => {
+                let hash: FixedBytes<32> = params
+                    .first()
+                    .cloned()
+                    .and_then(|hash| serde_json::from_value(hash).ok())
+                    .ok_or_else(invalid_params)?;
+                Ok(state
+                    .block_by_hash(&hash)
+                    .map_or(Value::Null, |block| block.to_json()))
+            }
+            "eth_getLogs" => {
+                let filter: Filter = params
+                    .first()
+                    .cloned()
+                    .and_then(|filter| serde_json::from_value(filter).ok())
+                    .ok_or_else(invalid_params)?;
+                let from_block = filter.get_from_block().unwrap_or(state.head().number);
+                let to_block = filter.get_to_block().unwrap_or(state.head().number);
+                let logs: Vec<Log> = state
+                    .blocks
+                    .iter()
+                    .filter(|block| block.number >= from_block && block.number <= to_block)
+                    .flat_map(|block| block.rpc_logs())
+                    .filter(|log| Self::filter_matches(&filter, log))
+                    .collect();
+                Ok(json!(logs))
+            }
+            "eth_getCode" => {
+                let address: Address = params
+                    .first()
+                    .cloned()
+                    .and_then(|address| serde_json::from_value(address).ok())
+                    .ok_or_else(invalid_params)?;
+                // Contract signers need some code to be treated as contracts
+                let code = if state.contract_signers.contains(&address) {
+                    Bytes::from(vec![0xfe])
+                } else {
+                    Bytes::new()
+                };
+                Ok(json!(code))
+            }
+            "eth_call" => {
+                let call = params.first().ok_or_else(invalid_params)?;
+                self.call(&state, call)
+            }
+            _ => Err((-32601, format!("Method not found: {}", method))),
+        }
+    }
+
+    fn filter_matches(filter: &Filter, log: &Log) -> bool {
+        filter.address.matches(&log.address())
+            && filter.topics.iter().enumerate().all(|(index, topic)| {
+                topic.is_empty()
+                    || log
+                        .topics()
+                        .get(index)
+                        .is_some_and(|log_topic| topic.matches(log_topic))
+            })
+    }
+
+    fn call(&self, state: &MockChainState, call: &Value) -> Result<Value, (i64, String)> {
+        let to: Option<Address> = call
+            .get("to")
+            .cloned()
+            .and_then(|to| serde_json::from_value(to).ok());
+        let input: Bytes = call
+            .get("input")
+            .or_else(|| call.get("data"))
+            .cloned()
+            .and_then(|input| serde_json::from_value(input).ok())
+            .unwrap_or_default();
+        let reverted = || (3, "execution reverted".to_string());
+
+        let to = match to {
+            Some(to) => to,
+            None => {
+                // A deployless ERC-6492 validation call, the signer's address is abi-encoded in the constructor arguments
+                let is_valid = state.contract_signers.iter().any(|signer| {
+                    let word = signer.into_word();
+                    input
+                        .windows(word.len())
+                        .any(|window| window == word.as_slice())
+                });
+                return if is_valid {
+                    Ok(json!(Bytes::from(vec![1u8])))
+                } else {
+                    Err(reverted())
+                };
+            }
+        };
+
+        if let Ok(call) = MockEnsRegistry::resolverCall::abi_decode(&input, true) {
+            let resolver = if state.ens_names.contains_key(&call.node) {
+                MOCK_ENS_RESOLVER
+            } else {
+                Address::ZERO
+            };
+            return Ok(json!(Bytes::from(resolver.into_word().to_vec())));
+        }
+        if let Ok(call) = MockEnsResolver::addrCall::abi_decode(&input, true) {
+            let address = state
+                .ens_names
+                .get(&call.node)
+                .copied()
+                .unwrap_or(Address::ZERO);
+            return Ok(json!(Bytes::from(address.into_word().to_vec())));
+        }
+        if MockErc1271::isValidSignatureCall::abi_decode(&input, true).is_ok() {
+            if !state.contract_signers.contains(&to) {
+                return Err(reverted());
+            }
+            let mut magic_value = [0u8; 32];
+            magic_value[..4].copy_from_slice(&MockErc1271::isValidSignatureCall::SELECTOR);
+            return Ok(json!(Bytes::from(magic_value.to_vec())));
+        }
+        Err(reverted())
+    }
+}
+
+async fn handle_request(State(chain): State<MockChain>, Json(request): Json<Value>) -> Json<Value> {
+    let response = match &request {
+        Value::Array(requests) => Value::Array(
+            requests
+                .iter()
+                .map(|request| chain.handle(request))
+                .collect(),
+        ),
+        request => chain.handle(request),
+    };
+    debug!(%request, "Mock chain request");
+    Json(response)
+}
@@ -0,0 +1,336 @@
+#[cfg(test)]
+mod tests {
+    use crate::connectors::onchain_events::mock_chain::{MockChain, MockLog};
+    use crate::connectors::onchain_events::{
+        ChainAPI, Config, ContractConfig, ContractKind, OnchainEventsRequest, RealL1Client,
+        SubscribeError, Subscriber, ETH_L1_ENS_REGISTRY, OP_MAINNET_FIRST_BLOCK,
+    };
+    use crate::core::validations::verification::make_verification_address_claim;
+    use crate::mempool::mempool::MempoolRequest;
+    use crate::proto::{
+        self, on_chain_event, OnChainEvent, OnChainEventType, VerificationAddAddressBody,
+    };
+    use crate::storage::db;
+    use crate::storage::store::engine::MempoolMessage;
+    use crate::storage::store::node_local_state::{Chain, LocalStateStore};
+    use crate::storage::store::test_helper;
+    use alloy_primitives::{address, Address};
+    use std::sync::Arc;
+    use tokio::sync::{broadcast, mpsc};
+
+    const CUSTODY: Address = address!("0x1111111111111111111111111111111111111111");
+    const RECOVERY: Address = address!("0x2222222222222222222222222222222222222222");
+
+    async fn start_chain() -> (MockChain, String) {
+        let chain = MockChain::new(10, OP_MAINNET_FIRST_BLOCK, 1_700_000_000);
+        // Leave room below the first scripted block for the confirmation depth
+        chain.mine_empty_blocks(2);
+        let url = chain.start().await.unwrap();
+        (chain, url)
+    }
+
+    fn subscriber(
+        rpc_url: String,
+        confirmation_depth: u64,
+    ) -> (Subscriber, mpsc::Receiver<MempoolRequest>) {
+        subscriber_with_config(Config {
+            rpc_url,
+            confirmation_depth: Some(confirmation_depth),
+            chain_id: Some(Chain::OPTIMISM.0),
+            ..Default::default()
+        })
+    }
+
+    fn subscriber_with_config(config: Config) -> (Subscriber, mpsc::Receiver<MempoolRequest>) {
+        let (mempool_tx, mempool_rx) = mpsc::channel(100);
+        (new_subscriber(&config, mempool_tx).unwrap(), mempool_rx)
+    }
+
+    fn new_subscriber(
+        config: &Config,
+        mempool_tx: mpsc::Sender<MempoolRequest>,
+    ) -> Result<Subscriber, SubscribeError> {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = db::RocksDB::new(dir.path().join("a.db").to_str().unwrap());
+        db.open().unwrap();
+        let (_, onchain_events_request_rx) = broadcast::channel::<OnchainEventsRequest>(10);
+        Subscriber::new(
+            config,
+            mempool_tx,
+            test_helper::statsd_client(),
+            LocalStateStore::new(Arc::new(db)),
+            onchain_events_request_rx,
+        )
+    }
+
+    fn submitted_events(mempool_rx: &mut mpsc::Receiver<MempoolRequest>) -> Vec<OnChainEvent> {
+        let mut events = vec![];
+        while let Ok(request) = mempool_rx.try_recv() {
+            if let MempoolRequest::AddMessage(MempoolMessage::ValidatorMessage(message), _, _) =
+                request
+            {
+                events.extend(message.on_chain_event);
+            }
+        }
+        events
+    }
+
+    fn registered_fid(event: &OnChainEvent) -> Option<u64> {
+        match &event.body {
+            Some(on_chain_event::Body::IdRegisterEventBody(_)) => Some(event.fid),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_are_submitted_once_confirmed() {
+        let (chain, url) = start_chain().await;
+        let (mut subscriber, mut mempool_rx) = subscriber(url, 2);
+
+        let block_number = chain.mine_block(vec![
+            MockLog::id_register(1, CUSTODY, RECOVERY),
+            MockLog::storage_rent(1, CUSTODY, 2),
+        ]);
+        subscriber
+            .sync_historical_events(OP_MAINNET_FIRST_BLOCK, chain.head())
+            .await
+            .unwrap();
+        assert!(submitted_events(&mut mempool_rx).is_empty());
+
+        chain.mine_empty_blocks(2);
+        subscriber
+            .sync_historical_events(block_number, chain.head())
+            .await
+            .unwrap();
+        let events = submitted_events(&mut mempool_rx);
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| event.r#type())
+                .collect::<Vec<_>>(),
+            vec![
+                OnChainEventType::EventTypeIdRegister,
+                OnChainEventType::EventTypeStorageRent
+            ]
+        );
+        assert!(events.iter().all(|event| event.fid == 1
+            && event.block_number as u64 == block_number
+            && event.block_hash == chain.block_hash(block_number).unwrap().to_vec()));
+
+        let finalized_block = subscriber
+            .local_state_store
+            .get_finalized_block(Chain::OPTIMISM)
+            .unwrap()
+            .unwrap();
+        assert_eq!(finalized_block.block_number, chain.head() - 2);
+        assert_eq!(
+            finalized_block.block_hash,
+            chain.block_hash(chain.head() - 2).unwrap().to_vec()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_events_from_orphaned_blocks_are_withheld() {
+        let (chain, url) = start_chain().await;
+        let (mut subscriber, mut mempool_rx) = subscriber(url, 2);
+
+        let block_number = chain.mine_block(vec![MockLog::id_register(1, CUSTODY, RECOVERY)]);
+        subscriber
+            .sync_historical_events(OP_MAINNET_FIRST_BLOCK, chain.head())
+            .await
+            .unwrap();
+        assert!(submitted_events(&mut mempool_rx).is_empty());
+
+        // The block with fid 1 is replaced by one registering fid 2 at the same height
+        chain.reorg(1);
+        assert_eq!(
+            chain.mine_block(vec![MockLog::id_register(2, CUSTODY, RECOVERY)]),
+            block_number
+        );
+        chain.mine_empty_blocks(2);
+        subscriber
+            .sync_historical_events(block_number + 1, chain.head())
+            .await
+            .unwrap();
+
+        let events = submitted_events(&mut mempool_rx);
+        assert_eq!(
+            events.iter().filter_map(registered_fid).collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_eq!(
+            events[0].block_hash,
+            chain.block_hash(block_number).unwrap().to_vec()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blocks_after_a_reorged_block_are_refetched() {
+        let (chain, url) = start_chain().await;
+        let (mut subscriber, mut mempool_rx) = subscriber(url, 3);
+
+        let block_number = chain.mine_block(vec![MockLog::id_register(1, CUSTODY, RECOVERY)]);
+        chain.mine_empty_blocks(1);
+        subscriber
+            .sync_historical_events(OP_MAINNET_FIRST_BLOCK, chain.head())
+            .await
+            .unwrap();
+        assert!(submitted_events(&mut mempool_rx).is_empty());
+
+        // Both blocks are replaced, and the block after the orphaned one now has an event
+        chain.reorg(2);
+        chain.mine_block(vec![MockLog::id_register(2, CUSTODY, RECOVERY)]);
+        chain.mine_block(vec![MockLog::id_register(3, CUSTODY, RECOVERY)]);
+        chain.mine_empty_blocks(3);
+        subscriber
+            .sync_historical_events(block_number + 2, chain.head())
+            .await
+            .unwrap();
+
+        let events = submitted_events(&mut mempool_rx);
+        assert_eq!(
+            events.iter().filter_map(registered_fid).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_configured_contracts() {
+        let (chain, url) = start_chain().await;
+        let id_registry = address!("0x3333333333333333333333333333333333333333");
+        let id_register = |fid| MockLog {
+            address: id_registry,
+            ..MockLog::id_register(fid, CUSTODY, RECOVERY)
+        };
+        let before_deployment = chain.mine_block(vec![id_register(1)]);
+        let deployment = chain.mine_block(vec![id_register(2)]);
+        // Logs from the mainnet deployment aren't indexed
+        chain.mine_block(vec![MockLog::id_register(3, CUSTODY, RECOVERY)]);
+        chain.mine_empty_blocks(1);
+
+        let (mut subscriber, mut mempool_rx) = subscriber_with_config(Config {
+            rpc_url: url,
+            confirmation_depth: Some(1),
+            chain_id: Some(31337),
+            contracts: vec![ContractConfig {
+                address: id_registry.to_string(),
+                kind: ContractKind::IdRegistry,
+                start_block: deployment,
+            }],
+            ..Default::default()
+        });
+        subscriber
+            .sync_historical_events(before_deployment, chain.head())
+            .await
+            .unwrap();
+
+        let events = submitted_events(&mut mempool_rx);
+        assert_eq!(
+            events.iter().filter_map(registered_fid).collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_eq!(events[0].chain_id, 31337);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_contract_address() {
+        let config = Config {
+            rpc_url: "http://127.0.0.1:8545".to_string(),
+            chain_id: Some(Chain::OPTIMISM.0),
+            contracts: vec![ContractConfig {
+                address: "not an address".to_string(),
+                kind: ContractKind::KeyRegistry,
+                start_block: 0,
+            }],
+            ..Default::default()
+        };
+        let (mempool_tx, _) = mpsc::channel(1);
+        assert!(matches!(
+            new_subscriber(&config, mempool_tx),
+            Err(SubscribeError::InvalidContractAddress(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chains_are_keyed_by_chain_id() {
+        let config = Config {
+            rpc_url: "http://127.0.0.1:8545".to_string(),
+            ..Default::default()
+        };
+        let (mempool_tx, _) = mpsc::channel(1);
+        assert!(matches!(
+            new_subscriber(&config, mempool_tx.clone()),
+            Err(SubscribeError::MissingChainId)
+        ));
+
+        // Only Optimism and Base mainnet have default contracts
+        let config = Config {
+            chain_id: Some(31337),
+            ..config
+        };
+        assert!(matches!(
+            new_subscriber(&config, mempool_tx.clone()),
+            Err(SubscribeError::NoContracts(31337))
+        ));
+
+        let subscriber = new_subscriber(
+            &Config {
+                chain_id: Some(Chain::BASE.0),
+                ..config
+            },
+            mempool_tx,
+        )
+        .unwrap();
+        assert_eq!(subscriber.chain(), Chain::BASE);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_ens_name() {
+        let (chain, url) = start_chain().await;
+        chain.register_ens_name("alice.eth", CUSTODY);
+        let client = RealL1Client::new(url, ETH_L1_ENS_REGISTRY).unwrap();
+
+        assert_eq!(
+            client
+                .resolve_ens_name("alice.eth".to_string())
+                .await
+                .unwrap(),
+            CUSTODY
+        );
+        assert!(client
+            .resolve_ens_name("bob.eth".to_string())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_contract_signature() {
+        let (chain, url) = start_chain().await;
+        chain.add_contract_signer(CUSTODY);
+        let client = RealL1Client::new(url, ETH_L1_ENS_REGISTRY).unwrap();
+
+        let verify = |address: Address| {
+            let body = VerificationAddAddressBody {
+                address: address.to_vec(),
+                claim_signature: vec![1; 65],
+                block_hash: vec![2; 32],
+                verification_type: 1,
+                chain_id: 10,
+                protocol: proto::Protocol::Ethereum as i32,
+            };
+            let claim = make_verification_address_claim(
+                123,
+                &body.address,
+                proto::FarcasterNetwork::Mainnet,
+                &body.block_hash,
+                proto::Protocol::Ethereum,
+            )
+            .unwrap();
+            let client = &client;
+            async move { client.verify_contract_signature(claim, &body).await }
+        };
+
+        assert!(verify(CUSTODY).await.is_ok());
+        assert!(verify(RECOVERY).await.is_err());
+    }
+}
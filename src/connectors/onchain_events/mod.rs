@@ -1,5 +1,6 @@
 use crate::cfg::Config as AppConfig;
-use crate::proto::TierPurchaseBody;
+use crate::connectors::solana::{RealSolanaClient, SolanaAPI};
+use crate::proto::{FinalizedBlock, TierPurchaseBody};
 use crate::storage::store::node_local_state;
 use alloy_primitives::U256;
 use alloy_primitives::{address, ruint::FromUintError, Address, FixedBytes};
@@ -10,9 +11,8 @@ use alloy_transport_http::{Client, Http};
 use async_trait::async_trait;
 use foundry_common::ens::EnsResolver::EnsResolverInstance;
 use foundry_common::ens::{namehash, EnsError, EnsRegistry};
-use futures_util::stream::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use thiserror::Error;
 use tokio::sync::{broadcast, mpsc};
@@ -33,6 +33,22 @@ use crate::{
     storage::store::{engine::MempoolMessage, node_local_state::LocalStateStore},
     utils::statsd_wrapper::StatsdClientWrapper,
 };
+use confirmations::{FetchedRange, FetchedRanges, PendingEvents};
+use ens_cache::CachedChainClient;
+use providers::ProviderPool;
+
+mod confirmations;
+#[cfg(test)]
+mod confirmations_tests;
+pub mod ens_cache;
+#[cfg(test)]
+mod ens_cache_tests;
+pub mod mock_chain;
+#[cfg(test)]
+mod mock_chain_tests;
+mod providers;
+#[cfg(test)]
+mod providers_tests;
 
 sol!(
     #[allow(missing_docs)]
@@ -63,33 +79,66 @@ sol!(
 );
 
 // Note these are the registry addresses, not the resolver addresses. We look up the resolver from the registry.
+// Used unless a registry is configured for the chain.
 static ETH_L1_ENS_REGISTRY: Address = address!("00000000000C2E074eC69A0dFb2997BA6C7d2e1e");
 static BASE_MAINNET_ENS_REGISTRY: Address = address!("0xB94704422c2a1E396835A571837Aa5AE53285a95");
 
-// For reference, in case it needs to be specified manually
+// Start blocks of the mainnet deployments, used when no contracts are configured
 const OP_MAINNET_FIRST_BLOCK: u64 = 108864739;
-static OP_MAINNET_CHAIN_ID: u32 = 10; // OP mainnet
 const BASE_MAINNET_FIRST_BLOCK: u64 = 31180908;
 static BASE_MAINNET_CHAIN_ID: u32 = 8453; // Base mainnet
 const RENT_EXPIRY_IN_SECONDS: u64 = 365 * 24 * 60 * 60; // One year
 
 const RETRY_TIMEOUT_SECONDS: u64 = 10;
 
+// Number of blocks on top of an event's block before it's submitted, unless overridden in config
+const DEFAULT_CONFIRMATION_DEPTH: u64 = 10;
+const CONFIRMATION_POLL_INTERVAL_SECONDS: u64 = 5;
+const HEALTH_CHECK_INTERVAL_SECONDS: u64 = 30;
+// Largest block range requested in a single eth_getLogs call
+const LOGS_BATCH_SIZE: u64 = 1000;
+
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub rpc_url: String,
+    pub fallback_rpc_urls: Vec<String>,
+    // Number of providers that must return identical logs and block hashes, up to a head that many
+    // providers have reached. Unset to trust the active provider alone.
+    pub logs_quorum: Option<usize>,
     pub start_block_number: Option<u64>,
     pub stop_block_number: Option<u64>,
     pub override_tier_registry_address: Option<String>, // For testing
+    pub confirmation_depth: Option<u64>,
+    // Chain the events are indexed from, recorded on events and keying the chain's sync state
+    pub chain_id: Option<u32>,
+    // Contracts to index. Defaults to the Farcaster deployments on Optimism and Base mainnet.
+    pub contracts: Vec<ContractConfig>,
+    // ENS registry for resolving Base names, only read from the Base section. Defaults to the Base mainnet registry.
+    pub ens_registry_address: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContractConfig {
+    pub address: String,
+    pub kind: ContractKind,
+    // Block the contract was deployed at, logs before it aren't fetched
+    pub start_block: u64,
 }
 
 impl Default for Config {
     fn default() -> Config {
         return Config {
             rpc_url: String::new(),
+            fallback_rpc_urls: vec![],
+            logs_quorum: None,
             start_block_number: None,
             stop_block_number: None,
             override_tier_registry_address: None,
+            confirmation_depth: None,
+            chain_id: None,
+            contracts: vec![],
+            ens_registry_address: None,
         };
     }
 }
@@ -101,6 +150,11 @@ pub enum OnchainEventsRequest {
         start_block_number: u64,
         stop_block_number: u64,
     },
+    // Responds with the fid's confirmed events on chain, without submitting them
+    FetchFidEvents {
+        fid: u64,
+        response_tx: mpsc::Sender<Vec<OnChainEvent>>,
+    },
 }
 
 #[derive(Error, Debug)]
@@ -143,6 +197,30 @@ pub enum SubscribeError {
 
     #[error("Unable to find block by hash")]
     UnableToFindBlockByHash,
+
+    #[error("Unable to find block by number")]
+    UnableToFindBlockByNumber,
+
+    #[error("Logs quorum of {quorum} exceeds the {num_providers} configured rpc providers")]
+    QuorumExceedsProviders { quorum: usize, num_providers: usize },
+
+    #[error("Only {num_agreeing} rpc providers agreed, quorum is {quorum}")]
+    QuorumNotReached { num_agreeing: usize, quorum: usize },
+
+    #[error("Quorum head {head} is below the requested block {to_block}")]
+    HeadBelowRange { head: u64, to_block: u64 },
+
+    #[error("Invalid contract address: {0}")]
+    InvalidContractAddress(String),
+
+    #[error("Missing chain id")]
+    MissingChainId,
+
+    #[error("No contracts configured for chain {0}")]
+    NoContracts(u32),
+
+    #[error("Chain {0} is configured more than once")]
+    DuplicateChain(u32),
 }
 
 #[async_trait]
@@ -155,37 +233,102 @@ pub trait ChainAPI: Send + Sync {
     ) -> Result<(), validations::error::ValidationError>;
 }
 
-#[derive(Eq, Hash, PartialEq, Debug)]
+#[derive(Eq, Hash, PartialEq, Debug, Clone, Copy, strum_macros::Display)]
+#[strum(serialize_all = "snake_case")]
 pub enum Chain {
     EthMainnet,
     BaseMainnet,
 }
 
+impl Chain {
+    pub fn chain_id(&self) -> u32 {
+        match self {
+            Chain::EthMainnet => 1,
+            Chain::BaseMainnet => BASE_MAINNET_CHAIN_ID,
+        }
+    }
+}
+
+fn ens_registry(configured: &Option<String>, default: Address) -> Address {
+    configured
+        .as_ref()
+        .map(|address| Address::from_str(address).unwrap())
+        .unwrap_or(default)
+}
+
+/** The chains onchain events are indexed from, the Optimism and Base sections followed by any further configured chains. Chains without an rpc url are skipped. */
+pub fn configured_chains(app_config: &AppConfig) -> Result<Vec<&Config>, SubscribeError> {
+    let mut chain_ids = HashSet::new();
+    let mut chains = vec![];
+    for config in [&app_config.onchain_events, &app_config.base_onchain_events]
+        .into_iter()
+        .chain(app_config.chains.iter())
+    {
+        if config.rpc_url.is_empty() {
+            continue;
+        }
+        let chain_id = config.chain_id.ok_or(SubscribeError::MissingChainId)?;
+        if !chain_ids.insert(chain_id) {
+            return Err(SubscribeError::DuplicateChain(chain_id));
+        }
+        chains.push(config);
+    }
+    Ok(chains)
+}
+
 pub struct ChainClients {
     pub chain_api_map: HashMap<Chain, Box<dyn ChainAPI>>,
+    pub solana_client: Option<Box<dyn SolanaAPI>>,
 }
 
 impl ChainClients {
-    pub fn new(app_config: &AppConfig) -> Self {
+    pub fn new(
+        app_config: &AppConfig,
+        local_state_store: LocalStateStore,
+        statsd_client: StatsdClientWrapper,
+    ) -> Self {
         let mut chain_api_map = HashMap::new();
+        let cached = |client: RealL1Client, chain: Chain| -> Box<dyn ChainAPI> {
+            Box::new(CachedChainClient::new(
+                Box::new(client),
+                chain,
+                app_config.ens_cache.clone(),
+                local_state_store.clone(),
+                statsd_client.clone(),
+            ))
+        };
         if !app_config.l1_rpc_url.is_empty() {
-            let client: Box<dyn ChainAPI> = Box::new(
-                RealL1Client::new(app_config.l1_rpc_url.clone(), ETH_L1_ENS_REGISTRY).unwrap(),
-            );
-            chain_api_map.insert(Chain::EthMainnet, client);
+            let client = RealL1Client::new(
+                app_config.l1_rpc_url.clone(),
+                ens_registry(&app_config.l1_ens_registry_address, ETH_L1_ENS_REGISTRY),
+            )
+            .unwrap();
+            chain_api_map.insert(Chain::EthMainnet, cached(client, Chain::EthMainnet));
         }
         if !app_config.base_onchain_events.rpc_url.is_empty() {
-            let client: Box<dyn ChainAPI> = Box::new(
-                RealL1Client::new(
-                    app_config.base_onchain_events.rpc_url.clone(),
+            let client = RealL1Client::new(
+                app_config.base_onchain_events.rpc_url.clone(),
+                ens_registry(
+                    &app_config.base_onchain_events.ens_registry_address,
                     BASE_MAINNET_ENS_REGISTRY,
-                )
-                .unwrap(),
-            );
-            chain_api_map.insert(Chain::BaseMainnet, client);
+                ),
+            )
+            .unwrap();
+            chain_api_map.insert(Chain::BaseMainnet, cached(client, Chain::BaseMainnet));
         }
 
-        ChainClients { chain_api_map }
+        let solana_client: Option<Box<dyn SolanaAPI>> = if app_config.solana.rpc_url.is_empty() {
+            None
+        } else {
+            Some(Box::new(RealSolanaClient::new(
+                app_config.solana.rpc_url.clone(),
+            )))
+        };
+
+        ChainClients {
+            chain_api_map,
+            solana_client,
+        }
     }
 
     pub fn for_chain(&self, chain: Chain) -> Result<&Box<dyn ChainAPI>, HubError> {
@@ -196,6 +339,12 @@ impl ChainClients {
             )),
         }
     }
+
+    pub fn solana(&self) -> Result<&Box<dyn SolanaAPI>, HubError> {
+        self.solana_client
+            .as_ref()
+            .ok_or_else(|| HubError::invalid_internal_state("No client configured for solana"))
+    }
 }
 
 pub struct RealL1Client {
@@ -258,7 +407,8 @@ impl ChainAPI for RealL1Client {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ContractKind {
     TierRegistry,
     StorageRegistry,
@@ -269,13 +419,38 @@ pub enum ContractKind {
 pub struct Contract {
     address: Address,
     kind: ContractKind,
+    start_block: u64,
 }
 
 impl Contract {
+    pub fn from_config(config: &ContractConfig) -> Result<Self, SubscribeError> {
+        let address = Address::from_str(&config.address)
+            .map_err(|_| SubscribeError::InvalidContractAddress(config.address.clone()))?;
+        Ok(Contract {
+            address,
+            kind: config.kind.clone(),
+            start_block: config.start_block,
+        })
+    }
+
+    /** The Farcaster deployments for a chain, none for chains other than Optimism and Base mainnet */
+    pub fn mainnet_contracts(chain: node_local_state::Chain) -> Vec<Self> {
+        match chain {
+            node_local_state::Chain::OPTIMISM => vec![
+                Contract::storage_registry(),
+                Contract::key_registry(),
+                Contract::id_registry(),
+            ],
+            node_local_state::Chain::BASE => vec![Contract::tier_registry()],
+            _ => vec![],
+        }
+    }
+
     pub fn storage_registry() -> Self {
         Contract {
             address: address!("00000000fcce7f938e7ae6d3c335bd6a1a7c593d"),
             kind: ContractKind::StorageRegistry,
+            start_block: OP_MAINNET_FIRST_BLOCK,
         }
     }
 
@@ -283,6 +458,7 @@ impl Contract {
         Contract {
             address: address!("00000000Fc1237824fb747aBDE0FF18990E59b7e"),
             kind: ContractKind::KeyRegistry,
+            start_block: OP_MAINNET_FIRST_BLOCK,
         }
     }
 
@@ -290,6 +466,7 @@ impl Contract {
         Contract {
             address: address!("00000000Fc6c5F01Fc30151999387Bb99A9f489b"),
             kind: ContractKind::IdRegistry,
+            start_block: OP_MAINNET_FIRST_BLOCK,
         }
     }
 
@@ -297,6 +474,7 @@ impl Contract {
         Contract {
             address: address!("0x00000000fc84484d585C3cF48d213424DFDE43FD"),
             kind: ContractKind::TierRegistry,
+            start_block: BASE_MAINNET_FIRST_BLOCK,
         }
     }
 
@@ -354,7 +532,7 @@ impl Contract {
 }
 
 pub struct Subscriber {
-    provider: RootProvider<Http<Client>>,
+    providers: ProviderPool,
     mempool_tx: mpsc::Sender<MempoolRequest>,
     start_block_number: Option<u64>,
     stop_block_number: Option<u64>,
@@ -362,14 +540,17 @@ pub struct Subscriber {
     local_state_store: LocalStateStore,
     onchain_events_request_rx: broadcast::Receiver<OnchainEventsRequest>,
     chain: node_local_state::Chain,
-    override_tier_registry_address: Option<String>,
+    contracts: Vec<Contract>,
+    confirmation_depth: u64,
+    pending_events: PendingEvents,
+    fetched_ranges: FetchedRanges,
+    // Highest block whose logs have all been fetched, the finalized block never moves past it
+    latest_seen_block: u64,
 }
 
-// TODO(aditi): Wait for 1 confirmation before "committing" an onchain event.
 impl Subscriber {
     pub fn new(
         config: &Config,
-        chain: node_local_state::Chain,
         mempool_tx: mpsc::Sender<MempoolRequest>,
         statsd_client: StatsdClientWrapper,
         local_state_store: LocalStateStore,
@@ -378,52 +559,84 @@ impl Subscriber {
         if config.rpc_url.is_empty() {
             return Err(SubscribeError::EmptyRpcUrl);
         }
-        let url = config.rpc_url.parse()?;
-        let provider = ProviderBuilder::new().on_http(url);
+        let chain = node_local_state::Chain(config.chain_id.ok_or(SubscribeError::MissingChainId)?);
+        let mut rpc_urls = vec![config.rpc_url.clone()];
+        rpc_urls.extend(config.fallback_rpc_urls.iter().cloned());
+        let providers = ProviderPool::new(
+            &rpc_urls,
+            config.logs_quorum.unwrap_or(1),
+            chain,
+            statsd_client.clone(),
+        )?;
+        let contracts = if config.contracts.is_empty() {
+            let mut contracts = Contract::mainnet_contracts(chain);
+            if let Some(tier_registry_address) = &config.override_tier_registry_address {
+                for contract in contracts.iter_mut() {
+                    if contract.kind == ContractKind::TierRegistry {
+                        contract.address =
+                            Address::from_str(tier_registry_address).map_err(|_| {
+                                SubscribeError::InvalidContractAddress(
+                                    tier_registry_address.clone(),
+                                )
+                            })?;
+                    }
+                }
+            }
+            contracts
+        } else {
+            config
+                .contracts
+                .iter()
+                .map(Contract::from_config)
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        if contracts.is_empty() {
+            return Err(SubscribeError::NoContracts(chain.0));
+        }
+        let first_block = Self::first_block(&contracts);
         Ok(Subscriber {
             local_state_store,
-            provider,
+            providers,
             mempool_tx,
             start_block_number: config
                 .start_block_number
-                .map(|start_block| start_block.max(Self::first_block(chain))),
+                .map(|start_block| start_block.max(first_block)),
             stop_block_number: config.stop_block_number,
             statsd_client,
             onchain_events_request_rx,
             chain,
-            override_tier_registry_address: config.override_tier_registry_address.clone(),
+            contracts,
+            confirmation_depth: config
+                .confirmation_depth
+                .unwrap_or(DEFAULT_CONFIRMATION_DEPTH),
+            pending_events: PendingEvents::new(),
+            fetched_ranges: FetchedRanges::new(),
+            latest_seen_block: 0,
         })
     }
 
-    fn contracts(&self) -> Vec<Contract> {
-        match self.chain {
-            node_local_state::Chain::Optimism => vec![
-                Contract::storage_registry(),
-                Contract::key_registry(),
-                Contract::id_registry(),
-            ],
-            node_local_state::Chain::Base => vec![match &self.override_tier_registry_address {
-                None => Contract::tier_registry(),
-                Some(tier_registry_address) => Contract {
-                    address: Address::from_str(&tier_registry_address).unwrap(),
-                    kind: ContractKind::TierRegistry,
-                },
-            }],
-        }
+    pub fn chain(&self) -> node_local_state::Chain {
+        self.chain
     }
 
-    fn first_block(chain: node_local_state::Chain) -> u64 {
-        match chain {
-            node_local_state::Chain::Optimism => OP_MAINNET_FIRST_BLOCK,
-            node_local_state::Chain::Base => BASE_MAINNET_FIRST_BLOCK,
-        }
+    fn contract_addresses(&self) -> Vec<Address> {
+        self.contracts()
+            .iter()
+            .map(|contract| contract.address)
+            .collect()
     }
 
-    fn chain_id(chain: node_local_state::Chain) -> u32 {
-        match chain {
-            node_local_state::Chain::Optimism => OP_MAINNET_CHAIN_ID,
-            node_local_state::Chain::Base => BASE_MAINNET_CHAIN_ID,
-        }
+    fn contracts(&self) -> Vec<Contract> {
+        self.contracts.clone()
+    }
+
+    /** The earliest block any of the contracts has logs in */
+    fn first_block(contracts: &[Contract]) -> u64 {
+        contracts
+            .iter()
+            .map(|contract| contract.start_block)
+            .min()
+            .unwrap_or(0)
     }
 
     fn count(&self, key: &str, value: i64) {
@@ -456,11 +669,28 @@ impl Subscriber {
             log_index,
             tx_index,
             r#type: event_type as i32,
-            chain_id: Self::chain_id(self.chain),
+            chain_id: self.chain.0,
             version: 0,
             body: Some(event_body),
             transaction_hash: transaction_hash.to_vec(),
         };
+        if self
+            .pending_events
+            .add(block_number as u64, block_hash, event)
+        {
+            warn!(
+                block_number,
+                block_hash = hex::encode(block_hash),
+                chain = self.chain.to_string(),
+                "Detected reorg, dropped pending events from orphaned block"
+            );
+            self.count("num_reorged_blocks", 1);
+        }
+    }
+
+    async fn submit_onchain_event(&mut self, event: OnChainEvent) {
+        let fid = event.fid;
+        let event_type = event.r#type();
         info!(
             fid,
             event_type = event_type.as_str_name(),
@@ -499,7 +729,7 @@ impl Subscriber {
         }
         self.gauge(
             &format!("latest_block_number_on_{}", self.chain.to_string()),
-            block_number as u64,
+            event.block_number as u64,
         );
         if let Err(err) = self
             .mempool_tx
@@ -507,6 +737,7 @@ impl Subscriber {
                 MempoolMessage::ValidatorMessage(ValidatorMessage {
                     on_chain_event: Some(event.clone()),
                     fname_transfer: None,
+                    storage_prune: None,
                 }),
                 MempoolSource::Local,
                 None,
@@ -524,31 +755,269 @@ impl Subscriber {
         }
     }
 
-    fn record_block_number(&self, block_number: u64) {
-        let latest_block_in_db = self.latest_block_in_db();
-        if block_number as u64 > latest_block_in_db {
-            match self
-                .local_state_store
-                .set_latest_block_number(self.chain.clone(), block_number)
-            {
+    fn finalized_block(&self) -> Option<FinalizedBlock> {
+        match self.local_state_store.get_finalized_block(self.chain) {
+            Ok(finalized_block) => finalized_block,
+            Err(err) => {
+                error!(
+                    err = err.to_string(),
+                    chain = self.chain.to_string(),
+                    "Unable to retrieve finalized block",
+                );
+                None
+            }
+        }
+    }
+
+    fn finalized_block_number(&self) -> u64 {
+        self.finalized_block()
+            .map_or(0, |finalized_block| finalized_block.block_number)
+    }
+
+    fn record_finalized_block(&self, block_number: u64, block_hash: FixedBytes<32>) {
+        if let Err(err) = self.local_state_store.set_finalized_block(
+            self.chain,
+            FinalizedBlock {
+                block_number,
+                block_hash: block_hash.to_vec(),
+            },
+        ) {
+            error!(
+                block_number,
+                err = err.to_string(),
+                chain = self.chain.to_string(),
+                "Unable to store finalized block",
+            );
+        }
+    }
+
+    async fn get_block_hash(
+        &mut self,
+        block_number: u64,
+    ) -> Result<FixedBytes<32>, SubscribeError> {
+        let mut retry_count = 0;
+        loop {
+            // With a quorum configured, reorgs are only detected on hashes enough providers agree on
+            match self.providers.get_block_hash(block_number).await {
+                Ok(Some(block_hash)) => {
+                    return Ok(block_hash);
+                }
+                Ok(None) => {
+                    return Err(SubscribeError::UnableToFindBlockByNumber);
+                }
                 Err(err) => {
+                    retry_count += 1;
+
+                    if retry_count > 5 {
+                        return Err(err);
+                    }
+
                     error!(
-                        block_number,
-                        err = err.to_string(),
                         chain = self.chain.to_string(),
-                        "Unable to store last block number",
+                        "Error getting block hash for block {}: {}. Retry {} in {} seconds",
+                        block_number,
+                        err,
+                        retry_count,
+                        RETRY_TIMEOUT_SECONDS
                     );
+
+                    tokio::time::sleep(tokio::time::Duration::from_secs(RETRY_TIMEOUT_SECONDS))
+                        .await;
+                }
+            }
+        }
+    }
+
+    /**
+     * Submits pending events from blocks that have reached the confirmation depth. Each fetched
+     * range is checked by the hash of its last block first, so a reorg is caught even if it only
+     * touched blocks where no events were seen. Ranges that were reorged out are fetched again
+     * from the canonical chain.
+     */
+    async fn submit_confirmed_events(
+        &mut self,
+        latest_block_on_chain: u64,
+    ) -> Result<(), SubscribeError> {
+        let confirmed_block = latest_block_on_chain
+            .saturating_sub(self.confirmation_depth)
+            .min(self.latest_seen_block);
+        let mut verified_block = None;
+        while let Some(range) = self.fetched_ranges.next_confirmed(confirmed_block) {
+            let canonical_hash = self.get_block_hash(range.stop_block).await?;
+            if canonical_hash == range.block_hash {
+                self.fetched_ranges.remove(range.stop_block);
+                verified_block = Some((range.stop_block, range.block_hash));
+            } else {
+                warn!(
+                    start_block = range.start_block,
+                    stop_block = range.stop_block,
+                    block_hash = hex::encode(range.block_hash),
+                    canonical_hash = hex::encode(canonical_hash),
+                    chain = self.chain.to_string(),
+                    "Fetched range was reorged out, refetching logs"
+                );
+                self.count("num_reorged_blocks", 1);
+                self.refetch_from(range.start_block).await?;
+            }
+        }
+
+        // Events from before the finalized block were fetched again on request
+        let finalized_block_number = self.finalized_block_number();
+        let submit_until = verified_block
+            .map_or(finalized_block_number, |(block_number, _)| block_number)
+            .max(finalized_block_number);
+        while let Some((block_number, block_hash)) =
+            self.pending_events.next_confirmed(submit_until)
+        {
+            let canonical_hash = self.get_block_hash(block_number).await?;
+            if canonical_hash == block_hash {
+                if let Some(block) = self.pending_events.take(block_number) {
+                    for event in block.events {
+                        self.submit_onchain_event(event).await;
+                    }
                 }
-                _ => {}
+            } else {
+                warn!(
+                    block_number,
+                    block_hash = hex::encode(block_hash),
+                    canonical_hash = hex::encode(canonical_hash),
+                    latest_seen_block = self.latest_seen_block,
+                    chain = self.chain.to_string(),
+                    "Pending block was reorged out, refetching logs"
+                );
+                self.count("num_reorged_blocks", 1);
+                // The refetched ranges are checked again on the next call before anything past
+                // this block is finalized
+                self.refetch_from(block_number).await?;
+                verified_block = None;
+                break;
+            }
+        }
+
+        if let Some((block_number, block_hash)) = verified_block {
+            if block_number > finalized_block_number {
+                self.record_finalized_block(block_number, block_hash);
             }
+        }
+        self.gauge(
+            &format!("pending_events_on_{}", self.chain.to_string()),
+            self.pending_events.num_events() as u64,
+        );
+        Ok(())
+    }
+
+    /**
+     * Reads the hashes a range is checked by once its logs are fetched. They're read before the
+     * logs, so if the range is reorged in between, its hash no longer matches once it's confirmed
+     * and it's fetched again. Blocks that are confirmed once the chain reaches [stop_block] get a
+     * range of their own, so their events don't wait on the rest.
+     */
+    async fn ranges_to_fetch(
+        &mut self,
+        start_block: u64,
+        stop_block: u64,
+    ) -> Result<Vec<FetchedRange>, SubscribeError> {
+        let mut stop_blocks = vec![];
+        let confirmed_block = stop_block.saturating_sub(self.confirmation_depth);
+        if confirmed_block >= start_block && confirmed_block < stop_block {
+            stop_blocks.push(confirmed_block);
+        }
+        stop_blocks.push(stop_block);
+
+        let mut ranges = vec![];
+        let mut from_block = start_block;
+        for to_block in stop_blocks {
+            ranges.push(FetchedRange {
+                start_block: from_block,
+                stop_block: to_block,
+                block_hash: self.get_block_hash(to_block).await?,
+            });
+            from_block = to_block + 1;
+        }
+        Ok(ranges)
+    }
+
+    fn add_fetched_ranges(&mut self, ranges: Vec<FetchedRange>) {
+        for range in ranges {
+            self.latest_seen_block = self.latest_seen_block.max(range.stop_block);
+            self.fetched_ranges.add(range);
+        }
+    }
+
+    /** Fetches the logs of every contract in the range. Fails rather than covering part of it, so callers can move past [stop_block]. */
+    async fn fetch_range(
+        &mut self,
+        start_block: u64,
+        stop_block: u64,
+    ) -> Result<(), SubscribeError> {
+        let ranges = self.ranges_to_fetch(start_block, stop_block).await?;
+        let filter = Filter::new()
+            .address(self.contract_addresses())
+            .from_block(start_block)
+            .to_block(stop_block);
+        self.get_logs_with_retry(filter, "all").await?;
+        self.add_fetched_ranges(ranges);
+        Ok(())
+    }
+
+    /** Drops everything fetched from [start_block] on and fetches it again over the same ranges */
+    async fn refetch_from(&mut self, start_block: u64) -> Result<(), SubscribeError> {
+        let stop_block = self.latest_seen_block.max(start_block);
+        self.pending_events.remove_range(start_block, stop_block);
+        let mut stop_blocks: Vec<u64> = self
+            .fetched_ranges
+            .remove_from(start_block)
+            .into_iter()
+            .map(|range| range.stop_block)
+            .collect();
+        if stop_blocks.last().is_none_or(|last| *last < stop_block) {
+            stop_blocks.push(stop_block);
+        }
+        let mut from_block = start_block;
+        for to_block in stop_blocks {
+            self.fetch_range(from_block, to_block).await?;
+            from_block = to_block + 1;
+        }
+        Ok(())
+    }
+
+    /** Block to resume syncing from. If the finalized block has since been reorged out, back off by the confirmation depth so the canonical logs are fetched again. */
+    async fn resume_block(&mut self) -> Result<u64, SubscribeError> {
+        let finalized_block = match self.finalized_block() {
+            None => return Ok(0),
+            Some(finalized_block) => finalized_block,
         };
+        if finalized_block.block_hash.is_empty() {
+            // Written before block hashes were tracked
+            return Ok(finalized_block.block_number);
+        }
+        let canonical_hash = self.get_block_hash(finalized_block.block_number).await?;
+        if canonical_hash.as_slice() == finalized_block.block_hash.as_slice() {
+            Ok(finalized_block.block_number)
+        } else {
+            error!(
+                block_number = finalized_block.block_number,
+                block_hash = hex::encode(&finalized_block.block_hash),
+                canonical_hash = hex::encode(canonical_hash),
+                confirmation_depth = self.confirmation_depth,
+                chain = self.chain.to_string(),
+                "Finalized block is no longer canonical, consider raising the confirmation depth"
+            );
+            Ok(finalized_block
+                .block_number
+                .saturating_sub(self.confirmation_depth))
+        }
     }
 
-    async fn get_block_timestamp(&self, block_hash: FixedBytes<32>) -> Result<u64, SubscribeError> {
+    async fn get_block_timestamp(
+        &mut self,
+        block_hash: FixedBytes<32>,
+    ) -> Result<u64, SubscribeError> {
         let mut retry_count = 0;
         loop {
             match self
-                .provider
+                .providers
+                .active()
                 .get_block_by_hash(block_hash, alloy_rpc_types::BlockTransactionsKind::Hashes)
                 .await
             {
@@ -560,6 +1029,7 @@ impl Subscriber {
                 }
                 Err(err) => {
                     retry_count += 1;
+                    self.providers.report_failure();
 
                     if retry_count > 5 {
                         return Err(err.into());
@@ -780,7 +1250,7 @@ impl Subscriber {
     }
 
     async fn get_logs(&mut self, filter: &Filter, event_kind: &str) -> Result<(), SubscribeError> {
-        let events = self.provider.get_logs(filter).await?;
+        let events = self.providers.get_logs(filter).await?;
         for event in events {
             let result = self.process_log(&event).await;
             match result {
@@ -842,21 +1312,27 @@ impl Subscriber {
             chain = self.chain.to_string(),
             "Starting historical sync"
         );
-        let batch_size = 1000;
+        let batch_size = LOGS_BATCH_SIZE;
         let mut start_block = initial_start_block;
         loop {
             let stop_block = final_stop_block.min(start_block + batch_size);
 
+            let ranges = self.ranges_to_fetch(start_block, stop_block).await?;
             for contract in self.contracts() {
+                if stop_block < contract.start_block {
+                    continue;
+                }
                 let filter = Filter::new()
                     .address(contract.address)
-                    .from_block(start_block)
+                    .from_block(start_block.max(contract.start_block))
                     .to_block(stop_block);
                 self.get_logs_with_retry(filter, contract.event_kind())
                     .await?;
             }
 
-            self.record_block_number(stop_block);
+            self.add_fetched_ranges(ranges);
+            let latest_block_on_chain = self.latest_block_on_chain().await?;
+            self.submit_confirmed_events(latest_block_on_chain).await?;
             start_block += batch_size;
 
             if start_block > final_stop_block {
@@ -871,44 +1347,17 @@ impl Subscriber {
         }
     }
 
-    fn latest_block_in_db(&self) -> u64 {
-        match self
-            .local_state_store
-            .get_latest_block_number(self.chain.clone())
-        {
-            Ok(number) => number.unwrap_or(0),
-            Err(err) => {
-                error!(
-                    err = err.to_string(),
-                    chain = self.chain.to_string(),
-                    "Unable to retrieve last block number",
-                );
-                0
-            }
-        }
-    }
-
     async fn latest_block_on_chain(&mut self) -> Result<u64, SubscribeError> {
         let mut retry_count = 0;
         loop {
-            match self
-                .provider
-                .get_block_by_number(
-                    alloy_rpc_types::BlockNumberOrTag::Latest,
-                    alloy_rpc_types::BlockTransactionsKind::Hashes,
-                )
-                .await
-            {
-                Ok(block) => {
-                    return Ok(block
-                        .ok_or(SubscribeError::LogMissingBlockNumber)?
-                        .header
-                        .number);
+            match self.providers.get_block_number().await {
+                Ok(block_number) => {
+                    return Ok(block_number);
                 }
                 Err(err) => {
                     retry_count += 1;
                     if retry_count > 5 {
-                        return Err(err.into());
+                        return Err(err);
                     }
 
                     error!(
@@ -932,22 +1381,14 @@ impl Subscriber {
             chain = self.chain.to_string(),
             "Starting live sync"
         );
-        let contract_addresses: Vec<Address> = self
-            .contracts()
-            .iter()
-            .map(|contract| contract.address)
-            .collect();
-        let filter = Filter::new()
-            .address(contract_addresses)
-            .from_block(start_block_number);
-
-        let filter = match self.stop_block_number {
-            None => filter,
-            Some(stop_block) => filter.to_block(stop_block),
-        };
-
-        let subscription = self.provider.watch_logs(&filter).await?;
-        let mut stream = subscription.into_stream();
+        // Resume after blocks already fetched if live sync is restarted
+        let mut next_block = start_block_number.max(self.latest_seen_block + 1);
+        let mut poll_interval = tokio::time::interval(tokio::time::Duration::from_secs(
+            CONFIRMATION_POLL_INTERVAL_SECONDS,
+        ));
+        let mut health_check_interval = tokio::time::interval(tokio::time::Duration::from_secs(
+            HEALTH_CHECK_INTERVAL_SECONDS,
+        ));
         loop {
             tokio::select! {
                  biased;
@@ -971,41 +1412,31 @@ impl Subscriber {
                                     }
 
 
+                                },
+                                OnchainEventsRequest::FetchFidEvents{..} => {
+                                    // Served by a separate subscriber, see [Subscriber::serve_fid_event_requests]
                                 }
                             }
                         }
                     }
                  }
-                 events = stream.next() => {
-                     match events {
-                         None => {
-                            // We want to trigger a retry here
-                             break;
-                         },
-                         Some(events) => {
-                             for event in events {
-                                 let result = self.process_log(&event).await;
-                                 match result {
-                                     Err(err) => {
-                                         error!(
-                                             "Error processing onchain event. Error: {:#?}. Event: {:#?}",
-                                             err, event,
-                                         )
-                                     }
-                                     Ok(()) => match event.block_number {
-                                         None => {}
-                                         Some(block_number) => {
-                                             self.record_block_number(block_number);
-                                         }
-                                     },
-                                 }
-                             }
-                         }
+                 _ = health_check_interval.tick() => {
+                     self.providers.check_health().await;
+                 }
+                 _ = poll_interval.tick() => {
+                     let latest_block_on_chain = self.latest_block_on_chain().await?;
+                     let stop_block = self
+                         .stop_block_number
+                         .map_or(latest_block_on_chain, |stop_block| stop_block.min(latest_block_on_chain))
+                         .min(next_block + LOGS_BATCH_SIZE - 1);
+                     if next_block <= stop_block {
+                         self.fetch_range(next_block, stop_block).await?;
+                         next_block = stop_block + 1;
                      }
+                     self.submit_confirmed_events(latest_block_on_chain).await?;
                  }
             }
         }
-        Ok(())
     }
 
     pub async fn retry_fid(&mut self, fid: u64) -> Result<(), SubscribeError> {
@@ -1015,13 +1446,82 @@ impl Subscriber {
             "Retrying onchain events for fid"
         );
         for contract in self.contracts() {
-            for retry_filter in contract.retry_filters(fid, Self::first_block(self.chain)) {
+            for retry_filter in contract.retry_filters(fid, contract.start_block) {
                 self.get_logs_with_retry(retry_filter, contract.event_kind())
                     .await?;
             }
         }
 
-        Ok(())
+        let latest_block_on_chain = self.latest_block_on_chain().await?;
+        self.submit_confirmed_events(latest_block_on_chain).await
+    }
+
+    /**
+     * Answers [OnchainEventsRequest::FetchFidEvents] until the request channel closes. Scanning a
+     * fid's history can take a while, so this runs on its own subscriber and providers instead of
+     * the one ingesting live events.
+     */
+    pub async fn serve_fid_event_requests(&mut self) {
+        loop {
+            match self.onchain_events_request_rx.recv().await {
+                Ok(OnchainEventsRequest::FetchFidEvents { fid, response_tx }) => {
+                    match self.fetch_confirmed_fid_events(fid).await {
+                        Ok(events) => {
+                            let _ = response_tx.send(events).await;
+                        }
+                        Err(err) => {
+                            error!(
+                                fid,
+                                chain = self.chain.to_string(),
+                                "Unable to fetch events for fid: {}",
+                                err.to_string()
+                            )
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(num_skipped)) => {
+                    warn!(
+                        num_skipped,
+                        chain = self.chain.to_string(),
+                        "Dropped fid event requests"
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /** Fetches the fid's events that have reached the confirmation depth, leaving events pending submission untouched */
+    pub async fn fetch_confirmed_fid_events(
+        &mut self,
+        fid: u64,
+    ) -> Result<Vec<OnChainEvent>, SubscribeError> {
+        let pending_events = std::mem::replace(&mut self.pending_events, PendingEvents::new());
+        let mut result = Ok(());
+        'contracts: for contract in self.contracts() {
+            for filter in contract.retry_filters(fid, contract.start_block) {
+                // Not retried, callers can ask again
+                result = self.get_logs(&filter, contract.event_kind()).await;
+                if result.is_err() {
+                    break 'contracts;
+                }
+            }
+        }
+        let mut fetched = std::mem::replace(&mut self.pending_events, pending_events);
+        result?;
+
+        let confirmed_block = self
+            .latest_block_on_chain()
+            .await?
+            .saturating_sub(self.confirmation_depth);
+        let mut events = vec![];
+        while let Some((block_number, _)) = fetched.next_confirmed(confirmed_block) {
+            if let Some(block) = fetched.take(block_number) {
+                events.extend(block.events);
+            }
+        }
+        Ok(events)
     }
 
     pub async fn retry_block_range(
@@ -1036,26 +1536,23 @@ impl Subscriber {
             "Retrying onchain events in range"
         );
         let filter = Filter::new()
-            .address(
-                self.contracts()
-                    .iter()
-                    .map(|contract| contract.address)
-                    .collect::<Vec<Address>>(),
-            )
+            .address(self.contract_addresses())
             .from_block(start_block_number)
             .to_block(stop_block_number);
         self.get_logs_with_retry(filter, "all").await?;
-        Ok(())
+        let latest_block_on_chain = self.latest_block_on_chain().await?;
+        self.submit_confirmed_events(latest_block_on_chain).await
     }
 
     pub async fn run(&mut self) -> Result<(), SubscribeError> {
         let latest_block_on_chain = self.latest_block_on_chain().await?;
-        let latest_block_in_db = self.latest_block_in_db();
+        let latest_block_in_db = self.resume_block().await?;
         info!(
             start_block_number = self.start_block_number,
             stop_block_numer = self.stop_block_number,
             latest_block_on_chain,
             latest_block_in_db,
+            confirmation_depth = self.confirmation_depth,
             chain = self.chain.to_string(),
             "Starting l2 events subscription"
         );
@@ -1063,7 +1560,7 @@ impl Subscriber {
         match self.start_block_number {
             None => {
                 // By default, start from the first block or the latest block in the db. Whichever is higher
-                live_sync_block = Some(Self::first_block(self.chain).max(latest_block_in_db));
+                live_sync_block = Some(Self::first_block(&self.contracts).max(latest_block_in_db));
             }
             Some(start_block_number) => {
                 let historical_sync_start_block = latest_block_in_db.max(start_block_number);
@@ -1136,13 +1633,18 @@ mod tests {
             l1_rpc_url: format!("https://eth-mainnet.g.alchemy.com/v2/{}", api_key).to_string(),
             base_onchain_events: onchain_events::Config {
                 rpc_url: format!("https://base-mainnet.g.alchemy.com/v2/{}", api_key).to_string(),
-                start_block_number: None,
-                stop_block_number: None,
-                override_tier_registry_address: None,
+                ..Default::default()
             },
             ..Default::default()
         };
-        let chain_clients = ChainClients::new(&app_config);
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = crate::storage::db::RocksDB::new(dir.path().join("a.db").to_str().unwrap());
+        db.open().unwrap();
+        let chain_clients = ChainClients::new(
+            &app_config,
+            LocalStateStore::new(std::sync::Arc::new(db)),
+            crate::storage::store::test_helper::statsd_client(),
+        );
         assert!(chain_clients.for_chain(Chain::EthMainnet).is_ok());
         assert!(chain_clients.for_chain(Chain::BaseMainnet).is_ok());
 
@@ -0,0 +1,366 @@
+use super::SubscribeError;
+use crate::storage::store::node_local_state::Chain;
+use crate::utils::statsd_wrapper::StatsdClientWrapper;
+use alloy_primitives::FixedBytes;
+use alloy_provider::{Provider, ProviderBuilder, RootProvider};
+use alloy_rpc_types::{BlockTransactionsKind, Filter, FilterBlockOption, Log};
+use alloy_transport_http::{Client, Http};
+use futures_util::future::join_all;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+// How long a provider that failed a request or fell behind is skipped for
+const PROVIDER_COOLDOWN: Duration = Duration::from_secs(60);
+// Providers whose head is further than this behind the most advanced provider are considered unhealthy
+const MAX_PROVIDER_LAG_BLOCKS: u64 = 20;
+
+struct RpcProvider {
+    url: String,
+    provider: RootProvider<Http<Client>>,
+    unhealthy_until: Option<Instant>,
+}
+
+impl RpcProvider {
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.unhealthy_until.is_none_or(|until| until <= now)
+    }
+}
+
+/** The RPC endpoints for a chain, in priority order. Requests go to the first healthy provider, and logs can optionally be cross-checked across providers before they're used. */
+pub struct ProviderPool {
+    providers: Vec<RpcProvider>,
+    active: usize,
+    quorum: usize,
+    chain: Chain,
+    statsd_client: StatsdClientWrapper,
+}
+
+impl ProviderPool {
+    pub fn new(
+        rpc_urls: &[String],
+        quorum: usize,
+        chain: Chain,
+        statsd_client: StatsdClientWrapper,
+    ) -> Result<ProviderPool, SubscribeError> {
+        if rpc_urls.is_empty() || rpc_urls.iter().any(|url| url.is_empty()) {
+            return Err(SubscribeError::EmptyRpcUrl);
+        }
+        if quorum > rpc_urls.len() {
+            return Err(SubscribeError::QuorumExceedsProviders {
+                quorum,
+                num_providers: rpc_urls.len(),
+            });
+        }
+        let mut providers = vec![];
+        for url in rpc_urls {
+            providers.push(RpcProvider {
+                url: url.clone(),
+                provider: ProviderBuilder::new().on_http(url.parse()?),
+                unhealthy_until: None,
+            });
+        }
+        Ok(ProviderPool {
+            providers,
+            active: 0,
+            quorum,
+            chain,
+            statsd_client,
+        })
+    }
+
+    pub fn active(&self) -> &RootProvider<Http<Client>> {
+        &self.providers[self.active].provider
+    }
+
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    pub fn num_healthy(&self) -> usize {
+        let now = Instant::now();
+        self.providers
+            .iter()
+            .filter(|provider| provider.is_healthy(now))
+            .count()
+    }
+
+    fn metric(&self, key: &str) -> String {
+        format!("onchain_events.{}_on_{}", key, self.chain)
+    }
+
+    fn mark_unhealthy(&mut self, index: usize) {
+        self.providers[index].unhealthy_until = Some(Instant::now() + PROVIDER_COOLDOWN);
+    }
+
+    /** Switches to the highest priority healthy provider. If none are healthy, picks the one that recovers soonest. */
+    fn select_active(&mut self) {
+        let now = Instant::now();
+        let next = self
+            .providers
+            .iter()
+            .position(|provider| provider.is_healthy(now))
+            .unwrap_or_else(|| {
+                (0..self.providers.len())
+                    .min_by_key(|index| self.providers[*index].unhealthy_until)
+                    .unwrap_or(0)
+            });
+        if next != self.active {
+            warn!(
+                from = self.providers[self.active].url,
+                to = self.providers[next].url,
+                chain = self.chain.to_string(),
+                "Switching rpc provider"
+            );
+            self.statsd_client.count(&self.metric("rpc_failovers"), 1);
+            self.active = next;
+        }
+        self.statsd_client.gauge(
+            &self.metric("unhealthy_rpc_providers"),
+            (self.providers.len() - self.num_healthy()) as u64,
+        );
+    }
+
+    /** Called after a request to the active provider fails, so the next attempt goes elsewhere */
+    pub fn report_failure(&mut self) {
+        self.mark_unhealthy(self.active);
+        self.select_active();
+    }
+
+    /** Polls every provider's head, taking providers that error or lag behind out of rotation */
+    pub async fn check_health(&mut self) {
+        let heads = join_all(
+            self.providers
+                .iter()
+                .map(|provider| provider.provider.get_block_number()),
+        )
+        .await;
+        let best_head = heads
+            .iter()
+            .filter_map(|head| head.as_ref().ok())
+            .max()
+            .copied()
+            .unwrap_or(0);
+        for (index, head) in heads.into_iter().enumerate() {
+            match head {
+                Ok(head) if best_head.saturating_sub(head) <= MAX_PROVIDER_LAG_BLOCKS => {
+                    self.providers[index].unhealthy_until = None;
+                }
+                Ok(head) => {
+                    warn!(
+                        url = self.providers[index].url,
+                        head,
+                        best_head,
+                        chain = self.chain.to_string(),
+                        "Rpc provider is lagging"
+                    );
+                    self.mark_unhealthy(index);
+                }
+                Err(err) => {
+                    warn!(
+                        url = self.providers[index].url,
+                        err = err.to_string(),
+                        chain = self.chain.to_string(),
+                        "Rpc provider health check failed"
+                    );
+                    self.mark_unhealthy(index);
+                }
+            }
+        }
+        self.select_active();
+    }
+
+    /** Drops the responses of providers that errored, taking them out of rotation */
+    fn successful_responses<T, E: std::fmt::Display>(
+        &mut self,
+        results: Vec<Result<T, E>>,
+        request: &str,
+    ) -> Vec<T> {
+        let mut responses = vec![];
+        for (index, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(response) => responses.push(response),
+                Err(err) => {
+                    warn!(
+                        url = self.providers[index].url,
+                        err = err.to_string(),
+                        chain = self.chain.to_string(),
+                        "Unable to get {} from rpc provider",
+                        request
+                    );
+                    self.mark_unhealthy(index);
+                }
+            }
+        }
+        self.select_active();
+        responses
+    }
+
+    /** Returns the response at least [quorum] providers agree on */
+    fn agreed_response<T: PartialEq>(
+        &self,
+        responses: Vec<T>,
+        request: &str,
+    ) -> Result<T, SubscribeError> {
+        let num_responses = responses.len();
+        match select_by_quorum(responses) {
+            Some((response, num_agreeing)) => {
+                if num_agreeing < num_responses {
+                    self.statsd_client.count(
+                        &self.metric(&format!("rpc_{}_disagreements", request)),
+                        (num_responses - num_agreeing) as i64,
+                    );
+                }
+                if num_agreeing >= self.quorum {
+                    Ok(response)
+                } else {
+                    Err(SubscribeError::QuorumNotReached {
+                        num_agreeing,
+                        quorum: self.quorum,
+                    })
+                }
+            }
+            None => Err(SubscribeError::QuorumNotReached {
+                num_agreeing: 0,
+                quorum: self.quorum,
+            }),
+        }
+    }
+
+    /** The latest block of the active provider, or when a quorum is configured, the latest block at least [quorum] providers have reached */
+    pub async fn get_block_number(&mut self) -> Result<u64, SubscribeError> {
+        if self.quorum <= 1 {
+            return match self.active().get_block_number().await {
+                Ok(head) => Ok(head),
+                Err(err) => {
+                    self.report_failure();
+                    Err(err.into())
+                }
+            };
+        }
+
+        let results = join_all(
+            self.providers
+                .iter()
+                .map(|provider| provider.provider.get_block_number()),
+        )
+        .await;
+        let heads = self.successful_responses(results, "block number");
+        let num_heads = heads.len();
+        quorum_head(heads, self.quorum).ok_or(SubscribeError::QuorumNotReached {
+            num_agreeing: num_heads,
+            quorum: self.quorum,
+        })
+    }
+
+    /** The hash of a block from the active provider, or the hash at least [quorum] providers agree on. None if the block doesn't exist. */
+    pub async fn get_block_hash(
+        &mut self,
+        block_number: u64,
+    ) -> Result<Option<FixedBytes<32>>, SubscribeError> {
+        if self.quorum <= 1 {
+            return match self
+                .active()
+                .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
+                .await
+            {
+                Ok(block) => Ok(block.map(|block| block.header.hash)),
+                Err(err) => {
+                    self.report_failure();
+                    Err(err.into())
+                }
+            };
+        }
+
+        let results = join_all(self.providers.iter().map(|provider| {
+            provider
+                .provider
+                .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
+        }))
+        .await;
+        let hashes = self
+            .successful_responses(results, "block")
+            .into_iter()
+            .map(|block| block.map(|block| block.header.hash))
+            .collect();
+        self.agreed_response(hashes, "block_hash")
+    }
+
+    /**
+     * Fetches logs from the active provider, or when a quorum is configured, from every provider,
+     * returning the logs at least [quorum] of them agree on. Quorum requests fail if the range ends
+     * past the quorum's head, so the logs returned always cover the whole range and providers that
+     * are a block or two behind don't cause disagreements.
+     */
+    pub async fn get_logs(&mut self, filter: &Filter) -> Result<Vec<Log>, SubscribeError> {
+        if self.quorum <= 1 {
+            return match self.active().get_logs(filter).await {
+                Ok(logs) => Ok(logs),
+                Err(err) => {
+                    self.report_failure();
+                    Err(err.into())
+                }
+            };
+        }
+
+        let head = self.get_block_number().await?;
+        let filter = bound_to_block(filter, head)?;
+        if filter.get_from_block().is_some_and(|from| from > head) {
+            return Ok(vec![]);
+        }
+        let results = join_all(
+            self.providers
+                .iter()
+                .map(|provider| provider.provider.get_logs(&filter)),
+        )
+        .await;
+        let responses = self
+            .successful_responses(results, "logs")
+            .into_iter()
+            .map(normalize_logs)
+            .collect();
+        self.agreed_response(responses, "logs")
+    }
+}
+
+/** The highest block at least [quorum] of the heads have reached */
+pub fn quorum_head(mut heads: Vec<u64>, quorum: usize) -> Option<u64> {
+    heads.sort_unstable_by(|a, b| b.cmp(a));
+    heads.get(quorum.max(1) - 1).copied()
+}
+
+/** Ends open ended ranges at head, they would otherwise end at each provider's own head. Ranges ending past head are refused rather than cut short, since callers advance to the end of the range they asked for. */
+pub fn bound_to_block(filter: &Filter, head: u64) -> Result<Filter, SubscribeError> {
+    match filter.block_option {
+        FilterBlockOption::Range { to_block, .. } => {
+            match to_block.and_then(|to_block| to_block.as_number()) {
+                Some(to_block) if to_block > head => {
+                    Err(SubscribeError::HeadBelowRange { head, to_block })
+                }
+                Some(_) => Ok(filter.clone()),
+                None => Ok(filter.clone().to_block(head)),
+            }
+        }
+        FilterBlockOption::AtBlockHash(_) => Ok(filter.clone()),
+    }
+}
+
+/** Strips fields providers are free to differ on, so responses can be compared */
+pub fn normalize_logs(mut logs: Vec<Log>) -> Vec<Log> {
+    for log in logs.iter_mut() {
+        log.block_timestamp = None;
+    }
+    logs.sort_by_key(|log| (log.block_number, log.log_index));
+    logs
+}
+
+/** Returns the most common response and how many providers returned it */
+pub fn select_by_quorum<T: PartialEq>(responses: Vec<T>) -> Option<(T, usize)> {
+    let mut counts: Vec<(T, usize)> = vec![];
+    for response in responses {
+        match counts.iter_mut().find(|(logs, _)| *logs == response) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((response, 1)),
+        }
+    }
+    counts.into_iter().max_by_key(|(_, count)| *count)
+}
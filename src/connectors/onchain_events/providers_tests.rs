@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod tests {
+    use crate::connectors::onchain_events::providers::{
+        bound_to_block, normalize_logs, quorum_head, select_by_quorum, ProviderPool,
+    };
+    use crate::connectors::onchain_events::SubscribeError;
+    use crate::storage::store::node_local_state::Chain;
+    use crate::storage::store::test_helper;
+    use alloy_primitives::FixedBytes;
+    use alloy_rpc_types::{Filter, Log};
+
+    fn log(block_number: u64, log_index: u64) -> Log {
+        Log {
+            block_number: Some(block_number),
+            block_hash: Some(FixedBytes::from([block_number as u8; 32])),
+            log_index: Some(log_index),
+            ..Default::default()
+        }
+    }
+
+    fn urls(num_providers: usize) -> Vec<String> {
+        (0..num_providers)
+            .map(|index| format!("http://localhost:{}", 8545 + index))
+            .collect()
+    }
+
+    #[test]
+    fn test_select_by_quorum() {
+        assert!(select_by_quorum::<Vec<Log>>(vec![]).is_none());
+
+        let honest = normalize_logs(vec![log(10, 1), log(10, 0)]);
+        let lying = normalize_logs(vec![log(10, 0)]);
+        let (logs, num_agreeing) =
+            select_by_quorum(vec![honest.clone(), lying.clone(), honest.clone()]).unwrap();
+        assert_eq!(logs, honest);
+        assert_eq!(num_agreeing, 2);
+
+        let (_, num_agreeing) = select_by_quorum(vec![honest, lying]).unwrap();
+        assert_eq!(num_agreeing, 1);
+    }
+
+    #[test]
+    fn test_quorum_head() {
+        // The highest block enough providers have reached, so a provider one block behind still agrees
+        assert_eq!(quorum_head(vec![100, 101, 99], 2), Some(100));
+        assert_eq!(quorum_head(vec![100, 101, 99], 3), Some(99));
+        assert_eq!(quorum_head(vec![100], 2), None);
+    }
+
+    #[test]
+    fn test_bound_to_block() {
+        let open_ended = bound_to_block(&Filter::new().from_block(10), 100).unwrap();
+        assert_eq!(open_ended.get_to_block(), Some(100));
+
+        // Cutting the range short would have the caller skip the blocks past head
+        assert!(matches!(
+            bound_to_block(&Filter::new().from_block(10).to_block(200), 100),
+            Err(SubscribeError::HeadBelowRange {
+                head: 100,
+                to_block: 200
+            })
+        ));
+        assert!(matches!(
+            bound_to_block(&Filter::new().from_block(150).to_block(200), 100),
+            Err(SubscribeError::HeadBelowRange { .. })
+        ));
+
+        let below_head = bound_to_block(&Filter::new().from_block(10).to_block(50), 100).unwrap();
+        assert_eq!(below_head.get_to_block(), Some(50));
+        assert_eq!(below_head.get_from_block(), Some(10));
+    }
+
+    #[test]
+    fn test_normalize_ignores_order_and_timestamps() {
+        let mut with_timestamp = log(10, 0);
+        with_timestamp.block_timestamp = Some(1234);
+        assert_eq!(
+            normalize_logs(vec![log(11, 0), with_timestamp]),
+            normalize_logs(vec![log(10, 0), log(11, 0)])
+        );
+    }
+
+    #[test]
+    fn test_failover() {
+        let mut pool =
+            ProviderPool::new(&urls(3), 1, Chain::OPTIMISM, test_helper::statsd_client()).unwrap();
+        assert_eq!(pool.active_index(), 0);
+        assert_eq!(pool.num_healthy(), 3);
+
+        pool.report_failure();
+        assert_eq!(pool.active_index(), 1);
+        pool.report_failure();
+        assert_eq!(pool.active_index(), 2);
+        assert_eq!(pool.num_healthy(), 1);
+
+        // With every provider down, fall back to the one that failed first
+        pool.report_failure();
+        assert_eq!(pool.active_index(), 0);
+        assert_eq!(pool.num_healthy(), 0);
+    }
+
+    #[test]
+    fn test_invalid_pool_config() {
+        assert!(matches!(
+            ProviderPool::new(&[], 1, Chain::BASE, test_helper::statsd_client()),
+            Err(SubscribeError::EmptyRpcUrl)
+        ));
+        assert!(matches!(
+            ProviderPool::new(&urls(2), 3, Chain::BASE, test_helper::statsd_client()),
+            Err(SubscribeError::QuorumExceedsProviders { .. })
+        ));
+    }
+}
@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use crate::connectors::solana::{
+        parse_squads_multisig, squads_multisig_discriminator, SolanaAccountAuthority,
+    };
+
+    fn multisig_data(threshold: u16, rent_collector: bool, members: &[([u8; 32], u8)]) -> Vec<u8> {
+        let mut data = squads_multisig_discriminator().to_vec();
+        data.extend([1; 32]); // create key
+        data.extend([2; 32]); // config authority
+        data.extend(threshold.to_le_bytes());
+        data.extend(0u32.to_le_bytes()); // time lock
+        data.extend(5u64.to_le_bytes()); // transaction index
+        data.extend(0u64.to_le_bytes()); // stale transaction index
+        if rent_collector {
+            data.push(1);
+            data.extend([3; 32]);
+        } else {
+            data.push(0);
+        }
+        data.push(255); // bump
+        data.extend((members.len() as u32).to_le_bytes());
+        for (key, permissions) in members {
+            data.extend(key);
+            data.push(*permissions);
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_squads_multisig() {
+        let members = [([4; 32], 0b111), ([5; 32], 0b010), ([6; 32], 0b101)];
+        for rent_collector in [false, true] {
+            let authority =
+                parse_squads_multisig(&multisig_data(2, rent_collector, &members)).unwrap();
+            // Members without the vote permission can't sign for the multisig
+            assert_eq!(
+                authority,
+                SolanaAccountAuthority {
+                    threshold: 2,
+                    members: vec![[4; 32], [5; 32]],
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_squads_multisig() {
+        let data = multisig_data(1, false, &[([4; 32], 0b111)]);
+        assert!(parse_squads_multisig(&data[..data.len() - 1]).is_err());
+
+        let mut data = data;
+        data[0] ^= 1;
+        assert!(parse_squads_multisig(&data).is_err());
+    }
+}
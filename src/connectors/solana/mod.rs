@@ -0,0 +1,162 @@
+use crate::core::validations::verification::SQUADS_PROGRAM_ID;
+use async_trait::async_trait;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+#[cfg(test)]
+mod client_tests;
+
+// Members need the vote permission to approve on behalf of a Squads multisig
+const SQUADS_VOTE_PERMISSION: u8 = 1 << 1;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Config {
+    pub rpc_url: String,
+}
+
+#[derive(Error, Debug)]
+pub enum SolanaError {
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+
+    #[error("Invalid rpc response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Account not found")]
+    AccountNotFound,
+
+    #[error("Unsupported account owned by {0}")]
+    UnsupportedAccount(String),
+}
+
+/** The keys allowed to sign for a Solana account, and how many of them must */
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolanaAccountAuthority {
+    pub threshold: u32,
+    pub members: Vec<[u8; 32]>,
+}
+
+#[async_trait]
+pub trait SolanaAPI: Send + Sync {
+    async fn get_account_authority(
+        &self,
+        address: [u8; 32],
+    ) -> Result<SolanaAccountAuthority, SolanaError>;
+}
+
+#[derive(Deserialize, Debug)]
+struct AccountInfoResponse {
+    result: Option<AccountInfoResult>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AccountInfoResult {
+    value: Option<AccountInfo>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AccountInfo {
+    owner: String,
+    // Encoded data followed by its encoding
+    data: (String, String),
+}
+
+pub struct RealSolanaClient {
+    client: reqwest::Client,
+    rpc_url: String,
+}
+
+impl RealSolanaClient {
+    pub fn new(rpc_url: String) -> Self {
+        RealSolanaClient {
+            client: reqwest::Client::new(),
+            rpc_url,
+        }
+    }
+}
+
+#[async_trait]
+impl SolanaAPI for RealSolanaClient {
+    async fn get_account_authority(
+        &self,
+        address: [u8; 32],
+    ) -> Result<SolanaAccountAuthority, SolanaError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getAccountInfo",
+            "params": [
+                bs58::encode(address).into_string(),
+                { "encoding": "base64", "commitment": "finalized" }
+            ],
+        });
+        let response = self
+            .client
+            .post(&self.rpc_url)
+            .json(&request)
+            .send()
+            .await?
+            .json::<AccountInfoResponse>()
+            .await?;
+
+        let account = response
+            .result
+            .ok_or_else(|| SolanaError::InvalidResponse("missing result".to_string()))?
+            .value
+            .ok_or(SolanaError::AccountNotFound)?;
+        if account.owner != SQUADS_PROGRAM_ID {
+            return Err(SolanaError::UnsupportedAccount(account.owner));
+        }
+
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(&account.data.0)
+            .map_err(|e| SolanaError::InvalidResponse(e.to_string()))?;
+        parse_squads_multisig(&data)
+    }
+}
+
+fn squads_multisig_discriminator() -> [u8; 8] {
+    Sha256::digest(b"account:Multisig")[..8].try_into().unwrap()
+}
+
+/** Decodes a Squads v4 multisig account, keeping only the members that can vote */
+pub fn parse_squads_multisig(data: &[u8]) -> Result<SolanaAccountAuthority, SolanaError> {
+    let invalid = |reason: &str| SolanaError::InvalidResponse(reason.to_string());
+    let read = |offset: usize, len: usize| {
+        data.get(offset..offset + len)
+            .ok_or_else(|| invalid("multisig account too short"))
+    };
+
+    if read(0, 8)? != squads_multisig_discriminator() {
+        return Err(invalid("not a multisig account"));
+    }
+
+    // Skip the discriminator, create key and config authority
+    let mut offset = 8 + 32 + 32;
+    let threshold = u16::from_le_bytes(read(offset, 2)?.try_into().unwrap()) as u32;
+    // Skip the threshold, time lock, transaction index and stale transaction index
+    offset += 2 + 4 + 8 + 8;
+    let has_rent_collector = read(offset, 1)?[0] == 1;
+    offset += 1;
+    if has_rent_collector {
+        offset += 32;
+    }
+    // Skip the bump
+    offset += 1;
+
+    let num_members = u32::from_le_bytes(read(offset, 4)?.try_into().unwrap()) as usize;
+    offset += 4;
+    let mut members = vec![];
+    for _ in 0..num_members {
+        let member = read(offset, 33)?;
+        if member[32] & SQUADS_VOTE_PERMISSION != 0 {
+            members.push(member[..32].try_into().unwrap());
+        }
+        offset += 33;
+    }
+
+    Ok(SolanaAccountAuthority { threshold, members })
+}
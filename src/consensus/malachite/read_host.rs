@@ -18,17 +18,28 @@ pub enum ReadHostMsg {
     Started {
         sync: ReadSyncRef,
     },
-    /// Request the earliest block height in the block store
+    /// Request the earliest height served to peers
     GetHistoryMinHeight {
         reply_to: RpcReplyPort<Height>,
     },
 
+    /// Request the earliest stored height, including backfilled values that aren't served
+    GetStoredMinHeight {
+        reply_to: RpcReplyPort<Height>,
+    },
+
     // Consensus has decided on a value
     ProcessDecidedValue {
         value: proto::DecidedValue,
         sync: ReadSyncRef,
     },
 
+    // A value below the earliest stored height was fetched from a peer to backfill history
+    BackfillDecidedValue {
+        value: proto::DecidedValue,
+        sync: ReadSyncRef,
+    },
+
     // Retrieve decided block from the block store
     GetDecidedValue {
         height: Height,
@@ -72,7 +83,10 @@ impl ReadHost {
             }
 
             ReadHostMsg::GetHistoryMinHeight { reply_to } => {
-                // For now until we implement pruning
+                reply_to.send(state.validator.get_served_min_height())?;
+            }
+
+            ReadHostMsg::GetStoredMinHeight { reply_to } => {
                 reply_to.send(state.validator.get_min_height())?;
             }
 
@@ -83,6 +97,12 @@ impl ReadHost {
                 }
             }
 
+            ReadHostMsg::BackfillDecidedValue { value, sync } => {
+                if state.validator.backfill_decided_value(value) {
+                    sync.cast(read_sync::Msg::Backfilled(state.validator.get_min_height()))?
+                }
+            }
+
             ReadHostMsg::GetDecidedValue { height, reply_to } => {
                 let decided_value = state.validator.get_decided_value(height);
                 reply_to.send(decided_value)?;
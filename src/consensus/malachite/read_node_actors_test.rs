@@ -69,6 +69,7 @@ mod tests {
             shard_id,
             test_helper::statsd_client(),
             config,
+            false,
         )
         .await
         .unwrap();
@@ -59,6 +59,9 @@ pub enum Msg {
         Option<RawDecidedValue<SnapchainValidatorContext>>,
     ),
 
+    /// Host stored a backfilled value, the given height is the new earliest stored height
+    Backfilled(Height),
+
     /// A timeout has elapsed
     TimeoutElapsed(TimeoutElapsed<Timeout>),
 }
@@ -81,6 +84,8 @@ impl From<TimeoutElapsed<Timeout>> for Msg {
 pub struct ReadParams {
     pub status_update_interval: Duration,
     pub request_timeout: Duration,
+    /// Fetch values below the earliest stored height from peers (archive mode)
+    pub backfill: bool,
 }
 
 impl Default for ReadParams {
@@ -88,6 +93,7 @@ impl Default for ReadParams {
         Self {
             status_update_interval: Duration::from_secs(5),
             request_timeout: Duration::from_secs(1),
+            backfill: false,
         }
     }
 }
@@ -108,6 +114,10 @@ pub struct State {
     initial_sync_completed: bool,
 
     connected_peers: HashSet<PeerId>,
+
+    /// In-flight request for a value below the earliest stored height. Handled outside the sync
+    /// state machine, which only moves forward.
+    backfill_request: Option<OutboundRequestId>,
 }
 
 impl State {
@@ -195,6 +205,69 @@ impl ReadSync {
         .map_err(|e| e.into())
     }
 
+    fn decode_value_response(
+        value: &RawDecidedValue<SnapchainValidatorContext>,
+    ) -> Result<proto::DecidedValue, prost::DecodeError> {
+        let value_bytes = value.value_bytes.as_ref();
+        let value = if value.certificate.value_id.shard_index == 0 {
+            proto::decided_value::Value::Block(proto::Block::decode(value_bytes)?)
+        } else {
+            proto::decided_value::Value::Shard(proto::ShardChunk::decode(value_bytes)?)
+        };
+        Ok(proto::DecidedValue { value: Some(value) })
+    }
+
+    /// Requests the value just below the earliest stored height from a peer that has it
+    async fn request_backfill(&self, state: &mut State) -> Result<(), ActorProcessingErr> {
+        if !self.params.backfill || state.backfill_request.is_some() {
+            return Ok(());
+        }
+
+        let min_height = ractor::call!(self.host, |reply_to| {
+            ReadHostMsg::GetStoredMinHeight { reply_to }
+        })?;
+        if min_height.block_number <= 1 {
+            return Ok(());
+        }
+        let height = min_height.decrement().unwrap();
+
+        let peer_id = state
+            .sync
+            .peers
+            .values()
+            .find(|status| {
+                state.connected_peers.contains(&status.peer_id)
+                    && status.history_min_height <= height
+                    && status.height >= height
+            })
+            .map(|status| status.peer_id);
+        let Some(peer_id) = peer_id else {
+            debug!(%height, "No peer available to backfill from");
+            return Ok(());
+        };
+
+        debug!(%height, peer = %peer_id, "Send backfill request to peer");
+        let request = Request::ValueRequest(sync::ValueRequest::new(height));
+        let result = ractor::call!(self.gossip, |reply_to| {
+            NetworkMsg::OutgoingRequest(peer_id, request.clone(), reply_to)
+        });
+        match result {
+            Ok(request_id) => {
+                let request_id = OutboundRequestId::new(request_id);
+                state.timers.start_timer(
+                    Timeout::Request(request_id.clone()),
+                    self.params.request_timeout,
+                );
+                state.backfill_request = Some(request_id);
+            }
+            Err(e) => {
+                error!("Failed to send backfill request to gossip layer: {e}");
+            }
+        }
+
+        Ok(())
+    }
+
     async fn handle_effect(
         &self,
         myself: &ActorRef<Msg>,
@@ -283,11 +356,18 @@ impl ReadSync {
                 self.process_input(&myself, state, sync::Input::Tick)
                     .await?;
 
+                self.request_backfill(state).await?;
+
                 if let Some(reply_to) = reply_to {
                     reply_to.send(()).unwrap();
                 }
             }
 
+            Msg::Backfilled(min_height) => {
+                debug!(%min_height, "Backfilled history");
+                self.request_backfill(state).await?;
+            }
+
             Msg::NetworkEvent(NetworkEvent::PeerDisconnected(peer_id)) => {
                 info!(%peer_id, "Disconnected from peer");
 
@@ -340,23 +420,37 @@ impl ReadSync {
                 // Cancel the timer associated with the request for which we just received a response
                 state.timers.cancel(&Timeout::Request(request_id.clone()));
 
+                if state.backfill_request.as_ref() == Some(&request_id) {
+                    state.backfill_request = None;
+                    if let Response::ValueResponse(value_response) = response {
+                        match value_response.value.as_ref() {
+                            Some(value) => match Self::decode_value_response(value) {
+                                Ok(decided_value) => {
+                                    debug!(peer_id = %peer, height = %value.certificate.height, "Received backfill value response");
+                                    self.host.cast(ReadHostMsg::BackfillDecidedValue {
+                                        value: decided_value,
+                                        sync: myself.clone(),
+                                    })?;
+                                }
+                                Err(err) => {
+                                    // Retried on the next tick
+                                    warn!(peer_id = %peer, height = %value.certificate.height, %err, "Dropping malformed backfill value response");
+                                }
+                            },
+                            None => {
+                                debug!(peer_id = %peer, height = %value_response.height, "Peer did not have backfill value");
+                            }
+                        }
+                    }
+                    return Ok(());
+                }
+
                 match response {
                     Response::ValueResponse(value_response) => {
                         let decided_value = value_response.value.as_ref().unwrap();
-                        let value_bytes =
-                            value_response.value.as_ref().unwrap().value_bytes.as_ref();
-                        let value = if decided_value.certificate.value_id.shard_index == 0 {
-                            proto::decided_value::Value::Block(
-                                proto::Block::decode(value_bytes).unwrap(),
-                            )
-                        } else {
-                            proto::decided_value::Value::Shard(
-                                proto::ShardChunk::decode(value_bytes).unwrap(),
-                            )
-                        };
                         debug!(peer_id = %peer, height = %decided_value.certificate.height, "Received sync value response");
                         self.host.cast(ReadHostMsg::ProcessDecidedValue {
-                            value: proto::DecidedValue { value: Some(value) },
+                            value: Self::decode_value_response(decided_value).unwrap(),
                             sync: myself.clone(),
                         })?;
                         self.process_input(
@@ -406,6 +500,12 @@ impl ReadSync {
                 warn!(?timeout, "Timeout elapsed");
 
                 match timeout {
+                    Timeout::Request(request_id)
+                        if state.backfill_request.as_ref() == Some(&request_id) =>
+                    {
+                        // Retried on the next tick
+                        state.backfill_request = None;
+                    }
                     Timeout::Request(request_id) => {
                         if let Some(inflight) = state.inflight.remove(&request_id) {
                             self.process_input(
@@ -458,6 +558,7 @@ impl Actor for ReadSync {
             connected_peers: HashSet::new(),
             ticker,
             initial_sync_completed: false,
+            backfill_request: None,
         })
     }
 
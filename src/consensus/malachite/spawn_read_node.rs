@@ -59,12 +59,14 @@ pub async fn spawn_read_sync_actor(
     network: NetworkRef<SnapchainValidatorContext>,
     host: ReadHostRef,
     config: ValueSyncConfig,
+    backfill: bool,
     registry: &SharedRegistry,
     span: Span,
 ) -> Result<ReadSyncRef, ractor::SpawnErr> {
     let params = ReadParams {
         status_update_interval: config.status_update_interval,
         request_timeout: config.request_timeout,
+        backfill,
     };
 
     let metrics = SyncMetrics::register(registry);
@@ -92,6 +94,7 @@ impl MalachiteReadNodeActors {
         shard_id: u32,
         statsd_client: StatsdClientWrapper,
         config: Config,
+        archive_mode: bool,
     ) -> Result<Self, ractor::SpawnErr> {
         let name = if shard_id == 0 {
             format!("Block")
@@ -112,6 +115,7 @@ impl MalachiteReadNodeActors {
             network_actor.clone(),
             host_actor.clone(),
             sync_config,
+            archive_mode,
             registry,
             span.clone(),
         )
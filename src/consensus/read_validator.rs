@@ -48,6 +48,19 @@ impl ReadValidator {
         }
     }
 
+    /**
+     * The earliest height served to peers. Backfilled shard chunks have no transactions, so peers
+     * syncing from them couldn't execute them.
+     */
+    pub fn get_served_min_height(&self) -> Height {
+        match &self.engine {
+            Engine::BlockEngine(engine) => engine.get_min_height(),
+            Engine::ShardEngine(engine) => engine
+                .backfilled_below()
+                .unwrap_or_else(|| engine.get_min_height()),
+        }
+    }
+
     fn commit_decided_value(&mut self, value: &DecidedValue, height: Height) {
         match &mut self.engine {
             Engine::ShardEngine(shard_engine) => match &value.value {
@@ -236,12 +249,112 @@ impl ReadValidator {
         num_committed_values
     }
 
+    fn get_decided_value_hash(value: &proto::DecidedValue) -> Vec<u8> {
+        match value.value.as_ref().unwrap() {
+            proto::decided_value::Value::Shard(shard_chunk) => shard_chunk.hash.clone(),
+            proto::decided_value::Value::Block(block) => block.hash.clone(),
+        }
+    }
+
+    // The hash the proposer would have computed for the value's header, or None if a block's shard
+    // witness doesn't match what the header commits to. Shard chunk transactions aren't covered
+    fn compute_decided_value_hash(value: &proto::DecidedValue) -> Option<Vec<u8>> {
+        match value.value.as_ref()? {
+            proto::decided_value::Value::Shard(shard_chunk) => {
+                let header = shard_chunk.header.as_ref()?;
+                Some(blake3::hash(&header.encode_to_vec()).as_bytes().to_vec())
+            }
+            proto::decided_value::Value::Block(block) => {
+                let header = block.header.as_ref()?;
+                let witness = block.shard_witness.as_ref()?;
+                let witness_hash = blake3::hash(&witness.encode_to_vec()).as_bytes().to_vec();
+                if witness_hash != header.shard_witnesses_hash {
+                    return None;
+                }
+                Some(blake3::hash(&header.encode_to_vec()).as_bytes().to_vec())
+            }
+        }
+    }
+
+    fn get_parent_hash_at(&self, height: Height) -> Option<Vec<u8>> {
+        match &self.engine {
+            Engine::ShardEngine(engine) => engine
+                .get_shard_chunk_by_height(height)
+                .map(|chunk| chunk.header.unwrap().parent_hash),
+            Engine::BlockEngine(engine) => engine
+                .get_block_by_height(height)
+                .map(|block| block.header.unwrap().parent_hash),
+        }
+    }
+
+    /**
+     * Stores a value from below the earliest stored height without executing it. The value must
+     * have valid signatures and be the parent of the earliest stored value, so history can only be
+     * extended backwards one height at a time. Only the header is verified, so shard chunks are
+     * stored without their transactions.
+     */
+    pub fn backfill_decided_value(&mut self, value: DecidedValue) -> bool {
+        let height = Self::get_decided_value_height(&value);
+        let min_height = self.get_min_height();
+        if height.increment() != min_height {
+            debug!(%height, %min_height, "Dropping backfilled value because it is not directly below the earliest stored height");
+            return false;
+        }
+
+        // The signatures and parent hash only cover the hash, so it has to match the header
+        if Self::compute_decided_value_hash(&value)
+            .is_none_or(|hash| hash != Self::get_decided_value_hash(&value))
+        {
+            error!(%height, "Dropping backfilled value because its hash does not match its contents");
+            return false;
+        }
+
+        if !self.verify_signatures(&value) {
+            error!(%height, "Dropping backfilled value because its signatures are invalid");
+            return false;
+        }
+
+        let Some(child_parent_hash) = self.get_parent_hash_at(min_height) else {
+            warn!(%height, %min_height, "Unable to find earliest stored value to verify backfill");
+            return false;
+        };
+        if Self::get_decided_value_hash(&value) != child_parent_hash {
+            error!(%height, "Dropping backfilled value because its hash does not match the parent hash of the next height");
+            return false;
+        }
+
+        let stored = match (&mut self.engine, &value.value) {
+            (Engine::ShardEngine(engine), Some(proto::decided_value::Value::Shard(chunk))) => {
+                engine.backfill_shard_chunk(chunk)
+            }
+            (Engine::BlockEngine(engine), Some(proto::decided_value::Value::Block(block))) => {
+                engine.backfill_block(block)
+            }
+            _ => {
+                error!(%height, "Dropping backfilled value for the wrong engine");
+                false
+            }
+        };
+        if stored {
+            debug!(%height, "Backfilled decided value");
+            self.statsd_client.count_with_shard(
+                self.shard_id,
+                "read_validator.num_backfilled_values",
+                1,
+            );
+        }
+        stored
+    }
+
     pub fn get_decided_value(
         &mut self,
         height: Height,
     ) -> Option<RawDecidedValue<SnapchainValidatorContext>> {
         match &self.engine {
             Engine::ShardEngine(shard_engine) => {
+                if height < self.get_served_min_height() {
+                    return None;
+                }
                 let shard_chunk = shard_engine.get_shard_chunk_by_height(height);
                 match shard_chunk {
                     Some(chunk) => {
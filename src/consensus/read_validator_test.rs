@@ -14,6 +14,7 @@ mod tests {
         EngineOptions, FID_FOR_TEST,
     };
     use libp2p::identity::ed25519::Keypair;
+    use prost::Message;
     use tokio::sync::mpsc;
 
     async fn setup(
@@ -273,4 +274,79 @@ mod tests {
         let num_processed = process_decided_value(&mut read_validator, &shard_chunk).await;
         assert_eq!(num_processed, 0);
     }
+
+    #[tokio::test]
+    async fn test_backfill_decided_value() {
+        let (mut proposer_engine, mut read_node_engine, mut read_validator, proposer_keypair, _) =
+            setup(0).await;
+        // Chain the chunks together with the hashes a proposer would have computed
+        let mut parent_hash = vec![];
+        let mut shard_chunks = vec![];
+        for _ in 0..3 {
+            let mut shard_chunk =
+                commit_event(&mut proposer_engine, &default_storage_event(FID_FOR_TEST)).await;
+            shard_chunk.header.as_mut().unwrap().parent_hash = parent_hash;
+            shard_chunk.hash = blake3::hash(&shard_chunk.header.as_ref().unwrap().encode_to_vec())
+                .as_bytes()
+                .to_vec();
+            parent_hash = shard_chunk.hash.clone();
+            shard_chunks.push(sign_chunk(&proposer_keypair, shard_chunk).await);
+        }
+        let [shard_chunk1, shard_chunk2, shard_chunk3] = shard_chunks.try_into().unwrap();
+
+        // Simulate a node started from a snapshot of everything from height 3
+        read_node_engine
+            .get_stores()
+            .shard_store
+            .put_shard_chunk(&shard_chunk3)
+            .unwrap();
+        assert_eq!(read_validator.get_min_height().block_number, 3);
+
+        let decided_value = |shard_chunk: &ShardChunk| proto::DecidedValue {
+            value: Some(proto::decided_value::Value::Shard(shard_chunk.clone())),
+        };
+
+        // Values must extend history one height at a time
+        assert!(!read_validator.backfill_decided_value(decided_value(&shard_chunk1)));
+        assert!(!read_validator.backfill_decided_value(decided_value(&shard_chunk3)));
+
+        // Values must be the parent of the earliest stored value
+        let mut wrong_parent = shard_chunk2.clone();
+        wrong_parent.header.as_mut().unwrap().parent_hash = vec![2; 32];
+        wrong_parent.hash = blake3::hash(&wrong_parent.header.as_ref().unwrap().encode_to_vec())
+            .as_bytes()
+            .to_vec();
+        let wrong_parent = sign_chunk(&proposer_keypair, wrong_parent).await;
+        assert!(!read_validator.backfill_decided_value(decided_value(&wrong_parent)));
+
+        // The hash must match the contents it was signed for
+        let mut tampered = shard_chunk2.clone();
+        tampered.header.as_mut().unwrap().shard_root = vec![1; 32];
+        assert!(!read_validator.backfill_decided_value(decided_value(&tampered)));
+
+        assert!(read_validator.backfill_decided_value(decided_value(&shard_chunk2)));
+        assert!(read_validator.backfill_decided_value(decided_value(&shard_chunk1)));
+        assert_eq!(read_validator.get_min_height().block_number, 1);
+        assert_eq!(read_node_engine.get_confirmed_height().block_number, 3);
+
+        // Transactions aren't covered by the header, so they are dropped
+        let height1 = shard_chunk1.header.as_ref().unwrap().height.unwrap();
+        assert!(!shard_chunk1.transactions.is_empty());
+        let backfilled = read_node_engine.get_shard_chunk_by_height(height1).unwrap();
+        assert_eq!(backfilled.hash, shard_chunk1.hash);
+        assert!(backfilled.transactions.is_empty());
+
+        // Backfilled values can't be executed, so they aren't served to peers
+        assert_eq!(read_validator.get_served_min_height().block_number, 3);
+        assert!(read_validator.get_decided_value(height1).is_none());
+        assert_eq!(
+            read_validator
+                .get_decided_value(shard_chunk3.header.as_ref().unwrap().height.unwrap())
+                .unwrap()
+                .certificate
+                .value_id
+                .hash,
+            shard_chunk3.hash
+        );
+    }
 }
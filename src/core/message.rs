@@ -39,6 +39,9 @@ impl proto::ValidatorMessage {
         if let Some(event) = &self.on_chain_event {
             return event.fid;
         }
+        if let Some(storage_prune) = &self.storage_prune {
+            return storage_prune.fid;
+        }
         0
     }
 }
@@ -120,6 +120,12 @@ pub enum ValidationError {
     InvalidEthClaimSignatureLength,
     #[error("claimSignature != 64 bytes")]
     InvalidSolClaimSignatureLength,
+    #[error("claimSignature must be a 32 byte authority followed by up to 16 signer signatures")]
+    InvalidSolAccountClaimSignatureLength,
+    #[error("address is not the authority account or its default vault")]
+    InvalidSolAccountAuthority,
+    #[error("not enough authority members signed the claim")]
+    SolAccountThresholdNotMet,
     #[error("invalid message type")]
     InvalidMessageType,
     #[error("invalid user data type")]
@@ -159,7 +159,7 @@ pub fn validate_message(
             }
         }
         Some(proto::message_data::Body::VerificationAddAddressBody(add)) => {
-            verification::validate_add_address(&add, message_data.fid, network)?;
+            verification::validate_add_address(&add, message_data.fid, network, version)?;
         }
         Some(proto::message_data::Body::LinkCompactStateBody(link_compact_state_body)) => {
             link::validate_link_compact_state_body(&link_compact_state_body)?;
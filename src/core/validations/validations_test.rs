@@ -4,10 +4,15 @@ mod tests {
         validate_user_data_add_body, validate_user_data_primary_address_ethereum,
         validate_user_data_primary_address_solana,
     };
-    use crate::core::validations::verification::{validate_add_address, validate_fname_transfer};
+    use crate::core::validations::verification::{
+        make_verification_address_claim, parse_sol_account_claim, recreate_solana_claim_message,
+        squads_vault_address, validate_add_address, validate_fname_transfer,
+        validate_sol_account_authority, SQUADS_VAULT_INDEX,
+    };
     use crate::proto;
     use crate::proto::FarcasterNetwork;
     use crate::version::version::EngineVersion;
+    use ed25519_dalek::{Signer, SigningKey};
     use proto::{FnameTransfer, UserDataBody, UserDataType, UserNameProof};
 
     #[test]
@@ -21,7 +26,12 @@ mod tests {
         protocol: 0,
       };
 
-        let result = validate_add_address(add_address_body, 2, proto::FarcasterNetwork::Mainnet);
+        let result = validate_add_address(
+            add_address_body,
+            2,
+            proto::FarcasterNetwork::Mainnet,
+            EngineVersion::latest(),
+        );
         assert!(result.is_ok());
     }
 
@@ -36,8 +46,12 @@ mod tests {
         protocol: 0,
       };
 
-        let result =
-            validate_add_address(add_address_body, 200739, proto::FarcasterNetwork::Mainnet);
+        let result = validate_add_address(
+            add_address_body,
+            200739,
+            proto::FarcasterNetwork::Mainnet,
+            EngineVersion::latest(),
+        );
         assert!(result.is_ok());
     }
 
@@ -52,7 +66,12 @@ mod tests {
         protocol: 0,
       };
 
-        let result = validate_add_address(add_address_body, 2, proto::FarcasterNetwork::Mainnet);
+        let result = validate_add_address(
+            add_address_body,
+            2,
+            proto::FarcasterNetwork::Mainnet,
+            EngineVersion::latest(),
+        );
         assert!(result.is_err());
     }
 
@@ -67,7 +86,12 @@ mod tests {
         protocol: 1,
       };
 
-        let result = validate_add_address(add_address_body, 2, proto::FarcasterNetwork::Mainnet);
+        let result = validate_add_address(
+            add_address_body,
+            2,
+            proto::FarcasterNetwork::Mainnet,
+            EngineVersion::latest(),
+        );
         assert!(result.is_ok());
     }
 
@@ -82,10 +106,157 @@ mod tests {
         protocol: 1,
       };
 
-        let result = validate_add_address(add_address_body, 2, proto::FarcasterNetwork::Mainnet);
+        let result = validate_add_address(
+            add_address_body,
+            2,
+            proto::FarcasterNetwork::Mainnet,
+            EngineVersion::latest(),
+        );
         assert!(result.is_err());
     }
 
+    fn sol_account_verification(
+        address: Vec<u8>,
+        authority: [u8; 32],
+        signers: &[SigningKey],
+    ) -> proto::VerificationAddAddressBody {
+        let block_hash = vec![2; 32];
+        let claim = make_verification_address_claim(
+            2,
+            &address,
+            FarcasterNetwork::Mainnet,
+            &block_hash,
+            proto::Protocol::Solana,
+        )
+        .unwrap();
+        let message = recreate_solana_claim_message(claim);
+
+        let mut claim_signature = authority.to_vec();
+        for signer in signers {
+            claim_signature.extend(signer.verifying_key().to_bytes());
+            claim_signature.extend(signer.sign(&message).to_bytes());
+        }
+        proto::VerificationAddAddressBody {
+            address,
+            claim_signature,
+            block_hash,
+            verification_type: 1,
+            chain_id: 0,
+            protocol: proto::Protocol::Solana as i32,
+        }
+    }
+
+    fn validate_sol_account(
+        body: &proto::VerificationAddAddressBody,
+        version: EngineVersion,
+    ) -> Result<(), ValidationError> {
+        validate_add_address(body, 2, FarcasterNetwork::Mainnet, version)
+    }
+
+    #[test]
+    fn test_validate_add_address_sol_account() {
+        let multisig = [9; 32];
+        let signers = [
+            SigningKey::from_bytes(&[1; 32]),
+            SigningKey::from_bytes(&[2; 32]),
+        ];
+
+        let body = sol_account_verification(multisig.to_vec(), multisig, &signers);
+        assert!(validate_sol_account(&body, EngineVersion::latest()).is_ok());
+
+        let vault = squads_vault_address(&multisig, SQUADS_VAULT_INDEX).unwrap();
+        let body = sol_account_verification(vault.to_vec(), multisig, &signers);
+        assert!(validate_sol_account(&body, EngineVersion::latest()).is_ok());
+
+        // Account verifications aren't recognized before the feature is enabled
+        assert_eq!(
+            validate_sol_account(&body, EngineVersion::V5),
+            Err(ValidationError::InvalidSolClaimSignatureLength)
+        );
+    }
+
+    #[test]
+    fn test_validate_add_address_invalid_sol_account() {
+        let multisig = [9; 32];
+        let signers = [
+            SigningKey::from_bytes(&[1; 32]),
+            SigningKey::from_bytes(&[2; 32]),
+        ];
+
+        let body = sol_account_verification(vec![8; 32], multisig, &signers);
+        assert_eq!(
+            validate_sol_account(&body, EngineVersion::latest()),
+            Err(ValidationError::InvalidSolAccountAuthority)
+        );
+
+        // Only the default vault can be verified
+        let vault = squads_vault_address(&multisig, 1).unwrap();
+        let body = sol_account_verification(vault.to_vec(), multisig, &signers);
+        assert_eq!(
+            validate_sol_account(&body, EngineVersion::latest()),
+            Err(ValidationError::InvalidSolAccountAuthority)
+        );
+
+        let mut body = sol_account_verification(multisig.to_vec(), multisig, &signers);
+        let last = body.claim_signature.len() - 1;
+        body.claim_signature[last] ^= 1;
+        assert_eq!(
+            validate_sol_account(&body, EngineVersion::latest()),
+            Err(ValidationError::InvalidSignature)
+        );
+
+        body.claim_signature.pop();
+        assert_eq!(
+            validate_sol_account(&body, EngineVersion::latest()),
+            Err(ValidationError::InvalidSolAccountClaimSignatureLength)
+        );
+
+        let body = sol_account_verification(multisig.to_vec(), multisig, &[]);
+        assert_eq!(
+            validate_sol_account(&body, EngineVersion::latest()),
+            Err(ValidationError::InvalidSolAccountClaimSignatureLength)
+        );
+
+        let body = sol_account_verification(
+            multisig.to_vec(),
+            multisig,
+            &[signers[0].clone(), signers[0].clone()],
+        );
+        assert_eq!(
+            validate_sol_account(&body, EngineVersion::latest()),
+            Err(ValidationError::InvalidClaimSignature)
+        );
+    }
+
+    #[test]
+    fn test_validate_sol_account_authority() {
+        let multisig = [9; 32];
+        let signers = [
+            SigningKey::from_bytes(&[1; 32]),
+            SigningKey::from_bytes(&[2; 32]),
+        ];
+        let body = sol_account_verification(multisig.to_vec(), multisig, &signers);
+        let claim = parse_sol_account_claim(&body).unwrap();
+        let members: Vec<[u8; 32]> = signers
+            .iter()
+            .map(|signer| signer.verifying_key().to_bytes())
+            .collect();
+
+        assert!(validate_sol_account_authority(&claim, 2, &members).is_ok());
+        assert_eq!(
+            validate_sol_account_authority(&claim, 3, &members),
+            Err(ValidationError::SolAccountThresholdNotMet)
+        );
+        assert_eq!(
+            validate_sol_account_authority(&claim, 2, &members[..1]),
+            Err(ValidationError::SolAccountThresholdNotMet)
+        );
+        assert_eq!(
+            validate_sol_account_authority(&claim, 0, &members),
+            Err(ValidationError::SolAccountThresholdNotMet)
+        );
+    }
+
     #[test]
     fn test_fname_transfer_verify_valid_signature() {
         let transfer = &FnameTransfer{
@@ -1,15 +1,20 @@
 use crate::core::validations::error::ValidationError;
 use crate::proto::{self, FarcasterNetwork, VerificationAddAddressBody};
+use crate::version::version::{EngineVersion, ProtocolFeature};
 use alloy_dyn_abi::TypedData;
 use alloy_provider::Provider;
 use alloy_transport::Transport;
 use eth_signature_verifier::Verification;
 use serde::Serialize;
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 
 const EIP_712_FARCASTER_VERIFICATION_CLAIM_CHAIN_IDS: [u16; 5] = [0, 1, 5, 10, 420];
 const FNAME_SIGNER_ADDRESS: alloy_primitives::Address =
     alloy_primitives::address!("Bc5274eFc266311015793d89E9B591fa46294741");
+pub const SQUADS_PROGRAM_ID: &str = "SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf";
+const MAX_SOL_ACCOUNT_SIGNERS: usize = 16;
+const SOL_ACCOUNT_SIGNER_LENGTH: usize = 96; // 32 byte public key followed by a 64 byte signature
 
 fn eip_712_farcaster_verification_claim() -> Value {
     json!({
@@ -108,12 +113,9 @@ pub fn name_registry_domain() -> Value {
     })
 }
 
-pub fn validate_fname_transfer(
-    transfer: &proto::FnameTransfer,
-    network: FarcasterNetwork,
-    signer_address: Option<alloy_primitives::Address>,
-) -> Result<(), ValidationError> {
-    let proof = transfer.proof.as_ref().unwrap();
+fn fname_proof_signing_hash(
+    proof: &proto::UserNameProof,
+) -> Result<alloy_primitives::B256, ValidationError> {
     let username = std::str::from_utf8(&proof.name);
     if username.is_err() {
         return Err(ValidationError::InvalidUsername);
@@ -140,30 +142,61 @@ pub fn validate_fname_transfer(
     if prehash.is_err() {
         return Err(ValidationError::InvalidHash);
     }
+    Ok(prehash.unwrap())
+}
+
+fn recover_fname_proof_signer(
+    hash: &alloy_primitives::B256,
+    signature: &[u8],
+) -> Result<alloy_primitives::Address, ValidationError> {
+    if signature.len() != 65 {
+        return Err(ValidationError::InvalidSignature);
+    }
+
+    let signature = alloy_primitives::PrimitiveSignature::from_bytes_and_parity(
+        &signature[0..64],
+        signature[64] != 0x1b && signature[64] != 0x00,
+    );
+
+    signature
+        .recover_address_from_prehash(hash)
+        .map_err(|_| ValidationError::InvalidSignature)
+}
+
+pub fn validate_fname_transfer(
+    transfer: &proto::FnameTransfer,
+    network: FarcasterNetwork,
+    signer_address: Option<alloy_primitives::Address>,
+) -> Result<(), ValidationError> {
+    let proof = transfer.proof.as_ref().unwrap();
+    let hash = fname_proof_signing_hash(proof)?;
 
     if network == FarcasterNetwork::Devnet {
         // Don't validate signatures on devnet (tests)
         return Ok(());
     }
 
-    if proof.signature.len() != 65 {
+    let fname_signer = signer_address.unwrap_or(FNAME_SIGNER_ADDRESS);
+    if recover_fname_proof_signer(&hash, &proof.signature)? != fname_signer {
         return Err(ValidationError::InvalidSignature);
     }
 
-    let hash = prehash.unwrap();
-    let fname_signer = signer_address.unwrap_or(FNAME_SIGNER_ADDRESS);
-    let signature = alloy_primitives::PrimitiveSignature::from_bytes_and_parity(
-        &proof.signature[0..64],
-        proof.signature[64] != 0x1b && proof.signature[64] != 0x00,
-    );
+    Ok(())
+}
 
-    let recovered_address = signature.recover_address_from_prehash(&hash);
-    if recovered_address.is_err() {
-        return Err(ValidationError::InvalidSignature);
+/** Checks the owner's signature over the same proof the fname server signs. Only EOA owners can be verified this way. */
+pub fn validate_fname_user_signature(
+    proof: &proto::UserNameProof,
+    user_signature: &[u8],
+    network: FarcasterNetwork,
+) -> Result<(), ValidationError> {
+    let hash = fname_proof_signing_hash(proof)?;
+
+    if network == FarcasterNetwork::Devnet {
+        return Ok(());
     }
 
-    let recovered = recovered_address.unwrap();
-    if recovered != fname_signer {
+    if recover_fname_proof_signer(&hash, user_signature)?.as_slice() != proof.owner.as_slice() {
         return Err(ValidationError::InvalidSignature);
     }
 
@@ -411,7 +444,7 @@ fn validate_verification_add_eth_address_signature(
     }
 }
 
-fn recreate_solana_claim_message(claim: VerificationAddressClaim) -> Vec<u8> {
+pub fn recreate_solana_claim_message(claim: VerificationAddressClaim) -> Vec<u8> {
     // We're using a simple ascii string instead of the full offchain signing spec because this provides better compatibility with wallet libraries
     let message_content = format!(
         "fid: {} address: {} network: {} blockHash: {} protocol: {}",
@@ -421,6 +454,141 @@ fn recreate_solana_claim_message(claim: VerificationAddressClaim) -> Vec<u8> {
     message_content.into_bytes()
 }
 
+/** The authority of a Solana account verification and the members that signed the claim for it */
+#[derive(Debug, PartialEq)]
+pub struct SolanaAccountClaim {
+    pub authority: [u8; 32],
+    pub signers: Vec<[u8; 32]>,
+}
+
+fn create_program_address(seeds: &[&[u8]], program_id: &[u8; 32]) -> Option<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    for seed in seeds {
+        hasher.update(seed);
+    }
+    hasher.update(program_id);
+    hasher.update(b"ProgramDerivedAddress");
+    let address: [u8; 32] = hasher.finalize().into();
+
+    // Program derived addresses must not have a private key, so they can't be points on the curve
+    match ed25519_dalek::VerifyingKey::from_bytes(&address) {
+        Ok(_) => None,
+        Err(_) => Some(address),
+    }
+}
+
+pub fn find_program_address(seeds: &[&[u8]], program_id: &[u8; 32]) -> Option<[u8; 32]> {
+    (0..=u8::MAX).rev().find_map(|bump| {
+        let bump = [bump];
+        let mut seeds = seeds.to_vec();
+        seeds.push(&bump);
+        create_program_address(&seeds, program_id)
+    })
+}
+
+pub fn squads_program_id() -> [u8; 32] {
+    bs58::decode(SQUADS_PROGRAM_ID)
+        .into_vec()
+        .unwrap()
+        .try_into()
+        .unwrap()
+}
+
+// Only the default vault can be verified, so validation derives a single address per claim
+pub const SQUADS_VAULT_INDEX: u8 = 0;
+
+pub fn squads_vault_address(multisig: &[u8; 32], vault_index: u8) -> Option<[u8; 32]> {
+    find_program_address(
+        &[b"multisig", multisig, b"vault", &[vault_index]],
+        &squads_program_id(),
+    )
+}
+
+/** Splits the claim signature of a Solana account verification into its authority and signers. The verified address must be the authority itself or its default Squads vault. */
+pub fn parse_sol_account_claim(
+    body: &proto::VerificationAddAddressBody,
+) -> Result<SolanaAccountClaim, ValidationError> {
+    let signatures_len = body.claim_signature.len().saturating_sub(32);
+    if body.claim_signature.len() < 32
+        || signatures_len == 0
+        || signatures_len % SOL_ACCOUNT_SIGNER_LENGTH != 0
+        || signatures_len / SOL_ACCOUNT_SIGNER_LENGTH > MAX_SOL_ACCOUNT_SIGNERS
+    {
+        return Err(ValidationError::InvalidSolAccountClaimSignatureLength);
+    }
+
+    let authority: [u8; 32] = body.claim_signature[..32].try_into().unwrap();
+    if body.address.as_slice() != authority
+        && squads_vault_address(&authority, SQUADS_VAULT_INDEX)
+            .is_none_or(|vault| body.address.as_slice() != vault)
+    {
+        return Err(ValidationError::InvalidSolAccountAuthority);
+    }
+
+    let mut signers: Vec<[u8; 32]> = vec![];
+    for chunk in body.claim_signature[32..].chunks(SOL_ACCOUNT_SIGNER_LENGTH) {
+        let signer: [u8; 32] = chunk[..32].try_into().unwrap();
+        if signers.contains(&signer) {
+            return Err(ValidationError::InvalidClaimSignature);
+        }
+        signers.push(signer);
+    }
+
+    Ok(SolanaAccountClaim { authority, signers })
+}
+
+/** Checks that enough members of the authority signed the claim. The members and threshold come from the chain, so this must happen out of the consensus loop. */
+pub fn validate_sol_account_authority(
+    claim: &SolanaAccountClaim,
+    threshold: u32,
+    members: &[[u8; 32]],
+) -> Result<(), ValidationError> {
+    let num_signed = claim
+        .signers
+        .iter()
+        .filter(|signer| members.contains(signer))
+        .count();
+    if threshold == 0 || num_signed < threshold as usize {
+        return Err(ValidationError::SolAccountThresholdNotMet);
+    }
+
+    Ok(())
+}
+
+fn validate_verification_add_sol_account_signature(
+    body: &proto::VerificationAddAddressBody,
+    fid: u64,
+    network: proto::FarcasterNetwork,
+) -> Result<(), ValidationError> {
+    parse_sol_account_claim(body)?;
+
+    let reconstructed_claim = make_verification_address_claim(
+        fid,
+        &body.address,
+        network,
+        &body.block_hash,
+        proto::Protocol::Solana,
+    );
+
+    if reconstructed_claim.is_err() {
+        return Err(ValidationError::InvalidData);
+    }
+
+    let full_message = recreate_solana_claim_message(reconstructed_claim.unwrap());
+
+    for chunk in body.claim_signature[32..].chunks(SOL_ACCOUNT_SIGNER_LENGTH) {
+        let public_key = ed25519_dalek::VerifyingKey::from_bytes(chunk[..32].try_into().unwrap())
+            .map_err(|_| ValidationError::InvalidData)?;
+        let signature = ed25519_dalek::Signature::from_bytes(chunk[32..].try_into().unwrap());
+        if public_key.verify_strict(&full_message, &signature).is_err() {
+            return Err(ValidationError::InvalidSignature);
+        }
+    }
+
+    // Whether the signers are members of the authority is verified out of the consensus loop
+    Ok(())
+}
+
 fn validate_verification_add_sol_address_signature(
     body: &proto::VerificationAddAddressBody,
     fid: u64,
@@ -487,6 +655,7 @@ fn validate_add_sol_address(
     body: &proto::VerificationAddAddressBody,
     fid: u64,
     network: proto::FarcasterNetwork,
+    version: EngineVersion,
 ) -> Result<(), ValidationError> {
     let valid_address = validate_sol_address(&body.address);
     if valid_address.is_err() {
@@ -498,7 +667,13 @@ fn validate_add_sol_address(
         return Err(valid_block_hash.unwrap_err());
     }
 
-    let valid_signature = validate_verification_add_sol_address_signature(body, fid, network);
+    let valid_signature = if body.verification_type == 1
+        && version.is_enabled(ProtocolFeature::SolanaAccountVerifications)
+    {
+        validate_verification_add_sol_account_signature(body, fid, network)
+    } else {
+        validate_verification_add_sol_address_signature(body, fid, network)
+    };
     if valid_signature.is_err() {
         return Err(valid_signature.unwrap_err());
     }
@@ -510,10 +685,13 @@ pub fn validate_add_address(
     body: &proto::VerificationAddAddressBody,
     fid: u64,
     network: proto::FarcasterNetwork,
+    version: EngineVersion,
 ) -> Result<(), ValidationError> {
     match body.protocol {
         x if x == proto::Protocol::Ethereum as i32 => validate_add_eth_address(body, fid, network),
-        x if x == proto::Protocol::Solana as i32 => validate_add_sol_address(body, fid, network),
+        x if x == proto::Protocol::Solana as i32 => {
+            validate_add_sol_address(body, fid, network, version)
+        }
         _ => Err(ValidationError::InvalidData),
     }
 }
@@ -0,0 +1,118 @@
+use crate::core::util::FarcasterTime;
+use crate::mempool::mempool::{MempoolRequest, MempoolSource};
+use crate::proto::{self, FarcasterNetwork, ShardChunk};
+use crate::storage::constants::PAGE_SIZE_MAX;
+use crate::storage::db::PageOptions;
+use crate::storage::store::engine::MempoolMessage;
+use crate::storage::store::stores::{Stores, StoresError};
+use crate::version::version::{EngineVersion, ProtocolFeature};
+use std::collections::HashMap;
+use tokio::sync::{mpsc, watch};
+use tokio::time::Duration;
+use tokio_cron_scheduler::{Job, JobSchedulerError};
+use tracing::{error, info};
+
+const THROTTLE: Duration = Duration::from_millis(100);
+
+/**
+ * Submits a storage prune for every fid in the shard that is over its storage limits. The engine
+ * decides what to prune when the prune is merged, so all validators prune the same messages.
+ * Prunes are stamped with the last committed shard chunk rather than the local clock, so the
+ * prunes every validator submits for the same height dedupe in the mempool.
+ */
+pub async fn submit_storage_prunes(
+    stores: &Stores,
+    mempool_tx: &mpsc::Sender<MempoolRequest>,
+    throttle: Duration,
+) -> Result<u64, StoresError> {
+    let header = match stores.shard_store.get_last_shard_chunk()? {
+        Some(ShardChunk {
+            header: Some(header),
+            ..
+        }) => header,
+        _ => return Ok(0),
+    };
+    let block_number = header.height.map_or(0, |height| height.block_number);
+    let timestamp = FarcasterTime::new(header.timestamp).to_unix_seconds();
+
+    let mut count = 0;
+    let mut page_options = PageOptions {
+        page_size: Some(PAGE_SIZE_MAX),
+        ..PageOptions::default()
+    };
+    loop {
+        let (fids, next_page_token) = stores.get_fids_over_storage_limits(&page_options)?;
+        for fid in fids {
+            let message = proto::ValidatorMessage {
+                on_chain_event: None,
+                fname_transfer: None,
+                storage_prune: Some(proto::StoragePrune {
+                    fid,
+                    timestamp,
+                    block_number,
+                }),
+            };
+            if let Err(e) = mempool_tx
+                .send(MempoolRequest::AddMessage(
+                    MempoolMessage::ValidatorMessage(message),
+                    MempoolSource::Local,
+                    None,
+                ))
+                .await
+            {
+                error!(fid, "Error submitting storage prune: {}", e);
+                continue;
+            }
+            count += 1;
+        }
+
+        match next_page_token {
+            None => break,
+            Some(page_token) => page_options.page_token = Some(page_token),
+        }
+        tokio::time::sleep(throttle).await;
+    }
+    Ok(count)
+}
+
+pub fn message_pruning_job(
+    schedule: &str,
+    network: FarcasterNetwork,
+    shard_stores: HashMap<u32, Stores>,
+    mempool_tx: mpsc::Sender<MempoolRequest>,
+    sync_complete_rx: watch::Receiver<bool>,
+) -> Result<Job, JobSchedulerError> {
+    Job::new_async(schedule, move |_, _| {
+        let sync_complete_rx = sync_complete_rx.clone();
+        let shard_stores = shard_stores.clone();
+        let mempool_tx = mempool_tx.clone();
+        Box::pin(async move {
+            if !EngineVersion::current(network).is_enabled(ProtocolFeature::StorageLimitPruning) {
+                return;
+            }
+
+            // Limits computed on a partially synced shard are not meaningful
+            let sync_complete = *sync_complete_rx.borrow();
+            if !sync_complete {
+                info!("Sync not complete, skipping message pruning");
+                return;
+            }
+
+            for (shard_id, stores) in shard_stores.iter() {
+                let count = submit_storage_prunes(stores, &mempool_tx, THROTTLE)
+                    .await
+                    .unwrap_or_else(|e| {
+                        error!("Error finding fids to prune for shard {}: {}", shard_id, e);
+                        0
+                    });
+                info!(
+                    "Submitted storage prunes for {} fids on shard {}",
+                    count, shard_id
+                );
+                stores
+                    .statsd
+                    .count_with_shard(*shard_id, "prune.storage_limit_fids", count);
+            }
+        })
+    })
+}
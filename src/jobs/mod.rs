@@ -1,3 +1,4 @@
 pub mod block_pruning;
 pub mod event_pruning;
+pub mod message_pruning;
 pub mod snapshot_upload;
@@ -1,17 +1,17 @@
 use axum::routing::get;
 use axum::Router;
-use hyper::server::conn::http1;
-use hyper::service::service_fn;
-use hyper_util::rt::TokioIo;
 use informalsystems_malachitebft_metrics::{export, SharedRegistry};
+use snapchain::connectors::fname::FnameRequest;
 use snapchain::connectors::onchain_events::{ChainClients, OnchainEventsRequest};
 use snapchain::consensus::consensus::SystemMessage;
 use snapchain::mempool::mempool::{Mempool, MempoolRequest, ReadNodeMempool};
 use snapchain::mempool::routing;
 use snapchain::network::admin_server::MyAdminService;
+use snapchain::network::api_limits::{ApiLimits, ApiLimitsInterceptor};
 use snapchain::network::gossip::{GossipEvent, SnapchainGossip};
 use snapchain::network::http_server::HubHttpServiceImpl;
 use snapchain::network::server::MyHubService;
+use snapchain::network::tls;
 use snapchain::node::snapchain_node::SnapchainNode;
 use snapchain::node::snapchain_read_node::SnapchainReadNode;
 use snapchain::proto::admin_service_server::AdminServiceServer;
@@ -19,7 +19,8 @@ use snapchain::proto::hub_service_server::HubServiceServer;
 use snapchain::storage::db::snapshot::download_snapshots;
 use snapchain::storage::db::RocksDB;
 use snapchain::storage::store::engine::Senders;
-use snapchain::storage::store::node_local_state::{self, LocalStateStore};
+use snapchain::storage::store::migrations::{self, DbKind};
+use snapchain::storage::store::node_local_state::LocalStateStore;
 use snapchain::storage::store::stores::Stores;
 use snapchain::storage::store::BlockStore;
 use snapchain::utils::statsd_wrapper::StatsdClientWrapper;
@@ -28,12 +29,16 @@ use std::error::Error;
 use std::net::{IpAddr, SocketAddr};
 use std::process;
 use std::sync::Arc;
+use std::time::Duration;
 use std::{fs, net};
 use tokio::net::TcpListener;
 use tokio::select;
 use tokio::signal::ctrl_c;
 use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::JoinHandle;
 use tokio_cron_scheduler::JobScheduler;
+use tokio_rustls::TlsAcceptor;
+use tonic::service::interceptor::InterceptedService;
 use tonic::transport::Server;
 use tracing::{error, info, warn};
 use tracing_subscriber::EnvFilter;
@@ -45,20 +50,24 @@ async fn start_servers(
     mut gossip: SnapchainGossip,
     mempool_tx: mpsc::Sender<MempoolRequest>,
     shutdown_tx: mpsc::Sender<()>,
+    node_shutdown_rx: watch::Receiver<bool>,
     onchain_events_request_tx: broadcast::Sender<OnchainEventsRequest>,
+    fid_events_request_tx: broadcast::Sender<OnchainEventsRequest>,
+    fname_request_tx: broadcast::Sender<FnameRequest>,
     statsd_client: StatsdClientWrapper,
     shard_stores: HashMap<u32, Stores>,
     shard_senders: HashMap<u32, Senders>,
     block_store: BlockStore,
     chain_clients: ChainClients,
-) {
+) -> JoinHandle<()> {
     let grpc_addr = app_config.rpc_address.clone();
     let grpc_socket_addr: SocketAddr = grpc_addr.parse().unwrap();
 
     let admin_service = MyAdminService::new(
         app_config.admin_rpc_auth.clone(),
         mempool_tx.clone(),
-        onchain_events_request_tx,
+        onchain_events_request_tx.clone(),
+        fname_request_tx,
         shard_stores.clone(),
         block_store.clone(),
         app_config.snapshot.clone(),
@@ -66,6 +75,16 @@ async fn start_servers(
         statsd_client.clone(),
     );
 
+    let api_limits = Arc::new(ApiLimits::new(
+        app_config.api_limits.clone(),
+        statsd_client.clone(),
+    ));
+    if api_limits.enabled() {
+        info!(
+            "API limits enabled with {} api keys",
+            app_config.api_limits.api_keys.len()
+        );
+    }
     let service = Arc::new(MyHubService::new(
         app_config.rpc_auth.clone(),
         block_store.clone(),
@@ -77,21 +96,45 @@ async fn start_servers(
         Box::new(routing::ShardRouter {}),
         mempool_tx.clone(),
         chain_clients,
+        fid_events_request_tx,
+        api_limits.clone(),
         VERSION.unwrap_or("unknown").to_string(),
         gossip.swarm.local_peer_id().to_string(),
+        app_config.archive_mode,
     ));
+    let http_api_limits = api_limits.clone();
+    let tls_acceptor = if app_config.tls.enabled() {
+        Some(tls::acceptor(&app_config.tls, None).expect("Failed to load TLS certificate"))
+    } else {
+        None
+    };
+
+    let admin_service = if admin_service.enabled() {
+        Some(AdminServiceServer::new(admin_service))
+    } else {
+        None
+    };
+    let (grpc_admin_service, separate_admin_service) = if app_config.admin_rpc_address.is_empty() {
+        (admin_service, None)
+    } else {
+        (None, admin_service)
+    };
+
     let grpc_service = service.clone();
     let grpc_shutdown_tx = shutdown_tx.clone();
+    let grpc_tls_acceptor = tls_acceptor.clone();
     tokio::spawn(async move {
         info!(grpc_addr = grpc_addr, "GrpcService listening",);
-        let mut server = Server::builder().add_service(HubServiceServer::from_arc(grpc_service));
+        let mut server = Server::builder().add_service(InterceptedService::new(
+            HubServiceServer::from_arc(grpc_service),
+            ApiLimitsInterceptor { api_limits },
+        ));
 
-        if admin_service.enabled() {
-            let admin_service = AdminServiceServer::new(admin_service);
+        if let Some(admin_service) = grpc_admin_service {
             server = server.add_service(admin_service);
         }
 
-        let resp = server.serve(grpc_socket_addr).await;
+        let resp = serve_grpc(server, grpc_socket_addr, grpc_tls_acceptor).await;
 
         let msg = "grpc server stopped";
         match resp {
@@ -102,42 +145,80 @@ async fn start_servers(
         grpc_shutdown_tx.send(()).await.ok();
     });
 
+    if let Some(admin_service) = separate_admin_service {
+        let admin_addr = app_config.admin_rpc_address.clone();
+        let admin_socket_addr: SocketAddr = admin_addr.parse().unwrap();
+        let admin_tls_acceptor = if app_config.tls.admin_client_ca_path.is_empty() {
+            tls_acceptor.clone()
+        } else {
+            Some(
+                tls::acceptor(&app_config.tls, Some(&app_config.tls.admin_client_ca_path))
+                    .expect("Failed to load TLS certificate or admin client CA"),
+            )
+        };
+        let admin_shutdown_tx = shutdown_tx.clone();
+        tokio::spawn(async move {
+            info!(admin_addr = admin_addr, "AdminService listening",);
+            let server = Server::builder().add_service(admin_service);
+            let resp = serve_grpc(server, admin_socket_addr, admin_tls_acceptor).await;
+
+            let msg = "admin grpc server stopped";
+            match resp {
+                Ok(()) => error!(msg),
+                Err(e) => error!(error = ?e, "{}", msg),
+            }
+
+            admin_shutdown_tx.send(()).await.ok();
+        });
+    }
+
     let http_addr = app_config.http_address.clone();
     let http_socket_addr: SocketAddr = http_addr.parse().unwrap();
 
     let http_shutdown_tx = shutdown_tx.clone();
-    let http_server_config = app_config.http_server.clone();
-    tokio::spawn(async move {
+    let router = snapchain::network::http_server::router(
+        HubHttpServiceImpl {
+            service: service.clone(),
+        },
+        statsd_client.clone(),
+        &app_config.http_server,
+        http_api_limits,
+    );
+    let http_server = tokio::spawn(async move {
         let listener = TcpListener::bind(http_socket_addr).await.unwrap();
         info!(http_addr = http_addr, "HttpService listening",);
 
-        let http_service = HubHttpServiceImpl {
-            service: service.clone(),
-        };
-        loop {
-            match listener.accept().await {
-                Ok((stream, _)) => {
-                    let io = TokioIo::new(stream);
-                    let http_server_config = http_server_config.clone();
-                    let service_clone = http_service.clone();
-                    tokio::spawn(async move {
-                        let router = snapchain::network::http_server::Router::new(service_clone);
-                        if let Err(err) = http1::Builder::new()
-                            .serve_connection(
-                                io,
-                                service_fn(|r| router.handle(r, &http_server_config)),
-                            )
-                            .await
-                        {
-                            error!("Error serving connection: {}", err);
-                        }
-                    });
-                }
-                Err(e) => {
-                    error!("Error accepting connection: {}", e);
-                    break;
-                }
+        // Stop accepting connections once the node shuts down, and let in-flight requests finish
+        let mut graceful_shutdown_rx = node_shutdown_rx.clone();
+        let resp = match tls_acceptor {
+            Some(acceptor) => {
+                tls::serve_http(
+                    tls::incoming(listener, acceptor),
+                    router,
+                    graceful_shutdown_rx,
+                )
+                .await
+            }
+            None => {
+                axum::serve(
+                    listener,
+                    router.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .with_graceful_shutdown(async move {
+                    _ = graceful_shutdown_rx.wait_for(|shutdown| *shutdown).await;
+                })
+                .await
             }
+        };
+
+        if *node_shutdown_rx.borrow() {
+            info!("http server shut down");
+            return;
+        }
+        let msg = "http server stopped";
+        match resp {
+            Ok(()) => error!(msg),
+            Err(e) => error!(error = ?e, "{}", msg),
         }
 
         http_shutdown_tx.send(()).await.ok();
@@ -149,6 +230,40 @@ async fn start_servers(
         gossip.start().await;
         info!("Gossip Stopped");
     });
+
+    http_server
+}
+
+async fn serve_grpc(
+    server: tonic::transport::server::Router,
+    addr: SocketAddr,
+    tls_acceptor: Option<TlsAcceptor>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match tls_acceptor {
+        Some(acceptor) => {
+            let listener = TcpListener::bind(addr).await?;
+            server
+                .serve_with_incoming(tls::incoming(listener, acceptor))
+                .await?
+        }
+        None => server.serve(addr).await?,
+    }
+    Ok(())
+}
+
+/** Signals the http server to shut down and waits for in-flight requests, up to the timeout. */
+async fn stop_http_server(
+    node_shutdown_tx: &watch::Sender<bool>,
+    http_server: &mut JoinHandle<()>,
+    shutdown_timeout: Duration,
+) {
+    node_shutdown_tx.send_replace(true);
+    if tokio::time::timeout(shutdown_timeout, http_server)
+        .await
+        .is_err()
+    {
+        warn!("Timed out waiting for http requests to finish");
+    }
 }
 
 async fn schedule_background_jobs(
@@ -156,32 +271,54 @@ async fn schedule_background_jobs(
     block_store: BlockStore,
     shard_stores: HashMap<u32, Stores>,
     sync_complete_rx: watch::Receiver<bool>,
+    mempool_tx: mpsc::Sender<MempoolRequest>,
     statsd_client: StatsdClientWrapper,
 ) {
     let sched = JobScheduler::new().await.unwrap();
     let mut jobs = vec![];
+    if app_config.archive_mode {
+        info!("Archive mode enabled, blocks, shard chunks and events will not be pruned");
+    }
     if app_config.read_node {
         if let Some(block_retention) = app_config.pruning.block_retention {
-            let schedule = "0 0 10 * * *"; // 10am UTC every day
-            let job = snapchain::jobs::block_pruning::block_pruning_job(
-                schedule,
-                block_retention,
-                block_store.clone(),
-                shard_stores.clone(),
-                sync_complete_rx,
-            )
-            .unwrap();
-            jobs.push(job);
+            if app_config.archive_mode {
+                warn!("Ignoring pruning.block_retention in archive mode");
+            } else {
+                let schedule = "0 0 10 * * *"; // 10am UTC every day
+                let job = snapchain::jobs::block_pruning::block_pruning_job(
+                    schedule,
+                    block_retention,
+                    block_store.clone(),
+                    shard_stores.clone(),
+                    sync_complete_rx,
+                )
+                .unwrap();
+                jobs.push(job);
+            }
         }
+    } else {
+        // Prunes go through consensus, so only validators can submit them. These enforce storage
+        // limits on current state rather than dropping history, so they also run in archive mode.
+        let message_pruning_job = snapchain::jobs::message_pruning::message_pruning_job(
+            "0 0 2 * * *", // 2 AM UTC every day
+            app_config.fc_network,
+            shard_stores.clone(),
+            mempool_tx,
+            sync_complete_rx,
+        )
+        .unwrap();
+        jobs.push(message_pruning_job);
     }
 
-    let event_pruning_job = snapchain::jobs::event_pruning::event_pruning_job(
-        "0 0 0 * * *", // midnight UTC every day
-        app_config.pruning.event_retention,
-        shard_stores.clone(),
-    )
-    .unwrap();
-    jobs.push(event_pruning_job);
+    if !app_config.archive_mode {
+        let event_pruning_job = snapchain::jobs::event_pruning::event_pruning_job(
+            "0 0 0 * * *", // midnight UTC every day
+            app_config.pruning.event_retention,
+            shard_stores.clone(),
+        )
+        .unwrap();
+        jobs.push(event_pruning_job);
+    }
 
     if app_config.snapshot.snapshot_upload_enabled() {
         let snapshot_upload_job = snapchain::jobs::snapshot_upload::snapshot_upload_job(
@@ -277,6 +414,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
         return Err("statsd prefix must be specified in config".into());
     }
 
+    // Client certificates are checked during the handshake, so admin needs its own listener
+    if !app_config.tls.admin_client_ca_path.is_empty()
+        && (!app_config.tls.enabled() || app_config.admin_rpc_address.is_empty())
+    {
+        return Err("tls.admin_client_ca_path requires tls.cert_path and admin_rpc_address".into());
+    }
+
     // TODO: parsing to SocketAddr only allows for IPs, DNS names won't work
     let (statsd_host, statsd_port) = match app_config.statsd.addr.parse::<SocketAddr>() {
         Ok(addr) => Ok((addr.ip().to_string(), addr.port())),
@@ -290,7 +434,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
         cadence::StatsdClient::builder(app_config.statsd.prefix.as_str(), sink).build();
     let statsd_client = StatsdClientWrapper::new(statsd_client, app_config.statsd.use_tags);
 
-    let block_db = RocksDB::open_shard_db(app_config.rocksdb_dir.as_str(), 0);
+    let block_db = RocksDB::open_shard_db(app_config.rocksdb_dir.as_str(), 0, &app_config.rocksdb);
+    if let Err(e) = migrations::run_migrations(&block_db, DbKind::Shard(0)) {
+        error!("Failed to migrate block db: {}", e);
+        process::exit(1);
+    }
     let block_store = BlockStore::new(block_db);
     info!(
         "Block db height {}",
@@ -307,6 +455,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         &app_config.gossip,
         system_tx.clone(),
         app_config.read_node,
+        app_config.archive_mode,
         app_config.fc_network,
         statsd_client.clone(),
     )
@@ -334,6 +483,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let gossip_tx = gossip.tx.clone();
 
     let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
+    let (node_shutdown_tx, node_shutdown_rx) = watch::channel(false);
+    let http_shutdown_timeout = app_config.http_server.shutdown_timeout;
 
     let registry = SharedRegistry::global();
 
@@ -359,14 +510,28 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let (messages_request_tx, messages_request_rx) = mpsc::channel(100);
 
-    let chains_clients = ChainClients::new(&app_config);
+    let global_db = RocksDB::open_global_db(&app_config.rocksdb_dir, &app_config.rocksdb);
+    if let Err(e) = migrations::run_migrations(&global_db, DbKind::Global) {
+        error!("Failed to migrate global db: {}", e);
+        process::exit(1);
+    }
+    let local_state_store = LocalStateStore::new(global_db);
+
+    let chains_clients = ChainClients::new(
+        &app_config,
+        local_state_store.clone(),
+        statsd_client.clone(),
+    );
 
     let (sync_complete_tx, sync_complete_rx) = watch::channel(false);
 
-    let (onchain_events_request_tx, onchain_events_request_rx) = broadcast::channel(100);
+    let (onchain_events_request_tx, _) = broadcast::channel(100);
+    // Fid event fetches for RequestOnChainEventRepair, served apart from live ingestion
+    let (fid_events_request_tx, _) = broadcast::channel(100);
+    let (fname_request_tx, fname_request_rx) = broadcast::channel(100);
 
     if app_config.read_node {
-        let node = SnapchainReadNode::create(
+        let node = match SnapchainReadNode::create(
             keypair.clone(),
             app_config.consensus.clone(),
             local_peer_id,
@@ -375,18 +540,29 @@ async fn main() -> Result<(), Box<dyn Error>> {
             messages_request_tx,
             block_store.clone(),
             app_config.rocksdb_dir.clone(),
+            &app_config.rocksdb,
+            &app_config.indexes,
             statsd_client.clone(),
             app_config.trie_branching_factor,
             app_config.fc_network,
+            app_config.archive_mode,
             registry,
         )
-        .await;
+        .await
+        {
+            Ok(node) => node,
+            Err(e) => {
+                error!("Failed to migrate shard db: {}", e);
+                process::exit(1);
+            }
+        };
 
         schedule_background_jobs(
             &app_config,
             block_store.clone(),
             node.shard_stores.clone(),
             sync_complete_rx,
+            mempool_tx.clone(),
             statsd_client.clone(),
         )
         .await;
@@ -400,12 +576,15 @@ async fn main() -> Result<(), Box<dyn Error>> {
         );
         tokio::spawn(async move { mempool.run().await });
 
-        start_servers(
+        let mut http_server = start_servers(
             &app_config,
             gossip,
             mempool_tx,
             shutdown_tx,
+            node_shutdown_rx.clone(),
             onchain_events_request_tx,
+            fid_events_request_tx,
+            fname_request_tx,
             statsd_client,
             node.shard_stores.clone(),
             node.shard_senders.clone(),
@@ -419,11 +598,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
             select! {
                 _ = ctrl_c() => {
                     info!("Received Ctrl-C, shutting down");
+                    stop_http_server(&node_shutdown_tx, &mut http_server, http_shutdown_timeout).await;
                     node.stop();
                     return Ok(());
                 }
                 _ = shutdown_rx.recv() => {
                     error!("Received shutdown signal, shutting down");
+                    stop_http_server(&node_shutdown_tx, &mut http_server, http_shutdown_timeout).await;
                     node.stop();
                     return Ok(());
                 }
@@ -458,6 +639,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         }
                         SystemMessage::ExitWithError(err) => {
                             error!("Exiting due to: {}", err);
+                            stop_http_server(&node_shutdown_tx, &mut http_server, http_shutdown_timeout).await;
                             node.stop();
                             return Err(err.into());
                         }
@@ -468,10 +650,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     } else {
         let (shard_decision_tx, shard_decision_rx) = broadcast::channel(100);
 
-        let global_db = RocksDB::open_global_db(&app_config.rocksdb_dir);
-        let local_state_store = LocalStateStore::new(global_db);
-
-        let node = SnapchainNode::create(
+        let node = match SnapchainNode::create(
             keypair.clone(),
             app_config.consensus.clone(),
             local_peer_id,
@@ -482,18 +661,28 @@ async fn main() -> Result<(), Box<dyn Error>> {
             block_store.clone(),
             local_state_store.clone(),
             app_config.rocksdb_dir.clone(),
+            &app_config.rocksdb,
+            &app_config.indexes,
             statsd_client.clone(),
             app_config.trie_branching_factor,
             app_config.fc_network,
             registry,
         )
-        .await;
+        .await
+        {
+            Ok(node) => node,
+            Err(e) => {
+                error!("Failed to migrate shard db: {}", e);
+                process::exit(1);
+            }
+        };
 
         schedule_background_jobs(
             &app_config,
             block_store.clone(),
             node.shard_stores.clone(),
             sync_complete_rx,
+            mempool_tx.clone(),
             statsd_client.clone(),
         )
         .await;
@@ -514,9 +703,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
         if !app_config.fnames.disable {
             let mut fetcher = snapchain::connectors::fname::Fetcher::new(
                 app_config.fnames.clone(),
+                app_config.fc_network,
                 mempool_tx.clone(),
                 statsd_client.clone(),
                 local_state_store.clone(),
+                fname_request_rx,
             );
 
             tokio::spawn(async move {
@@ -524,54 +715,46 @@ async fn main() -> Result<(), Box<dyn Error>> {
             });
         }
 
-        if !app_config.onchain_events.rpc_url.is_empty() {
+        for chain_config in snapchain::connectors::onchain_events::configured_chains(&app_config)? {
             let mut onchain_events_subscriber =
                 snapchain::connectors::onchain_events::Subscriber::new(
-                    &app_config.onchain_events,
-                    node_local_state::Chain::Optimism,
+                    chain_config,
                     mempool_tx.clone(),
                     statsd_client.clone(),
                     local_state_store.clone(),
-                    onchain_events_request_rx,
+                    onchain_events_request_tx.subscribe(),
                 )?;
+            let chain = onchain_events_subscriber.chain();
             tokio::spawn(async move {
                 let result = onchain_events_subscriber.run().await;
                 match result {
                     Ok(()) => {}
                     Err(e) => {
-                        error!("Error subscribing to on chain events on optimism {:#?}", e);
+                        error!("Error subscribing to on chain events on {} {:#?}", chain, e);
                     }
                 }
             });
-        }
-
-        if !app_config.base_onchain_events.rpc_url.is_empty() {
-            let mut onchain_events_subscriber =
-                snapchain::connectors::onchain_events::Subscriber::new(
-                    &app_config.base_onchain_events,
-                    node_local_state::Chain::Base,
-                    mempool_tx.clone(),
-                    statsd_client.clone(),
-                    local_state_store,
-                    onchain_events_request_tx.subscribe(),
-                )?;
+            let mut fid_events_subscriber = snapchain::connectors::onchain_events::Subscriber::new(
+                chain_config,
+                mempool_tx.clone(),
+                statsd_client.clone(),
+                local_state_store.clone(),
+                fid_events_request_tx.subscribe(),
+            )?;
             tokio::spawn(async move {
-                let result = onchain_events_subscriber.run().await;
-                match result {
-                    Ok(()) => {}
-                    Err(e) => {
-                        error!("Error subscribing to on chain events on base {:#?}", e);
-                    }
-                }
+                fid_events_subscriber.serve_fid_event_requests().await;
             });
         }
 
-        start_servers(
+        let mut http_server = start_servers(
             &app_config,
             gossip,
             mempool_tx.clone(),
             shutdown_tx.clone(),
+            node_shutdown_rx.clone(),
             onchain_events_request_tx,
+            fid_events_request_tx,
+            fname_request_tx,
             statsd_client,
             node.shard_stores.clone(),
             node.shard_senders.clone(),
@@ -614,11 +797,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
             select! {
                 _ = ctrl_c() => {
                     info!("Received Ctrl-C, shutting down");
+                    stop_http_server(&node_shutdown_tx, &mut http_server, http_shutdown_timeout).await;
                     node.stop();
                     return Ok(());
                 }
                 _ = shutdown_rx.recv() => {
                     error!("Received shutdown signal, shutting down");
+                    stop_http_server(&node_shutdown_tx, &mut http_server, http_shutdown_timeout).await;
                     node.stop();
                     return Ok(());
                 }
@@ -643,6 +828,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         },
                         SystemMessage::ExitWithError(err) => {
                             error!("Exiting due to: {}", err);
+                            stop_http_server(&node_shutdown_tx, &mut http_server, http_shutdown_timeout).await;
                             node.stop();
                             return Err(err.into());
                         }
@@ -197,6 +197,16 @@ impl proto::ValidatorMessage {
                 hex::encode(&event.transaction_hash) + &event.log_index.to_string(),
             );
         }
+        if let Some(storage_prune) = &self.storage_prune {
+            return MempoolKey::new(
+                MempoolMessageKind::ValidatorMessage,
+                storage_prune.timestamp,
+                format!(
+                    "storage_prune_{}_{}",
+                    storage_prune.fid, storage_prune.block_number
+                ),
+            );
+        }
         todo!();
     }
 }
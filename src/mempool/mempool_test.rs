@@ -67,6 +67,7 @@ mod tests {
                     &config,
                     system_tx,
                     false,
+                    false,
                     proto::FarcasterNetwork::Devnet,
                     statsd_client.clone(),
                 )
@@ -130,12 +131,14 @@ mod tests {
         let valid = mempool.message_is_valid(&MempoolMessage::ValidatorMessage(ValidatorMessage {
             on_chain_event: Some(onchain_event.clone()),
             fname_transfer: None,
+            storage_prune: None,
         }));
         assert!(valid.is_ok());
         test_helper::commit_event(&mut engine, &onchain_event).await;
         let valid = mempool.message_is_valid(&MempoolMessage::ValidatorMessage(ValidatorMessage {
             on_chain_event: Some(onchain_event.clone()),
             fname_transfer: None,
+            storage_prune: None,
         }));
         // Mempool allows duplicate on-chain events
         assert!(valid.is_ok())
@@ -157,6 +160,7 @@ mod tests {
         let valid = mempool.message_is_valid(&MempoolMessage::ValidatorMessage(ValidatorMessage {
             on_chain_event: None,
             fname_transfer: Some(fname_transfer.clone()),
+            storage_prune: None,
         }));
         assert!(valid.is_ok());
         test_helper::commit_fname_transfer(&mut engine, &fname_transfer).await;
@@ -167,6 +171,7 @@ mod tests {
         let valid = mempool.message_is_valid(&MempoolMessage::ValidatorMessage(ValidatorMessage {
             on_chain_event: None,
             fname_transfer: Some(fname_transfer),
+            storage_prune: None,
         }));
         assert!(valid.is_ok())
     }
@@ -239,6 +244,44 @@ mod tests {
         assert_eq!(size[&1], 2);
     }
 
+    #[tokio::test]
+    async fn test_storage_prunes_for_same_height_dedupe() {
+        let (_, _, mut mempool, mempool_tx, _request_tx, _decision_tx, _) =
+            setup(None, false).await;
+        tokio::spawn(async move {
+            mempool.run().await;
+        });
+
+        let storage_prune = |block_number| {
+            MempoolMessage::ValidatorMessage(ValidatorMessage {
+                on_chain_event: None,
+                fname_transfer: None,
+                storage_prune: Some(proto::StoragePrune {
+                    fid: 123,
+                    timestamp: 100,
+                    block_number,
+                }),
+            })
+        };
+
+        // The same prune submitted by two validators, then a prune for a later height
+        for block_number in [5, 5, 6] {
+            mempool_tx
+                .send(MempoolRequest::AddMessage(
+                    storage_prune(block_number),
+                    MempoolSource::Local,
+                    None,
+                ))
+                .await
+                .unwrap();
+        }
+
+        let (req, res) = oneshot::channel();
+        mempool_tx.send(MempoolRequest::GetSize(req)).await.unwrap();
+        let size = res.await.unwrap();
+        assert_eq!(size[&1], 2);
+    }
+
     #[tokio::test]
     async fn test_mempool_prioritization() {
         let (_, _, mut mempool, mempool_tx, messages_request_tx, _shard_decision_tx, _) =
@@ -274,6 +317,7 @@ mod tests {
                 MempoolMessage::ValidatorMessage(ValidatorMessage {
                     on_chain_event: Some(onchain_event),
                     fname_transfer: None,
+                    storage_prune: None,
                 }),
                 MempoolSource::Local,
                 None,
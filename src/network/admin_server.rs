@@ -1,3 +1,4 @@
+use crate::connectors::fname::FnameRequest;
 use crate::connectors::onchain_events::OnchainEventsRequest;
 use crate::jobs::snapshot_upload::upload_snapshot;
 use crate::mempool::mempool::{MempoolRequest, MempoolSource};
@@ -5,8 +6,8 @@ use crate::network::rpc_extensions::authenticate_request;
 use crate::network::server::MEMPOOL_ADD_REQUEST_TIMEOUT;
 use crate::proto::admin_service_server::AdminService;
 use crate::proto::{
-    self, Empty, FarcasterNetwork, FnameTransfer, OnChainEvent, RetryOnchainEventsRequest,
-    UploadSnapshotRequest, UserNameProof, ValidatorMessage,
+    self, Empty, FarcasterNetwork, FnameTransfer, OnChainEvent, RetryFnameTransfersRequest,
+    RetryOnchainEventsRequest, UploadSnapshotRequest, UserNameProof, ValidatorMessage,
 };
 use crate::storage;
 use crate::storage::store::engine::MempoolMessage;
@@ -26,6 +27,7 @@ pub struct MyAdminService {
     allowed_users: HashMap<String, String>,
     pub mempool_tx: mpsc::Sender<MempoolRequest>,
     onchain_events_request_tx: broadcast::Sender<OnchainEventsRequest>,
+    fname_request_tx: broadcast::Sender<FnameRequest>,
     snapshot_config: storage::db::snapshot::Config,
     shard_stores: HashMap<u32, Stores>,
     block_store: BlockStore,
@@ -47,6 +49,7 @@ impl MyAdminService {
         rpc_auth: String,
         mempool_tx: mpsc::Sender<MempoolRequest>,
         onchain_events_request_tx: broadcast::Sender<OnchainEventsRequest>,
+        fname_request_tx: broadcast::Sender<FnameRequest>,
         shard_stores: HashMap<u32, Stores>,
         block_store: BlockStore,
         snapshot_config: storage::db::snapshot::Config,
@@ -65,6 +68,7 @@ impl MyAdminService {
             allowed_users,
             mempool_tx,
             onchain_events_request_tx,
+            fname_request_tx,
             shard_stores,
             block_store,
             snapshot_config,
@@ -117,6 +121,7 @@ impl AdminService for MyAdminService {
                 MempoolMessage::ValidatorMessage(ValidatorMessage {
                     on_chain_event: Some(onchain_event.clone()),
                     fname_transfer: None,
+                    storage_prune: None,
                 }),
                 MempoolSource::RPC,
                 Some(tx),
@@ -173,6 +178,7 @@ impl AdminService for MyAdminService {
                         from_fid: 0, // Assume the username is being transfer from the "root" fid to the one in the username proof
                         proof: Some(username_proof.clone()),
                     }),
+                    storage_prune: None,
                 }),
                 MempoolSource::RPC,
                 Some(tx),
@@ -223,6 +229,27 @@ impl AdminService for MyAdminService {
         Ok(Response::new(Empty {}))
     }
 
+    async fn retry_fname_transfers(
+        &self,
+        request: Request<RetryFnameTransfersRequest>,
+    ) -> std::result::Result<Response<Empty>, Status> {
+        authenticate_request(&request, &self.allowed_users)?;
+
+        let request = request.into_inner();
+        if request.start_id > request.stop_id {
+            return Err(Status::invalid_argument(
+                "start_id must not be greater than stop_id",
+            ));
+        }
+        self.fname_request_tx
+            .send(FnameRequest::RetryTransferIdRange {
+                start_id: request.start_id,
+                stop_id: request.stop_id,
+            })
+            .map_err(|_| Status::internal("unable to handle request"))?;
+        Ok(Response::new(Empty {}))
+    }
+
     async fn upload_snapshot(
         &self,
         request: Request<UploadSnapshotRequest>,
@@ -0,0 +1,382 @@
+use crate::proto;
+use crate::utils::statsd_wrapper::StatsdClientWrapper;
+use governor::clock::QuantaClock;
+use governor::state::{InMemoryState, NotKeyed};
+use governor::{Quota, RateLimiter};
+use moka::policy::EvictionPolicy;
+use moka::sync::{Cache, CacheBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+
+/** Header (or gRPC metadata key) clients send their api key in */
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+type DirectRateLimiter = RateLimiter<NotKeyed, InMemoryState, QuantaClock>;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+pub struct ApiQuota {
+    pub requests_per_second: u32,
+    /// Open Subscribe and GetBlocks streams
+    pub max_concurrent_streams: u32,
+    /// Larger page sizes are lowered to this
+    pub max_page_size: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiKeyConfig {
+    /// Used in logs and metrics instead of the key
+    pub name: String,
+    pub key: String,
+    #[serde(flatten)]
+    pub quota: ApiQuota,
+}
+
+/** Limits on RequestOnChainEventRepair calls that fetch from chain. Applied even when `enabled` is false. */
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+pub struct RepairQuota {
+    /// Across all callers
+    pub requests_per_minute: u32,
+    pub requests_per_minute_per_client: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Config {
+    pub enabled: bool,
+    /// Quota for requests without an api key, applied per ip address
+    pub anonymous: ApiQuota,
+    pub api_keys: Vec<ApiKeyConfig>,
+    pub onchain_event_repairs: RepairQuota,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            anonymous: ApiQuota {
+                requests_per_second: 10,
+                max_concurrent_streams: 2,
+                max_page_size: 100,
+            },
+            api_keys: vec![],
+            onchain_event_repairs: RepairQuota {
+                requests_per_minute: 30,
+                requests_per_minute_per_client: 2,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ApiLimitError {
+    #[error("unknown api key")]
+    UnknownApiKey,
+
+    #[error("rate limit exceeded")]
+    RateLimited,
+
+    #[error("too many concurrent streams")]
+    TooManyStreams,
+}
+
+impl ApiLimitError {
+    fn metric_name(&self) -> &'static str {
+        match self {
+            ApiLimitError::UnknownApiKey => "unknown_api_key",
+            ApiLimitError::RateLimited => "rate_limited",
+            ApiLimitError::TooManyStreams => "too_many_streams",
+        }
+    }
+}
+
+impl From<ApiLimitError> for Status {
+    fn from(err: ApiLimitError) -> Self {
+        match err {
+            ApiLimitError::UnknownApiKey => Status::unauthenticated(err.to_string()),
+            ApiLimitError::RateLimited | ApiLimitError::TooManyStreams => {
+                Status::resource_exhausted(err.to_string())
+            }
+        }
+    }
+}
+
+/** The caller of a request, identified by api key or, for anonymous callers, ip address. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiClient {
+    /// Key name, or "anonymous"
+    pub name: String,
+    /// Rate limits and stream counts are tracked per id
+    id: String,
+    pub quota: ApiQuota,
+}
+
+/**
+ * Identifies the caller of an HTTP request that's forwarded to the gRPC service, so the service can
+ * apply per client limits to it.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallerId(pub String);
+
+impl CallerId {
+    pub fn new(client: Option<&ApiClient>, remote_ip: Option<IpAddr>) -> Self {
+        match (client, remote_ip) {
+            (Some(client), _) => CallerId(client.id.clone()),
+            (None, Some(ip)) => CallerId(format!("ip:{}", ip)),
+            (None, None) => CallerId("ip:unknown".to_string()),
+        }
+    }
+
+    pub fn of<T>(request: &Request<T>) -> Self {
+        match request.extensions().get::<CallerId>() {
+            Some(caller) => caller.clone(),
+            None => CallerId::new(
+                request.extensions().get::<ApiClient>(),
+                request.remote_addr().map(|addr| addr.ip()),
+            ),
+        }
+    }
+}
+
+impl ApiClient {
+    pub fn limit_page_size(&self, page_size: Option<u32>) -> Option<u32> {
+        Some(
+            page_size
+                .unwrap_or(self.quota.max_page_size)
+                .min(self.quota.max_page_size),
+        )
+    }
+}
+
+/**
+ * Enforces per client quotas for the gRPC and HTTP servers. Anonymous callers share the
+ * anonymous quota per ip address, callers with an api key get the quota configured for the key.
+ */
+pub struct ApiLimits {
+    enabled: bool,
+    anonymous: ApiQuota,
+    api_keys: HashMap<String, (String, ApiQuota)>,
+    rate_limiters: Cache<String, Arc<DirectRateLimiter>>,
+    streams: Cache<String, Arc<Semaphore>>,
+    repair_quota: RepairQuota,
+    repair_rate_limiter: DirectRateLimiter,
+    repair_rate_limiters: Cache<String, Arc<DirectRateLimiter>>,
+    statsd_client: StatsdClientWrapper,
+}
+
+fn per_minute(requests: u32) -> DirectRateLimiter {
+    RateLimiter::direct(Quota::per_minute(NonZeroU32::new(requests.max(1)).unwrap()))
+}
+
+impl ApiLimits {
+    pub fn new(config: Config, statsd_client: StatsdClientWrapper) -> Self {
+        let api_keys = config
+            .api_keys
+            .into_iter()
+            .map(|api_key| (api_key.key, (api_key.name, api_key.quota)))
+            .collect();
+        ApiLimits {
+            enabled: config.enabled,
+            anonymous: config.anonymous,
+            api_keys,
+            rate_limiters: CacheBuilder::new(1_000_000)
+                .time_to_idle(Duration::from_secs(60))
+                .eviction_policy(EvictionPolicy::lru())
+                .build(),
+            // Semaphores are kept while streams are open, so they must outlive long streams
+            streams: CacheBuilder::new(1_000_000)
+                .time_to_idle(Duration::from_secs(60 * 60 * 24))
+                .build(),
+            repair_quota: config.onchain_event_repairs,
+            repair_rate_limiter: per_minute(config.onchain_event_repairs.requests_per_minute),
+            repair_rate_limiters: CacheBuilder::new(100_000)
+                .time_to_idle(Duration::from_secs(60 * 10))
+                .eviction_policy(EvictionPolicy::lru())
+                .build(),
+            statsd_client,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn reject(
+        &self,
+        server: &str,
+        client: Option<&ApiClient>,
+        err: ApiLimitError,
+    ) -> ApiLimitError {
+        let name = client.map_or("unknown", |client| client.name.as_str());
+        self.statsd_client.count(
+            &format!("{}.api_limits.{}.{}", server, err.metric_name(), name),
+            1,
+        );
+        err
+    }
+
+    pub fn identify(
+        &self,
+        api_key: Option<&str>,
+        remote_ip: Option<IpAddr>,
+    ) -> Result<ApiClient, ApiLimitError> {
+        match api_key {
+            Some(api_key) => match self.api_keys.get(api_key) {
+                Some((name, quota)) => Ok(ApiClient {
+                    name: name.clone(),
+                    id: format!("key:{}", name),
+                    quota: *quota,
+                }),
+                None => Err(ApiLimitError::UnknownApiKey),
+            },
+            None => Ok(ApiClient {
+                name: "anonymous".to_string(),
+                id: match remote_ip {
+                    Some(ip) => format!("ip:{}", ip),
+                    None => "ip:unknown".to_string(),
+                },
+                quota: self.anonymous,
+            }),
+        }
+    }
+
+    /** Identifies the caller and consumes one request from its quota. */
+    pub fn check_request(
+        &self,
+        server: &str,
+        api_key: Option<&str>,
+        remote_ip: Option<IpAddr>,
+    ) -> Result<ApiClient, ApiLimitError> {
+        let client = self
+            .identify(api_key, remote_ip)
+            .map_err(|err| self.reject(server, None, err))?;
+        let rate_limiter = self.rate_limiters.get_with(client.id.clone(), || {
+            Arc::new(RateLimiter::direct(Quota::per_second(
+                NonZeroU32::new(client.quota.requests_per_second.max(1)).unwrap(),
+            )))
+        });
+        if rate_limiter.check().is_err() {
+            return Err(self.reject(server, Some(&client), ApiLimitError::RateLimited));
+        }
+        Ok(client)
+    }
+
+    /** Consumes one onchain event repair from the caller's and the global repair quota. */
+    pub fn check_onchain_event_repair(&self, caller: &CallerId) -> Result<(), ApiLimitError> {
+        let rate_limiter = self.repair_rate_limiters.get_with(caller.0.clone(), || {
+            Arc::new(per_minute(self.repair_quota.requests_per_minute_per_client))
+        });
+        if rate_limiter.check().is_err() || self.repair_rate_limiter.check().is_err() {
+            self.statsd_client
+                .count("rpc.api_limits.onchain_event_repair_rate_limited", 1);
+            return Err(ApiLimitError::RateLimited);
+        }
+        Ok(())
+    }
+
+    /** The returned permit counts against the client's streams until it's dropped. */
+    pub fn acquire_stream(
+        &self,
+        server: &str,
+        client: &ApiClient,
+    ) -> Result<OwnedSemaphorePermit, ApiLimitError> {
+        let semaphore = self.streams.get_with(client.id.clone(), || {
+            Arc::new(Semaphore::new(client.quota.max_concurrent_streams as usize))
+        });
+        semaphore
+            .try_acquire_owned()
+            .map_err(|_| self.reject(server, Some(client), ApiLimitError::TooManyStreams))
+    }
+}
+
+/**
+ * Applies [ApiLimits] to gRPC calls. The [ApiClient] is added to the request extensions so
+ * handlers can apply the page size and stream quotas.
+ */
+#[derive(Clone)]
+pub struct ApiLimitsInterceptor {
+    pub api_limits: Arc<ApiLimits>,
+}
+
+impl Interceptor for ApiLimitsInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if !self.api_limits.enabled() {
+            return Ok(request);
+        }
+        let api_key = request
+            .metadata()
+            .get(API_KEY_HEADER)
+            .and_then(|key| key.to_str().ok());
+        let remote_ip = request.remote_addr().map(|addr| addr.ip());
+        let client = self.api_limits.check_request("rpc", api_key, remote_ip)?;
+        request.extensions_mut().insert(client);
+        request.extensions_mut().insert(self.api_limits.clone());
+        Ok(request)
+    }
+}
+
+/** Requests with a page size that is capped by the client's quota. */
+pub trait PagedRequest {
+    fn page_size_mut(&mut self) -> &mut Option<u32>;
+}
+
+macro_rules! impl_paged_request {
+    ($($request:ty),*) => {
+        $(
+            impl PagedRequest for $request {
+                fn page_size_mut(&mut self) -> &mut Option<u32> {
+                    &mut self.page_size
+                }
+            }
+        )*
+    };
+}
+
+impl_paged_request!(
+    proto::FidRequest,
+    proto::FidTimestampRequest,
+    proto::FidsRequest,
+    proto::CastsByParentRequest,
+    proto::CastsByEmbedRequest,
+    proto::ReactionsByFidRequest,
+    proto::ReactionsByTargetRequest,
+    proto::OnChainEventRequest,
+    proto::LinksByFidRequest,
+    proto::LinksByTargetRequest,
+    proto::EventsRequest,
+    proto::UsernamesByPrefixRequest
+);
+
+/** Lowers the page size to the caller's quota, if the request went through the interceptor. */
+pub fn limit_page_size<T: PagedRequest>(mut request: Request<T>) -> Request<T> {
+    if let Some(client) = request.extensions().get::<ApiClient>().cloned() {
+        let page_size = request.get_mut().page_size_mut();
+        *page_size = client.limit_page_size(*page_size);
+    }
+    request
+}
+
+/** The caller's page size quota, for requests that don't take a page size. */
+pub fn max_results<T>(request: &Request<T>) -> Option<usize> {
+    request
+        .extensions()
+        .get::<ApiClient>()
+        .map(|client| client.quota.max_page_size as usize)
+}
+
+/** Reserves a stream for the caller, if the request went through the interceptor. */
+pub fn acquire_stream<T>(request: &Request<T>) -> Result<Option<OwnedSemaphorePermit>, Status> {
+    match (
+        request.extensions().get::<ApiClient>(),
+        request.extensions().get::<Arc<ApiLimits>>(),
+    ) {
+        (Some(client), Some(api_limits)) => Ok(Some(api_limits.acquire_stream("rpc", client)?)),
+        _ => Ok(None),
+    }
+}
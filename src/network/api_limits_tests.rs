@@ -0,0 +1,212 @@
+#[cfg(test)]
+mod tests {
+    use crate::network::api_limits::{
+        limit_page_size, ApiKeyConfig, ApiLimitError, ApiLimits, ApiLimitsInterceptor, ApiQuota,
+        CallerId, Config, RepairQuota, API_KEY_HEADER,
+    };
+    use crate::network::http_server::limit_query_page_size;
+    use crate::proto;
+    use crate::storage::store::test_helper::statsd_client;
+    use std::net::IpAddr;
+    use std::sync::Arc;
+    use tonic::service::Interceptor;
+    use tonic::{Code, Request};
+
+    const ANONYMOUS: ApiQuota = ApiQuota {
+        requests_per_second: 2,
+        max_concurrent_streams: 1,
+        max_page_size: 10,
+    };
+
+    const PARTNER: ApiQuota = ApiQuota {
+        requests_per_second: 5,
+        max_concurrent_streams: 3,
+        max_page_size: 500,
+    };
+
+    fn api_limits() -> ApiLimits {
+        ApiLimits::new(
+            Config {
+                enabled: true,
+                anonymous: ANONYMOUS,
+                api_keys: vec![ApiKeyConfig {
+                    name: "partner".to_string(),
+                    key: "secret".to_string(),
+                    quota: PARTNER,
+                }],
+                onchain_event_repairs: RepairQuota {
+                    requests_per_minute: 3,
+                    requests_per_minute_per_client: 2,
+                },
+            },
+            statsd_client(),
+        )
+    }
+
+    fn ip(ip: &str) -> Option<IpAddr> {
+        Some(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn test_anonymous_rate_limit_is_per_ip() {
+        let api_limits = api_limits();
+        for _ in 0..ANONYMOUS.requests_per_second {
+            let client = api_limits
+                .check_request("rpc", None, ip("10.0.0.1"))
+                .unwrap();
+            assert_eq!(client.name, "anonymous");
+            assert_eq!(client.quota, ANONYMOUS);
+        }
+        assert_eq!(
+            api_limits.check_request("rpc", None, ip("10.0.0.1")),
+            Err(ApiLimitError::RateLimited)
+        );
+
+        // Other callers have their own quota
+        assert!(api_limits
+            .check_request("rpc", None, ip("10.0.0.2"))
+            .is_ok());
+        assert!(api_limits
+            .check_request("rpc", Some("secret"), ip("10.0.0.1"))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_api_key_quota() {
+        let api_limits = api_limits();
+        for i in 0..PARTNER.requests_per_second {
+            // The quota follows the key, not the ip address
+            let ip = ip(&format!("10.0.0.{}", i));
+            let client = api_limits.check_request("rpc", Some("secret"), ip).unwrap();
+            assert_eq!(client.name, "partner");
+            assert_eq!(client.quota, PARTNER);
+        }
+        assert_eq!(
+            api_limits.check_request("rpc", Some("secret"), ip("10.0.0.1")),
+            Err(ApiLimitError::RateLimited)
+        );
+
+        assert_eq!(
+            api_limits.check_request("rpc", Some("wrong"), ip("10.0.0.1")),
+            Err(ApiLimitError::UnknownApiKey)
+        );
+    }
+
+    #[test]
+    fn test_concurrent_streams() {
+        let api_limits = api_limits();
+        let client = api_limits.identify(None, ip("10.0.0.1")).unwrap();
+
+        let permit = api_limits.acquire_stream("rpc", &client).unwrap();
+        assert_eq!(
+            api_limits.acquire_stream("rpc", &client).err(),
+            Some(ApiLimitError::TooManyStreams)
+        );
+
+        drop(permit);
+        assert!(api_limits.acquire_stream("rpc", &client).is_ok());
+    }
+
+    #[test]
+    fn test_onchain_event_repair_limits() {
+        let api_limits = api_limits();
+        let caller = |ip_address| CallerId::new(None, ip(ip_address));
+
+        for _ in 0..2 {
+            assert!(api_limits
+                .check_onchain_event_repair(&caller("10.0.0.1"))
+                .is_ok());
+        }
+        assert_eq!(
+            api_limits.check_onchain_event_repair(&caller("10.0.0.1")),
+            Err(ApiLimitError::RateLimited)
+        );
+
+        // Other callers share the global quota
+        assert!(api_limits
+            .check_onchain_event_repair(&caller("10.0.0.2"))
+            .is_ok());
+        assert_eq!(
+            api_limits.check_onchain_event_repair(&caller("10.0.0.3")),
+            Err(ApiLimitError::RateLimited)
+        );
+
+        // Callers with an api key are limited by key rather than ip address
+        let client = api_limits.identify(Some("secret"), ip("10.0.0.4")).unwrap();
+        assert_eq!(
+            CallerId::new(Some(&client), ip("10.0.0.4")),
+            CallerId::new(Some(&client), ip("10.0.0.5"))
+        );
+    }
+
+    #[test]
+    fn test_interceptor() {
+        let mut interceptor = ApiLimitsInterceptor {
+            api_limits: Arc::new(api_limits()),
+        };
+
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert(API_KEY_HEADER, "wrong".parse().unwrap());
+        let status = interceptor.call(request).unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // Requests from the interceptor get the caller's page size limit
+        let request = interceptor.call(Request::new(())).unwrap();
+        let (metadata, extensions, _) = request.into_parts();
+        let request = Request::from_parts(
+            metadata,
+            extensions,
+            proto::FidRequest {
+                fid: 1,
+                page_size: Some(1000),
+                ..Default::default()
+            },
+        );
+        let request = limit_page_size(request);
+        assert_eq!(request.get_ref().page_size, Some(ANONYMOUS.max_page_size));
+
+        // Requests that didn't go through the interceptor are not limited
+        let request = limit_page_size(Request::new(proto::FidRequest {
+            fid: 1,
+            page_size: Some(1000),
+            ..Default::default()
+        }));
+        assert_eq!(request.get_ref().page_size, Some(1000));
+    }
+
+    #[test]
+    fn test_limit_query_page_size() {
+        let api_limits = api_limits();
+        let client = api_limits.identify(None, None).unwrap();
+
+        assert_eq!(
+            limit_query_page_size("fid=1&pageSize=100", &client),
+            "fid=1&pageSize=10"
+        );
+        assert_eq!(
+            limit_query_page_size("fid=1&page_size=5", &client),
+            "fid=1&page_size=5"
+        );
+        assert_eq!(
+            limit_query_page_size("fid=1", &client),
+            "fid=1&page_size=10"
+        );
+        assert_eq!(limit_query_page_size("", &client), "page_size=10");
+
+        // Encoded keys and values are limited like plain ones
+        assert_eq!(
+            limit_query_page_size("fid=1&page%5Fsize=100", &client),
+            "fid=1&page_size=10"
+        );
+        assert_eq!(
+            limit_query_page_size("fid=1&pageSize=%31%30%30", &client),
+            "fid=1&pageSize=10"
+        );
+        assert_eq!(
+            limit_query_page_size("fid=1&pageSize=1e9", &client),
+            "fid=1&pageSize=10"
+        );
+    }
+}
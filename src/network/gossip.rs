@@ -159,6 +159,7 @@ pub struct SnapchainGossip {
     system_tx: Sender<SystemMessage>,
     sync_channels: HashMap<InboundRequestId, sync::ResponseChannel>,
     read_node: bool,
+    archive_mode: bool,
     enable_autodiscovery: bool,
     bootstrap_addrs: HashSet<String>,
     connected_bootstrap_addrs: HashSet<String>,
@@ -175,6 +176,7 @@ impl SnapchainGossip {
         config: &Config,
         system_tx: Sender<SystemMessage>,
         read_node: bool,
+        archive_mode: bool,
         fc_network: FarcasterNetwork,
         statsd_client: StatsdClientWrapper,
     ) -> Result<Self, Box<dyn std::error::Error>> {
@@ -303,6 +305,7 @@ impl SnapchainGossip {
             system_tx,
             sync_channels: HashMap::new(),
             read_node,
+            archive_mode,
             bootstrap_addrs: config.bootstrap_addrs().into_iter().collect(),
             announce_address,
             fc_network,
@@ -387,6 +390,7 @@ impl SnapchainGossip {
                     .duration_since(std::time::UNIX_EPOCH)
                     .unwrap()
                     .as_millis() as u64,
+                archive_mode: self.archive_mode,
             }),
         };
 
@@ -584,6 +588,7 @@ impl SnapchainGossip {
         info!(
             peer_id = peer_id.to_string(),
             ip = contact_info_body.gossip_address,
+            archive_mode = contact_info_body.archive_mode,
             "Received contact info from peer"
         );
 
@@ -95,6 +95,7 @@ async fn test_gossip_communication() {
         &config1,
         system_tx1,
         true,
+        false,
         FarcasterNetwork::Devnet,
         statsd_client(),
     )
@@ -105,6 +106,7 @@ async fn test_gossip_communication() {
         &config2,
         system_tx2,
         false,
+        false,
         FarcasterNetwork::Devnet,
         statsd_client(),
     )
@@ -116,6 +118,7 @@ async fn test_gossip_communication() {
         &config3,
         system_tx3,
         false,
+        false,
         FarcasterNetwork::Devnet,
         statsd_client(),
     )
@@ -194,6 +197,7 @@ async fn test_bootstrap_peer_reconnection() {
         &config2,
         system_tx2,
         false,
+        false,
         FarcasterNetwork::Devnet,
         statsd_client(),
     )
@@ -210,6 +214,7 @@ async fn test_bootstrap_peer_reconnection() {
         &config1,
         system_tx1,
         false,
+        false,
         FarcasterNetwork::Devnet,
         statsd_client(),
     )
@@ -237,6 +242,7 @@ async fn test_bootstrap_peer_reconnection() {
         &Config::new(node2_addr.clone(), node1_addr.clone()),
         system_tx2,
         false,
+        false,
         FarcasterNetwork::Devnet,
         statsd_client(),
     )
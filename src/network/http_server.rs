@@ -1,16 +1,30 @@
+use axum::body::Bytes;
+use axum::extract::{
+    ConnectInfo, DefaultBodyLimit, Extension, FromRequest, FromRequestParts, MatchedPath, State,
+};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::request::Parts;
+use axum::http::uri::PathAndQuery;
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, MethodRouter};
+use axum::Json;
 use base64::prelude::*;
-use http_body_util::combinators::BoxBody;
-use http_body_util::{BodyExt, Full};
-use hyper::header::HeaderValue;
-use hyper::{body::Bytes, Method};
-use hyper::{HeaderMap, Request, Response, StatusCode};
+use futures::StreamExt;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::convert::Infallible;
-use std::future::Future;
+use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tonic::async_trait;
 use tonic::metadata::MetadataValue;
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::timeout::TimeoutLayer;
 
 use crate::proto::{
     self, embed, hub_service_server::HubService, link_body::Target, message_data::Body, CastType,
@@ -22,18 +36,35 @@ use crate::proto::{
     reaction_request, reactions_by_target_request, Protocol,
 };
 use crate::storage::store::account::message_decode;
+use crate::utils::statsd_wrapper::StatsdClientWrapper;
 
+use super::api_limits::{ApiClient, ApiLimitError, ApiLimits, CallerId, API_KEY_HEADER};
+use super::openapi::{OPENAPI_DOCUMENT, OPENAPI_PATH};
 use super::server::MyHubService;
 
+/** Upper bound on blocks returned by one call to /v1/blocks */
+const MAX_BLOCKS_PER_REQUEST: usize = 100;
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
     cors_origin: String,
+    /// Requests that take longer than this are answered with a 408
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+    /// Largest request body accepted, in bytes
+    pub max_request_body_size: usize,
+    /// How long to wait for in-flight requests to finish when the node shuts down
+    #[serde(with = "humantime_serde")]
+    pub shutdown_timeout: Duration,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             cors_origin: "*".to_string(),
+            request_timeout: Duration::from_secs(30),
+            max_request_body_size: 2 * 1024 * 1024,
+            shutdown_timeout: Duration::from_secs(10),
         }
     }
 }
@@ -104,20 +135,21 @@ mod serdehex {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Message {
     pub data: MessageData,
     pub hash: String,
     #[serde(rename = "hashScheme")]
     pub hash_scheme: String,
     #[serde(with = "serdebase64")]
+    #[schemars(with = "String")]
     pub signature: Vec<u8>,
     #[serde(rename = "signatureScheme")]
     pub signature_scheme: String,
     pub signer: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct MessageData {
     #[serde(rename = "type")]
     pub message_type: String,
@@ -155,25 +187,25 @@ pub struct MessageData {
     pub link_compact_state_body: Option<LinkCompactStateBody>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum EmbedUrlOrCastId {
     Url(EmbedUrl),
     CastId(EmbedCastId),
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct EmbedUrl {
     pub url: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct EmbedCastId {
     #[serde(rename = "castId")]
     pub cast_id: CastId,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct CastAddBody {
     #[serde(rename = "embedsDeprecated")]
     pub embeds_deprecated: Vec<String>,
@@ -190,13 +222,14 @@ pub struct CastAddBody {
     pub cast_type: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct CastRemoveBody {
     #[serde(rename = "targetHash", with = "serdebase64")]
+    #[schemars(with = "String")]
     pub target_hash: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ReactionBody {
     #[serde(rename = "type")]
     pub reaction_type: String,
@@ -206,10 +239,11 @@ pub struct ReactionBody {
     pub target_url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct VerificationAddAddressBody {
     pub address: String,
     #[serde(rename = "claimSignature", with = "serdebase64")]
+    #[schemars(with = "String")]
     pub claim_signature: Vec<u8>,
     #[serde(rename = "blockHash")]
     pub block_hash: String,
@@ -220,20 +254,20 @@ pub struct VerificationAddAddressBody {
     pub protocol: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct VerificationRemoveBody {
     pub address: String,
     pub protocol: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct UserDataBody {
     #[serde(rename = "type")]
     pub user_data_type: String,
     pub value: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LinkBody {
     #[serde(rename = "type")]
     pub link_type: String,
@@ -243,12 +277,13 @@ pub struct LinkBody {
     pub target_fid: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct UsernameProofBody {
     pub timestamp: u64,
     pub name: String,
     pub owner: String,
     #[serde(with = "serdebase64")]
+    #[schemars(with = "String")]
     pub signature: Vec<u8>,
     pub fid: u64,
     #[serde(rename = "type")]
@@ -256,25 +291,30 @@ pub struct UsernameProofBody {
 }
 
 // Serialize as base64 strings for compatibility with hubs
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FrameActionBody {
     #[serde(with = "serdebase64")]
+    #[schemars(with = "String")]
     pub url: Vec<u8>,
     #[serde(rename = "buttonIndex")]
     pub button_index: u32,
     #[serde(rename = "castId", skip_serializing_if = "Option::is_none")]
     pub cast_id: Option<CastId>,
     #[serde(with = "serdebase64", rename = "inputText")]
+    #[schemars(with = "String")]
     pub input_text: Vec<u8>,
     #[serde(with = "serdebase64")]
+    #[schemars(with = "String")]
     pub state: Vec<u8>,
     #[serde(with = "serdebase64", rename = "transactionId")]
+    #[schemars(with = "String")]
     pub transaction_id: Vec<u8>,
     #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
     pub address: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LinkCompactStateBody {
     #[serde(rename = "type")]
     pub link_compact_type: String,
@@ -282,29 +322,29 @@ pub struct LinkCompactStateBody {
     pub target_fids: Vec<u64>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct CastId {
     pub fid: u64,
     pub hash: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct PagedResponse {
     pub messages: Vec<Message>,
     #[serde(rename = "nextPageToken", skip_serializing_if = "Option::is_none")]
     pub next_page_token: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct IdRequest {
     pub fid: String,
     pub hash: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct InfoRequest {} // Doesn't take dbstats not sure if issue
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct InfoResponse {
     #[serde(rename = "dbStats", skip_serializing_if = "Option::is_none")]
     pub db_stats: Option<DbStats>,
@@ -315,9 +355,33 @@ pub struct InfoResponse {
     pub version: String,
     #[serde(rename = "peer_id")]
     pub peer_id: String,
+    #[serde(rename = "archiveMode")]
+    pub archive_mode: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct RocksDbProperties {
+    #[serde(rename = "blockCacheCapacity")]
+    pub block_cache_capacity: u64,
+    #[serde(rename = "blockCacheUsage")]
+    pub block_cache_usage: u64,
+    #[serde(rename = "blockCachePinnedUsage")]
+    pub block_cache_pinned_usage: u64,
+    #[serde(rename = "numRunningCompactions")]
+    pub num_running_compactions: u64,
+    #[serde(rename = "numRunningFlushes")]
+    pub num_running_flushes: u64,
+    #[serde(rename = "numSnapshots")]
+    pub num_snapshots: u64,
+    #[serde(rename = "backgroundErrors")]
+    pub background_errors: u64,
+    #[serde(rename = "actualDelayedWriteRate")]
+    pub actual_delayed_write_rate: u64,
+    #[serde(rename = "isWriteStopped")]
+    pub is_write_stopped: bool,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct DbStats {
     #[serde(rename = "numMessages")]
     pub num_messages: u64,
@@ -325,9 +389,11 @@ pub struct DbStats {
     pub num_fid_registrations: u64,
     #[serde(rename = "approxSize")]
     pub approx_size: u64,
+    #[serde(rename = "rocksdbProperties", skip_serializing_if = "Option::is_none")]
+    pub rocksdb_properties: Option<RocksDbProperties>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ShardInfo {
     #[serde(rename = "shardId")]
     pub shard_id: u32,
@@ -343,10 +409,12 @@ pub struct ShardInfo {
     pub block_delay: u64,
     #[serde(rename = "mempoolSize")]
     pub mempool_size: u64,
+    #[serde(rename = "rocksdbProperties", skip_serializing_if = "Option::is_none")]
+    pub rocksdb_properties: Option<RocksDbProperties>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct GetFidsRequest {
     pub shard_id: u32,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -356,6 +424,7 @@ pub struct GetFidsRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub page_token: Option<Vec<u8>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub reverse: Option<bool>,
@@ -368,6 +437,7 @@ pub struct GetFidsRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub pageToken: Option<Vec<u8>>,
 }
 
@@ -382,7 +452,7 @@ impl GetFidsRequest {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct GetFidsResponse {
     pub fids: Vec<u64>,
     #[serde(rename = "nextPageToken", skip_serializing_if = "Option::is_none")]
@@ -390,7 +460,7 @@ pub struct GetFidsResponse {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct FidRequest {
     pub fid: u64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -400,6 +470,7 @@ pub struct FidRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub page_token: Option<Vec<u8>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub reverse: Option<bool>,
@@ -412,6 +483,7 @@ pub struct FidRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub pageToken: Option<Vec<u8>>,
 }
 
@@ -427,7 +499,7 @@ impl FidRequest {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct FidTimestampRequest {
     pub fid: u64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -437,6 +509,7 @@ pub struct FidTimestampRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub page_token: Option<Vec<u8>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub reverse: Option<bool>,
@@ -453,6 +526,7 @@ pub struct FidTimestampRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub pageToken: Option<Vec<u8>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub startTimestamp: Option<u64>,
@@ -474,7 +548,7 @@ impl FidTimestampRequest {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct CastsByParentRequest {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub fid: Option<u64>,
@@ -489,9 +563,14 @@ pub struct CastsByParentRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub page_token: Option<Vec<u8>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub reverse: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_timestamp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_timestamp: Option<u64>,
 
     // For backwards compatibility
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -501,6 +580,7 @@ pub struct CastsByParentRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub pageToken: Option<Vec<u8>>,
 }
 
@@ -514,11 +594,89 @@ impl CastsByParentRequest {
             page_size: self.page_size.or(self.pageSize),
             page_token: self.page_token.or(self.pageToken),
             reverse: self.reverse,
+            start_timestamp: self.start_timestamp,
+            stop_timestamp: self.stop_timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CastsByEmbedRequest {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u32>,
+    #[serde(
+        default,
+        with = "serdebase64opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schemars(with = "Option<String>")]
+    pub page_token: Option<Vec<u8>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverse: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_timestamp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_timestamp: Option<u64>,
+}
+
+impl CastsByEmbedRequest {
+    pub fn to_proto(self) -> proto::CastsByEmbedRequest {
+        proto::CastsByEmbedRequest {
+            url: self.url,
+            page_size: self.page_size,
+            page_token: self.page_token,
+            reverse: self.reverse,
+            start_timestamp: self.start_timestamp,
+            stop_timestamp: self.stop_timestamp,
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CastStatsRequest {
+    /// Passed as cast_ids[0][fid]=..&cast_ids[0][hash]=..
+    #[serde(default)]
+    pub cast_ids: Vec<CastId>,
+}
+
+impl CastStatsRequest {
+    pub fn to_proto(self) -> Result<proto::CastStatsRequest, ErrorResponse> {
+        let cast_ids = self
+            .cast_ids
+            .into_iter()
+            .map(|cast_id| {
+                let hash = hex::decode(cast_id.hash.trim_start_matches("0x")).map_err(|e| {
+                    ErrorResponse {
+                        error: "Invalid hash".to_string(),
+                        error_detail: Some(e.to_string()),
+                    }
+                })?;
+                Ok(proto::CastId {
+                    fid: cast_id.fid,
+                    hash,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(proto::CastStatsRequest { cast_ids })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CastStats {
+    #[serde(rename = "castId")]
+    pub cast_id: CastId,
+    pub likes: u64,
+    pub recasts: u64,
+    pub replies: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CastStatsResponse {
+    pub stats: Vec<CastStats>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ReactionRequest {
     fid: u64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -527,13 +685,15 @@ pub struct ReactionRequest {
     target_fid: Option<u64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     target_hash: Option<String>,
+    #[schemars(with = "String")]
     reaction_type: ReactionType,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ReactionsByFidRequest {
     fid: u64,
+    #[schemars(with = "String")]
     reaction_type: ReactionType,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     page_size: Option<u32>,
@@ -542,6 +702,7 @@ pub struct ReactionsByFidRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     page_token: Option<Vec<u8>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     reverse: Option<bool>,
@@ -554,6 +715,7 @@ pub struct ReactionsByFidRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub pageToken: Option<Vec<u8>>,
 }
 
@@ -570,12 +732,13 @@ impl ReactionsByFidRequest {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ReactionsByCastRequest {
     pub target_fid: u64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub target_hash: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
     pub reaction_type: Option<ReactionType>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub page_size: Option<u32>,
@@ -584,6 +747,7 @@ pub struct ReactionsByCastRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub page_token: Option<Vec<u8>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub reverse: Option<bool>,
@@ -596,6 +760,7 @@ pub struct ReactionsByCastRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub pageToken: Option<Vec<u8>>,
 }
 
@@ -615,13 +780,14 @@ impl ReactionsByCastRequest {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ReactionsByTargetRequest {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub target_cast_id: Option<CastId>,
     #[serde(default, rename = "url", skip_serializing_if = "Option::is_none")]
     pub target_url: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
     pub reaction_type: Option<ReactionType>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub page_size: Option<u32>,
@@ -630,6 +796,7 @@ pub struct ReactionsByTargetRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub page_token: Option<Vec<u8>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub reverse: Option<bool>,
@@ -642,6 +809,7 @@ pub struct ReactionsByTargetRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub pageToken: Option<Vec<u8>>,
 }
 
@@ -660,7 +828,7 @@ impl ReactionsByTargetRequest {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct LinkRequest {
     fid: u64,
     link_type: String,
@@ -669,7 +837,7 @@ pub struct LinkRequest {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct LinksByFidRequest {
     fid: u64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -681,6 +849,7 @@ pub struct LinksByFidRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     page_token: Option<Vec<u8>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     reverse: Option<bool>,
@@ -693,6 +862,7 @@ pub struct LinksByFidRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub pageToken: Option<Vec<u8>>,
 }
 
@@ -709,7 +879,7 @@ impl LinksByFidRequest {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct LinksByTargetRequest {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     target_fid: Option<u64>,
@@ -722,6 +892,7 @@ pub struct LinksByTargetRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     page_token: Option<Vec<u8>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     reverse: Option<bool>,
@@ -734,6 +905,7 @@ pub struct LinksByTargetRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub pageToken: Option<Vec<u8>>,
 }
 
@@ -749,13 +921,13 @@ impl LinksByTargetRequest {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub enum StorageUnitType {
     UnitTypeLegacy = 0,
     UnitType2024 = 1,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct StorageUnitDetails {
     #[serde(rename = "unitType")]
     unit_type: StorageUnitType,
@@ -763,13 +935,13 @@ pub struct StorageUnitDetails {
     unit_size: u32,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct TierDetails {
     tier_type: TierType,
     expires_at: u64,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub enum StoreType {
     None = 0,
     Casts = 1,
@@ -780,7 +952,7 @@ pub enum StoreType {
     UsernameProofs = 6,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct StorageLimit {
     #[serde(rename = "storeType")]
     pub store_type: StoreType,
@@ -793,7 +965,7 @@ pub struct StorageLimit {
     pub earliest_hash: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct StorageLimitsResponse {
     pub limits: Vec<StorageLimit>,
     pub units: u32,
@@ -802,28 +974,113 @@ pub struct StorageLimitsResponse {
     pub tier_subscriptions: Vec<TierDetails>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct TierPurchasePeriod {
+    pub purchase: OnChainEvent,
+    #[serde(rename = "startsAt")]
+    pub starts_at: u64,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct TierStatus {
+    #[serde(rename = "tierType")]
+    pub tier_type: TierType,
+    pub active: bool,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: u64,
+    pub purchases: Vec<TierPurchasePeriod>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct TierStatusResponse {
+    pub tiers: Vec<TierStatus>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ProfileRequest {
+    pub fid: u64,
+}
+
+impl ProfileRequest {
+    pub fn to_proto(self) -> proto::ProfileRequest {
+        proto::ProfileRequest { fid: self.fid }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ProfileResponse {
+    pub fid: u64,
+    #[serde(rename = "userData")]
+    pub user_data: Vec<Message>,
+    #[serde(rename = "primaryAddressEthereum")]
+    pub primary_address_ethereum: String,
+    #[serde(rename = "primaryAddressSolana")]
+    pub primary_address_solana: String,
+    pub verifications: Vec<Message>,
+    #[serde(rename = "storageLimits")]
+    pub storage_limits: StorageLimitsResponse,
+    #[serde(rename = "followerCount")]
+    pub follower_count: u64,
+    #[serde(rename = "followingCount")]
+    pub following_count: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct UsernameProofRequest {
     name: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct UserNameProof {
     pub timestamp: u64,
     pub name: String,
     pub owner: String,
     #[serde(with = "serdebase64")]
+    #[schemars(with = "String")]
     pub signature: Vec<u8>,
     pub fid: u64,
     pub r#type: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct UsernameProofsResponse {
     pub proofs: Vec<UserNameProof>,
 }
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct UsernamesByPrefixRequest {
+    pub prefix: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u32>,
+    #[serde(
+        default,
+        with = "serdebase64opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schemars(with = "Option<String>")]
+    pub page_token: Option<Vec<u8>>,
+}
+
+impl UsernamesByPrefixRequest {
+    pub fn to_proto(self) -> proto::UsernamesByPrefixRequest {
+        proto::UsernamesByPrefixRequest {
+            prefix: self.prefix,
+            page_size: self.page_size,
+            page_token: self.page_token,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct UsernamesByPrefixResponse {
+    pub proofs: Vec<UserNameProof>,
+    #[serde(rename = "nextPageToken", skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+}
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub enum OnChainEventType {
     EVENT_TYPE_NONE = 0,
     EVENT_TYPE_SIGNER = 1,
@@ -833,7 +1090,7 @@ pub enum OnChainEventType {
     EVENT_TYPE_TIER_PURCHASE = 5,
 }
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub enum SignerEventType {
     SIGNER_EVENT_TYPE_NONE = 0,
     SIGNER_EVENT_TYPE_ADD = 1,
@@ -841,13 +1098,13 @@ pub enum SignerEventType {
     SIGNER_EVENT_TYPE_ADMIN_RESET = 3,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub enum TierType {
     None = 0,
     Pro = 1,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub enum IdRegisterEventType {
     None = 0,
     Register = 1,
@@ -855,55 +1112,62 @@ pub enum IdRegisterEventType {
     ChangeRecovery = 3,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct SignerEventBody {
     #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
     pub key: Vec<u8>,
     #[serde(rename = "keyType")]
     pub key_type: u32,
     #[serde(rename = "eventType")]
     pub event_type: SignerEventType,
     #[serde(with = "serdebase64")]
+    #[schemars(with = "String")]
     pub metadata: Vec<u8>,
     #[serde(rename = "metadataType")]
     pub metadata_type: u32,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct SignerMigratedEventBody {
     #[serde(rename = "migratedAt")]
     pub migrated_at: u32,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct IdRegisterEventBody {
     #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
     pub to: Vec<u8>,
     #[serde(rename = "eventType")]
     pub event_type: IdRegisterEventType,
     #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
     pub from: Vec<u8>,
     #[serde(with = "serdehex", rename = "recoveryAddress")]
+    #[schemars(with = "String")]
     pub recovery_address: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct StorageRentEventBody {
     #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
     pub payer: Vec<u8>,
     pub units: u32,
     pub expiry: u32,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct TierPurchaseEventBody {
     #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
     pub payer: Vec<u8>,
     pub for_days: u64,
     pub tier_type: TierType,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct OnChainEvent {
     pub r#type: OnChainEventType,
     #[serde(rename = "chainId")]
@@ -911,10 +1175,12 @@ pub struct OnChainEvent {
     #[serde(rename = "blockNumber")]
     pub block_number: u32,
     #[serde(with = "serdehex", rename = "blockHash")]
+    #[schemars(with = "String")]
     pub block_hash: Vec<u8>,
     #[serde(rename = "blockTimestamp")]
     pub block_timestamp: u64,
     #[serde(with = "serdehex", rename = "transactionHash")]
+    #[schemars(with = "String")]
     pub transaction_hash: Vec<u8>,
     #[serde(rename = "logIndex")]
     pub log_index: u32,
@@ -942,18 +1208,37 @@ pub struct OnChainEvent {
     pub version: u32,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct OnChainEventResponse {
     pub events: Vec<OnChainEvent>,
     #[serde(rename = "nextPageToken", skip_serializing_if = "Option::is_none")]
     pub next_page_token: Option<String>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct OnChainEventRepairRequest {
+    pub fid: u64,
+}
+
+impl OnChainEventRepairRequest {
+    pub fn to_proto(self) -> proto::OnChainEventRepairRequest {
+        proto::OnChainEventRepairRequest { fid: self.fid }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct OnChainEventRepairResponse {
+    #[serde(rename = "missingEvents")]
+    pub missing_events: Vec<OnChainEvent>,
+    pub resubmitted: bool,
+}
+
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct FidAddressTypeRequest {
     pub fid: u64,
     #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
     pub address: Vec<u8>,
 }
 
@@ -967,7 +1252,7 @@ impl FidAddressTypeRequest {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct FidAddressTypeResponse {
     pub is_custody: bool,
     pub is_auth: bool,
@@ -975,7 +1260,7 @@ pub struct FidAddressTypeResponse {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct OnChainEventRequest {
     fid: u64,
     event_type: OnChainEventType,
@@ -986,6 +1271,7 @@ pub struct OnChainEventRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     page_token: Option<Vec<u8>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     reverse: Option<bool>,
@@ -998,6 +1284,7 @@ pub struct OnChainEventRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub pageToken: Option<Vec<u8>>,
 }
 
@@ -1014,7 +1301,7 @@ impl OnChainEventRequest {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub enum HubEventType {
     HUB_EVENT_TYPE_NONE = 0,
     HUB_EVENT_TYPE_MERGE_MESSAGE = 1,
@@ -1024,39 +1311,40 @@ pub enum HubEventType {
     HUB_EVENT_TYPE_MERGE_ON_CHAIN_EVENT = 9,
     HUB_EVENT_TYPE_MERGE_FAILURE = 10,
     HUB_EVENT_TYPE_BLOCK_CONFIRMED = 11,
+    HUB_EVENT_TYPE_TIER_EXPIRED = 12,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct MergeMessageBody {
     pub message: Message,
     #[serde(rename = "deletedMessages")]
     pub deleted_messages: Vec<Message>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct MergeFailureBody {
     pub message: Message,
     pub code: String,
     pub reason: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct PruneMessageBody {
     pub message: Message,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct RevokeMessageBody {
     pub message: Message,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct MergeOnChainEventBody {
     #[serde(rename = "onChainEvent")]
     pub on_chain_event: OnChainEvent,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct MergeUsernameProofBody {
     #[serde(rename = "usernameProof", skip_serializing_if = "Option::is_none")]
     pub username_proof: Option<UsernameProofBody>,
@@ -1077,7 +1365,7 @@ pub struct MergeUsernameProofBody {
     pub deleted_username_proof_message: Option<Message>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct BlockConfirmedBody {
     #[serde(rename = "blockNumber")]
     pub block_number: u64,
@@ -1085,12 +1373,22 @@ pub struct BlockConfirmedBody {
     pub shard_index: u32,
     pub timestamp: u64,
     #[serde(rename = "blockHash", with = "serdehex")]
+    #[schemars(with = "String")]
     pub block_hash: Vec<u8>,
     #[serde(rename = "totalEvents")]
     pub total_events: u64,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct TierExpiredBody {
+    pub fid: u64,
+    #[serde(rename = "tierType")]
+    pub tier_type: TierType,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct HubEvent {
     #[serde(rename = "type")]
     pub hub_event_type: String,
@@ -1115,13 +1413,15 @@ pub struct HubEvent {
     pub merge_failure_body: Option<MergeFailureBody>,
     #[serde(rename = "blockConfirmedBody", skip_serializing_if = "Option::is_none")]
     pub block_confirmed_body: Option<BlockConfirmedBody>,
+    #[serde(rename = "tierExpiredBody", skip_serializing_if = "Option::is_none")]
+    pub tier_expired_body: Option<TierExpiredBody>,
     #[serde(rename = "blockNumber")]
     pub block_number: u64,
     #[serde(rename = "shardIndex")]
     pub shard_index: u32,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct EventsResponse {
     pub events: Vec<HubEvent>,
     // TODO: What's the best way to support next page token with multiple shards?
@@ -1130,7 +1430,7 @@ pub struct EventsResponse {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct EventsRequest {
     #[serde(default, rename = "from_event_id")] // To keep it consistent with hubble
     start_id: u64,
@@ -1145,6 +1445,7 @@ pub struct EventsRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     page_token: Option<Vec<u8>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     reverse: Option<bool>,
@@ -1157,6 +1458,7 @@ pub struct EventsRequest {
         with = "serdebase64opt",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<String>")]
     pub pageToken: Option<Vec<u8>>,
 }
 
@@ -1173,7 +1475,7 @@ impl EventsRequest {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct EventRequest {
     event_id: u64,
     shard_index: u32,
@@ -1187,9 +1489,10 @@ impl EventRequest {
         }
     }
 }
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct IdRegistryEventByAddressRequest {
     #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
     pub address: Vec<u8>,
 }
 
@@ -1201,14 +1504,297 @@ impl IdRegistryEventByAddressRequest {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct UserDataRequest {
+    pub fid: u64,
+    #[schemars(with = "String")]
+    pub user_data_type: UserDataType,
+}
+
+impl UserDataRequest {
+    pub fn to_proto(self) -> proto::UserDataRequest {
+        proto::UserDataRequest {
+            fid: self.fid,
+            user_data_type: self.user_data_type as i32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct VerificationRequest {
+    pub fid: u64,
+    #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
+    pub address: Vec<u8>,
+}
+
+impl VerificationRequest {
+    pub fn to_proto(self) -> proto::VerificationRequest {
+        proto::VerificationRequest {
+            fid: self.fid,
+            address: self.address,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct FidsByVerifiedAddressRequest {
+    /// 0x prefixed hex for Ethereum, base58 for Solana
+    pub address: String,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub protocol: Protocol,
+}
+
+impl FidsByVerifiedAddressRequest {
+    pub fn to_proto(self) -> Result<proto::FidsByVerifiedAddressRequest, ErrorResponse> {
+        let address = match self.protocol {
+            Protocol::Ethereum => {
+                hex::decode(self.address.trim_start_matches("0x")).map_err(|e| e.to_string())
+            }
+            Protocol::Solana => bs58::decode(&self.address)
+                .into_vec()
+                .map_err(|e| e.to_string()),
+        }
+        .map_err(|e| ErrorResponse {
+            error: "Invalid address".to_string(),
+            error_detail: Some(e),
+        })?;
+        Ok(proto::FidsByVerifiedAddressRequest {
+            address,
+            protocol: self.protocol as i32,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct SignerRequest {
+    pub fid: u64,
+    #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
+    pub signer: Vec<u8>,
+}
+
+impl SignerRequest {
+    pub fn to_proto(self) -> proto::SignerRequest {
+        proto::SignerRequest {
+            fid: self.fid,
+            signer: self.signer,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct BlocksRequest {
+    #[serde(default)]
+    pub shard_id: u32,
+    pub start_block_number: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_block_number: Option<u64>,
+}
+
+impl BlocksRequest {
+    pub fn to_proto(self) -> proto::BlocksRequest {
+        proto::BlocksRequest {
+            shard_id: self.shard_id,
+            start_block_number: self.start_block_number,
+            stop_block_number: self.stop_block_number,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ShardChunksRequest {
+    pub shard_id: u32,
+    pub start_block_number: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_block_number: Option<u64>,
+}
+
+impl ShardChunksRequest {
+    pub fn to_proto(self) -> proto::ShardChunksRequest {
+        proto::ShardChunksRequest {
+            shard_id: self.shard_id,
+            start_block_number: self.start_block_number,
+            stop_block_number: self.stop_block_number,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct TrieNodeMetadataRequest {
+    pub shard_id: u32,
+    #[serde(default, with = "serdehex")]
+    #[schemars(with = "String")]
+    pub prefix: Vec<u8>,
+}
+
+impl TrieNodeMetadataRequest {
+    pub fn to_proto(self) -> proto::TrieNodeMetadataRequest {
+        proto::TrieNodeMetadataRequest {
+            shard_id: self.shard_id,
+            prefix: self.prefix,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct TrieNodeMetadataResponse {
+    #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
+    pub prefix: Vec<u8>,
+    #[serde(rename = "numMessages")]
+    pub num_messages: u64,
+    pub hash: String,
+    pub children: Vec<TrieNodeMetadataResponse>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct Height {
+    #[serde(rename = "shardIndex")]
+    pub shard_index: u32,
+    #[serde(rename = "blockNumber")]
+    pub block_number: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CommitSignature {
+    #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
+    pub signer: Vec<u8>,
+    #[serde(with = "serdebase64")]
+    #[schemars(with = "String")]
+    pub signature: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct Commits {
+    pub height: Option<Height>,
+    pub round: i64,
+    #[serde(rename = "shardIndex")]
+    pub shard_index: u32,
+    #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
+    pub hash: Vec<u8>,
+    pub signatures: Vec<CommitSignature>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct BlockHeader {
+    pub height: Option<Height>,
+    pub timestamp: u64,
+    pub version: u32,
+    #[serde(rename = "chainId")]
+    pub chain_id: String,
+    #[serde(rename = "shardWitnessesHash", with = "serdehex")]
+    #[schemars(with = "String")]
+    pub shard_witnesses_hash: Vec<u8>,
+    #[serde(rename = "parentHash", with = "serdehex")]
+    #[schemars(with = "String")]
+    pub parent_hash: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ShardChunkWitness {
+    pub height: Option<Height>,
+    #[serde(rename = "shardRoot", with = "serdehex")]
+    #[schemars(with = "String")]
+    pub shard_root: Vec<u8>,
+    #[serde(rename = "shardHash", with = "serdehex")]
+    #[schemars(with = "String")]
+    pub shard_hash: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct Block {
+    pub header: Option<BlockHeader>,
+    #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
+    pub hash: Vec<u8>,
+    #[serde(rename = "shardChunkWitnesses")]
+    pub shard_chunk_witnesses: Vec<ShardChunkWitness>,
+    pub commits: Option<Commits>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct BlocksResponse {
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct FnameTransfer {
+    pub id: u64,
+    #[serde(rename = "fromFid")]
+    pub from_fid: u64,
+    pub proof: Option<UserNameProof>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct StoragePrune {
+    pub fid: u64,
+    pub timestamp: u64,
+    #[serde(rename = "blockNumber")]
+    pub block_number: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ValidatorMessage {
+    #[serde(rename = "onChainEvent", skip_serializing_if = "Option::is_none")]
+    pub on_chain_event: Option<OnChainEvent>,
+    #[serde(rename = "fnameTransfer", skip_serializing_if = "Option::is_none")]
+    pub fname_transfer: Option<FnameTransfer>,
+    #[serde(rename = "storagePrune", skip_serializing_if = "Option::is_none")]
+    pub storage_prune: Option<StoragePrune>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct Transaction {
+    pub fid: u64,
+    #[serde(rename = "userMessages")]
+    pub user_messages: Vec<Message>,
+    #[serde(rename = "systemMessages")]
+    pub system_messages: Vec<ValidatorMessage>,
+    #[serde(rename = "accountRoot", with = "serdehex")]
+    #[schemars(with = "String")]
+    pub account_root: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ShardHeader {
+    pub height: Option<Height>,
+    pub timestamp: u64,
+    #[serde(rename = "parentHash", with = "serdehex")]
+    #[schemars(with = "String")]
+    pub parent_hash: Vec<u8>,
+    #[serde(rename = "shardRoot", with = "serdehex")]
+    #[schemars(with = "String")]
+    pub shard_root: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ShardChunk {
+    pub header: Option<ShardHeader>,
+    #[serde(with = "serdehex")]
+    #[schemars(with = "String")]
+    pub hash: Vec<u8>,
+    pub transactions: Vec<Transaction>,
+    pub commits: Option<Commits>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ShardChunksResponse {
+    #[serde(rename = "shardChunks")]
+    pub shard_chunks: Vec<ShardChunk>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ValidationResult {
     pub valid: bool,
     pub message: Option<Message>,
 }
 
 // Common error response
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ErrorResponse {
     pub error: String,
     pub error_detail: Option<String>,
@@ -1220,6 +1806,22 @@ pub struct HubHttpServiceImpl {
     pub service: Arc<MyHubService>,
 }
 
+fn map_proto_rocksdb_properties_to_json_rocksdb_properties(
+    properties: &proto::RocksDbProperties,
+) -> RocksDbProperties {
+    RocksDbProperties {
+        block_cache_capacity: properties.block_cache_capacity,
+        block_cache_usage: properties.block_cache_usage,
+        block_cache_pinned_usage: properties.block_cache_pinned_usage,
+        num_running_compactions: properties.num_running_compactions,
+        num_running_flushes: properties.num_running_flushes,
+        num_snapshots: properties.num_snapshots,
+        background_errors: properties.background_errors,
+        actual_delayed_write_rate: properties.actual_delayed_write_rate,
+        is_write_stopped: properties.is_write_stopped,
+    }
+}
+
 fn map_get_info_response_to_json_info_response(
     info_response: proto::GetInfoResponse,
 ) -> Result<InfoResponse, ErrorResponse> {
@@ -1228,10 +1830,15 @@ fn map_get_info_response_to_json_info_response(
             num_messages: db_stats.num_messages,
             num_fid_registrations: db_stats.num_fid_registrations,
             approx_size: db_stats.approx_size,
+            rocksdb_properties: db_stats
+                .rocksdb_properties
+                .as_ref()
+                .map(map_proto_rocksdb_properties_to_json_rocksdb_properties),
         }),
         num_shards: info_response.num_shards,
         peer_id: info_response.peer_id,
         version: info_response.version,
+        archive_mode: info_response.archive_mode,
         shard_infos: info_response
             .shard_infos
             .iter()
@@ -1243,6 +1850,10 @@ fn map_get_info_response_to_json_info_response(
                 approx_size: shard_info.approx_size,
                 block_delay: shard_info.block_delay,
                 mempool_size: shard_info.mempool_size,
+                rocksdb_properties: shard_info
+                    .rocksdb_properties
+                    .as_ref()
+                    .map(map_proto_rocksdb_properties_to_json_rocksdb_properties),
             })
             .collect(),
     })
@@ -1816,6 +2427,94 @@ fn map_proto_message_to_json_message(message: proto::Message) -> Result<Message,
     }
 }
 
+fn map_proto_storage_limits_response_to_json_storage_limits_response(
+    limits: proto::StorageLimitsResponse,
+) -> StorageLimitsResponse {
+    StorageLimitsResponse {
+        limits: limits
+            .limits
+            .iter()
+            .map(|l: &proto::StorageLimit| StorageLimit {
+                store_type: match l.store_type {
+                    1 => StoreType::Casts,
+                    2 => StoreType::Links,
+                    3 => StoreType::Reactions,
+                    4 => StoreType::UserData,
+                    5 => StoreType::Verifications,
+                    6 => StoreType::UsernameProofs,
+                    _ => StoreType::None,
+                },
+                name: l.name.clone(),
+                limit: l.limit,
+                used: l.used,
+                earliest_timestamp: l.earliest_timestamp,
+                earliest_hash: l.earliest_hash.clone(),
+            })
+            .collect(),
+        units: limits.units,
+        unit_details: limits
+            .unit_details
+            .iter()
+            .map(|u: &proto::StorageUnitDetails| StorageUnitDetails {
+                unit_size: u.unit_size,
+                unit_type: match u.unit_type {
+                    1 => StorageUnitType::UnitType2024,
+                    _ => StorageUnitType::UnitTypeLegacy,
+                },
+            })
+            .collect(),
+        tier_subscriptions: limits
+            .tier_subscriptions
+            .iter()
+            .map(|d| TierDetails {
+                tier_type: match d.tier_type {
+                    1 => TierType::Pro,
+                    _ => TierType::None,
+                },
+                expires_at: d.expires_at,
+            })
+            .collect(),
+    }
+}
+
+fn map_proto_tier_type_to_json_tier_type(tier_type: i32) -> TierType {
+    match tier_type {
+        1 => TierType::Pro,
+        _ => TierType::None,
+    }
+}
+
+fn map_proto_tier_status_response_to_json_tier_status_response(
+    response: proto::TierStatusResponse,
+) -> Result<TierStatusResponse, ErrorResponse> {
+    Ok(TierStatusResponse {
+        tiers: response
+            .tiers
+            .into_iter()
+            .map(|status| {
+                Ok(TierStatus {
+                    tier_type: map_proto_tier_type_to_json_tier_type(status.tier_type),
+                    active: status.active,
+                    expires_at: status.expires_at,
+                    purchases: status
+                        .purchases
+                        .into_iter()
+                        .map(|period| {
+                            Ok(TierPurchasePeriod {
+                                purchase: map_proto_on_chain_event_to_json_on_chain_event(
+                                    period.purchase.unwrap_or_default(),
+                                )?,
+                                starts_at: period.starts_at,
+                                expires_at: period.expires_at,
+                            })
+                        })
+                        .collect::<Result<_, ErrorResponse>>()?,
+                })
+            })
+            .collect::<Result<_, ErrorResponse>>()?,
+    })
+}
+
 fn map_proto_messages_response_to_json_paged_response(
     messages_response: proto::MessagesResponse,
 ) -> Result<PagedResponse, ErrorResponse> {
@@ -1930,6 +2629,7 @@ fn map_proto_hub_event_to_json_hub_event(
     let mut merge_on_chain_event_body: Option<MergeOnChainEventBody> = None;
     let mut merge_failure_body: Option<MergeFailureBody> = None;
     let mut block_confirmed_body: Option<BlockConfirmedBody> = None;
+    let mut tier_expired_body: Option<TierExpiredBody> = None;
     match &hub_event.body {
         None => {}
         Some(hub_event::Body::MergeMessageBody(body)) => {
@@ -1999,6 +2699,13 @@ fn map_proto_hub_event_to_json_hub_event(
                 total_events: body.total_events,
             });
         }
+        Some(hub_event::Body::TierExpiredBody(body)) => {
+            tier_expired_body = Some(TierExpiredBody {
+                fid: body.fid,
+                tier_type: map_proto_tier_type_to_json_tier_type(body.tier_type),
+                expires_at: body.expires_at,
+            });
+        }
     }
 
     Ok(HubEvent {
@@ -2017,26 +2724,174 @@ fn map_proto_hub_event_to_json_hub_event(
         merge_on_chain_event_body,
         merge_failure_body,
         block_confirmed_body,
+        tier_expired_body,
         block_number: hub_event.block_number,
         shard_index: hub_event.shard_index,
     })
 }
 
-// Service trait for type-safe request handling
-#[async_trait]
-pub trait HubHttpService {
-    async fn get_info(&self, req: InfoRequest) -> Result<InfoResponse, ErrorResponse>;
-    async fn get_fids(&self, req: GetFidsRequest) -> Result<GetFidsResponse, ErrorResponse>;
-    async fn get_cast_by_id(&self, req: IdRequest) -> Result<Message, ErrorResponse>;
-    async fn get_casts_by_fid(
-        &self,
-        req: FidTimestampRequest,
-    ) -> Result<PagedResponse, ErrorResponse>;
-    async fn get_casts_by_mention(&self, req: FidRequest) -> Result<PagedResponse, ErrorResponse>;
-    async fn get_casts_by_parent(
-        &self,
-        req: CastsByParentRequest,
+fn map_proto_username_proof_to_json_username_proof(proof: &proto::UserNameProof) -> UserNameProof {
+    UserNameProof {
+        timestamp: proof.timestamp,
+        name: String::from_utf8_lossy(&proof.name).to_string(),
+        owner: format!("0x{}", hex::encode(&proof.owner)),
+        signature: proof.signature.clone(),
+        fid: proof.fid,
+        r#type: proof.r#type().as_str_name().to_owned(),
+    }
+}
+
+fn map_proto_height_to_json_height(height: Option<proto::Height>) -> Option<Height> {
+    height.map(|height| Height {
+        shard_index: height.shard_index,
+        block_number: height.block_number,
+    })
+}
+
+fn map_proto_commits_to_json_commits(commits: Option<proto::Commits>) -> Option<Commits> {
+    commits.map(|commits| {
+        let value = commits.value.unwrap_or_default();
+        Commits {
+            height: map_proto_height_to_json_height(commits.height),
+            round: commits.round,
+            shard_index: value.shard_index,
+            hash: value.hash,
+            signatures: commits
+                .signatures
+                .into_iter()
+                .map(|signature| CommitSignature {
+                    signer: signature.signer,
+                    signature: signature.signature,
+                })
+                .collect(),
+        }
+    })
+}
+
+fn map_proto_block_to_json_block(block: proto::Block) -> Block {
+    Block {
+        header: block.header.map(|header| BlockHeader {
+            height: map_proto_height_to_json_height(header.height),
+            timestamp: header.timestamp,
+            version: header.version,
+            chain_id: header.chain_id().as_str_name().to_owned(),
+            shard_witnesses_hash: header.shard_witnesses_hash,
+            parent_hash: header.parent_hash,
+        }),
+        hash: block.hash,
+        shard_chunk_witnesses: block
+            .shard_witness
+            .map(|witness| witness.shard_chunk_witnesses)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|witness| ShardChunkWitness {
+                height: map_proto_height_to_json_height(witness.height),
+                shard_root: witness.shard_root,
+                shard_hash: witness.shard_hash,
+            })
+            .collect(),
+        commits: map_proto_commits_to_json_commits(block.commits),
+    }
+}
+
+fn map_proto_validator_message_to_json_validator_message(
+    message: proto::ValidatorMessage,
+) -> Result<ValidatorMessage, ErrorResponse> {
+    Ok(ValidatorMessage {
+        on_chain_event: message
+            .on_chain_event
+            .map(map_proto_on_chain_event_to_json_on_chain_event)
+            .transpose()?,
+        fname_transfer: message.fname_transfer.map(|transfer| FnameTransfer {
+            id: transfer.id,
+            from_fid: transfer.from_fid,
+            proof: transfer
+                .proof
+                .as_ref()
+                .map(map_proto_username_proof_to_json_username_proof),
+        }),
+        storage_prune: message.storage_prune.map(|prune| StoragePrune {
+            fid: prune.fid,
+            timestamp: prune.timestamp,
+            block_number: prune.block_number,
+        }),
+    })
+}
+
+fn map_proto_shard_chunk_to_json_shard_chunk(
+    shard_chunk: proto::ShardChunk,
+) -> Result<ShardChunk, ErrorResponse> {
+    let transactions = shard_chunk
+        .transactions
+        .into_iter()
+        .map(|transaction| {
+            Ok(Transaction {
+                fid: transaction.fid,
+                user_messages: transaction
+                    .user_messages
+                    .into_iter()
+                    .map(map_proto_message_to_json_message)
+                    .collect::<Result<_, _>>()?,
+                system_messages: transaction
+                    .system_messages
+                    .into_iter()
+                    .map(map_proto_validator_message_to_json_validator_message)
+                    .collect::<Result<_, _>>()?,
+                account_root: transaction.account_root,
+            })
+        })
+        .collect::<Result<_, ErrorResponse>>()?;
+    Ok(ShardChunk {
+        header: shard_chunk.header.map(|header| ShardHeader {
+            height: map_proto_height_to_json_height(header.height),
+            timestamp: header.timestamp,
+            parent_hash: header.parent_hash,
+            shard_root: header.shard_root,
+        }),
+        hash: shard_chunk.hash,
+        transactions,
+        commits: map_proto_commits_to_json_commits(shard_chunk.commits),
+    })
+}
+
+fn map_proto_trie_node_metadata_to_json_trie_node_metadata(
+    metadata: proto::TrieNodeMetadataResponse,
+) -> TrieNodeMetadataResponse {
+    TrieNodeMetadataResponse {
+        prefix: metadata.prefix,
+        num_messages: metadata.num_messages,
+        hash: metadata.hash,
+        children: metadata
+            .children
+            .into_iter()
+            .map(map_proto_trie_node_metadata_to_json_trie_node_metadata)
+            .collect(),
+    }
+}
+
+// Service trait for type-safe request handling
+#[async_trait]
+pub trait HubHttpService {
+    async fn get_info(&self, req: InfoRequest) -> Result<InfoResponse, ErrorResponse>;
+    async fn get_fids(&self, req: GetFidsRequest) -> Result<GetFidsResponse, ErrorResponse>;
+    async fn get_cast_by_id(&self, req: IdRequest) -> Result<Message, ErrorResponse>;
+    async fn get_casts_by_fid(
+        &self,
+        req: FidTimestampRequest,
     ) -> Result<PagedResponse, ErrorResponse>;
+    async fn get_casts_by_mention(&self, req: FidRequest) -> Result<PagedResponse, ErrorResponse>;
+    async fn get_casts_by_parent(
+        &self,
+        req: CastsByParentRequest,
+    ) -> Result<PagedResponse, ErrorResponse>;
+    async fn get_casts_by_embed(
+        &self,
+        req: CastsByEmbedRequest,
+    ) -> Result<PagedResponse, ErrorResponse>;
+    async fn get_cast_stats(
+        &self,
+        req: CastStatsRequest,
+    ) -> Result<CastStatsResponse, ErrorResponse>;
     async fn get_reaction_by_id(&self, req: ReactionRequest) -> Result<Message, ErrorResponse>;
     async fn get_reactions_by_fid(
         &self,
@@ -2060,10 +2915,12 @@ pub trait HubHttpService {
         req: LinksByTargetRequest,
     ) -> Result<PagedResponse, ErrorResponse>;
     async fn get_user_data_by_fid(&self, req: FidRequest) -> Result<PagedResponse, ErrorResponse>;
+    async fn get_profile(&self, req: ProfileRequest) -> Result<ProfileResponse, ErrorResponse>;
     async fn get_storage_limits_by_fid(
         &self,
         req: FidRequest,
     ) -> Result<StorageLimitsResponse, ErrorResponse>;
+    async fn get_tier_status(&self, req: FidRequest) -> Result<TierStatusResponse, ErrorResponse>;
     async fn get_user_name_proof_by_name(
         &self,
         req: UsernameProofRequest,
@@ -2072,6 +2929,10 @@ pub trait HubHttpService {
         &self,
         req: FidRequest,
     ) -> Result<UsernameProofsResponse, ErrorResponse>;
+    async fn get_usernames_by_prefix(
+        &self,
+        req: UsernamesByPrefixRequest,
+    ) -> Result<UsernamesByPrefixResponse, ErrorResponse>;
     async fn validate_message(
         &self,
         req: proto::Message,
@@ -2097,12 +2958,61 @@ pub trait HubHttpService {
         &self,
         req: FidAddressTypeRequest,
     ) -> Result<FidAddressTypeResponse, ErrorResponse>;
+    async fn get_fids_by_verified_address(
+        &self,
+        req: FidsByVerifiedAddressRequest,
+    ) -> Result<GetFidsResponse, ErrorResponse>;
     async fn get_events(&self, req: EventsRequest) -> Result<EventsResponse, ErrorResponse>;
     async fn get_event_by_id(&self, req: EventRequest) -> Result<HubEvent, ErrorResponse>;
     async fn get_id_registry_on_chain_event_by_address(
         &self,
         req: IdRegistryEventByAddressRequest,
     ) -> Result<OnChainEvent, ErrorResponse>;
+    async fn request_on_chain_event_repair(
+        &self,
+        req: OnChainEventRepairRequest,
+        caller: CallerId,
+    ) -> Result<OnChainEventRepairResponse, ErrorResponse>;
+    async fn get_id_registry_on_chain_event(
+        &self,
+        req: FidRequest,
+    ) -> Result<OnChainEvent, ErrorResponse>;
+    async fn get_on_chain_signer(&self, req: SignerRequest) -> Result<OnChainEvent, ErrorResponse>;
+    async fn get_user_data(&self, req: UserDataRequest) -> Result<Message, ErrorResponse>;
+    async fn get_verification(&self, req: VerificationRequest) -> Result<Message, ErrorResponse>;
+    async fn get_link_compact_state_message_by_fid(
+        &self,
+        req: FidRequest,
+    ) -> Result<PagedResponse, ErrorResponse>;
+    async fn get_all_cast_messages_by_fid(
+        &self,
+        req: FidTimestampRequest,
+    ) -> Result<PagedResponse, ErrorResponse>;
+    async fn get_all_reaction_messages_by_fid(
+        &self,
+        req: FidTimestampRequest,
+    ) -> Result<PagedResponse, ErrorResponse>;
+    async fn get_all_verification_messages_by_fid(
+        &self,
+        req: FidTimestampRequest,
+    ) -> Result<PagedResponse, ErrorResponse>;
+    async fn get_all_user_data_messages_by_fid(
+        &self,
+        req: FidTimestampRequest,
+    ) -> Result<PagedResponse, ErrorResponse>;
+    async fn get_all_link_messages_by_fid(
+        &self,
+        req: FidTimestampRequest,
+    ) -> Result<PagedResponse, ErrorResponse>;
+    async fn get_blocks(&self, req: BlocksRequest) -> Result<BlocksResponse, ErrorResponse>;
+    async fn get_shard_chunks(
+        &self,
+        req: ShardChunksRequest,
+    ) -> Result<ShardChunksResponse, ErrorResponse>;
+    async fn get_trie_metadata_by_prefix(
+        &self,
+        req: TrieNodeMetadataRequest,
+    ) -> Result<TrieNodeMetadataResponse, ErrorResponse>;
 }
 
 #[async_trait]
@@ -2245,6 +3155,58 @@ impl HubHttpService for HubHttpServiceImpl {
         map_proto_messages_response_to_json_paged_response(proto_resp)
     }
 
+    async fn get_casts_by_embed(
+        &self,
+        req: CastsByEmbedRequest,
+    ) -> Result<PagedResponse, ErrorResponse> {
+        let service = &self.service;
+
+        let grpc_req = tonic::Request::new(req.to_proto());
+        let response = service
+            .get_casts_by_embed(grpc_req)
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get casts by embed".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        let proto_resp = response.into_inner();
+        map_proto_messages_response_to_json_paged_response(proto_resp)
+    }
+
+    /// GET /v1/castStats
+    async fn get_cast_stats(
+        &self,
+        req: CastStatsRequest,
+    ) -> Result<CastStatsResponse, ErrorResponse> {
+        let service = &self.service;
+        let grpc_req = tonic::Request::new(req.to_proto()?);
+        let response = service
+            .get_cast_stats(grpc_req)
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get cast stats".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        let stats = response
+            .into_inner()
+            .stats
+            .into_iter()
+            .map(|stats| {
+                let cast_id = stats.cast_id.unwrap_or_default();
+                CastStats {
+                    cast_id: CastId {
+                        fid: cast_id.fid,
+                        hash: format!("0x{}", hex::encode(cast_id.hash)),
+                    },
+                    likes: stats.likes,
+                    recasts: stats.recasts,
+                    replies: stats.replies,
+                }
+            })
+            .collect();
+        Ok(CastStatsResponse { stats })
+    }
+
     async fn get_reaction_by_id(&self, req: ReactionRequest) -> Result<Message, ErrorResponse> {
         let service = &self.service;
         let target = if req.target_fid.is_some() {
@@ -2463,6 +3425,40 @@ impl HubHttpService for HubHttpServiceImpl {
         map_proto_messages_response_to_json_paged_response(proto_resp)
     }
 
+    /// GET /v1/profile
+    async fn get_profile(&self, req: ProfileRequest) -> Result<ProfileResponse, ErrorResponse> {
+        let service = &self.service;
+        let grpc_req = tonic::Request::new(req.to_proto());
+        let response = service
+            .get_profile(grpc_req)
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get profile".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        let profile = response.into_inner();
+        Ok(ProfileResponse {
+            fid: profile.fid,
+            user_data: profile
+                .user_data
+                .into_iter()
+                .map(map_proto_message_to_json_message)
+                .collect::<Result<_, _>>()?,
+            primary_address_ethereum: profile.primary_address_ethereum,
+            primary_address_solana: profile.primary_address_solana,
+            verifications: profile
+                .verifications
+                .into_iter()
+                .map(map_proto_message_to_json_message)
+                .collect::<Result<_, _>>()?,
+            storage_limits: map_proto_storage_limits_response_to_json_storage_limits_response(
+                profile.storage_limits.unwrap_or_default(),
+            ),
+            follower_count: profile.follower_count,
+            following_count: profile.following_count,
+        })
+    }
+
     /// GET /v1/storageLimitsByFid
     async fn get_storage_limits_by_fid(
         &self,
@@ -2477,52 +3473,25 @@ impl HubHttpService for HubHttpServiceImpl {
                 error: "Failed to get storage limits".to_string(),
                 error_detail: Some(e.to_string()),
             })?;
-        let limits = response.into_inner();
-        Ok(StorageLimitsResponse {
-            limits: limits
-                .limits
-                .iter()
-                .map(|l: &proto::StorageLimit| StorageLimit {
-                    store_type: match l.store_type {
-                        1 => StoreType::Casts,
-                        2 => StoreType::Links,
-                        3 => StoreType::Reactions,
-                        4 => StoreType::UserData,
-                        5 => StoreType::Verifications,
-                        6 => StoreType::UsernameProofs,
-                        _ => StoreType::None,
-                    },
-                    name: l.name.clone(),
-                    limit: l.limit,
-                    used: l.used,
-                    earliest_timestamp: l.earliest_timestamp,
-                    earliest_hash: l.earliest_hash.clone(),
-                })
-                .collect(),
-            units: limits.units,
-            unit_details: limits
-                .unit_details
-                .iter()
-                .map(|u: &proto::StorageUnitDetails| StorageUnitDetails {
-                    unit_size: u.unit_size,
-                    unit_type: match u.unit_type {
-                        1 => StorageUnitType::UnitType2024,
-                        _ => StorageUnitType::UnitTypeLegacy,
-                    },
-                })
-                .collect(),
-            tier_subscriptions: limits
-                .tier_subscriptions
-                .iter()
-                .map(|d| TierDetails {
-                    tier_type: match d.tier_type {
-                        1 => TierType::Pro,
-                        _ => TierType::None,
-                    },
-                    expires_at: d.expires_at,
-                })
-                .collect(),
-        })
+        Ok(
+            map_proto_storage_limits_response_to_json_storage_limits_response(
+                response.into_inner(),
+            ),
+        )
+    }
+
+    /// GET /v1/tierStatus
+    async fn get_tier_status(&self, req: FidRequest) -> Result<TierStatusResponse, ErrorResponse> {
+        let service = &self.service;
+        let grpc_req = tonic::Request::new(req.to_proto());
+        let response = service
+            .get_tier_status(grpc_req)
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get tier status".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        map_proto_tier_status_response_to_json_tier_status_response(response.into_inner())
     }
 
     /// GET /v1/userNameProofByName
@@ -2542,17 +3511,7 @@ impl HubHttpService for HubHttpServiceImpl {
                 error_detail: Some(e.to_string()),
             })?;
         let proof = response.into_inner();
-        let proof_type = proof.r#type().as_str_name().to_owned();
-        Ok(UserNameProof {
-            timestamp: proof.timestamp,
-            name: std::str::from_utf8(&proof.name.as_slice())
-                .unwrap()
-                .to_string(),
-            owner: format!("0x{}", hex::encode(&proof.owner)),
-            signature: proof.signature,
-            fid: proof.fid,
-            r#type: proof_type,
-        })
+        Ok(map_proto_username_proof_to_json_username_proof(&proof))
     }
 
     /// GET /v1/userNameProofsByFid
@@ -2574,18 +3533,38 @@ impl HubHttpService for HubHttpServiceImpl {
             proofs: proof
                 .proofs
                 .iter()
-                .map(|p| UserNameProof {
-                    timestamp: p.timestamp,
-                    name: std::str::from_utf8(&p.name.as_slice()).unwrap().to_string(),
-                    owner: format!("0x{}", hex::encode(&p.owner)),
-                    signature: p.signature.clone(),
-                    fid: p.fid,
-                    r#type: p.r#type().as_str_name().to_owned(),
-                })
+                .map(map_proto_username_proof_to_json_username_proof)
                 .collect(),
         })
     }
 
+    /// GET /v1/usernamesByPrefix
+    async fn get_usernames_by_prefix(
+        &self,
+        req: UsernamesByPrefixRequest,
+    ) -> Result<UsernamesByPrefixResponse, ErrorResponse> {
+        let grpc_req = tonic::Request::new(req.to_proto());
+        let response = self
+            .service
+            .get_usernames_by_prefix(grpc_req)
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get usernames by prefix".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        let response = response.into_inner();
+        Ok(UsernamesByPrefixResponse {
+            proofs: response
+                .proofs
+                .iter()
+                .map(map_proto_username_proof_to_json_username_proof)
+                .collect(),
+            next_page_token: response
+                .next_page_token
+                .map(|token| BASE64_STANDARD.encode(token)),
+        })
+    }
+
     /// POST /v1/validateMessage
     async fn validate_message(
         &self,
@@ -2770,6 +3749,33 @@ impl HubHttpService for HubHttpServiceImpl {
         map_proto_on_chain_event_to_json_on_chain_event(onchain)
     }
 
+    /// POST /v1/onChainEventRepair
+    async fn request_on_chain_event_repair(
+        &self,
+        req: OnChainEventRepairRequest,
+        caller: CallerId,
+    ) -> Result<OnChainEventRepairResponse, ErrorResponse> {
+        let mut grpc_req = tonic::Request::new(req.to_proto());
+        grpc_req.extensions_mut().insert(caller);
+        let response = self
+            .service
+            .request_on_chain_event_repair(grpc_req)
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to repair on chain events".to_string(),
+                error_detail: Some(e.to_string()),
+            })?
+            .into_inner();
+        Ok(OnChainEventRepairResponse {
+            missing_events: response
+                .missing_events
+                .into_iter()
+                .map(map_proto_on_chain_event_to_json_on_chain_event)
+                .collect::<Result<_, _>>()?,
+            resubmitted: response.resubmitted,
+        })
+    }
+
     /// GET /v1/fidAddressType
     async fn get_fid_address_type(
         &self,
@@ -2791,393 +3797,631 @@ impl HubHttpService for HubHttpServiceImpl {
             is_verified: proto_resp.is_verified,
         })
     }
-}
+    async fn get_fids_by_verified_address(
+        &self,
+        req: FidsByVerifiedAddressRequest,
+    ) -> Result<GetFidsResponse, ErrorResponse> {
+        let grpc_req = tonic::Request::new(req.to_proto()?);
+        let response = self
+            .service
+            .get_fids_by_verified_address(grpc_req)
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get fids by verified address".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        Ok(GetFidsResponse {
+            fids: response.into_inner().fids,
+            next_page_token: None,
+        })
+    }
 
-// Router implementation
-pub struct Router {
-    service: Arc<HubHttpServiceImpl>,
-}
+    /// GET /v1/onChainIdRegistryEvent
+    async fn get_id_registry_on_chain_event(
+        &self,
+        req: FidRequest,
+    ) -> Result<OnChainEvent, ErrorResponse> {
+        let response = self
+            .service
+            .get_id_registry_on_chain_event(tonic::Request::new(req.to_proto()))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get id registry event".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        map_proto_on_chain_event_to_json_on_chain_event(response.into_inner())
+    }
 
-impl Router {
-    pub fn new(service: HubHttpServiceImpl) -> Self {
-        Self {
-            service: Arc::new(service),
-        }
+    /// GET /v1/onChainSigner
+    async fn get_on_chain_signer(&self, req: SignerRequest) -> Result<OnChainEvent, ErrorResponse> {
+        let response = self
+            .service
+            .get_on_chain_signer(tonic::Request::new(req.to_proto()))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get on chain signer".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        map_proto_on_chain_event_to_json_on_chain_event(response.into_inner())
+    }
+
+    /// GET /v1/userData
+    async fn get_user_data(&self, req: UserDataRequest) -> Result<Message, ErrorResponse> {
+        let response = self
+            .service
+            .get_user_data(tonic::Request::new(req.to_proto()))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get user data".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        map_proto_message_to_json_message(response.into_inner())
     }
 
-    pub async fn handle(
+    /// GET /v1/verification
+    async fn get_verification(&self, req: VerificationRequest) -> Result<Message, ErrorResponse> {
+        let response = self
+            .service
+            .get_verification(tonic::Request::new(req.to_proto()))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get verification".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        map_proto_message_to_json_message(response.into_inner())
+    }
+
+    /// GET /v1/linkCompactStateMessageByFid
+    async fn get_link_compact_state_message_by_fid(
         &self,
-        req: Request<hyper::body::Incoming>,
-        config: &Config,
-    ) -> Result<Response<BoxBody<Bytes, Infallible>>, Infallible> {
-        let mut response = match (req.method(), req.uri().path()) {
-            (&Method::GET, "/v1/info") => {
-                self.handle_request::<InfoRequest, InfoResponse, _>(req, |service, req| {
-                    Box::pin(async move { service.get_info(req).await })
-                })
-                .await
-            }
-            (&Method::GET, "/v1/fids") => {
-                self.handle_request::<GetFidsRequest, GetFidsResponse, _>(req, |service, req| {
-                    Box::pin(async move { service.get_fids(req).await })
-                })
-                .await
-            }
-            (&Method::GET, "/v1/castById") => {
-                self.handle_request::<IdRequest, Message, _>(req, |service, req| {
-                    Box::pin(async move { service.get_cast_by_id(req).await })
-                })
-                .await
-            }
-            (&Method::GET, "/v1/castsByFid") => {
-                self.handle_request::<FidTimestampRequest, PagedResponse, _>(
-                    req,
-                    move |service, req| {
-                        Box::pin(async move { service.get_casts_by_fid(req).await })
-                    },
-                )
-                .await
-            }
-            (&Method::GET, "/v1/castsByMention") => {
-                self.handle_request::<FidRequest, PagedResponse, _>(req, |service, req| {
-                    Box::pin(async move { service.get_casts_by_mention(req).await })
-                })
-                .await
-            }
-            (&Method::GET, "/v1/castsByParent") => {
-                self.handle_request::<CastsByParentRequest, PagedResponse, _>(
-                    req,
-                    |service, req| Box::pin(async move { service.get_casts_by_parent(req).await }),
-                )
-                .await
-            }
-            (&Method::GET, "/v1/reactionById") => {
-                self.handle_request::<ReactionRequest, Message, _>(req, |service, req| {
-                    Box::pin(async move { service.get_reaction_by_id(req).await })
-                })
-                .await
-            }
-            (&Method::GET, "/v1/reactionsByFid") => {
-                self.handle_request::<ReactionsByFidRequest, PagedResponse, _>(
-                    req,
-                    |service, req| Box::pin(async move { service.get_reactions_by_fid(req).await }),
-                )
-                .await
-            }
-            (&Method::GET, "/v1/reactionsByCast") => {
-                self.handle_request::<ReactionsByCastRequest, PagedResponse, _>(
-                    req,
-                    |service, req| {
-                        Box::pin(async move { service.get_reactions_by_cast(req).await })
-                    },
-                )
-                .await
-            }
-            (&Method::GET, "/v1/reactionsByTarget") => {
-                self.handle_request::<ReactionsByTargetRequest, PagedResponse, _>(
-                    req,
-                    |service, req| {
-                        Box::pin(async move { service.get_reactions_by_target(req).await })
-                    },
-                )
-                .await
-            }
-            (&Method::GET, "/v1/linkById") => {
-                self.handle_request::<LinkRequest, Message, _>(req, |service, req| {
-                    Box::pin(async move { service.get_link_by_id(req).await })
-                })
-                .await
-            }
-            (&Method::GET, "/v1/linksByFid") => {
-                self.handle_request::<LinksByFidRequest, PagedResponse, _>(req, |service, req| {
-                    Box::pin(async move { service.get_links_by_fid(req).await })
-                })
-                .await
-            }
-            (&Method::GET, "/v1/linksByTargetFid") => {
-                // For linksByTargetFid we assume that the service uses a FidTimestampRequest
-                // (similar to castsByFid) to return all link messages for a target fid.
-                self.handle_request::<LinksByTargetRequest, PagedResponse, _>(
-                    req,
-                    |service, req| {
-                        Box::pin(async move { service.get_links_by_target_fid(req).await })
-                    },
-                )
-                .await
-            }
-            // missing user_data_type
-            (&Method::GET, "/v1/userDataByFid") => {
-                self.handle_request::<FidRequest, PagedResponse, _>(req, |service, req| {
-                    Box::pin(async move { service.get_user_data_by_fid(req).await })
-                })
-                .await
-            }
-            (&Method::GET, "/v1/storageLimitsByFid") => {
-                self.handle_request::<FidRequest, StorageLimitsResponse, _>(req, |service, req| {
-                    Box::pin(async move { service.get_storage_limits_by_fid(req).await })
-                })
-                .await
-            }
-            (&Method::GET, "/v1/userNameProofByName") => {
-                self.handle_request::<UsernameProofRequest, UserNameProof, _>(
-                    req,
-                    |service, req| {
-                        Box::pin(async move { service.get_user_name_proof_by_name(req).await })
-                    },
-                )
-                .await
-            }
-            (&Method::GET, "/v1/userNameProofsByFid") => {
-                self.handle_request::<FidRequest, UsernameProofsResponse, _>(req, |service, req| {
-                    Box::pin(async move { service.get_user_name_proofs_by_fid(req).await })
-                })
-                .await
-            }
-            (&Method::POST, "/v1/validateMessage") => {
-                self.handle_protobuf_request::<ValidationResult, _>(
-                    req,
-                    |service, _headers, req| {
-                        Box::pin(async move { service.validate_message(req).await })
-                    },
-                )
-                .await
-            }
-            (&Method::POST, "/v1/submitMessage") => {
-                self.handle_protobuf_request::<Message, _>(req, |service, headers, req| {
-                    Box::pin(async move { service.submit_message(req, headers).await })
-                })
-                .await
-            }
-            // Missing address
-            (&Method::GET, "/v1/verificationsByFid") => {
-                self.handle_request::<FidRequest, PagedResponse, _>(req, |service, req| {
-                    Box::pin(async move { service.get_verifications_by_fid(req).await })
-                })
-                .await
-            }
-            // Missing signer
-            (&Method::GET, "/v1/onChainSignersByFid") => {
-                self.handle_request::<FidRequest, OnChainEventResponse, _>(req, |service, req| {
-                    Box::pin(async move { service.get_on_chain_signers_by_fid(req).await })
-                })
-                .await
-            }
-            (&Method::GET, "/v1/onChainEventsByFid") => {
-                self.handle_request::<OnChainEventRequest, OnChainEventResponse, _>(
-                    req,
-                    |service, req| {
-                        Box::pin(async move { service.get_on_chain_events_by_fid(req).await })
-                    },
-                )
-                .await
-            }
-            (&Method::GET, "/v1/onChainIdRegistryEventByAddress") => {
-                self.handle_request::<IdRegistryEventByAddressRequest, OnChainEvent, _>(
-                    req,
-                    |service, req| {
-                        Box::pin(async move {
-                            service.get_id_registry_on_chain_event_by_address(req).await
-                        })
-                    },
-                )
-                .await
-            }
-            (&Method::GET, "/v1/fidAddressType") => {
-                self.handle_request::<FidAddressTypeRequest, FidAddressTypeResponse, _>(
-                    req,
-                    |service, req| Box::pin(async move { service.get_fid_address_type(req).await }),
-                )
-                .await
-            }
-            (&Method::GET, "/v1/events") => {
-                self.handle_request::<EventsRequest, EventsResponse, _>(req, |service, req| {
-                    Box::pin(async move { service.get_events(req).await })
-                })
-                .await
-            }
-            (&Method::GET, "/v1/eventById") => {
-                self.handle_request::<EventRequest, HubEvent, _>(req, |service, req| {
-                    Box::pin(async move { service.get_event_by_id(req).await })
-                })
-                .await
-            }
-            _ => Ok(Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(Full::new(Bytes::from("Not Found")).boxed())
-                .unwrap()),
-        };
+        req: FidRequest,
+    ) -> Result<PagedResponse, ErrorResponse> {
+        let response = self
+            .service
+            .get_link_compact_state_message_by_fid(tonic::Request::new(req.to_proto()))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get link compact state".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        map_proto_messages_response_to_json_paged_response(response.into_inner())
+    }
 
-        if let Ok(res) = &mut response {
-            res.headers_mut().append(
-                "Access-Control-Allow-Origin",
-                HeaderValue::from_str(&config.cors_origin).unwrap(),
-            );
-        }
+    /// GET /v1/allCastMessagesByFid
+    async fn get_all_cast_messages_by_fid(
+        &self,
+        req: FidTimestampRequest,
+    ) -> Result<PagedResponse, ErrorResponse> {
+        let response = self
+            .service
+            .get_all_cast_messages_by_fid(tonic::Request::new(req.to_proto()))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get cast messages".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        map_proto_messages_response_to_json_paged_response(response.into_inner())
+    }
 
-        response
+    /// GET /v1/allReactionMessagesByFid
+    async fn get_all_reaction_messages_by_fid(
+        &self,
+        req: FidTimestampRequest,
+    ) -> Result<PagedResponse, ErrorResponse> {
+        let response = self
+            .service
+            .get_all_reaction_messages_by_fid(tonic::Request::new(req.to_proto()))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get reaction messages".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        map_proto_messages_response_to_json_paged_response(response.into_inner())
     }
 
-    async fn handle_protobuf_request<Resp, F>(
+    /// GET /v1/allVerificationMessagesByFid
+    async fn get_all_verification_messages_by_fid(
         &self,
-        req: Request<hyper::body::Incoming>,
-        handler: impl FnOnce(Arc<HubHttpServiceImpl>, HeaderMap<HeaderValue>, proto::Message) -> F,
-    ) -> Result<Response<BoxBody<Bytes, Infallible>>, Infallible>
-    where
-        Resp: Serialize,
-        F: Future<Output = Result<Resp, ErrorResponse>>,
-    {
-        let content_type = req.headers().get("content-type");
-        if content_type.is_none() {
-            return Ok(Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .body(Full::new(Bytes::from("Missing content type")).boxed())
-                .unwrap());
-        } else {
-            let content_type_str = content_type.unwrap().to_str();
-            if content_type_str.is_err() {
-                return Ok(Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(Full::new(Bytes::from("Missing content type")).boxed())
-                    .unwrap());
-            }
+        req: FidTimestampRequest,
+    ) -> Result<PagedResponse, ErrorResponse> {
+        let response = self
+            .service
+            .get_all_verification_messages_by_fid(tonic::Request::new(req.to_proto()))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get verification messages".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        map_proto_messages_response_to_json_paged_response(response.into_inner())
+    }
 
-            let content_type_str = content_type_str.unwrap();
-            if content_type_str != "application/octet-stream" {
-                return Ok(Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(
-                        Full::new(Bytes::from(format!(
-                            "Invalid content type: {}",
-                            content_type_str
-                        )))
-                        .boxed(),
-                    )
-                    .unwrap());
-            }
-        }
+    /// GET /v1/allUserDataMessagesByFid
+    async fn get_all_user_data_messages_by_fid(
+        &self,
+        req: FidTimestampRequest,
+    ) -> Result<PagedResponse, ErrorResponse> {
+        let response = self
+            .service
+            .get_all_user_data_messages_by_fid(tonic::Request::new(req.to_proto()))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get user data messages".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        map_proto_messages_response_to_json_paged_response(response.into_inner())
+    }
 
-        let headers = req.headers().clone();
+    /// GET /v1/allLinkMessagesByFid
+    async fn get_all_link_messages_by_fid(
+        &self,
+        req: FidTimestampRequest,
+    ) -> Result<PagedResponse, ErrorResponse> {
+        let response = self
+            .service
+            .get_all_link_messages_by_fid(tonic::Request::new(req.to_proto()))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get link messages".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        map_proto_messages_response_to_json_paged_response(response.into_inner())
+    }
 
-        // Parse request
-        let req_obj = match self.parse_protobuf_request(req).await {
-            Ok(req) => req,
-            Err(resp) => {
-                return Ok(Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(Full::new(resp.into_body()).boxed())
-                    .unwrap())
-            }
-        };
+    /// GET /v1/blocks
+    ///
+    /// The gRPC method streams blocks, so the HTTP route returns at most [MAX_BLOCKS_PER_REQUEST]
+    /// blocks per call. Clients page by advancing start_block_number.
+    async fn get_blocks(&self, req: BlocksRequest) -> Result<BlocksResponse, ErrorResponse> {
+        let start_block_number = req.start_block_number;
+        let last_block_number =
+            start_block_number.saturating_add(MAX_BLOCKS_PER_REQUEST as u64 - 1);
+        let stop_block_number = req
+            .stop_block_number
+            .map_or(last_block_number, |stop| stop.min(last_block_number));
+        let response = self
+            .service
+            .get_blocks(tonic::Request::new(proto::BlocksRequest {
+                stop_block_number: Some(stop_block_number),
+                ..req.to_proto()
+            }))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get blocks".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
 
-        // Handle request
-        match handler(self.service.clone(), headers, req_obj).await {
-            Ok(resp) => Ok(Response::builder()
-                .status(StatusCode::OK)
-                .header("content-type", "application/json")
-                .body(Full::new(Bytes::from(serde_json::to_vec(&resp).unwrap())).boxed())
-                .unwrap()),
-            Err(err) => Ok(Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .header("content-type", "application/json")
-                .body(Full::new(Bytes::from(serde_json::to_vec(&err).unwrap())).boxed())
-                .unwrap()),
+        let mut stream = response.into_inner();
+        let mut blocks = vec![];
+        while let Some(block) = stream.next().await {
+            let block = block.map_err(|e| ErrorResponse {
+                error: "Failed to get blocks".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+            blocks.push(map_proto_block_to_json_block(block));
+            if blocks.len() >= MAX_BLOCKS_PER_REQUEST {
+                break;
+            }
         }
+        Ok(BlocksResponse { blocks })
     }
 
-    async fn handle_request<Req, Resp, F>(
+    /// GET /v1/shardChunks
+    ///
+    /// Like /v1/blocks, returns at most [MAX_BLOCKS_PER_REQUEST] shard chunks per call. Clients
+    /// page by advancing start_block_number.
+    async fn get_shard_chunks(
         &self,
-        req: Request<hyper::body::Incoming>,
-        handler: impl FnOnce(Arc<HubHttpServiceImpl>, Req) -> F,
-    ) -> Result<Response<BoxBody<Bytes, Infallible>>, Infallible>
-    where
-        Req: DeserializeOwned,
-        Resp: Serialize,
-        F: Future<Output = Result<Resp, ErrorResponse>>,
-    {
-        // Parse request
-        let req_obj = match self.parse_request::<Req>(req).await {
-            Ok(req) => req,
-            Err(resp) => {
-                return Ok(Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(Full::new(resp.into_body()).boxed())
-                    .unwrap())
-            }
-        };
-
-        // Handle request
-        match handler(self.service.clone(), req_obj).await {
-            Ok(resp) => Ok(Response::builder()
-                .status(StatusCode::OK)
-                .header("content-type", "application/json")
-                .body(Full::new(Bytes::from(serde_json::to_vec(&resp).unwrap())).boxed())
-                .unwrap()),
-            Err(err) => Ok(Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .header("content-type", "application/json")
-                .body(Full::new(Bytes::from(serde_json::to_vec(&err).unwrap())).boxed())
-                .unwrap()),
-        }
+        req: ShardChunksRequest,
+    ) -> Result<ShardChunksResponse, ErrorResponse> {
+        // The stop block is exclusive for shard chunks
+        let last_block_number = req
+            .start_block_number
+            .saturating_add(MAX_BLOCKS_PER_REQUEST as u64);
+        let stop_block_number = req
+            .stop_block_number
+            .map_or(last_block_number, |stop| stop.min(last_block_number));
+        let response = self
+            .service
+            .get_shard_chunks(tonic::Request::new(proto::ShardChunksRequest {
+                stop_block_number: Some(stop_block_number),
+                ..req.to_proto()
+            }))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get shard chunks".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        Ok(ShardChunksResponse {
+            shard_chunks: response
+                .into_inner()
+                .shard_chunks
+                .into_iter()
+                .take(MAX_BLOCKS_PER_REQUEST)
+                .map(map_proto_shard_chunk_to_json_shard_chunk)
+                .collect::<Result<_, _>>()?,
+        })
     }
 
-    async fn parse_protobuf_request(
+    /// GET /v1/trieMetadataByPrefix
+    async fn get_trie_metadata_by_prefix(
         &self,
-        req: Request<hyper::body::Incoming>,
-    ) -> Result<proto::Message, Response<Bytes>> {
-        // For POST/PUT requests, parse body
-        let body_bytes = req.collect().await;
-        if body_bytes.is_err() {
-            return Err(Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(Bytes::from(format!(
-                    "Internal server error: {}",
-                    body_bytes.unwrap_err().to_string()
-                )))
-                .unwrap());
-        }
+        req: TrieNodeMetadataRequest,
+    ) -> Result<TrieNodeMetadataResponse, ErrorResponse> {
+        let response = self
+            .service
+            .get_trie_metadata_by_prefix(tonic::Request::new(req.to_proto()))
+            .await
+            .map_err(|e| ErrorResponse {
+                error: "Failed to get trie metadata".to_string(),
+                error_detail: Some(e.to_string()),
+            })?;
+        Ok(map_proto_trie_node_metadata_to_json_trie_node_metadata(
+            response.into_inner(),
+        ))
+    }
+}
+
+pub struct HttpRoute {
+    pub method: Method,
+    pub path: &'static str,
+    /// The HubService gRPC method the route exposes
+    pub grpc_method: &'static str,
+    /// Query parameters, or None for routes that take a protobuf encoded message body
+    pub request: Option<SchemaFn>,
+    pub response: SchemaFn,
+    handler: fn() -> MethodRouter<HttpState>,
+}
 
-        match message_decode(&body_bytes.unwrap().to_bytes().slice(..)) {
-            Ok(parsed) => Ok(parsed),
-            Err(e) => Err(Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(Bytes::from(format!("Invalid protobuf data: {}", e)))
-                .unwrap()),
+pub type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+pub type HttpState = Arc<HubHttpServiceImpl>;
+
+fn schema<T: JsonSchema>(generator: &mut SchemaGenerator) -> Schema {
+    generator.subschema_for::<T>()
+}
+
+/** A GET route that reads its request from the query string and responds with JSON. */
+macro_rules! query_route {
+    ($path:literal, $grpc_method:literal, $handler:ident, $req:ty => $resp:ty) => {
+        HttpRoute {
+            method: Method::GET,
+            path: $path,
+            grpc_method: $grpc_method,
+            request: Some(schema::<$req>),
+            response: schema::<$resp>,
+            handler: || {
+                get(
+                    |State(service): State<HttpState>, QueryString(req): QueryString<$req>| async move {
+                        service.$handler(req).await.map(Json::<$resp>)
+                    },
+                )
+            },
         }
+    };
+}
+
+/**
+ * Every route served by [router] and the gRPC method behind it. The request and response types
+ * of each route also drive the OpenAPI document; http_server_tests checks that every HubService
+ * method has a route.
+ */
+pub const HTTP_ROUTES: &[HttpRoute] = &[
+    HttpRoute {
+        method: Method::POST,
+        path: "/v1/submitMessage",
+        grpc_method: "SubmitMessage",
+        request: None,
+        response: schema::<Message>,
+        handler: || {
+            post(
+                |State(service): State<HttpState>,
+                 headers: HeaderMap,
+                 ProtobufMessage(message): ProtobufMessage| async move {
+                    service
+                        .submit_message(message, headers)
+                        .await
+                        .map(Json::<Message>)
+                },
+            )
+        },
+    },
+    HttpRoute {
+        method: Method::POST,
+        path: "/v1/validateMessage",
+        grpc_method: "ValidateMessage",
+        request: None,
+        response: schema::<ValidationResult>,
+        handler: || {
+            post(
+                |State(service): State<HttpState>, ProtobufMessage(message): ProtobufMessage| async move {
+                    service
+                        .validate_message(message)
+                        .await
+                        .map(Json::<ValidationResult>)
+                },
+            )
+        },
+    },
+    query_route!("/v1/blocks", "GetBlocks", get_blocks, BlocksRequest => BlocksResponse),
+    query_route!("/v1/shardChunks", "GetShardChunks", get_shard_chunks, ShardChunksRequest => ShardChunksResponse),
+    query_route!("/v1/info", "GetInfo", get_info, InfoRequest => InfoResponse),
+    query_route!("/v1/fids", "GetFids", get_fids, GetFidsRequest => GetFidsResponse),
+    query_route!("/v1/eventById", "GetEvent", get_event_by_id, EventRequest => HubEvent),
+    query_route!("/v1/events", "GetEvents", get_events, EventsRequest => EventsResponse),
+    query_route!("/v1/castById", "GetCast", get_cast_by_id, IdRequest => Message),
+    query_route!("/v1/castsByParent", "GetCastsByParent", get_casts_by_parent, CastsByParentRequest => PagedResponse),
+    query_route!("/v1/castsByMention", "GetCastsByMention", get_casts_by_mention, FidRequest => PagedResponse),
+    query_route!("/v1/castsByEmbed", "GetCastsByEmbed", get_casts_by_embed, CastsByEmbedRequest => PagedResponse),
+    query_route!("/v1/castStats", "GetCastStats", get_cast_stats, CastStatsRequest => CastStatsResponse),
+    query_route!("/v1/reactionById", "GetReaction", get_reaction_by_id, ReactionRequest => Message),
+    query_route!("/v1/reactionsByFid", "GetReactionsByFid", get_reactions_by_fid, ReactionsByFidRequest => PagedResponse),
+    query_route!("/v1/reactionsByCast", "GetReactionsByCast", get_reactions_by_cast, ReactionsByCastRequest => PagedResponse),
+    query_route!("/v1/reactionsByTarget", "GetReactionsByTarget", get_reactions_by_target, ReactionsByTargetRequest => PagedResponse),
+    query_route!("/v1/userData", "GetUserData", get_user_data, UserDataRequest => Message),
+    query_route!("/v1/userDataByFid", "GetUserDataByFid", get_user_data_by_fid, FidRequest => PagedResponse),
+    query_route!("/v1/profile", "GetProfile", get_profile, ProfileRequest => ProfileResponse),
+    query_route!("/v1/userNameProofByName", "GetUsernameProof", get_user_name_proof_by_name, UsernameProofRequest => UserNameProof),
+    query_route!("/v1/userNameProofsByFid", "GetUserNameProofsByFid", get_user_name_proofs_by_fid, FidRequest => UsernameProofsResponse),
+    query_route!("/v1/usernamesByPrefix", "GetUsernamesByPrefix", get_usernames_by_prefix, UsernamesByPrefixRequest => UsernamesByPrefixResponse),
+    query_route!("/v1/verification", "GetVerification", get_verification, VerificationRequest => Message),
+    query_route!("/v1/verificationsByFid", "GetVerificationsByFid", get_verifications_by_fid, FidRequest => PagedResponse),
+    query_route!("/v1/onChainSigner", "GetOnChainSigner", get_on_chain_signer, SignerRequest => OnChainEvent),
+    query_route!("/v1/onChainSignersByFid", "GetOnChainSignersByFid", get_on_chain_signers_by_fid, FidRequest => OnChainEventResponse),
+    query_route!("/v1/onChainEventsByFid", "GetOnChainEvents", get_on_chain_events_by_fid, OnChainEventRequest => OnChainEventResponse),
+    query_route!("/v1/onChainIdRegistryEvent", "GetIdRegistryOnChainEvent", get_id_registry_on_chain_event, FidRequest => OnChainEvent),
+    query_route!("/v1/onChainIdRegistryEventByAddress", "GetIdRegistryOnChainEventByAddress", get_id_registry_on_chain_event_by_address, IdRegistryEventByAddressRequest => OnChainEvent),
+    HttpRoute {
+        method: Method::POST,
+        path: "/v1/onChainEventRepair",
+        grpc_method: "RequestOnChainEventRepair",
+        request: Some(schema::<OnChainEventRepairRequest>),
+        response: schema::<OnChainEventRepairResponse>,
+        handler: || {
+            post(
+                |State(service): State<HttpState>,
+                 client: Option<Extension<ApiClient>>,
+                 connect_info: Option<ConnectInfo<SocketAddr>>,
+                 QueryString(req): QueryString<OnChainEventRepairRequest>| async move {
+                    let caller = CallerId::new(
+                        client.as_ref().map(|Extension(client)| client),
+                        connect_info.map(|ConnectInfo(addr)| addr.ip()),
+                    );
+                    service
+                        .request_on_chain_event_repair(req, caller)
+                        .await
+                        .map(Json::<OnChainEventRepairResponse>)
+                },
+            )
+        },
+    },
+    query_route!("/v1/storageLimitsByFid", "GetCurrentStorageLimitsByFid", get_storage_limits_by_fid, FidRequest => StorageLimitsResponse),
+    query_route!("/v1/tierStatus", "GetTierStatus", get_tier_status, FidRequest => TierStatusResponse),
+    query_route!("/v1/fidAddressType", "GetFidAddressType", get_fid_address_type, FidAddressTypeRequest => FidAddressTypeResponse),
+    query_route!("/v1/fidsByVerifiedAddress", "GetFidsByVerifiedAddress", get_fids_by_verified_address, FidsByVerifiedAddressRequest => GetFidsResponse),
+    query_route!("/v1/linkById", "GetLink", get_link_by_id, LinkRequest => Message),
+    query_route!("/v1/linksByFid", "GetLinksByFid", get_links_by_fid, LinksByFidRequest => PagedResponse),
+    query_route!("/v1/linksByTargetFid", "GetLinksByTarget", get_links_by_target_fid, LinksByTargetRequest => PagedResponse),
+    query_route!("/v1/linkCompactStateMessageByFid", "GetLinkCompactStateMessageByFid", get_link_compact_state_message_by_fid, FidRequest => PagedResponse),
+    query_route!("/v1/allCastMessagesByFid", "GetAllCastMessagesByFid", get_all_cast_messages_by_fid, FidTimestampRequest => PagedResponse),
+    query_route!("/v1/allReactionMessagesByFid", "GetAllReactionMessagesByFid", get_all_reaction_messages_by_fid, FidTimestampRequest => PagedResponse),
+    query_route!("/v1/allVerificationMessagesByFid", "GetAllVerificationMessagesByFid", get_all_verification_messages_by_fid, FidTimestampRequest => PagedResponse),
+    query_route!("/v1/allUserDataMessagesByFid", "GetAllUserDataMessagesByFid", get_all_user_data_messages_by_fid, FidTimestampRequest => PagedResponse),
+    query_route!("/v1/allLinkMessagesByFid", "GetAllLinkMessagesByFid", get_all_link_messages_by_fid, FidTimestampRequest => PagedResponse),
+    query_route!("/v1/trieMetadataByPrefix", "GetTrieMetadataByPrefix", get_trie_metadata_by_prefix, TrieNodeMetadataRequest => TrieNodeMetadataResponse),
+    // Reads through GetAllCastMessagesByFid so it can also take a timestamp range, but accepts
+    // every GetCastsByFid parameter
+    query_route!("/v1/castsByFid", "GetCastsByFid", get_casts_by_fid, FidTimestampRequest => PagedResponse),
+];
+
+impl IntoResponse for ErrorResponse {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, Json(self)).into_response()
     }
+}
 
-    async fn parse_request<T: DeserializeOwned>(
-        &self,
-        req: Request<hyper::body::Incoming>,
-    ) -> Result<T, Response<Bytes>> {
-        // For GET requests, parse from query string
-        if req.method() == Method::GET {
-            let query = req.uri().query().unwrap_or("");
-            return serde_qs::from_str(query).map_err(|e| {
-                Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(Bytes::from(format!("Invalid query parameters: {}", e)))
-                    .unwrap()
-            });
-        }
+/** Deserializes a request from the query string with serde_qs, so nested fields are supported. */
+pub struct QueryString<T>(pub T);
 
-        // For POST/PUT requests, parse body
-        let body_bytes = req.collect().await;
-        if body_bytes.is_err() {
-            return Err(Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(Bytes::from(format!(
-                    "Internal server error: {}",
-                    body_bytes.unwrap_err().to_string()
-                )))
-                .unwrap());
+#[async_trait]
+impl<T, S> FromRequestParts<S> for QueryString<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or("");
+        serde_qs::from_str(query).map(QueryString).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid query parameters: {}", e),
+            )
+                .into_response()
+        })
+    }
+}
+
+/** A protobuf encoded message body, sent with the application/octet-stream content type. */
+pub struct ProtobufMessage(pub proto::Message);
+
+#[async_trait]
+impl<S> FromRequest<S> for ProtobufMessage
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: axum::extract::Request, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok());
+        match content_type {
+            None => return Err((StatusCode::BAD_REQUEST, "Missing content type").into_response()),
+            Some("application/octet-stream") => {}
+            Some(content_type) => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid content type: {}", content_type),
+                )
+                    .into_response())
+            }
         }
 
-        match serde_json::from_slice(&body_bytes.unwrap().to_bytes().slice(..)) {
-            Ok(parsed) => Ok(parsed),
-            Err(e) => Err(Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .body(Bytes::from(format!("Invalid request format: {}", e)))
-                .unwrap()),
+        // Respects the DefaultBodyLimit set on the router
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        message_decode(&body).map(ProtobufMessage).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid protobuf data: {}", e),
+            )
+                .into_response()
+        })
+    }
+}
+
+/** Records request counts, errors and latency per route, keyed by the route path. */
+async fn record_metrics(
+    State(statsd_client): State<StatsdClientWrapper>,
+    path: MatchedPath,
+    request: axum::extract::Request,
+    next: Next,
+) -> Response {
+    let start = Instant::now();
+    let response = next.run(request).await;
+
+    let name = path.as_str().trim_start_matches("/v1/");
+    statsd_client.count(&format!("http.{}.requests", name), 1);
+    if !response.status().is_success() {
+        statsd_client.count(&format!("http.{}.errors", name), 1);
+    }
+    statsd_client.time(
+        &format!("http.{}.duration", name),
+        start.elapsed().as_millis() as u64,
+    );
+    response
+}
+
+fn cors_layer(cors_origin: &str) -> CorsLayer {
+    let allow_origin = if cors_origin == "*" {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::exact(HeaderValue::from_str(cors_origin).unwrap())
+    };
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([CONTENT_TYPE])
+}
+
+/** The routes in [HTTP_ROUTES] and the OpenAPI document, without middleware or state. */
+pub fn routes() -> axum::Router<HttpState> {
+    HTTP_ROUTES
+        .iter()
+        .fold(axum::Router::new(), |router, route| {
+            router.route(route.path, (route.handler)())
+        })
+        .route(OPENAPI_PATH, get(|| async { Json(&*OPENAPI_DOCUMENT) }))
+}
+
+/** Lowers page sizes in a query string to the client's quota, adding one if it's missing. */
+pub fn limit_query_page_size(query: &str, client: &ApiClient) -> String {
+    let mut has_page_size = false;
+    let mut params: Vec<String> = query
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            // Keys are matched after decoding, the same way the request is deserialized
+            let (key, value) = url::form_urlencoded::parse(param.as_bytes())
+                .next()
+                .unwrap_or_default();
+            if key == "page_size" || key == "pageSize" {
+                has_page_size = true;
+                // Sizes that don't parse are replaced too, so they can't get past the limit
+                let page_size = client.limit_page_size(value.parse::<u32>().ok()).unwrap();
+                format!("{}={}", key, page_size)
+            } else {
+                param.to_string()
+            }
+        })
+        .collect();
+    if !has_page_size {
+        params.push(format!("page_size={}", client.quota.max_page_size));
+    }
+    params.join("&")
+}
+
+/** Applies [ApiLimits] to every request, before it's routed. */
+async fn enforce_api_limits(
+    State(api_limits): State<Arc<ApiLimits>>,
+    mut request: axum::extract::Request,
+    next: Next,
+) -> Response {
+    let api_key = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|key| key.to_str().ok());
+    let remote_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let client = match api_limits.check_request("http", api_key, remote_ip) {
+        Ok(client) => client,
+        Err(err) => {
+            let status = match err {
+                ApiLimitError::UnknownApiKey => StatusCode::UNAUTHORIZED,
+                ApiLimitError::RateLimited | ApiLimitError::TooManyStreams => {
+                    StatusCode::TOO_MANY_REQUESTS
+                }
+            };
+            let error = ErrorResponse {
+                error: err.to_string(),
+                error_detail: None,
+            };
+            return (status, Json(error)).into_response();
         }
+    };
+
+    let query = limit_query_page_size(request.uri().query().unwrap_or(""), &client);
+    let path_and_query = format!("{}?{}", request.uri().path(), query);
+    let mut uri = request.uri().clone().into_parts();
+    uri.path_and_query = PathAndQuery::try_from(path_and_query).ok();
+    if let Ok(uri) = Uri::from_parts(uri) {
+        *request.uri_mut() = uri;
+    }
+    request.extensions_mut().insert(client);
+    next.run(request).await
+}
+
+/**
+ * Builds the HTTP API router. Requests that take longer than the configured timeout get a 408,
+ * bodies over the size limit get a 413, and responses are gzipped when the client accepts it.
+ */
+pub fn router(
+    service: HubHttpServiceImpl,
+    statsd_client: StatsdClientWrapper,
+    config: &Config,
+    api_limits: Arc<ApiLimits>,
+) -> axum::Router {
+    let mut router = routes()
+        // Route layers only run for matched routes, so 404s don't create a metric per path
+        .route_layer(TimeoutLayer::new(config.request_timeout))
+        .route_layer(middleware::from_fn_with_state(
+            statsd_client,
+            record_metrics,
+        ))
+        .layer(DefaultBodyLimit::max(config.max_request_body_size));
+    if api_limits.enabled() {
+        router = router.layer(middleware::from_fn_with_state(
+            api_limits,
+            enforce_api_limits,
+        ));
     }
+    router
+        .layer(CompressionLayer::new())
+        .layer(cors_layer(&config.cors_origin))
+        .with_state(Arc::new(service))
 }
@@ -0,0 +1,192 @@
+#[cfg(test)]
+mod tests {
+    use crate::network::http_server::{
+        routes, FidRequest, ProtobufMessage, QueryString, HTTP_ROUTES,
+    };
+    use crate::network::openapi::openapi_document;
+    use crate::proto;
+    use axum::body::Body;
+    use axum::extract::DefaultBodyLimit;
+    use axum::http::{Request, StatusCode};
+    use axum::routing::{get, post};
+    use prost::Message;
+    use serde_json::Value;
+    use std::collections::HashSet;
+    use tower::ServiceExt;
+
+    // Streaming methods with no request/response equivalent. Events are available over /v1/events.
+    const NOT_SERVED_OVER_HTTP: &[&str] = &["Subscribe"];
+
+    fn hub_service_methods() -> Vec<String> {
+        include_str!("../proto/rpc.proto")
+            .lines()
+            .map(|line| line.trim())
+            .filter_map(|line| line.strip_prefix("rpc "))
+            .map(|rpc| rpc.split('(').next().unwrap().trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_every_hub_service_method_has_http_route() {
+        let methods = hub_service_methods();
+        assert!(methods.len() > 0);
+
+        let routed: HashSet<&str> = HTTP_ROUTES.iter().map(|r| r.grpc_method).collect();
+        let missing: Vec<&String> = methods
+            .iter()
+            .filter(|m| !routed.contains(m.as_str()))
+            .filter(|m| !NOT_SERVED_OVER_HTTP.contains(&m.as_str()))
+            .collect();
+        assert!(missing.is_empty(), "Missing HTTP routes for {:?}", missing);
+
+        for route in HTTP_ROUTES {
+            assert!(
+                methods.contains(&route.grpc_method.to_string()),
+                "{} maps to unknown method {}",
+                route.path,
+                route.grpc_method
+            );
+        }
+    }
+
+    #[test]
+    fn test_http_routes_build_router() {
+        let mut paths = HashSet::new();
+        for route in HTTP_ROUTES {
+            assert!(paths.insert(route.path), "Duplicate route {}", route.path);
+        }
+        // axum panics on conflicting routes
+        routes();
+    }
+
+    async fn send(router: axum::Router, request: Request<Body>) -> (StatusCode, String) {
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8_lossy(&body).to_string())
+    }
+
+    #[tokio::test]
+    async fn test_query_string_extractor() {
+        let router = axum::Router::new().route(
+            "/fid",
+            get(|QueryString(req): QueryString<FidRequest>| async move { req.fid.to_string() }),
+        );
+
+        let request = Request::get("/fid?fid=123").body(Body::empty()).unwrap();
+        assert_eq!(
+            send(router.clone(), request).await,
+            (StatusCode::OK, "123".to_string())
+        );
+
+        let request = Request::get("/fid?fid=abc").body(Body::empty()).unwrap();
+        let (status, body) = send(router, request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.starts_with("Invalid query parameters"));
+    }
+
+    #[tokio::test]
+    async fn test_protobuf_message_extractor() {
+        let router = axum::Router::new()
+            .route(
+                "/message",
+                post(|ProtobufMessage(message): ProtobufMessage| async move {
+                    hex::encode(message.hash)
+                }),
+            )
+            .layer(DefaultBodyLimit::max(64));
+
+        let message = proto::Message {
+            hash: vec![1, 2, 3],
+            ..Default::default()
+        };
+        let request = Request::post("/message")
+            .header("content-type", "application/octet-stream")
+            .body(Body::from(message.encode_to_vec()))
+            .unwrap();
+        assert_eq!(
+            send(router.clone(), request).await,
+            (StatusCode::OK, "010203".to_string())
+        );
+
+        let request = Request::post("/message").body(Body::empty()).unwrap();
+        assert_eq!(
+            send(router.clone(), request).await,
+            (StatusCode::BAD_REQUEST, "Missing content type".to_string())
+        );
+
+        let request = Request::post("/message")
+            .header("content-type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        assert_eq!(
+            send(router.clone(), request).await,
+            (
+                StatusCode::BAD_REQUEST,
+                "Invalid content type: application/json".to_string()
+            )
+        );
+
+        let request = Request::post("/message")
+            .header("content-type", "application/octet-stream")
+            .body(Body::from(vec![0; 65]))
+            .unwrap();
+        let (status, _) = send(router, request).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match (key.as_str(), value) {
+                        ("$ref", Value::String(reference)) => refs.push(reference.clone()),
+                        _ => collect_refs(value, refs),
+                    }
+                }
+            }
+            Value::Array(values) => values.iter().for_each(|v| collect_refs(v, refs)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_openapi_document_matches_routes() {
+        let document = openapi_document();
+        let paths = document["paths"].as_object().unwrap();
+
+        let num_operations: usize = paths.values().map(|p| p.as_object().unwrap().len()).sum();
+        assert_eq!(num_operations, HTTP_ROUTES.len());
+
+        for route in HTTP_ROUTES {
+            let method = route.method.as_str().to_lowercase();
+            let operation = &paths[route.path][&method];
+            assert!(
+                operation.is_object(),
+                "{} {} missing from the document",
+                method,
+                route.path
+            );
+            match route.request {
+                Some(_) => assert!(operation["parameters"].is_array()),
+                None => assert!(operation["requestBody"].is_object()),
+            }
+        }
+
+        let mut refs = vec![];
+        collect_refs(&document, &mut refs);
+        assert!(refs.len() > 0);
+        for reference in refs {
+            let name = reference
+                .strip_prefix("#/components/schemas/")
+                .expect("Unexpected reference");
+            assert!(
+                document["components"]["schemas"][name].is_object(),
+                "Unresolved reference {}",
+                reference
+            );
+        }
+    }
+}
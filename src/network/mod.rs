@@ -1,10 +1,19 @@
 pub mod admin_server;
+pub mod api_limits;
 pub mod gossip;
 pub mod http_server;
+pub mod openapi;
 pub mod rpc_extensions;
 pub mod server;
+pub mod tls;
 
+#[cfg(test)]
+mod api_limits_tests;
 #[cfg(test)]
 mod gossip_test;
 #[cfg(test)]
+mod http_server_tests;
+#[cfg(test)]
 mod server_tests;
+#[cfg(test)]
+mod tls_tests;
@@ -0,0 +1,114 @@
+use once_cell::sync::Lazy;
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use serde_json::{json, Map, Value};
+
+use super::http_server::{ErrorResponse, HttpRoute, HTTP_ROUTES};
+
+pub const OPENAPI_PATH: &str = "/v1/openapi.json";
+
+/** The document is generated once, it only depends on the route table. */
+pub static OPENAPI_DOCUMENT: Lazy<Value> = Lazy::new(openapi_document);
+
+fn operation_id(route: &HttpRoute) -> String {
+    route.path.trim_start_matches("/v1/").to_string()
+}
+
+fn resolve<'a>(schema: &'a Value, definitions: &'a Map<String, Value>) -> &'a Value {
+    match schema.get("$ref").and_then(|r| r.as_str()) {
+        Some(reference) => {
+            let name = reference.rsplit('/').next().unwrap();
+            definitions.get(name).unwrap_or(schema)
+        }
+        None => schema,
+    }
+}
+
+/** Query requests are flat structs, each field becomes a query parameter. */
+fn query_parameters(request: &Value, definitions: &Map<String, Value>) -> Vec<Value> {
+    let request = resolve(request, definitions);
+    let required: Vec<&str> = request
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| r.iter().filter_map(|name| name.as_str()).collect())
+        .unwrap_or_default();
+    request
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .map(|properties| {
+            properties
+                .iter()
+                .map(|(name, schema)| {
+                    json!({
+                        "name": name,
+                        "in": "query",
+                        "required": required.contains(&name.as_str()),
+                        "schema": schema,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn operation(route: &HttpRoute, generator: &mut SchemaGenerator) -> Value {
+    let response = serde_json::to_value((route.response)(generator)).unwrap();
+    let error = serde_json::to_value(generator.subschema_for::<ErrorResponse>()).unwrap();
+    let mut operation = json!({
+        "operationId": operation_id(route),
+        "description": format!("Served by the {} gRPC method", route.grpc_method),
+        "responses": {
+            "200": {
+                "description": "Success",
+                "content": { "application/json": { "schema": response } },
+            },
+            "400": {
+                "description": "Invalid request or the request failed",
+                "content": { "application/json": { "schema": error } },
+            },
+        },
+    });
+
+    match route.request {
+        Some(request) => {
+            let request = serde_json::to_value(request(generator)).unwrap();
+            let definitions = serde_json::to_value(generator.definitions()).unwrap();
+            operation["parameters"] =
+                Value::Array(query_parameters(&request, definitions.as_object().unwrap()));
+        }
+        None => {
+            operation["requestBody"] = json!({
+                "description": "A protobuf encoded Message",
+                "required": true,
+                "content": {
+                    "application/octet-stream": {
+                        "schema": { "type": "string", "format": "binary" },
+                    },
+                },
+            });
+        }
+    }
+    operation
+}
+
+/** Builds an OpenAPI 3 document from [HTTP_ROUTES] and the JSON request and response types. */
+pub fn openapi_document() -> Value {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+    let mut paths = Map::new();
+    for route in HTTP_ROUTES {
+        let operation = operation(route, &mut generator);
+        let path = paths.entry(route.path).or_insert_with(|| json!({}));
+        path[route.method.as_str().to_lowercase()] = operation;
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Snapchain HTTP API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": generator.take_definitions(),
+        },
+    })
+}
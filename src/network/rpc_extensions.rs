@@ -1,7 +1,8 @@
 use crate::core::error::HubError;
 use crate::proto;
 use crate::proto::{
-    CastsByParentRequest, FidRequest, FidTimestampRequest, LinksByFidRequest, ReactionsByFidRequest,
+    CastsByEmbedRequest, CastsByParentRequest, FidRequest, FidTimestampRequest, LinksByFidRequest,
+    ReactionsByFidRequest,
 };
 use crate::storage::db::PageOptions;
 use crate::storage::store::account::MessagesPage;
@@ -83,21 +84,28 @@ impl FidRequest {
     }
 }
 
+fn timestamps(
+    start_timestamp: Option<u64>,
+    stop_timestamp: Option<u64>,
+) -> (Option<u32>, Option<u32>) {
+    let start_timestamp = match start_timestamp {
+        Some(ts) => Some(ts as u32),
+        None => None,
+    };
+    let stop_timestamp = match stop_timestamp {
+        Some(ts) => Some(ts as u32),
+        None => None,
+    };
+    (start_timestamp, stop_timestamp)
+}
+
 impl FidTimestampRequest {
     pub fn page_options(&self) -> PageOptions {
         page_options(self.page_size, self.page_token.clone(), self.reverse)
     }
 
     pub fn timestamps(&self) -> (Option<u32>, Option<u32>) {
-        let start_timestamp = match self.start_timestamp {
-            Some(ts) => Some(ts as u32),
-            None => None,
-        };
-        let stop_timestamp = match self.stop_timestamp {
-            Some(ts) => Some(ts as u32),
-            None => None,
-        };
-        (start_timestamp, stop_timestamp)
+        timestamps(self.start_timestamp, self.stop_timestamp)
     }
 }
 
@@ -105,6 +113,16 @@ impl CastsByParentRequest {
     pub fn page_options(&self) -> PageOptions {
         page_options(self.page_size, self.page_token.clone(), self.reverse)
     }
+
+    pub fn timestamps(&self) -> (Option<u32>, Option<u32>) {
+        timestamps(self.start_timestamp, self.stop_timestamp)
+    }
+}
+
+impl CastsByEmbedRequest {
+    pub fn timestamps(&self) -> (Option<u32>, Option<u32>) {
+        timestamps(self.start_timestamp, self.stop_timestamp)
+    }
 }
 
 impl ReactionsByFidRequest {
@@ -1,5 +1,6 @@
+use super::api_limits::{acquire_stream, limit_page_size, max_results, ApiLimits, CallerId};
 use super::rpc_extensions::{authenticate_request, AsMessagesResponse, AsSingleMessageResponse};
-use crate::connectors::onchain_events::{Chain, ChainClients};
+use crate::connectors::onchain_events::{Chain, ChainClients, OnchainEventsRequest};
 use crate::core::error::HubError;
 use crate::core::util::{get_farcaster_time, FarcasterTime};
 use crate::core::validations;
@@ -13,7 +14,9 @@ use crate::proto::message_data;
 use crate::proto::on_chain_event::Body;
 use crate::proto::reaction_body;
 use crate::proto::reactions_by_target_request;
+use crate::proto::CastsByEmbedRequest;
 use crate::proto::CastsByParentRequest;
+use crate::proto::FidsByVerifiedAddressRequest;
 use crate::proto::FidsRequest;
 use crate::proto::FidsResponse;
 use crate::proto::GetInfoResponse;
@@ -44,36 +47,43 @@ use crate::proto::{
     BlocksRequest, EventRequest, EventsRequest, EventsResponse, ShardChunksRequest,
     ShardChunksResponse, SubscribeRequest,
 };
+use crate::proto::{CastStats, CastStatsRequest, CastStatsResponse};
 use crate::proto::{FidAddressTypeRequest, FidAddressTypeResponse};
 use crate::proto::{FidRequest, FidTimestampRequest};
-use crate::proto::{GetInfoRequest, StorageLimitsResponse};
+use crate::proto::{GetInfoRequest, StorageLimitsResponse, TierStatusResponse, TierType};
 use crate::proto::{
     LinkRequest, LinksByFidRequest, Message, MessagesResponse, ReactionRequest,
     ReactionsByFidRequest, UserDataRequest, VerificationRequest,
 };
+use crate::proto::{OnChainEventRepairRequest, OnChainEventRepairResponse};
+use crate::proto::{ProfileRequest, ProfileResponse};
+use crate::proto::{UsernamesByPrefixRequest, UsernamesByPrefixResponse};
 use crate::storage::constants::OnChainEventPostfix;
 use crate::storage::constants::RootPrefix;
+use crate::storage::constants::PAGE_SIZE_MAX;
 use crate::storage::db::PageOptions;
 use crate::storage::db::RocksDbTransactionBatch;
 use crate::storage::store::account::MessagesPage;
 use crate::storage::store::account::UsernameProofStore;
 use crate::storage::store::account::{message_bytes_decode, IntoI32};
 use crate::storage::store::account::{
-    CastStore, LinkStore, ReactionStore, UserDataStore, VerificationStore,
+    CastStore, CastStoreDef, LinkCountDirection, LinkStore, ReactionStore, Store, UserDataStore,
+    VerificationStore, FOLLOW_LINK_TYPE,
 };
 use crate::storage::store::account::{EventsPage, HubEventIdGenerator};
 use crate::storage::store::engine::{MempoolMessage, MessageValidationError, Senders, ShardEngine};
 use crate::storage::store::stores::Stores;
 use crate::storage::store::BlockStore;
 use crate::utils::statsd_wrapper::StatsdClientWrapper;
-use crate::version::version::EngineVersion;
+use crate::version::version::{EngineVersion, ProtocolFeature};
 use hex::ToHex;
 use moka::policy::EvictionPolicy;
 use moka::sync::{Cache, CacheBuilder};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{broadcast, mpsc, oneshot};
 use tokio::time::timeout;
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::metadata::AsciiMetadataValue;
@@ -82,6 +92,24 @@ use tracing::{debug, error, info};
 
 pub const MEMPOOL_ADD_REQUEST_TIMEOUT: Duration = Duration::from_millis(500);
 const MEMPOOL_SIZE_REQUEST_TIMEOUT: Duration = Duration::from_millis(100);
+// A fid's events are fetched from chain and resubmitted at most once per interval
+const ONCHAIN_EVENT_REPAIR_INTERVAL: Duration = Duration::from_secs(10 * 60);
+// Below the http server's default request timeout
+const ONCHAIN_EVENT_REPAIR_TIMEOUT: Duration = Duration::from_secs(25);
+// Callers page through longer ranges by advancing start_block_number
+pub const MAX_SHARD_CHUNKS_PER_REQUEST: u64 = 100;
+
+fn add_rocksdb_properties(total: &mut proto::RocksDbProperties, db: &proto::RocksDbProperties) {
+    total.block_cache_capacity += db.block_cache_capacity;
+    total.block_cache_usage += db.block_cache_usage;
+    total.block_cache_pinned_usage += db.block_cache_pinned_usage;
+    total.num_running_compactions += db.num_running_compactions;
+    total.num_running_flushes += db.num_running_flushes;
+    total.num_snapshots += db.num_snapshots;
+    total.background_errors += db.background_errors;
+    total.actual_delayed_write_rate += db.actual_delayed_write_rate;
+    total.is_write_stopped |= db.is_write_stopped;
+}
 
 pub struct MyHubService {
     allowed_users: HashMap<String, String>,
@@ -96,7 +124,12 @@ pub struct MyHubService {
     network: proto::FarcasterNetwork,
     version: String,
     peer_id: String,
+    archive_mode: bool,
     id_registry_cache: Cache<Vec<u8>, OnChainEvent>,
+    onchain_events_request_tx: broadcast::Sender<OnchainEventsRequest>,
+    api_limits: Arc<ApiLimits>,
+    // Events fetched from chain for recently repaired fids
+    onchain_event_repairs: Cache<u64, Vec<OnChainEvent>>,
 }
 
 impl MyHubService {
@@ -111,8 +144,11 @@ impl MyHubService {
         message_router: Box<dyn routing::MessageRouter>,
         mempool_tx: mpsc::Sender<MempoolRequest>,
         chain_clients: ChainClients,
+        onchain_events_request_tx: broadcast::Sender<OnchainEventsRequest>,
+        api_limits: Arc<ApiLimits>,
         version: String,
         peer_id: String,
+        archive_mode: bool,
     ) -> Self {
         let mut allowed_users = HashMap::new();
         for auth in rpc_auth.split(",") {
@@ -133,6 +169,10 @@ impl MyHubService {
             .eviction_policy(EvictionPolicy::lru())
             .build();
 
+        let onchain_event_repairs = CacheBuilder::new(100_000)
+            .time_to_live(ONCHAIN_EVENT_REPAIR_INTERVAL)
+            .build();
+
         let service = Self {
             allowed_users,
             network,
@@ -146,11 +186,80 @@ impl MyHubService {
             mempool_tx,
             version,
             peer_id,
+            archive_mode,
             id_registry_cache,
+            onchain_events_request_tx,
+            api_limits,
+            onchain_event_repairs,
         };
         service
     }
 
+    /** Asks the onchain event subscribers for the fid's confirmed events on every indexed chain */
+    async fn fetch_onchain_events_for_fid(&self, fid: u64) -> Result<Vec<OnChainEvent>, Status> {
+        let (response_tx, mut response_rx) = mpsc::channel(10);
+        let num_subscribers = self
+            .onchain_events_request_tx
+            .send(OnchainEventsRequest::FetchFidEvents { fid, response_tx })
+            .map_err(|_| Status::unavailable("onchain events are not indexed by this node"))?;
+
+        let mut events = vec![];
+        let mut num_responses = 0;
+        let _ = timeout(ONCHAIN_EVENT_REPAIR_TIMEOUT, async {
+            while num_responses < num_subscribers {
+                match response_rx.recv().await {
+                    Some(chain_events) => {
+                        events.extend(chain_events);
+                        num_responses += 1;
+                    }
+                    None => break,
+                }
+            }
+        })
+        .await;
+        if num_responses < num_subscribers {
+            return Err(Status::unavailable(
+                "timed out fetching onchain events from chain",
+            ));
+        }
+        Ok(events)
+    }
+
+    /** Events from chain that are in none of the shards' onchain event stores */
+    fn missing_onchain_events(
+        &self,
+        fid: u64,
+        chain_events: &[OnChainEvent],
+    ) -> Result<Vec<OnChainEvent>, Status> {
+        let event_types: HashSet<proto::OnChainEventType> =
+            chain_events.iter().map(|event| event.r#type()).collect();
+        let mut stored = HashSet::new();
+        for stores in self.shard_stores.values() {
+            for event_type in &event_types {
+                let events = stores
+                    .onchain_event_store
+                    .get_onchain_events(*event_type, Some(fid))
+                    .map_err(|e| Status::internal(format!("Store error: {:?}", e)))?;
+                stored.extend(
+                    events
+                        .into_iter()
+                        .map(|event| (event.chain_id, event.transaction_hash, event.log_index)),
+                );
+            }
+        }
+        Ok(chain_events
+            .iter()
+            .filter(|event| {
+                !stored.contains(&(
+                    event.chain_id,
+                    event.transaction_hash.clone(),
+                    event.log_index,
+                ))
+            })
+            .cloned()
+            .collect())
+    }
+
     async fn submit_message_internal(
         &self,
         message: proto::Message,
@@ -176,6 +285,7 @@ impl MyHubService {
                 stores.trie.clone(),
                 1,
                 stores.store_limits.clone(),
+                stores.index_config.clone(),
                 self.statsd_client.clone(),
                 100,
                 None,
@@ -208,7 +318,18 @@ impl MyHubService {
                         self.validate_ens_username_proof(fid, &proof).await?;
                     }
                     Some(proto::message_data::Body::VerificationAddAddressBody(body)) => {
-                        if body.verification_type == 1 {
+                        if body.verification_type == 1
+                            && body.protocol == proto::Protocol::Solana as i32
+                        {
+                            if !EngineVersion::current(self.network)
+                                .is_enabled(ProtocolFeature::SolanaAccountVerifications)
+                            {
+                                return Err(HubError::validation_failure(
+                                    "solana account verifications are not supported yet",
+                                ));
+                            }
+                            self.validate_sol_account_signature(body).await?;
+                        } else if body.verification_type == 1 {
                             let claim_result =
                                 validations::verification::make_verification_address_claim(
                                     message_data.fid,
@@ -298,6 +419,98 @@ impl MyHubService {
         self.get_stores_for_shard(shard_id)
     }
 
+    /**
+     * Runs a cast index query on every shard and merges the results by timestamp, so pages are
+     * ordered across shards. The page token holds a cursor per shard, just past the last cast
+     * returned from it.
+     */
+    fn get_casts_from_all_shards(
+        &self,
+        page_size: Option<u32>,
+        page_token: Option<Vec<u8>>,
+        reverse: bool,
+        query: impl Fn(&Store<CastStoreDef>, &PageOptions) -> Result<MessagesPage, HubError>,
+        cursor: impl Fn(&Message) -> Result<Vec<u8>, HubError>,
+    ) -> Result<Response<MessagesResponse>, Status> {
+        let mut shards: Vec<(&u32, &Stores)> = self.shard_stores.iter().collect();
+        shards.sort_by_key(|(shard_id, _)| **shard_id);
+        let mut cursors: Vec<Option<Vec<u8>>> = if let Some(token_bytes) = page_token {
+            serde_json::from_slice(&token_bytes)
+                .map_err(|e| Status::invalid_argument(format!("Invalid page token: {}", e)))?
+        } else {
+            vec![None; shards.len()]
+        };
+        if cursors.len() != shards.len() {
+            return Err(Status::invalid_argument(
+                "Page token does not match number of shards".to_string(),
+            ));
+        }
+        let page_size = page_size.map_or(PAGE_SIZE_MAX, |s| s as usize);
+
+        // Each shard's next casts in index order, and whether it has more past them
+        let mut pages: Vec<(VecDeque<Message>, bool)> = shards
+            .iter()
+            .zip(cursors.iter())
+            .map(|((_, stores), shard_cursor)| {
+                let page_options = PageOptions {
+                    page_size: Some(page_size),
+                    page_token: shard_cursor.clone(),
+                    reverse,
+                };
+                let page = query(&stores.cast_store, &page_options).unwrap_or(MessagesPage {
+                    messages: vec![],
+                    next_page_token: None,
+                });
+                (
+                    VecDeque::from(page.messages),
+                    page.next_page_token.is_some(),
+                )
+            })
+            .collect();
+
+        // Same order as the index keys, which start with the timestamp and hash
+        let order = |message: &Message| {
+            message
+                .data
+                .as_ref()
+                .map(|data| (data.timestamp, message.hash.clone(), data.fid))
+        };
+        let mut messages = vec![];
+        while messages.len() < page_size {
+            let fronts = pages.iter().enumerate().filter_map(|(index, (casts, _))| {
+                casts.front().map(|message| (index, order(message)))
+            });
+            let next = if reverse {
+                fronts.max_by(|a, b| a.1.cmp(&b.1))
+            } else {
+                fronts.min_by(|a, b| a.1.cmp(&b.1))
+            };
+            let Some((index, _)) = next else {
+                break;
+            };
+            let message = pages[index].0.pop_front().unwrap();
+            cursors[index] = Some(
+                cursor(&message).map_err(|e| Status::internal(format!("Store error: {:?}", e)))?,
+            );
+            messages.push(message);
+        }
+
+        let has_more = pages
+            .iter()
+            .any(|(casts, shard_has_more)| !casts.is_empty() || *shard_has_more);
+        let next_page_token = if has_more {
+            Some(serde_json::to_vec(&cursors).map_err(|e| {
+                Status::internal(format!("Failed to serialize next_page_token: {}", e))
+            })?)
+        } else {
+            None
+        };
+        Ok(Response::new(MessagesResponse {
+            messages,
+            next_page_token,
+        }))
+    }
+
     pub async fn validate_contract_signature(
         &self,
         claim: VerificationAddressClaim,
@@ -314,6 +527,34 @@ impl MyHubService {
             })
     }
 
+    pub async fn validate_sol_account_signature(
+        &self,
+        body: &VerificationAddAddressBody,
+    ) -> Result<(), HubError> {
+        let claim = validations::verification::parse_sol_account_claim(body)
+            .map_err(|e| HubError::validation_failure(&e.to_string()))?;
+        let authority = self
+            .chain_clients
+            .solana()?
+            .get_account_authority(claim.authority)
+            .await
+            .map_err(|e| {
+                HubError::validation_failure(
+                    format!(
+                        "could not fetch solana account authority: {}",
+                        e.to_string()
+                    )
+                    .as_str(),
+                )
+            })?;
+        validations::verification::validate_sol_account_authority(
+            &claim,
+            authority.threshold,
+            &authority.members,
+        )
+        .map_err(|e| HubError::validation_failure(&e.to_string()))
+    }
+
     pub async fn validate_ens_username_proof(
         &self,
         fid: u64,
@@ -589,6 +830,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<BlocksRequest>,
     ) -> Result<Response<Self::GetBlocksStream>, Status> {
+        let stream_permit = acquire_stream(&request)?;
         let start_block_number = request.get_ref().start_block_number;
         let stop_block_number = request.get_ref().stop_block_number;
         // TODO(aditi): Rethink the channel size
@@ -599,6 +841,7 @@ impl HubService for MyHubService {
         let block_store = self.block_store.clone();
 
         tokio::spawn(async move {
+            let _stream_permit = stream_permit;
             let mut next_page_token = None;
             loop {
                 match block_store.get_blocks(
@@ -641,7 +884,17 @@ impl HubService for MyHubService {
         // TODO(aditi): Write unit tests for these functions.
         let shard_index = request.get_ref().shard_id;
         let start_block_number = request.get_ref().start_block_number;
-        let stop_block_number = request.get_ref().stop_block_number;
+        let max_shard_chunks = max_results(&request).map_or(MAX_SHARD_CHUNKS_PER_REQUEST, |max| {
+            MAX_SHARD_CHUNKS_PER_REQUEST.min(max as u64)
+        });
+        // The stop block is an exclusive bound in the shard store
+        let last_block_number = start_block_number.saturating_add(max_shard_chunks);
+        let stop_block_number = Some(
+            request
+                .get_ref()
+                .stop_block_number
+                .map_or(last_block_number, |stop| stop.min(last_block_number)),
+        );
 
         info!( {shard_index, start_block_number, stop_block_number},
             "Received call to [get_shard_chunks] RPC");
@@ -673,6 +926,7 @@ impl HubService for MyHubService {
         let mut total_fid_registrations = 0;
         let mut total_approx_size = 0;
         let mut total_num_messages = 0;
+        let mut total_rocksdb_properties = proto::RocksDbProperties::default();
         let mut shard_infos = Vec::new();
 
         let (size_req, size_res) = oneshot::channel();
@@ -688,6 +942,8 @@ impl HubService for MyHubService {
             });
 
         let current_time = get_farcaster_time().unwrap_or(0);
+        let block_rocksdb_properties = self.block_store.db.properties();
+        add_rocksdb_properties(&mut total_rocksdb_properties, &block_rocksdb_properties);
         let block_info = proto::ShardInfo {
             shard_id: 0,
             max_height: self.block_store.max_block_number().unwrap_or(0),
@@ -696,6 +952,7 @@ impl HubService for MyHubService {
             approx_size: self.block_store.db.approximate_size(),
             block_delay: current_time - self.block_store.max_block_timestamp().unwrap_or(0),
             mempool_size: 0,
+            rocksdb_properties: Some(block_rocksdb_properties),
         };
         shard_infos.push(block_info);
 
@@ -731,6 +988,8 @@ impl HubService for MyHubService {
                 as u64;
 
             let max_block_time = shard_store.shard_store.max_block_timestamp().unwrap_or(0);
+            let shard_rocksdb_properties = shard_store.db.properties();
+            add_rocksdb_properties(&mut total_rocksdb_properties, &shard_rocksdb_properties);
 
             let info = proto::ShardInfo {
                 shard_id: *shard_index,
@@ -743,6 +1002,7 @@ impl HubService for MyHubService {
                 // Returning 0 would mean the clients would think the mempool is empty
                 // So, return a high value
                 mempool_size: *mempool_size.get(shard_index).unwrap_or(&(u32::MAX as u64)),
+                rocksdb_properties: Some(shard_rocksdb_properties),
             };
             shard_infos.push(info);
             total_num_messages += shard_num_messages;
@@ -755,11 +1015,13 @@ impl HubService for MyHubService {
                 num_fid_registrations: total_fid_registrations,
                 num_messages: total_num_messages,
                 approx_size: total_approx_size,
+                rocksdb_properties: Some(total_rocksdb_properties),
             }),
             shard_infos,
             num_shards: self.num_shards,
             version: self.version.clone(),
             peer_id: self.peer_id.clone(),
+            archive_mode: self.archive_mode,
         }))
     }
 
@@ -767,6 +1029,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<FidsRequest>,
     ) -> Result<Response<proto::FidsResponse>, Status> {
+        let request = limit_page_size(request);
         let inner_request = request.into_inner();
 
         let stores = self.get_stores_for_shard(inner_request.shard_id)?;
@@ -799,6 +1062,8 @@ impl HubService for MyHubService {
             request.get_ref().from_id,
             request.get_ref().shard_index
         );
+        // Held by every task that feeds the stream, so it's released once the client hangs up
+        let stream_permit = Arc::new(acquire_stream(&request)?);
         let (server_tx, client_rx) = mpsc::channel::<Result<HubEvent, Status>>(100);
         let events_txs = match request.get_ref().shard_index {
             Some(shard_id) => match self.shard_senders.get(&(shard_id)) {
@@ -887,7 +1152,9 @@ impl HubService for MyHubService {
                 inner_events.resize(event_types_filter.len(), 0);
                 inner_events.copy_from_slice(event_types_filter.as_slice());
                 let tx = server_tx.clone();
+                let stream_permit = stream_permit.clone();
                 tokio::spawn(async move {
+                    let _stream_permit = stream_permit;
                     let filtered_events = inner_events.clone();
                     let mut event_rx = event_tx.subscribe();
                     loop {
@@ -959,6 +1226,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<EventsRequest>,
     ) -> Result<Response<EventsResponse>, Status> {
+        let request = limit_page_size(request);
         let req = request.into_inner();
 
         let num_shards;
@@ -989,6 +1257,21 @@ impl HubService for MyHubService {
                 "Page token does not match number of shards".to_string(),
             ));
         }
+        // Backfilled heights have no events, fail rather than silently starting later
+        for store in shard_stores.iter() {
+            let executed_start_height = store
+                .shard_store
+                .get_executed_start_height()
+                .map_err(|e| Status::internal(e.to_string()))?;
+            if let Some(height) = executed_start_height {
+                if req.start_id < HubEventIdGenerator::make_event_id_for_block_number(height) {
+                    return Err(Status::failed_precondition(format!(
+                        "Events before height {} are not available on shard {}, history below it was backfilled without events",
+                        height, store.shard_id
+                    )));
+                }
+            }
+        }
         let pages: Vec<EventsPage> = shard_stores
             .iter()
             .zip(per_shard_tokens.into_iter())
@@ -1032,6 +1315,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<FidRequest>,
     ) -> Result<Response<proto::MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let request = request.into_inner();
         let stores = self.get_stores_for(request.fid)?;
         let options = request.page_options();
@@ -1042,6 +1326,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<FidTimestampRequest>,
     ) -> Result<Response<proto::MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let request = request.into_inner();
         let stores = self.get_stores_for(request.fid)?;
         let (start_ts, stop_ts) = request.timestamps();
@@ -1079,6 +1364,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<ReactionsByFidRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let request = request.into_inner();
         let stores = self.get_stores_for(request.fid)?;
         let options = request.page_options();
@@ -1095,6 +1381,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<FidTimestampRequest>,
     ) -> Result<Response<proto::MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let request = request.into_inner();
         let stores = self.get_stores_for(request.fid)?;
         let (start_ts, stop_ts) = request.timestamps();
@@ -1121,6 +1408,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<LinksByFidRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let request = request.into_inner();
         let stores = self.get_stores_for(request.fid)?;
         let options = request.page_options();
@@ -1137,6 +1425,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<FidTimestampRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let request = request.into_inner();
         let stores = self.get_stores_for(request.fid)?;
         let (start_ts, stop_ts) = request.timestamps();
@@ -1166,6 +1455,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<FidRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let request = request.into_inner();
         let stores = self.get_stores_for(request.fid)?;
         let options = request.page_options();
@@ -1179,10 +1469,95 @@ impl HubService for MyHubService {
         .as_response()
     }
 
+    async fn get_profile(
+        &self,
+        request: Request<ProfileRequest>,
+    ) -> Result<Response<ProfileResponse>, Status> {
+        let fid = request.into_inner().fid;
+        let stores = self.get_stores_for(fid)?;
+        let internal = |err: HubError| Status::internal(err.to_string());
+
+        let user_data = UserDataStore::get_user_data_adds_by_fid(
+            &stores.user_data_store,
+            fid,
+            &PageOptions::default(),
+            None,
+            None,
+        )
+        .map_err(internal)?
+        .messages;
+
+        let mut verifications = vec![];
+        let mut page_options = PageOptions::default();
+        loop {
+            let page = VerificationStore::get_verification_adds_by_fid(
+                &stores.verification_store,
+                fid,
+                &page_options,
+            )
+            .map_err(internal)?;
+            verifications.extend(page.messages);
+            match page.next_page_token {
+                Some(page_token) => page_options.page_token = Some(page_token),
+                None => break,
+            }
+        }
+
+        let storage_limits = stores
+            .get_storage_limits(fid)
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        let following_count = LinkStore::get_link_count(
+            &stores.link_store,
+            fid,
+            LinkCountDirection::ByFid,
+            FOLLOW_LINK_TYPE,
+        )
+        .map_err(internal)?;
+        // Follows are stored on the shard of the follower
+        let mut follower_count = 0;
+        for stores in self.shard_stores.values() {
+            follower_count += LinkStore::get_link_count(
+                &stores.link_store,
+                fid,
+                LinkCountDirection::ByTarget,
+                FOLLOW_LINK_TYPE,
+            )
+            .map_err(internal)?;
+        }
+
+        let mut profile = ProfileResponse {
+            fid,
+            verifications,
+            storage_limits: Some(storage_limits),
+            follower_count,
+            following_count,
+            ..Default::default()
+        };
+        for message in &user_data {
+            if let Some(message_data::Body::UserDataBody(body)) =
+                message.data.as_ref().and_then(|data| data.body.as_ref())
+            {
+                match body.r#type() {
+                    proto::UserDataType::UserDataPrimaryAddressEthereum => {
+                        profile.primary_address_ethereum = body.value.clone()
+                    }
+                    proto::UserDataType::UserDataPrimaryAddressSolana => {
+                        profile.primary_address_solana = body.value.clone()
+                    }
+                    _ => {}
+                }
+            }
+        }
+        profile.user_data = user_data;
+        Ok(Response::new(profile))
+    }
+
     async fn get_all_user_data_messages_by_fid(
         &self,
         request: Request<FidTimestampRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let request = request.into_inner();
         let stores = self.get_stores_for(request.fid)?;
         let (start_ts, stop_ts) = request.timestamps();
@@ -1233,6 +1608,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<FidRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let request = request.into_inner();
         let stores = self.get_stores_for(request.fid)?;
         let options = request.page_options();
@@ -1244,10 +1620,49 @@ impl HubService for MyHubService {
         .as_response()
     }
 
+    async fn get_fids_by_verified_address(
+        &self,
+        request: Request<FidsByVerifiedAddressRequest>,
+    ) -> Result<Response<FidsResponse>, Status> {
+        let request = request.into_inner();
+        let protocol = proto::Protocol::try_from(request.protocol)
+            .map_err(|_| Status::invalid_argument("Invalid protocol".to_string()))?;
+        let address_length = match protocol {
+            proto::Protocol::Ethereum => 20,
+            proto::Protocol::Solana => 32,
+        };
+        if request.address.len() != address_length {
+            return Err(Status::invalid_argument(format!(
+                "Invalid {} address",
+                protocol.as_str_name()
+            )));
+        }
+
+        // Each shard indexes the verifications of its own fids
+        let mut fids = vec![];
+        for stores in self.shard_stores.values() {
+            let fid = VerificationStore::get_fid_by_verified_address(
+                &stores.verification_store,
+                protocol,
+                &request.address,
+            )
+            .map_err(|err| Status::internal(err.to_string()))?;
+            fids.extend(fid);
+        }
+        fids.sort();
+        fids.dedup();
+
+        Ok(Response::new(FidsResponse {
+            fids,
+            next_page_token: None,
+        }))
+    }
+
     async fn get_all_verification_messages_by_fid(
         &self,
         request: Request<FidTimestampRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let request = request.into_inner();
         let stores = self.get_stores_for(request.fid)?;
         let (start_ts, stop_ts) = request.timestamps();
@@ -1261,6 +1676,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<FidRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let request = request.into_inner();
         let stores = self.get_stores_for(request.fid)?;
         let options = request.page_options();
@@ -1280,10 +1696,26 @@ impl HubService for MyHubService {
         Ok(Response::new(limits))
     }
 
+    async fn get_tier_status(
+        &self,
+        request: Request<FidRequest>,
+    ) -> Result<Response<TierStatusResponse>, Status> {
+        let request = request.into_inner();
+        let stores = self.get_stores_for(request.fid)?;
+        let status = stores
+            .onchain_event_store
+            .get_tier_status(TierType::Pro, request.fid, &FarcasterTime::current())
+            .map_err(|err| Status::internal(err.to_string()))?;
+        Ok(Response::new(TierStatusResponse {
+            tiers: vec![status],
+        }))
+    }
+
     async fn get_casts_by_parent(
         &self,
         request: Request<CastsByParentRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let req = request.into_inner();
         let parent = match req.parent {
             Some(casts_by_parent_request::Parent::ParentCastId(cast_id)) => {
@@ -1294,56 +1726,87 @@ impl HubService for MyHubService {
             }
             None => return Err(Status::not_found("Parent not specified".to_string())),
         };
-        let num_shards = self.shard_stores.len();
-        let per_shard_tokens: Vec<Option<Vec<u8>>> = if let Some(token_bytes) = req.page_token {
-            serde_json::from_slice(&token_bytes)
-                .map_err(|e| Status::invalid_argument(format!("Invalid page token: {}", e)))?
-        } else {
-            vec![None; num_shards]
-        };
-        if per_shard_tokens.len() != num_shards {
-            return Err(Status::invalid_argument(
-                "Page token does not match number of shards".to_string(),
-            ));
+        let (start_timestamp, stop_timestamp) = req.timestamps();
+        self.get_casts_from_all_shards(
+            req.page_size,
+            req.page_token,
+            req.reverse.unwrap_or(false),
+            |cast_store, page_options| {
+                CastStore::get_casts_by_parent(
+                    cast_store,
+                    &parent,
+                    start_timestamp,
+                    stop_timestamp,
+                    page_options,
+                )
+            },
+            |message| CastStore::cast_by_parent_cursor(&parent, message),
+        )
+    }
+
+    async fn get_casts_by_embed(
+        &self,
+        request: Request<CastsByEmbedRequest>,
+    ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
+        let req = request.into_inner();
+        if req.url.is_empty() {
+            return Err(Status::invalid_argument("Url not specified".to_string()));
         }
-        let pages: Vec<MessagesPage> = self
-            .shard_stores
-            .iter()
-            .zip(per_shard_tokens.into_iter())
-            .map(|(shard_entry, shard_token)| {
-                let page_options = PageOptions {
-                    page_size: req.page_size.map(|s| s as usize),
-                    page_token: shard_token,
-                    reverse: req.reverse.unwrap_or(false),
-                };
-                let cast_store = &shard_entry.1.cast_store;
-                return CastStore::get_casts_by_parent(cast_store, &parent, &page_options)
-                    .unwrap_or(MessagesPage {
-                        messages: vec![],
-                        next_page_token: None,
-                    });
-            })
-            .collect();
-        let combined_messages: Vec<Message> = pages
-            .iter()
-            .flat_map(|page| page.messages.clone())
-            .collect();
-        let next_page_tokens: Vec<Option<Vec<u8>>> =
-            pages.into_iter().map(|page| page.next_page_token).collect();
-        let new_page_token = serde_json::to_vec(&next_page_tokens)
-            .map_err(|e| Status::internal(format!("Failed to serialize next_page_token: {}", e)))?;
-        let response = MessagesResponse {
-            messages: combined_messages,
-            next_page_token: Some(new_page_token),
-        };
+        let (start_timestamp, stop_timestamp) = req.timestamps();
+        self.get_casts_from_all_shards(
+            req.page_size,
+            req.page_token,
+            req.reverse.unwrap_or(false),
+            |cast_store, page_options| {
+                CastStore::get_casts_by_embed(
+                    cast_store,
+                    &req.url,
+                    start_timestamp,
+                    stop_timestamp,
+                    page_options,
+                )
+            },
+            |message| CastStore::cast_by_embed_cursor(&req.url, message),
+        )
+    }
 
-        Ok(Response::new(response))
+    async fn get_cast_stats(
+        &self,
+        request: Request<CastStatsRequest>,
+    ) -> Result<Response<CastStatsResponse>, Status> {
+        let cast_ids = request.into_inner().cast_ids;
+        if cast_ids.len() > PAGE_SIZE_MAX {
+            return Err(Status::invalid_argument(format!(
+                "At most {} casts per request",
+                PAGE_SIZE_MAX
+            )));
+        }
+
+        // Reactions and replies are counted on the shard of their author
+        let mut stats = vec![];
+        for cast_id in cast_ids {
+            let mut cast_stats = CastStats {
+                cast_id: Some(cast_id.clone()),
+                ..Default::default()
+            };
+            for stores in self.shard_stores.values() {
+                let shard_stats = CastStore::get_cast_stats(&stores.cast_store, &cast_id)
+                    .map_err(|err| Status::internal(err.to_string()))?;
+                cast_stats.likes += shard_stats.likes;
+                cast_stats.recasts += shard_stats.recasts;
+                cast_stats.replies += shard_stats.replies;
+            }
+            stats.push(cast_stats);
+        }
+        Ok(Response::new(CastStatsResponse { stats }))
     }
 
     async fn get_casts_by_mention(
         &self,
         request: Request<FidRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let req = request.into_inner();
         let mention = req.fid;
 
@@ -1405,6 +1868,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<ReactionsByTargetRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let req = request.into_inner();
 
         let reaction_type = req
@@ -1483,6 +1947,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<ReactionsByTargetRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let req = request.into_inner();
 
         let reaction_type = req.reaction_type.unwrap_or(ReactionType::None.into()); // Use enum vs 0?
@@ -1618,10 +2083,72 @@ impl HubService for MyHubService {
         }
     }
 
+    async fn get_usernames_by_prefix(
+        &self,
+        request: Request<UsernamesByPrefixRequest>,
+    ) -> Result<Response<UsernamesByPrefixResponse>, Status> {
+        let request = limit_page_size(request);
+        let req = request.into_inner();
+        let prefix = req.prefix.to_lowercase();
+        let page_size = req.page_size.map_or(PAGE_SIZE_MAX, |size| {
+            (size as usize).clamp(1, PAGE_SIZE_MAX)
+        });
+        let after = req.page_token.as_deref();
+
+        // Every source returns its first page_size names, so the first page_size names of the
+        // union are complete. Transferred fnames can be on more than one shard, the latest proof
+        // wins
+        let mut proofs: BTreeMap<Vec<u8>, UserNameProof> = BTreeMap::new();
+        let mut keep_latest = |proof: UserNameProof| match proofs.get(&proof.name) {
+            Some(existing) if existing.timestamp >= proof.timestamp => {}
+            _ => {
+                proofs.insert(proof.name.clone(), proof);
+            }
+        };
+        for stores in self.shard_stores.values() {
+            let fnames = UserDataStore::get_username_proofs_by_prefix(
+                &stores.user_data_store,
+                prefix.as_bytes(),
+                after,
+                page_size,
+            )
+            .map_err(|err| Status::internal(err.to_string()))?;
+            fnames.into_iter().for_each(&mut keep_latest);
+
+            let messages = UsernameProofStore::get_username_proofs_by_prefix(
+                &stores.username_proof_store,
+                prefix.as_bytes(),
+                after,
+                page_size,
+            )
+            .map_err(|err| Status::internal(err.to_string()))?;
+            for message in messages {
+                if let Some(message_data::Body::UsernameProofBody(proof)) =
+                    message.data.and_then(|data| data.body)
+                {
+                    keep_latest(proof);
+                }
+            }
+        }
+
+        let page: Vec<UserNameProof> = proofs.into_values().take(page_size).collect();
+        let next_page_token = if page.len() == page_size {
+            page.last().map(|proof| proof.name.clone())
+        } else {
+            None
+        };
+        Ok(Response::new(UsernamesByPrefixResponse {
+            // Deleted fnames
+            proofs: page.into_iter().filter(|proof| proof.fid != 0).collect(),
+            next_page_token,
+        }))
+    }
+
     async fn get_user_name_proofs_by_fid(
         &self,
         request: Request<FidRequest>,
     ) -> Result<Response<UsernameProofsResponse>, Status> {
+        let max_proofs = max_results(&request).unwrap_or(usize::MAX);
         let req = request.into_inner();
         let fid = req.fid;
 
@@ -1661,7 +2188,7 @@ impl HubService for MyHubService {
                         })
                     }));
 
-                    if page.next_page_token.is_none() {
+                    if page.next_page_token.is_none() || all_proofs.len() >= max_proofs {
                         break;
                     }
 
@@ -1692,6 +2219,7 @@ impl HubService for MyHubService {
             }
         }
 
+        combined_proofs.truncate(max_proofs);
         let response = UsernameProofsResponse {
             proofs: combined_proofs,
         };
@@ -1731,6 +2259,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<FidRequest>,
     ) -> Result<Response<OnChainEventResponse>, Status> {
+        let request = limit_page_size(request);
         let req = request.into_inner();
         let fid = req.fid;
 
@@ -1758,24 +2287,30 @@ impl HubService for MyHubService {
         &self,
         request: Request<OnChainEventRequest>,
     ) -> Result<Response<OnChainEventResponse>, Status> {
+        let request = limit_page_size(request);
         let req = request.into_inner();
         let fid = req.fid;
 
         let event_type = proto::OnChainEventType::try_from(req.event_type)
             .map_err(|_| Status::invalid_argument("Invalid event type"))?;
 
-        let mut combined_events = Vec::new();
-        for (_shard_id, stores) in &self.shard_stores {
-            let events = stores
-                .onchain_event_store
-                .get_onchain_events(event_type, Some(fid))
-                .map_err(|e| Status::internal(format!("Store error: {:?}", e)))?;
-            combined_events.extend(events);
-        }
+        let stores = self.get_stores_for(fid)?;
+        let events_page = stores
+            .onchain_event_store
+            .get_onchain_events_page(
+                event_type,
+                Some(fid),
+                &PageOptions {
+                    page_size: req.page_size.map(|s| s as usize),
+                    page_token: req.page_token.clone(),
+                    reverse: req.reverse.unwrap_or(false),
+                },
+            )
+            .map_err(|e| Status::internal(format!("Store error: {:?}", e)))?;
 
         let response = OnChainEventResponse {
-            events: combined_events,
-            next_page_token: None,
+            events: events_page.onchain_events,
+            next_page_token: events_page.next_page_token,
         };
         Ok(Response::new(response))
     }
@@ -1838,6 +2373,60 @@ impl HubService for MyHubService {
         Err(Status::not_found("no id-registry event for address"))
     }
 
+    async fn request_on_chain_event_repair(
+        &self,
+        request: Request<OnChainEventRepairRequest>,
+    ) -> Result<Response<OnChainEventRepairResponse>, Status> {
+        let caller = CallerId::of(&request);
+        let fid = request.into_inner().fid;
+        if fid == 0 {
+            return Err(Status::invalid_argument("fid cannot be 0"));
+        }
+
+        // Recently repaired fids are only compared against the events fetched last time
+        if let Some(chain_events) = self.onchain_event_repairs.get(&fid) {
+            return Ok(Response::new(OnChainEventRepairResponse {
+                missing_events: self.missing_onchain_events(fid, &chain_events)?,
+                resubmitted: false,
+            }));
+        }
+
+        self.api_limits.check_onchain_event_repair(&caller)?;
+        let chain_events = self.fetch_onchain_events_for_fid(fid).await?;
+        self.onchain_event_repairs.insert(fid, chain_events.clone());
+        let missing_events = self.missing_onchain_events(fid, &chain_events)?;
+        for event in &missing_events {
+            self.mempool_tx
+                .send(MempoolRequest::AddMessage(
+                    MempoolMessage::ValidatorMessage(proto::ValidatorMessage {
+                        on_chain_event: Some(event.clone()),
+                        fname_transfer: None,
+                        storage_prune: None,
+                    }),
+                    MempoolSource::Local,
+                    None,
+                ))
+                .await
+                .map_err(|_| Status::unavailable("mempool channel send error"))?;
+        }
+        if !missing_events.is_empty() {
+            info!(
+                fid,
+                num_missing_events = missing_events.len(),
+                "Resubmitted missing onchain events"
+            );
+            self.statsd_client.count(
+                "rpc.onchain_event_repair.resubmitted_events",
+                missing_events.len() as i64,
+            );
+        }
+
+        Ok(Response::new(OnChainEventRepairResponse {
+            resubmitted: !missing_events.is_empty(),
+            missing_events,
+        }))
+    }
+
     async fn get_fid_address_type(
         &self,
         request: Request<FidAddressTypeRequest>,
@@ -1905,6 +2494,7 @@ impl HubService for MyHubService {
         &self,
         request: Request<LinksByTargetRequest>,
     ) -> Result<Response<MessagesResponse>, Status> {
+        let request = limit_page_size(request);
         let req = request.into_inner();
 
         if req.link_type.clone().is_none() {
@@ -9,12 +9,14 @@ mod tests {
     use std::time::{Duration, Instant};
     use tokio::time::{sleep, timeout};
 
-    use crate::connectors::onchain_events::{Chain, ChainAPI, ChainClients};
+    use crate::connectors::onchain_events::{Chain, ChainAPI, ChainClients, OnchainEventsRequest};
+    use crate::connectors::solana::{SolanaAPI, SolanaAccountAuthority, SolanaError};
     use crate::core::validations::{self, verification::VerificationAddressClaim};
     use crate::mempool::mempool::{self, Mempool};
     use crate::mempool::routing;
     use crate::mempool::routing::MessageRouter;
-    use crate::network::server::MyHubService;
+    use crate::network::api_limits::{self, ApiLimits};
+    use crate::network::server::{MyHubService, MAX_SHARD_CHUNKS_PER_REQUEST};
     use crate::proto::hub_service_server::HubService;
     use crate::proto::{
         self, EventRequest, EventsRequest, HubEvent, HubEventType, OnChainEventType, ShardChunk,
@@ -23,13 +25,13 @@ mod tests {
     };
     use crate::proto::{FidRequest, SubscribeRequest};
     use crate::storage::db::{self, RocksDB, RocksDbTransactionBatch};
-    use crate::storage::store::account::{HubEventIdGenerator, SEQUENCE_BITS};
+    use crate::storage::store::account::{HubEventIdGenerator, UserDataStore, SEQUENCE_BITS};
     use crate::storage::store::engine::{Senders, ShardEngine};
-    use crate::storage::store::stores::Stores;
+    use crate::storage::store::stores::{IndexConfig, Stores};
     use crate::storage::store::test_helper::{commit_event, generate_signer, register_user};
     use crate::storage::store::{test_helper, BlockStore};
     use crate::storage::trie::merkle_trie;
-    use crate::utils::factory::{events_factory, messages_factory};
+    use crate::utils::factory::{events_factory, messages_factory, shard_chunk_factory};
     use crate::utils::statsd_wrapper::StatsdClientWrapper;
     use futures::future;
     use futures::StreamExt;
@@ -54,6 +56,26 @@ mod tests {
         }
     }
 
+    const SOL_MULTISIG: [u8; 32] = [9; 32];
+
+    struct MockSolanaClient {}
+
+    #[async_trait]
+    impl SolanaAPI for MockSolanaClient {
+        async fn get_account_authority(
+            &self,
+            address: [u8; 32],
+        ) -> Result<SolanaAccountAuthority, SolanaError> {
+            if address != SOL_MULTISIG {
+                return Err(SolanaError::AccountNotFound);
+            }
+            Ok(SolanaAccountAuthority {
+                threshold: 2,
+                members: vec![[4; 32], [5; 32], [6; 32]],
+            })
+        }
+    }
+
     struct MockL1Client {}
 
     #[async_trait]
@@ -185,6 +207,19 @@ mod tests {
         HashMap<u32, Senders>,
         [ShardEngine; 2],
         MyHubService,
+    ) {
+        let (onchain_events_request_tx, _) = broadcast::channel(10);
+        make_server_with_onchain_events(rpc_auth, onchain_events_request_tx).await
+    }
+
+    async fn make_server_with_onchain_events(
+        rpc_auth: Option<String>,
+        onchain_events_request_tx: broadcast::Sender<OnchainEventsRequest>,
+    ) -> (
+        HashMap<u32, Stores>,
+        HashMap<u32, Senders>,
+        [ShardEngine; 2],
+        MyHubService,
     ) {
         let statsd_client = StatsdClientWrapper::new(
             cadence::StatsdClient::builder("", cadence::NopMetricSink {}).build(),
@@ -192,12 +227,15 @@ mod tests {
         );
 
         let limits = test_helper::limits::test_store_limits();
+        let index_config = IndexConfig { cast_embeds: true };
         let (engine1, _) = test_helper::new_engine_with_options(test_helper::EngineOptions {
             limits: Some(limits.clone()),
+            index_config: index_config.clone(),
             ..Default::default()
         });
         let (engine2, _) = test_helper::new_engine_with_options(test_helper::EngineOptions {
             limits: Some(limits.clone()),
+            index_config,
             ..Default::default()
         });
         let db1 = engine1.db.clone();
@@ -210,6 +248,7 @@ mod tests {
             1,
             merkle_trie::MerkleTrie::new(16).unwrap(),
             limits.clone(),
+            IndexConfig::default(),
             test_helper::statsd_client(),
         );
         let shard1_senders = engine1.get_senders();
@@ -219,6 +258,7 @@ mod tests {
             2,
             merkle_trie::MerkleTrie::new(16).unwrap(),
             limits.clone(),
+            IndexConfig::default(),
             test_helper::statsd_client(),
         );
         let shard2_senders = engine2.get_senders();
@@ -252,6 +292,7 @@ mod tests {
 
         let mut chain_clients = ChainClients {
             chain_api_map: HashMap::new(),
+            solana_client: Some(Box::new(MockSolanaClient {})),
         };
         chain_clients.chain_api_map.insert(
             Chain::EthMainnet,
@@ -276,8 +317,14 @@ mod tests {
                 message_router,
                 mempool_tx.clone(),
                 chain_clients,
+                onchain_events_request_tx,
+                Arc::new(ApiLimits::new(
+                    api_limits::Config::default(),
+                    test_helper::statsd_client(),
+                )),
                 "0.1.2".to_string(),
                 "asddef".to_string(),
+                false,
             ),
         )
     }
@@ -539,6 +586,35 @@ mod tests {
         assert!(events[0].shard_index > 0);
     }
 
+    #[tokio::test]
+    async fn test_get_events_fails_below_backfilled_history() {
+        let (stores, _, _, service) = make_server(None).await;
+        let shard_chunk = shard_chunk_factory::create_shard_chunk(1, Some(4), None);
+        stores[&1]
+            .shard_store
+            .put_backfilled_shard_chunk(&shard_chunk, 5)
+            .unwrap();
+
+        let get_events = |start_id| {
+            service.get_events(Request::new(proto::EventsRequest {
+                start_id,
+                shard_index: Some(1),
+                stop_id: None,
+                page_size: None,
+                page_token: None,
+                reverse: None,
+            }))
+        };
+
+        let error = get_events(0).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::FailedPrecondition);
+        assert!(
+            get_events(HubEventIdGenerator::make_event_id_for_block_number(5))
+                .await
+                .is_ok()
+        );
+    }
+
     #[tokio::test]
     async fn test_submit_message_fails_with_error_for_invalid_messages() {
         let (_stores, _senders, [mut engine1, _], service) = make_server(None).await;
@@ -910,6 +986,216 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_get_fids_by_verified_address() {
+        let (_, _, [mut engine1, mut engine2], service) = make_server(None).await;
+        let engine1 = &mut engine1;
+        let engine2 = &mut engine2;
+        test_helper::register_user(
+            SHARD1_FID,
+            test_helper::default_signer(),
+            test_helper::default_custody_address(),
+            engine1,
+        )
+        .await;
+        test_helper::register_user(
+            SHARD2_FID,
+            test_helper::default_signer(),
+            test_helper::default_custody_address(),
+            engine2,
+        )
+        .await;
+
+        let eth_address = hex::decode("91031dcfdea024b4d51e775486111d2b2a715871").unwrap();
+        let sol_address =
+            hex::decode("83f7335253bfaf321de49f25f6fd67fa8f1d0665b4cab33f67f7e4341bfd91d0")
+                .unwrap();
+        let eth_verification = messages_factory::verifications::create_verification_add(
+            SHARD1_FID,
+            0,
+            eth_address.clone(),
+            vec![1; 65],
+            vec![2; 32],
+            None,
+            None,
+        );
+        let sol_verification = messages_factory::create_message_with_data(
+            SHARD2_FID,
+            proto::MessageType::VerificationAddEthAddress,
+            proto::message_data::Body::VerificationAddAddressBody(
+                proto::VerificationAddAddressBody {
+                    address: sol_address.clone(),
+                    claim_signature: vec![1; 64],
+                    block_hash: vec![2; 32],
+                    verification_type: 0,
+                    chain_id: 0,
+                    protocol: proto::Protocol::Solana as i32,
+                },
+            ),
+            None,
+            None,
+        );
+        test_helper::commit_message(engine1, &eth_verification).await;
+        test_helper::commit_message(engine2, &sol_verification).await;
+
+        let request = |address: &Vec<u8>, protocol: proto::Protocol| {
+            Request::new(proto::FidsByVerifiedAddressRequest {
+                address: address.clone(),
+                protocol: protocol as i32,
+            })
+        };
+
+        let response = service
+            .get_fids_by_verified_address(request(&eth_address, proto::Protocol::Ethereum))
+            .await
+            .unwrap();
+        assert_eq!(response.into_inner().fids, vec![SHARD1_FID]);
+
+        let response = service
+            .get_fids_by_verified_address(request(&sol_address, proto::Protocol::Solana))
+            .await
+            .unwrap();
+        assert_eq!(response.into_inner().fids, vec![SHARD2_FID]);
+
+        let status = service
+            .get_fids_by_verified_address(request(&sol_address, proto::Protocol::Ethereum))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let verification_remove = messages_factory::verifications::create_verification_remove(
+            SHARD1_FID,
+            eth_address.clone(),
+            Some(eth_verification.data.as_ref().unwrap().timestamp + 1),
+            None,
+        );
+        test_helper::commit_message(engine1, &verification_remove).await;
+        let response = service
+            .get_fids_by_verified_address(request(&eth_address, proto::Protocol::Ethereum))
+            .await
+            .unwrap();
+        assert!(response.into_inner().fids.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_profile() {
+        let (_, _, [mut engine1, mut engine2], service) = make_server(None).await;
+        let engine1 = &mut engine1;
+        let engine2 = &mut engine2;
+        let shard2_fid = SHARD2_FID + 2;
+        test_helper::register_user(
+            SHARD1_FID,
+            test_helper::default_signer(),
+            test_helper::default_custody_address(),
+            engine1,
+        )
+        .await;
+        for fid in [SHARD2_FID, shard2_fid] {
+            test_helper::register_user(
+                fid,
+                test_helper::default_signer(),
+                test_helper::default_custody_address(),
+                engine2,
+            )
+            .await;
+        }
+
+        let eth_address = hex::decode("91031dcfdea024b4d51e775486111d2b2a715871").unwrap();
+        let primary_address = alloy_primitives::Address::from_slice(&eth_address).to_checksum(None);
+        let verification = messages_factory::verifications::create_verification_add(
+            SHARD1_FID,
+            0,
+            eth_address,
+            vec![1; 65],
+            vec![2; 32],
+            None,
+            None,
+        );
+        test_helper::commit_message(engine1, &verification).await;
+        let timestamp = messages_factory::farcaster_time();
+        let display = messages_factory::user_data::create_user_data_add(
+            SHARD1_FID,
+            UserDataType::Display,
+            &"Alice".to_string(),
+            Some(timestamp),
+            None,
+        );
+        let primary = messages_factory::user_data::create_user_data_add(
+            SHARD1_FID,
+            UserDataType::UserDataPrimaryAddressEthereum,
+            &primary_address,
+            Some(timestamp + 1),
+            None,
+        );
+        test_helper::commit_message(engine1, &display).await;
+        test_helper::commit_message(engine1, &primary).await;
+
+        // Followers are on both shards
+        test_helper::commit_message(
+            engine1,
+            &messages_factory::links::create_link_add(SHARD1_FID, "follow", SHARD2_FID, None, None),
+        )
+        .await;
+        for fid in [SHARD2_FID, shard2_fid] {
+            test_helper::commit_message(
+                engine2,
+                &messages_factory::links::create_link_add(
+                    fid,
+                    "follow",
+                    SHARD1_FID,
+                    Some(timestamp),
+                    None,
+                ),
+            )
+            .await;
+        }
+        // Other link types are not counted
+        test_helper::commit_message(
+            engine2,
+            &messages_factory::links::create_link_add(SHARD2_FID, "mute", SHARD1_FID, None, None),
+        )
+        .await;
+
+        let get_profile = |fid| service.get_profile(Request::new(proto::ProfileRequest { fid }));
+        let profile = get_profile(SHARD1_FID).await.unwrap().into_inner();
+        assert_eq!(profile.fid, SHARD1_FID);
+        assert_eq!(profile.user_data, vec![display, primary]);
+        assert_eq!(profile.primary_address_ethereum, primary_address);
+        assert_eq!(profile.primary_address_solana, "");
+        assert_eq!(profile.verifications, vec![verification]);
+        let storage_limits = service
+            .get_current_storage_limits_by_fid(Request::new(FidRequest {
+                fid: SHARD1_FID,
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(profile.storage_limits, Some(storage_limits));
+        assert_eq!(profile.follower_count, 2);
+        assert_eq!(profile.following_count, 1);
+
+        // Removed links are no longer counted
+        test_helper::commit_message(
+            engine2,
+            &messages_factory::links::create_link_remove(
+                shard2_fid,
+                "follow",
+                SHARD1_FID,
+                Some(timestamp + 1),
+                None,
+            ),
+        )
+        .await;
+        let profile = get_profile(SHARD1_FID).await.unwrap().into_inner();
+        assert_eq!(profile.follower_count, 1);
+
+        let profile = get_profile(SHARD2_FID).await.unwrap().into_inner();
+        assert_eq!(profile.follower_count, 1);
+        assert_eq!(profile.following_count, 1);
+        assert!(profile.user_data.is_empty());
+    }
+
     #[tokio::test]
     async fn test_cast_apis() {
         let (_, _, [mut engine1, mut engine2], service) = make_server(None).await;
@@ -1138,10 +1424,33 @@ mod tests {
                 page_size: Some(1),
                 page_token: None,
                 reverse: None,
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+        // The page size is for the merged page, not per shard
+        assert_eq!(message_hashes(&response), vec![reply_1.hash.clone()]);
+
+        let page_token = response.get_ref().next_page_token.clone();
+        let response = service
+            .get_casts_by_parent(Request::new(proto::CastsByParentRequest {
+                parent: Some(proto::casts_by_parent_request::Parent::ParentCastId(
+                    proto::CastId {
+                        fid: SHARD1_FID,
+                        hash: original_cast.hash.clone(),
+                    },
+                )),
+                page_size: Some(2),
+                page_token: page_token,
+                reverse: None,
+                ..Default::default()
             }))
             .await
             .unwrap();
-        test_helper::assert_contains_all_messages(&response, &[&reply_1, &reply_3_another_shard]);
+        assert_eq!(
+            message_hashes(&response),
+            vec![reply_2.hash.clone(), reply_3_another_shard.hash.clone()]
+        );
 
         let page_token = response.get_ref().next_page_token.clone();
         let response = service
@@ -1155,10 +1464,15 @@ mod tests {
                 page_size: Some(2),
                 page_token: page_token,
                 reverse: None,
+                ..Default::default()
             }))
             .await
             .unwrap();
-        test_helper::assert_contains_all_messages(&response, &[&reply_2, &reply_4_another_shard]);
+        assert_eq!(
+            message_hashes(&response),
+            vec![reply_4_another_shard.hash.clone()]
+        );
+        assert_eq!(response.get_ref().next_page_token, None);
 
         // Test reverse pagination
         let response = service
@@ -1172,6 +1486,7 @@ mod tests {
                 page_size: Some(1),
                 page_token: None,
                 reverse: Some(true),
+                ..Default::default()
             }))
             .await
             .unwrap();
@@ -1188,10 +1503,393 @@ mod tests {
                 page_size: Some(2),
                 page_token: page_token.clone(),
                 reverse: Some(true),
+                ..Default::default()
             }))
             .await
             .unwrap();
-        test_helper::assert_contains_all_messages(&response, &[&reply_1, &reply_3_another_shard]);
+        assert_eq!(
+            message_hashes(&response),
+            vec![reply_3_another_shard.hash.clone(), reply_2.hash.clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_cast_stats() {
+        let (_, _, [mut engine1, mut engine2], service) = make_server(None).await;
+        let engine1 = &mut engine1;
+        let engine2 = &mut engine2;
+        test_helper::register_user(
+            SHARD1_FID,
+            test_helper::default_signer(),
+            test_helper::default_custody_address(),
+            engine1,
+        )
+        .await;
+        test_helper::register_user(
+            SHARD2_FID,
+            test_helper::default_signer(),
+            test_helper::default_custody_address(),
+            engine2,
+        )
+        .await;
+
+        let cast = messages_factory::casts::create_cast_add(SHARD1_FID, "test", None, None);
+        let timestamp = cast.data.as_ref().unwrap().timestamp;
+        let cast_id = proto::CastId {
+            fid: SHARD1_FID,
+            hash: cast.hash.clone(),
+        };
+        test_helper::commit_message(engine1, &cast).await;
+
+        let reaction = |fid, message_type, reaction_type, timestamp| {
+            messages_factory::reactions::create_cast_reaction(
+                fid,
+                message_type,
+                reaction_type,
+                cast_id.clone(),
+                Some(timestamp),
+                None,
+            )
+        };
+        // Reactions and replies from both shards
+        test_helper::commit_message(
+            engine1,
+            &reaction(
+                SHARD1_FID,
+                proto::MessageType::ReactionAdd,
+                proto::ReactionType::Like,
+                timestamp,
+            ),
+        )
+        .await;
+        for reaction_type in [proto::ReactionType::Like, proto::ReactionType::Recast] {
+            test_helper::commit_message(
+                engine2,
+                &reaction(
+                    SHARD2_FID,
+                    proto::MessageType::ReactionAdd,
+                    reaction_type,
+                    timestamp,
+                ),
+            )
+            .await;
+        }
+        let reply_1 = messages_factory::casts::create_cast_with_parent(
+            SHARD1_FID,
+            "reply 1",
+            SHARD1_FID,
+            &cast.hash,
+            Some(timestamp + 1),
+            None,
+        );
+        let reply_2 = messages_factory::casts::create_cast_with_parent(
+            SHARD2_FID,
+            "reply 2",
+            SHARD1_FID,
+            &cast.hash,
+            Some(timestamp + 1),
+            None,
+        );
+        test_helper::commit_message(engine1, &reply_1).await;
+        test_helper::commit_message(engine2, &reply_2).await;
+
+        let other_cast_id = proto::CastId {
+            fid: SHARD2_FID,
+            hash: vec![1; 20],
+        };
+        let get_stats = || {
+            service.get_cast_stats(Request::new(proto::CastStatsRequest {
+                cast_ids: vec![cast_id.clone(), other_cast_id.clone()],
+            }))
+        };
+        let stats = get_stats().await.unwrap().into_inner().stats;
+        assert_eq!(
+            stats,
+            vec![
+                proto::CastStats {
+                    cast_id: Some(cast_id.clone()),
+                    likes: 2,
+                    recasts: 1,
+                    replies: 2,
+                },
+                proto::CastStats {
+                    cast_id: Some(other_cast_id.clone()),
+                    ..Default::default()
+                },
+            ]
+        );
+
+        // Removes are no longer counted
+        test_helper::commit_message(
+            engine2,
+            &reaction(
+                SHARD2_FID,
+                proto::MessageType::ReactionRemove,
+                proto::ReactionType::Like,
+                timestamp + 2,
+            ),
+        )
+        .await;
+        test_helper::commit_message(
+            engine2,
+            &messages_factory::casts::create_cast_remove(
+                SHARD2_FID,
+                &reply_2.hash,
+                Some(timestamp + 2),
+                None,
+            ),
+        )
+        .await;
+        let stats = get_stats().await.unwrap().into_inner().stats;
+        assert_eq!(stats[0].likes, 1);
+        assert_eq!(stats[0].recasts, 1);
+        assert_eq!(stats[0].replies, 1);
+
+        let too_many = service
+            .get_cast_stats(Request::new(proto::CastStatsRequest {
+                cast_ids: vec![cast_id.clone(); 1001],
+            }))
+            .await;
+        assert_eq!(too_many.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    fn message_hashes(response: &tonic::Response<proto::MessagesResponse>) -> Vec<Vec<u8>> {
+        response
+            .get_ref()
+            .messages
+            .iter()
+            .map(|message| message.hash.clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_get_casts_by_parent_url_in_time_range() {
+        let (_, _, [mut engine1, mut engine2], service) = make_server(None).await;
+        let engine1 = &mut engine1;
+        let engine2 = &mut engine2;
+        test_helper::register_user(
+            SHARD1_FID,
+            test_helper::default_signer(),
+            test_helper::default_custody_address(),
+            engine1,
+        )
+        .await;
+        test_helper::register_user(
+            SHARD2_FID,
+            test_helper::default_signer(),
+            test_helper::default_custody_address(),
+            engine2,
+        )
+        .await;
+
+        let url = "chain://eip155:1/erc721:0x39d89b649ffa044383333d297e325d42d31329b2";
+        let ts = messages_factory::farcaster_time();
+        let cast1 = messages_factory::casts::create_cast_with_parent_url(
+            SHARD1_FID,
+            "cast 1",
+            url,
+            Some(ts),
+            None,
+        );
+        let cast2 = messages_factory::casts::create_cast_with_parent_url(
+            SHARD2_FID,
+            "cast 2",
+            url,
+            Some(ts + 5),
+            None,
+        );
+        let cast3 = messages_factory::casts::create_cast_with_parent_url(
+            SHARD1_FID,
+            "cast 3",
+            url,
+            Some(ts + 10),
+            None,
+        );
+        let cast4 = messages_factory::casts::create_cast_with_parent_url(
+            SHARD2_FID,
+            "cast 4",
+            url,
+            Some(ts + 20),
+            None,
+        );
+        test_helper::commit_message(engine1, &cast1).await;
+        test_helper::commit_message(engine2, &cast2).await;
+        test_helper::commit_message(engine1, &cast3).await;
+        test_helper::commit_message(engine2, &cast4).await;
+
+        let request = |start_timestamp: Option<u32>,
+                       stop_timestamp: Option<u32>,
+                       page_token: Option<Vec<u8>>| {
+            Request::new(proto::CastsByParentRequest {
+                parent: Some(proto::casts_by_parent_request::Parent::ParentUrl(
+                    url.to_string(),
+                )),
+                page_size: Some(1),
+                page_token,
+                reverse: Some(true),
+                start_timestamp: start_timestamp.map(|ts| ts as u64),
+                stop_timestamp: stop_timestamp.map(|ts| ts as u64),
+            })
+        };
+
+        // Newest first across shards, one page at a time
+        let mut hashes = vec![];
+        let mut page_token = None;
+        loop {
+            let response = service
+                .get_casts_by_parent(request(None, None, page_token))
+                .await
+                .unwrap();
+            hashes.extend(message_hashes(&response));
+            page_token = response.get_ref().next_page_token.clone();
+            if page_token.is_none() {
+                break;
+            }
+        }
+        assert_eq!(
+            hashes,
+            vec![
+                cast4.hash.clone(),
+                cast3.hash.clone(),
+                cast2.hash.clone(),
+                cast1.hash.clone()
+            ]
+        );
+
+        // Both bounds are inclusive
+        let response = service
+            .get_casts_by_parent(request(Some(ts), Some(ts + 10), None))
+            .await
+            .unwrap();
+        assert_eq!(message_hashes(&response), vec![cast3.hash.clone()]);
+
+        let page_token = response.get_ref().next_page_token.clone();
+        let response = service
+            .get_casts_by_parent(request(Some(ts), Some(ts + 10), page_token))
+            .await
+            .unwrap();
+        assert_eq!(message_hashes(&response), vec![cast2.hash.clone()]);
+
+        let page_token = response.get_ref().next_page_token.clone();
+        let response = service
+            .get_casts_by_parent(request(Some(ts), Some(ts + 10), page_token))
+            .await
+            .unwrap();
+        assert_eq!(message_hashes(&response), vec![cast1.hash.clone()]);
+
+        let page_token = response.get_ref().next_page_token.clone();
+        let response = service
+            .get_casts_by_parent(request(Some(ts), Some(ts + 10), page_token))
+            .await
+            .unwrap();
+        test_helper::assert_messages_empty(&response);
+        assert_eq!(response.get_ref().next_page_token, None);
+
+        let response = service
+            .get_casts_by_parent(request(Some(ts + 11), None, None))
+            .await
+            .unwrap();
+        assert_eq!(message_hashes(&response), vec![cast4.hash.clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_get_casts_by_embed() {
+        let (_, _, [mut engine1, mut engine2], service) = make_server(None).await;
+        let engine1 = &mut engine1;
+        let engine2 = &mut engine2;
+        test_helper::register_user(
+            SHARD1_FID,
+            test_helper::default_signer(),
+            test_helper::default_custody_address(),
+            engine1,
+        )
+        .await;
+        test_helper::register_user(
+            SHARD2_FID,
+            test_helper::default_signer(),
+            test_helper::default_custody_address(),
+            engine2,
+        )
+        .await;
+
+        let url_embed = |url: &str| proto::Embed {
+            embed: Some(proto::embed::Embed::Url(url.to_string())),
+        };
+        let ts = messages_factory::farcaster_time();
+        let cast1 = messages_factory::casts::create_cast_add_rich(
+            SHARD1_FID,
+            "cast 1",
+            None,
+            vec![url_embed("https://a.xyz"), url_embed("https://b.xyz")],
+            None,
+            Some(ts),
+            None,
+        );
+        let cast2 = messages_factory::casts::create_cast_add_rich(
+            SHARD2_FID,
+            "cast 2",
+            None,
+            vec![url_embed("https://a.xyz")],
+            None,
+            Some(ts + 1),
+            None,
+        );
+        let cast3 = messages_factory::casts::create_cast_add_rich(
+            SHARD1_FID,
+            "cast 3",
+            None,
+            vec![url_embed("https://a.xyz")],
+            None,
+            Some(ts + 2),
+            None,
+        );
+        let cast3_remove = messages_factory::casts::create_cast_remove(
+            SHARD1_FID,
+            &cast3.hash,
+            Some(ts + 3),
+            None,
+        );
+        test_helper::commit_message(engine1, &cast1).await;
+        test_helper::commit_message(engine2, &cast2).await;
+        test_helper::commit_message(engine1, &cast3).await;
+        test_helper::commit_message(engine1, &cast3_remove).await;
+
+        let request = |url: &str, start_timestamp: Option<u32>| {
+            Request::new(proto::CastsByEmbedRequest {
+                url: url.to_string(),
+                start_timestamp: start_timestamp.map(|ts| ts as u64),
+                ..Default::default()
+            })
+        };
+
+        // Removed casts are dropped from the index
+        let response = service
+            .get_casts_by_embed(request("https://a.xyz", None))
+            .await
+            .unwrap();
+        assert_eq!(
+            message_hashes(&response),
+            vec![cast1.hash.clone(), cast2.hash.clone()]
+        );
+
+        let response = service
+            .get_casts_by_embed(request("https://a.xyz", Some(ts + 1)))
+            .await
+            .unwrap();
+        assert_eq!(message_hashes(&response), vec![cast2.hash.clone()]);
+
+        let response = service
+            .get_casts_by_embed(request("https://b.xyz", None))
+            .await
+            .unwrap();
+        assert_eq!(message_hashes(&response), vec![cast1.hash.clone()]);
+
+        // Urls must match exactly
+        let response = service
+            .get_casts_by_embed(request("https://a.xy", None))
+            .await
+            .unwrap();
+        test_helper::assert_messages_empty(&response);
     }
 
     #[tokio::test]
@@ -1400,6 +2098,107 @@ mod tests {
         assert_eq!(proof.r#type, UserNameType::UsernameTypeEnsL1 as i32);
     }
 
+    fn merge_fname(stores: &Stores, name: &str, fid: u64, timestamp: u64) {
+        let proof = UserNameProof {
+            timestamp,
+            name: name.as_bytes().to_vec(),
+            owner: test_helper::default_custody_address(),
+            signature: vec![],
+            fid,
+            r#type: UserNameType::UsernameTypeFname as i32,
+        };
+        let mut txn = RocksDbTransactionBatch::new();
+        UserDataStore::merge_username_proof(&stores.user_data_store, &proof, &mut txn).unwrap();
+        stores.db.commit(txn).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_usernames_by_prefix() {
+        let (stores, _, [mut engine1, mut engine2], service) = make_server(None).await;
+        let signer = test_helper::default_signer();
+        let owner = test_helper::default_custody_address();
+        test_helper::register_user(SHARD1_FID, signer.clone(), owner.clone(), &mut engine1).await;
+        test_helper::register_user(SHARD2_FID, signer.clone(), owner.clone(), &mut engine2).await;
+
+        let timestamp = messages_factory::farcaster_time() as u64;
+        for (fid, name, engine) in [
+            (SHARD1_FID, "alice.eth", &mut engine1),
+            (SHARD2_FID, "alicia.eth", &mut engine2),
+        ] {
+            let proof_message = messages_factory::username_proof::create_username_proof(
+                fid,
+                UserNameType::UsernameTypeEnsL1,
+                name.to_string(),
+                owner.clone(),
+                "signature".to_string(),
+                timestamp,
+                None,
+            );
+            test_helper::commit_message(engine, &proof_message).await;
+        }
+
+        let shard1 = stores.get(&1).unwrap();
+        let shard2 = stores.get(&2).unwrap();
+        merge_fname(shard1, "alice", SHARD1_FID, timestamp);
+        merge_fname(shard1, "bob", SHARD1_FID, timestamp);
+        // Transferred to the fid on shard 2, shard 1 still has the old proof
+        merge_fname(shard1, "alex", SHARD1_FID, timestamp);
+        merge_fname(shard2, "alex", SHARD2_FID, timestamp + 10);
+        // Deleted names are skipped
+        merge_fname(shard1, "albert", SHARD1_FID, timestamp);
+        merge_fname(shard1, "albert", 0, timestamp + 5);
+
+        let get_page = |page_size: u32, page_token: Option<Vec<u8>>| {
+            service.get_usernames_by_prefix(Request::new(proto::UsernamesByPrefixRequest {
+                prefix: "AL".to_string(),
+                page_size: Some(page_size),
+                page_token,
+            }))
+        };
+        let names = |response: &proto::UsernamesByPrefixResponse| {
+            response
+                .proofs
+                .iter()
+                .map(|proof| (String::from_utf8(proof.name.clone()).unwrap(), proof.fid))
+                .collect::<Vec<_>>()
+        };
+
+        let response = get_page(10, None).await.unwrap().into_inner();
+        assert_eq!(
+            names(&response),
+            vec![
+                ("alex".to_string(), SHARD2_FID),
+                ("alice".to_string(), SHARD1_FID),
+                ("alice.eth".to_string(), SHARD1_FID),
+                ("alicia.eth".to_string(), SHARD2_FID),
+            ]
+        );
+        assert_eq!(response.next_page_token, None);
+
+        let first_page = get_page(2, None).await.unwrap().into_inner();
+        assert_eq!(names(&first_page), vec![("alex".to_string(), SHARD2_FID)]);
+        let second_page = get_page(2, first_page.next_page_token)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            names(&second_page),
+            vec![
+                ("alice".to_string(), SHARD1_FID),
+                ("alice.eth".to_string(), SHARD1_FID)
+            ]
+        );
+        let third_page = get_page(2, second_page.next_page_token)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            names(&third_page),
+            vec![("alicia.eth".to_string(), SHARD2_FID)]
+        );
+        assert_eq!(third_page.next_page_token, None);
+    }
+
     #[tokio::test]
     async fn test_get_fids() {
         let (_, _, [mut engine1, mut engine2], service) = make_server(None).await;
@@ -1610,4 +2409,161 @@ mod tests {
             .iter()
             .all(|event| event.r#type() == OnChainEventType::EventTypeSigner));
     }
+
+    #[tokio::test]
+    async fn test_request_on_chain_event_repair() {
+        let (onchain_events_request_tx, mut onchain_events_request_rx) = broadcast::channel(10);
+        let (_, _, [mut engine1, _], service) =
+            make_server_with_onchain_events(None, onchain_events_request_tx).await;
+
+        let stored_event = events_factory::create_rent_event(SHARD1_FID, None, Some(1), false);
+        let missing_event = events_factory::create_rent_event(SHARD1_FID, None, Some(2), false);
+        commit_event(&mut engine1, &stored_event).await;
+
+        // Stands in for the onchain events subscriber
+        let chain_events = vec![stored_event.clone(), missing_event.clone()];
+        tokio::spawn(async move {
+            while let Ok(request) = onchain_events_request_rx.recv().await {
+                if let OnchainEventsRequest::FetchFidEvents { response_tx, .. } = request {
+                    response_tx.send(chain_events.clone()).await.unwrap();
+                }
+            }
+        });
+
+        let repair = |fid| {
+            service.request_on_chain_event_repair(Request::new(proto::OnChainEventRepairRequest {
+                fid,
+            }))
+        };
+
+        let response = repair(SHARD1_FID).await.unwrap().into_inner();
+        assert_eq!(response.missing_events, vec![missing_event.clone()]);
+        assert!(response.resubmitted);
+
+        // Repeat requests are reported but not resubmitted
+        let response = repair(SHARD1_FID).await.unwrap().into_inner();
+        assert_eq!(response.missing_events, vec![missing_event]);
+        assert!(!response.resubmitted);
+
+        assert!(repair(0).await.is_err());
+
+        // Fetches from chain count against the caller's repair quota
+        assert!(repair(SHARD2_FID).await.is_ok());
+        assert_eq!(
+            repair(SHARD1_FID + 2).await.unwrap_err().code(),
+            tonic::Code::ResourceExhausted
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_on_chain_events_is_paged() {
+        let (_, _, [mut engine1, _], service) = make_server(None).await;
+        for units in 1..=3 {
+            let event = events_factory::create_rent_event(SHARD1_FID, None, Some(units), false);
+            commit_event(&mut engine1, &event).await;
+        }
+
+        let get_page = |page_token| {
+            service.get_on_chain_events(Request::new(proto::OnChainEventRequest {
+                fid: SHARD1_FID,
+                event_type: OnChainEventType::EventTypeStorageRent as i32,
+                page_size: Some(2),
+                page_token,
+                reverse: None,
+            }))
+        };
+
+        let page = get_page(None).await.unwrap().into_inner();
+        assert_eq!(page.events.len(), 2);
+        assert!(page.next_page_token.is_some());
+        let page = get_page(page.next_page_token).await.unwrap().into_inner();
+        assert_eq!(page.events.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_shard_chunks_is_capped() {
+        let (stores, _, _, service) = make_server(None).await;
+        for height in 0..150 {
+            let shard_chunk = shard_chunk_factory::create_shard_chunk(1, Some(height), None);
+            stores[&1]
+                .shard_store
+                .put_shard_chunk(&shard_chunk)
+                .unwrap();
+        }
+
+        let get_shard_chunks = |start_block_number, stop_block_number| {
+            service.get_shard_chunks(Request::new(proto::ShardChunksRequest {
+                shard_id: 1,
+                start_block_number,
+                stop_block_number,
+            }))
+        };
+
+        let response = get_shard_chunks(0, None).await.unwrap().into_inner();
+        assert_eq!(
+            response.shard_chunks.len(),
+            MAX_SHARD_CHUNKS_PER_REQUEST as usize
+        );
+        let response = get_shard_chunks(0, Some(u64::MAX))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            response.shard_chunks.len(),
+            MAX_SHARD_CHUNKS_PER_REQUEST as usize
+        );
+        let response = get_shard_chunks(120, None).await.unwrap().into_inner();
+        assert_eq!(response.shard_chunks.len(), 30);
+        let response = get_shard_chunks(10, Some(20)).await.unwrap().into_inner();
+        assert_eq!(response.shard_chunks.len(), 10);
+        let response = get_shard_chunks(u64::MAX, None).await.unwrap().into_inner();
+        assert!(response.shard_chunks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_request_on_chain_event_repair_without_subscribers() {
+        let (_, _, _, service) = make_server(None).await;
+
+        let result = service
+            .request_on_chain_event_repair(Request::new(proto::OnChainEventRepairRequest {
+                fid: SHARD1_FID,
+            }))
+            .await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Unavailable);
+    }
+
+    #[tokio::test]
+    async fn test_sol_account_signature() {
+        let (_, _, _, service) = make_server(None).await;
+
+        let body = |authority: [u8; 32], signers: &[[u8; 32]]| {
+            let mut claim_signature = authority.to_vec();
+            for signer in signers {
+                claim_signature.extend(signer);
+                claim_signature.extend([0; 64]);
+            }
+            VerificationAddAddressBody {
+                address: authority.to_vec(),
+                claim_signature,
+                block_hash: vec![2; 32],
+                verification_type: 1,
+                chain_id: 0,
+                protocol: proto::Protocol::Solana as i32,
+            }
+        };
+
+        assert!(service
+            .validate_sol_account_signature(&body(SOL_MULTISIG, &[[4; 32], [6; 32]]))
+            .await
+            .is_ok());
+        // Signers that aren't members don't count towards the threshold
+        assert!(service
+            .validate_sol_account_signature(&body(SOL_MULTISIG, &[[4; 32], [7; 32]]))
+            .await
+            .is_err());
+        assert!(service
+            .validate_sol_account_signature(&body([8; 32], &[[4; 32], [5; 32]]))
+            .await
+            .is_err());
+    }
 }
@@ -0,0 +1,311 @@
+use axum::extract::ConnectInfo;
+use axum::Extension;
+use futures::StreamExt;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use rustls::crypto::{ring, CryptoProvider};
+use rustls::pki_types::CertificateDer;
+use rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use rustls::sign::CertifiedKey;
+use rustls::{RootCertStore, ServerConfig};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinSet;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error, info};
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Config {
+    /// PEM encoded certificate chain. TLS is disabled when empty
+    pub cert_path: String,
+    /// PEM encoded private key for the certificate
+    pub key_path: String,
+    /// How often to check the certificate and key for changes
+    #[serde(with = "humantime_serde")]
+    pub reload_interval: Duration,
+    /// PEM encoded CA certificates. When set, admin clients must present a certificate signed by
+    /// one of them. Requires a separate admin_rpc_address
+    pub admin_client_ca_path: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            cert_path: "".to_string(),
+            key_path: "".to_string(),
+            reload_interval: Duration::from_secs(60),
+            admin_client_ca_path: "".to_string(),
+        }
+    }
+}
+
+impl Config {
+    pub fn enabled(&self) -> bool {
+        !self.cert_path.is_empty()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("no certificates found in {0}")]
+    NoCertificates(String),
+
+    #[error("no private key found in {0}")]
+    NoPrivateKey(String),
+
+    #[error(transparent)]
+    Rustls(#[from] rustls::Error),
+
+    #[error(transparent)]
+    ClientVerifier(#[from] rustls::server::VerifierBuilderError),
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, TlsError> {
+    fs::read(path).map_err(|source| TlsError::Io {
+        path: path.to_string(),
+        source,
+    })
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let pem = read_file(path)?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|source| TlsError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificates(path.to_string()));
+    }
+    Ok(certs)
+}
+
+fn load_certified_key(
+    provider: &CryptoProvider,
+    cert_path: &str,
+    key_path: &str,
+) -> Result<CertifiedKey, TlsError> {
+    let certs = load_certs(cert_path)?;
+    let pem = read_file(key_path)?;
+    let key = rustls_pemfile::private_key(&mut pem.as_slice())
+        .map_err(|source| TlsError::Io {
+            path: key_path.to_string(),
+            source,
+        })?
+        .ok_or_else(|| TlsError::NoPrivateKey(key_path.to_string()))?;
+    let key = provider.key_provider.load_private_key(key)?;
+    Ok(CertifiedKey::new(certs, key))
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/** Serves the certificate and key from disk, and swaps them when the files change. */
+#[derive(Debug)]
+pub struct ReloadingCertResolver {
+    provider: Arc<CryptoProvider>,
+    cert_path: String,
+    key_path: String,
+    key: RwLock<Arc<CertifiedKey>>,
+    modified: Mutex<(Option<SystemTime>, Option<SystemTime>)>,
+}
+
+impl ReloadingCertResolver {
+    pub fn new(
+        provider: Arc<CryptoProvider>,
+        cert_path: &str,
+        key_path: &str,
+    ) -> Result<Self, TlsError> {
+        let modified = (modified(cert_path), modified(key_path));
+        let key = load_certified_key(&provider, cert_path, key_path)?;
+        Ok(Self {
+            provider,
+            cert_path: cert_path.to_string(),
+            key_path: key_path.to_string(),
+            key: RwLock::new(Arc::new(key)),
+            modified: Mutex::new(modified),
+        })
+    }
+
+    /**
+     * Loads the certificate and key again if either file changed. On errors the current key is
+     * kept, and the reload is retried on the next call.
+     */
+    pub fn reload_if_changed(&self) -> Result<bool, TlsError> {
+        let modified = (modified(&self.cert_path), modified(&self.key_path));
+        let mut last_modified = self.modified.lock().unwrap();
+        if *last_modified == modified {
+            return Ok(false);
+        }
+        let key = load_certified_key(&self.provider, &self.cert_path, &self.key_path)?;
+        *self.key.write().unwrap() = Arc::new(key);
+        *last_modified = modified;
+        Ok(true)
+    }
+
+    pub fn certified_key(&self) -> Arc<CertifiedKey> {
+        self.key.read().unwrap().clone()
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.certified_key())
+    }
+}
+
+/**
+ * Builds the rustls config for a server. When [client_ca_path] is set, clients must present a
+ * certificate signed by one of the CAs in it.
+ */
+pub fn server_config(
+    config: &Config,
+    client_ca_path: Option<&str>,
+) -> Result<(Arc<ServerConfig>, Arc<ReloadingCertResolver>), TlsError> {
+    let provider = Arc::new(ring::default_provider());
+    let resolver = Arc::new(ReloadingCertResolver::new(
+        provider.clone(),
+        &config.cert_path,
+        &config.key_path,
+    )?);
+
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+    let builder = match client_ca_path {
+        Some(client_ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(client_ca_path)? {
+                roots.add(cert)?;
+            }
+            let verifier =
+                WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider).build()?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut server_config = builder.with_cert_resolver(resolver.clone());
+    // gRPC requires http2, the http api serves both
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok((Arc::new(server_config), resolver))
+}
+
+/** Builds a TLS acceptor, and keeps reloading the certificate from disk in the background. */
+pub fn acceptor(config: &Config, client_ca_path: Option<&str>) -> Result<TlsAcceptor, TlsError> {
+    let (server_config, resolver) = server_config(config, client_ca_path)?;
+    let reload_interval = config.reload_interval;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(reload_interval);
+        loop {
+            interval.tick().await;
+            match resolver.reload_if_changed() {
+                Ok(true) => info!(cert_path = resolver.cert_path, "Reloaded TLS certificate"),
+                Ok(false) => {}
+                Err(err) => error!("Failed to reload TLS certificate: {}", err),
+            }
+        }
+    });
+    Ok(TlsAcceptor::from(server_config))
+}
+
+/**
+ * Accepts connections and completes TLS handshakes in the background. Clients that fail the
+ * handshake, for example without a valid client certificate, are dropped.
+ */
+pub fn incoming(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+) -> ReceiverStream<Result<TlsStream<TcpStream>, io::Error>> {
+    let (tx, rx) = mpsc::channel(128);
+    tokio::spawn(async move {
+        while !tx.is_closed() {
+            let (stream, remote_addr) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(err) => {
+                    _ = tx.send(Err(err)).await;
+                    break;
+                }
+            };
+            let acceptor = acceptor.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        _ = tx.send(Ok(stream)).await;
+                    }
+                    Ok(Err(err)) => debug!(%remote_addr, "TLS handshake failed: {}", err),
+                    Err(_) => debug!(%remote_addr, "TLS handshake timed out"),
+                }
+            });
+        }
+    });
+    ReceiverStream::new(rx)
+}
+
+/**
+ * Serves the HTTP API over TLS connections from [incoming]. Once shutdown is signalled, stops
+ * accepting connections and waits for in-flight requests.
+ */
+pub async fn serve_http(
+    mut incoming: ReceiverStream<Result<TlsStream<TcpStream>, io::Error>>,
+    router: axum::Router,
+    mut shutdown_rx: watch::Receiver<bool>,
+) -> Result<(), io::Error> {
+    let mut connections = JoinSet::new();
+    loop {
+        let stream = tokio::select! {
+            stream = incoming.next() => stream,
+            Some(_) = connections.join_next() => continue,
+            _ = shutdown_rx.wait_for(|shutdown| *shutdown) => break,
+        };
+        let stream = match stream {
+            Some(stream) => stream?,
+            None => break,
+        };
+
+        let mut router = router.clone();
+        if let Ok(remote_addr) = stream.get_ref().0.peer_addr() {
+            router = router.layer(Extension(ConnectInfo(remote_addr)));
+        }
+        let service = TowerToHyperService::new(router);
+        let mut shutdown_rx = shutdown_rx.clone();
+        connections.spawn(async move {
+            let builder = auto::Builder::new(TokioExecutor::new());
+            let connection = builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+            tokio::pin!(connection);
+            tokio::select! {
+                result = connection.as_mut() => {
+                    if let Err(err) = result {
+                        debug!("Error serving connection: {}", err);
+                    }
+                }
+                _ = shutdown_rx.wait_for(|shutdown| *shutdown) => {
+                    connection.as_mut().graceful_shutdown();
+                    _ = connection.await;
+                }
+            }
+        });
+    }
+
+    while connections.join_next().await.is_some() {}
+    Ok(())
+}
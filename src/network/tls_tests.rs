@@ -0,0 +1,180 @@
+#[cfg(test)]
+mod tests {
+    use crate::network::tls::{self, Config, ReloadingCertResolver};
+    use futures::StreamExt;
+    use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+    use rustls::crypto::ring;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+    use rustls::{ClientConfig, RootCertStore};
+    use std::fs::{self, File};
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_rustls::TlsConnector;
+
+    struct TestCert {
+        cert_pem: String,
+        cert_der: CertificateDer<'static>,
+        key: KeyPair,
+    }
+
+    fn self_signed(name: &str) -> TestCert {
+        let key = KeyPair::generate().unwrap();
+        let cert = CertificateParams::new(vec![name.to_string()])
+            .unwrap()
+            .self_signed(&key)
+            .unwrap();
+        TestCert {
+            cert_pem: cert.pem(),
+            cert_der: cert.der().clone(),
+            key,
+        }
+    }
+
+    /** Writes the file and moves its modified time forward, so reloads see the change. */
+    fn write(path: &str, contents: &str, age: u64) {
+        fs::write(path, contents).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(1000 - age);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    fn config(dir: &TempDir, cert: &TestCert) -> Config {
+        let cert_path = dir.path().join("cert.pem").to_str().unwrap().to_string();
+        let key_path = dir.path().join("key.pem").to_str().unwrap().to_string();
+        write(&cert_path, &cert.cert_pem, 0);
+        write(&key_path, &cert.key.serialize_pem(), 0);
+        Config {
+            cert_path,
+            key_path,
+            ..Config::default()
+        }
+    }
+
+    fn resolver(config: &Config) -> ReloadingCertResolver {
+        ReloadingCertResolver::new(
+            Arc::new(ring::default_provider()),
+            &config.cert_path,
+            &config.key_path,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_reload_if_changed() {
+        let dir = TempDir::new().unwrap();
+        let first = self_signed("first");
+        let config = config(&dir, &first);
+        let resolver = resolver(&config);
+        assert_eq!(resolver.certified_key().cert[0], first.cert_der);
+        assert!(!resolver.reload_if_changed().unwrap());
+
+        let second = self_signed("second");
+        write(&config.cert_path, &second.cert_pem, 1);
+        write(&config.key_path, &second.key.serialize_pem(), 1);
+        assert!(resolver.reload_if_changed().unwrap());
+        assert_eq!(resolver.certified_key().cert[0], second.cert_der);
+        assert!(!resolver.reload_if_changed().unwrap());
+    }
+
+    #[test]
+    fn test_failed_reload_keeps_current_certificate() {
+        let dir = TempDir::new().unwrap();
+        let first = self_signed("first");
+        let config = config(&dir, &first);
+        let resolver = resolver(&config);
+
+        write(&config.key_path, "not a key", 1);
+        assert!(resolver.reload_if_changed().is_err());
+        assert_eq!(resolver.certified_key().cert[0], first.cert_der);
+
+        // Retried until the files are valid again
+        write(&config.key_path, &first.key.serialize_pem(), 2);
+        assert!(resolver.reload_if_changed().unwrap());
+    }
+
+    async fn connect(
+        addr: std::net::SocketAddr,
+        server: &TestCert,
+        client: Option<&TestCert>,
+    ) -> std::io::Result<()> {
+        let mut roots = RootCertStore::empty();
+        roots.add(server.cert_der.clone()).unwrap();
+        let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+        let client_config = match client {
+            Some(client) => builder
+                .with_client_auth_cert(
+                    vec![client.cert_der.clone()],
+                    PrivateKeyDer::try_from(client.key.serialize_der()).unwrap(),
+                )
+                .unwrap(),
+            None => builder.with_no_client_auth(),
+        };
+
+        let stream = TcpStream::connect(addr).await?;
+        let mut stream = TlsConnector::from(Arc::new(client_config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await?;
+        stream.write_all(b"ping").await?;
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_certificate_verification() {
+        let dir = TempDir::new().unwrap();
+        let server = self_signed("localhost");
+        let config = config(&dir, &server);
+
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(vec![]).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let ca_path = dir.path().join("ca.pem").to_str().unwrap().to_string();
+        write(&ca_path, &ca.pem(), 0);
+
+        let client_key = KeyPair::generate().unwrap();
+        let client_cert = CertificateParams::new(vec!["client".to_string()])
+            .unwrap()
+            .signed_by(&client_key, &ca, &ca_key)
+            .unwrap();
+        let client = TestCert {
+            cert_pem: client_cert.pem(),
+            cert_der: client_cert.der().clone(),
+            key: client_key,
+        };
+
+        let acceptor = tls::acceptor(&config, Some(&ca_path)).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut incoming = tls::incoming(listener, acceptor);
+
+        // Clients without a certificate, or with one the CA didn't sign, fail the handshake
+        assert!(connect(addr, &server, None).await.is_err());
+        assert!(connect(addr, &server, Some(&self_signed("client")))
+            .await
+            .is_err());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), incoming.next())
+                .await
+                .is_err()
+        );
+
+        let client = tokio::spawn(async move { connect(addr, &server, Some(&client)).await });
+        let mut stream = incoming.next().await.unwrap().unwrap();
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        stream.write_all(b"pong").await.unwrap();
+        client.await.unwrap().unwrap();
+    }
+}
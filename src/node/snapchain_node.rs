@@ -7,11 +7,12 @@ use crate::core::types::{Address, ShardId, SnapchainShard, SnapchainValidatorCon
 use crate::mempool::mempool::MempoolMessagesRequest;
 use crate::network::gossip::GossipEvent;
 use crate::proto::{Block, FarcasterNetwork, ShardChunk};
-use crate::storage::db::RocksDB;
+use crate::storage::db::{self, RocksDB};
 use crate::storage::store::engine::{BlockEngine, Senders, ShardEngine};
+use crate::storage::store::migrations::{self, DbKind, MigrationError};
 use crate::storage::store::node_local_state::LocalStateStore;
-use crate::storage::store::stores::StoreLimits;
 use crate::storage::store::stores::Stores;
+use crate::storage::store::stores::{IndexConfig, StoreLimits};
 use crate::storage::store::BlockStore;
 use crate::storage::trie::merkle_trie;
 use crate::utils::statsd_wrapper::StatsdClientWrapper;
@@ -44,11 +45,13 @@ impl SnapchainNode {
         block_store: BlockStore,
         local_state_store: LocalStateStore,
         rocksdb_dir: String,
+        rocksdb_config: &db::Config,
+        index_config: &IndexConfig,
         statsd_client: StatsdClientWrapper,
         trie_branching_factor: u32,
         network: FarcasterNetwork,
         registry: &SharedRegistry,
-    ) -> Self {
+    ) -> Result<Self, MigrationError> {
         let validator_address = Address(keypair.public().to_bytes());
 
         let mut consensus_actors = BTreeMap::new();
@@ -67,7 +70,8 @@ impl SnapchainNode {
             let shard = SnapchainShard::new(shard_id);
             let ctx = SnapchainValidatorContext::new(keypair.clone());
 
-            let db = RocksDB::open_shard_db(rocksdb_dir.clone().as_str(), shard_id);
+            let db = RocksDB::open_shard_db(rocksdb_dir.clone().as_str(), shard_id, rocksdb_config);
+            migrations::run_migrations(&db, DbKind::Shard(shard_id))?;
             let trie = merkle_trie::MerkleTrie::new(trie_branching_factor).unwrap(); //TODO: don't unwrap()
             let engine = ShardEngine::new(
                 db.clone(),
@@ -75,6 +79,7 @@ impl SnapchainNode {
                 trie,
                 shard_id,
                 StoreLimits::default(),
+                index_config.clone(),
                 statsd_client.clone(),
                 config.max_messages_per_block,
                 Some(messages_request_tx.clone()),
@@ -163,12 +168,12 @@ impl SnapchainNode {
         }
         consensus_actors.insert(0, block_consensus_actor.unwrap());
 
-        Self {
+        Ok(Self {
             consensus_actors,
             address: validator_address,
             shard_senders,
             shard_stores,
-        }
+        })
     }
 
     pub fn id(&self) -> String {
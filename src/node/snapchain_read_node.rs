@@ -6,10 +6,11 @@ use crate::core::types::{Address, ShardId, SnapchainShard, SnapchainValidatorCon
 use crate::mempool::mempool::MempoolMessagesRequest;
 use crate::network::gossip::GossipEvent;
 use crate::proto;
-use crate::storage::db::RocksDB;
+use crate::storage::db::{self, RocksDB};
 use crate::storage::store::engine::{BlockEngine, Senders, ShardEngine};
-use crate::storage::store::stores::StoreLimits;
+use crate::storage::store::migrations::{self, DbKind, MigrationError};
 use crate::storage::store::stores::Stores;
+use crate::storage::store::stores::{IndexConfig, StoreLimits};
 use crate::storage::store::BlockStore;
 use crate::storage::trie::merkle_trie;
 use crate::utils::statsd_wrapper::StatsdClientWrapper;
@@ -40,11 +41,14 @@ impl SnapchainReadNode {
         messages_request_tx: mpsc::Sender<MempoolMessagesRequest>,
         block_store: BlockStore,
         rocksdb_dir: String,
+        rocksdb_config: &db::Config,
+        index_config: &IndexConfig,
         statsd_client: StatsdClientWrapper,
         trie_branching_factor: u32,
         farcaster_network: proto::FarcasterNetwork,
+        archive_mode: bool,
         registry: &SharedRegistry,
-    ) -> Self {
+    ) -> Result<Self, MigrationError> {
         let validator_address = Address(keypair.public().to_bytes());
 
         let mut consensus_actors = BTreeMap::new();
@@ -62,7 +66,8 @@ impl SnapchainReadNode {
 
             let ctx = SnapchainValidatorContext::new(keypair.clone());
 
-            let db = RocksDB::open_shard_db(rocksdb_dir.clone().as_str(), shard_id);
+            let db = RocksDB::open_shard_db(rocksdb_dir.clone().as_str(), shard_id, rocksdb_config);
+            migrations::run_migrations(&db, DbKind::Shard(shard_id))?;
             let trie = merkle_trie::MerkleTrie::new(trie_branching_factor).unwrap(); //TODO: don't unwrap()
             let engine = ShardEngine::new(
                 db.clone(),
@@ -70,6 +75,7 @@ impl SnapchainReadNode {
                 trie,
                 shard_id,
                 StoreLimits::default(),
+                index_config.clone(),
                 statsd_client.clone(),
                 config.max_messages_per_block,
                 Some(messages_request_tx.clone()),
@@ -89,6 +95,7 @@ impl SnapchainReadNode {
                 shard_id,
                 statsd_client.clone(),
                 config.clone(),
+                archive_mode,
             )
             .await;
 
@@ -115,6 +122,7 @@ impl SnapchainReadNode {
             block_shard.shard_id(),
             statsd_client.clone(),
             config.clone(),
+            archive_mode,
         )
         .await;
         if block_actor.is_err() {
@@ -122,12 +130,12 @@ impl SnapchainReadNode {
         }
         consensus_actors.insert(0, block_actor.unwrap());
 
-        Self {
+        Ok(Self {
             consensus_actors,
             address: validator_address,
             shard_senders,
             shard_stores,
-        }
+        })
     }
 
     pub fn id(&self) -> String {
@@ -27,8 +27,8 @@ pub enum RootPrefix {
 
     // /* Used to store on chain events */
     OnChainEvent = 12,
-    // /** DB Schema version */
-    // DBSchemaVersion = 13,
+    /* DB schema version and in-progress migration state */
+    DBSchemaVersion = 13,
 
     // /* Used to index verifications by address */
     VerificationByAddress = 14,
@@ -44,6 +44,18 @@ pub enum RootPrefix {
 
     /* Used to index blocks by timestamp */
     BlockIndex = 18,
+
+    /* Used to index casts by embedded url, when enabled */
+    CastsByEmbed = 19,
+
+    /* Used to count links by fid and by target */
+    LinkCounts = 20,
+
+    /* Used to count reactions and replies by cast */
+    CastCounts = 21,
+
+    /* Earliest height with transactions and events, on nodes that backfill older shard chunks */
+    ExecutedStartHeight = 22,
 }
 
 /** Copied from the JS code */
@@ -113,4 +125,8 @@ pub enum OnChainEventPostfix {
 
     #[allow(dead_code)] // TODO
     IdRegisterByCustodyAddress = 53,
+
+    // When each fid's tier expires, and the same expiries ordered by time
+    TierExpiryByFid = 54,
+    TierExpiryByTimestamp = 55,
 }
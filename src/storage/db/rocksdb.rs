@@ -1,11 +1,18 @@
 use crate::core::error::HubError;
+use crate::proto::RocksDbProperties;
+use crate::storage::constants::RootPrefix;
 use crate::storage::db::multi_chunk_writer::MultiChunkWriter;
 use crate::storage::util::increment_vec_u8;
-use rocksdb::{Options, TransactionDB, DB};
+use rocksdb::{
+    BlockBasedOptions, BoundColumnFamily, Cache, ColumnFamilyDescriptor, Options, TransactionDB, DB,
+};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::{self};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock, RwLockReadGuard};
+use strum::IntoEnumIterator;
 use thiserror::Error;
 use tokio::time::Duration;
 use tracing::{info, warn};
@@ -26,6 +33,173 @@ pub enum RocksdbError {
     BackupError(#[from] std::io::Error),
 }
 
+const EVENTS_PERIODIC_COMPACTION_SECONDS: u64 = 60 * 60 * 24; // 1 day
+const RATE_LIMITER_REFILL_PERIOD_US: i64 = 100_000; // RocksDB default
+const RATE_LIMITER_FAIRNESS: i32 = 10; // RocksDB default
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionType {
+    None,
+    Snappy,
+    Lz4,
+    Zstd,
+}
+
+impl CompressionType {
+    fn to_rocksdb(&self) -> rocksdb::DBCompressionType {
+        match self {
+            CompressionType::None => rocksdb::DBCompressionType::None,
+            CompressionType::Snappy => rocksdb::DBCompressionType::Snappy,
+            CompressionType::Lz4 => rocksdb::DBCompressionType::Lz4,
+            CompressionType::Zstd => rocksdb::DBCompressionType::Zstd,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Config {
+    /**
+     * Block cache in bytes for each database, split between its column families. Every shard db,
+     * the block db and the global db get their own cache, so memory use is a multiple of this.
+     */
+    pub block_cache_size: usize,
+    /** Memtable size in bytes, per column family */
+    pub write_buffer_size: usize,
+    pub max_background_jobs: i32,
+    /** Compression for frequently read data (messages, indexes, trie nodes) */
+    pub compression: CompressionType,
+    /** Compression for append-mostly data (events, blocks and shard chunks) */
+    pub cold_compression: CompressionType,
+    /** Limit on flush and compaction writes in bytes per second */
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub compaction_rate_limit: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            block_cache_size: 512 * 1024 * 1024, // 512MB
+            write_buffer_size: 64 * 1024 * 1024, // 64MB
+            max_background_jobs: 2,
+            compression: CompressionType::Lz4,
+            cold_compression: CompressionType::Zstd,
+            compaction_rate_limit: None,
+        }
+    }
+}
+
+/**
+ * Column families separate data with different access patterns, so each can have its own
+ * compaction, caching and bloom filter settings. Keys are routed to a column family by their
+ * RootPrefix, so callers never need to name one explicitly.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::EnumIter)]
+pub enum ColumnFamily {
+    Default = 0,
+    TrieNodes = 1,
+    Events = 2,
+    Chunks = 3,
+    Messages = 4,
+    Indexes = 5,
+}
+
+impl ColumnFamily {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColumnFamily::Default => rocksdb::DEFAULT_COLUMN_FAMILY_NAME,
+            ColumnFamily::TrieNodes => "trie_nodes",
+            ColumnFamily::Events => "events",
+            ColumnFamily::Chunks => "chunks",
+            ColumnFamily::Messages => "messages",
+            ColumnFamily::Indexes => "indexes",
+        }
+    }
+
+    pub fn for_key(key: &[u8]) -> ColumnFamily {
+        let prefix = match key.first() {
+            Some(prefix) => *prefix,
+            None => return ColumnFamily::Default,
+        };
+
+        if prefix == RootPrefix::SyncMerkleTrieNode as u8 {
+            ColumnFamily::TrieNodes
+        } else if prefix == RootPrefix::HubEvents as u8 {
+            ColumnFamily::Events
+        } else if prefix == RootPrefix::Block as u8 || prefix == RootPrefix::Shard as u8 {
+            ColumnFamily::Chunks
+        } else if prefix == RootPrefix::User as u8
+            || prefix == RootPrefix::OnChainEvent as u8
+            || prefix == RootPrefix::FNameUserNameProof as u8
+        {
+            ColumnFamily::Messages
+        } else if prefix == RootPrefix::CastsByParent as u8
+            || prefix == RootPrefix::CastsByMention as u8
+            || prefix == RootPrefix::LinksByTarget as u8
+            || prefix == RootPrefix::ReactionsByTarget as u8
+            || prefix == RootPrefix::VerificationByAddress as u8
+            || prefix == RootPrefix::FNameUserNameProofByFid as u8
+            || prefix == RootPrefix::UserNameProofByName as u8
+            || prefix == RootPrefix::BlockIndex as u8
+            || prefix == RootPrefix::CastsByEmbed as u8
+            || prefix == RootPrefix::LinkCounts as u8
+            || prefix == RootPrefix::CastCounts as u8
+        {
+            ColumnFamily::Indexes
+        } else {
+            ColumnFamily::Default
+        }
+    }
+
+    // Percentage of the block cache given to this column family. Trie nodes and messages are read
+    // on every merge, events and chunks are mostly appended and read sequentially.
+    fn block_cache_share(&self) -> usize {
+        match self {
+            ColumnFamily::Default => 5,
+            ColumnFamily::TrieNodes => 40,
+            ColumnFamily::Events => 5,
+            ColumnFamily::Chunks => 10,
+            ColumnFamily::Messages => 25,
+            ColumnFamily::Indexes => 15,
+        }
+    }
+
+    fn block_cache_size(&self, config: &Config) -> usize {
+        config.block_cache_size / 100 * self.block_cache_share()
+    }
+
+    fn options(&self, config: &Config, cache: &Cache) -> Options {
+        let mut opts = Options::default();
+        opts.set_write_buffer_size(config.write_buffer_size);
+
+        let mut block_opts = BlockBasedOptions::default();
+        block_opts.set_block_cache(cache);
+
+        match self {
+            ColumnFamily::TrieNodes | ColumnFamily::Messages | ColumnFamily::Indexes => {
+                // Point lookups dominate, so bloom filters are worth the memory
+                block_opts.set_bloom_filter(10.0, false);
+                block_opts.set_cache_index_and_filter_blocks(true);
+                opts.set_compression_type(config.compression.to_rocksdb());
+            }
+            ColumnFamily::Events => {
+                // Events are pruned by age, periodic compaction drops the tombstones left behind
+                opts.set_compression_type(config.cold_compression.to_rocksdb());
+                opts.set_periodic_compaction_seconds(EVENTS_PERIODIC_COMPACTION_SECONDS);
+            }
+            ColumnFamily::Chunks => {
+                opts.set_compression_type(config.cold_compression.to_rocksdb());
+            }
+            ColumnFamily::Default => {
+                opts.set_compression_type(config.compression.to_rocksdb());
+            }
+        }
+
+        opts.set_block_based_table_factory(&block_opts);
+        opts
+    }
+}
+
 /** Hold a transaction. List of key/value pairs that will be committed together */
 #[derive(Clone)]
 pub struct RocksDbTransactionBatch {
@@ -67,6 +241,12 @@ pub struct IteratorOptions {
 pub struct RocksDB {
     pub db: RwLock<Option<rocksdb::TransactionDB>>,
     pub path: String,
+    config: Config,
+    // Block caches for each column family, indexed by `ColumnFamily as usize`
+    caches: RwLock<Vec<Cache>>,
+    // Set while a db created before column families existed is being migrated. All keys are
+    // read from and written to the default column family until the migration completes.
+    legacy_layout: AtomicBool,
 }
 
 #[derive(Debug, Default)]
@@ -84,22 +264,32 @@ impl std::fmt::Debug for RocksDB {
 
 impl RocksDB {
     pub fn new(path: &str) -> RocksDB {
+        RocksDB::new_with_config(path, Config::default())
+    }
+
+    pub fn new_with_config(path: &str, config: Config) -> RocksDB {
         info!({ path }, "Opening RocksDB database");
 
         RocksDB {
             db: RwLock::new(None),
             path: path.to_string(),
+            config,
+            caches: RwLock::new(vec![]),
+            legacy_layout: AtomicBool::new(false),
         }
     }
 
-    pub fn open_shard_db(db_dir: &str, shard_id: u32) -> Arc<RocksDB> {
-        let db = RocksDB::new(format!("{}/shard-{}", db_dir, shard_id).as_str());
+    pub fn open_shard_db(db_dir: &str, shard_id: u32, config: &Config) -> Arc<RocksDB> {
+        let db = RocksDB::new_with_config(
+            format!("{}/shard-{}", db_dir, shard_id).as_str(),
+            config.clone(),
+        );
         db.open().unwrap();
         Arc::new(db)
     }
 
-    pub fn open_global_db(db_dir: &str) -> Arc<RocksDB> {
-        let db = RocksDB::new(format!("{}/global", db_dir).as_str());
+    pub fn open_global_db(db_dir: &str, config: &Config) -> Arc<RocksDB> {
+        let db = RocksDB::new_with_config(format!("{}/global", db_dir).as_str(), config.clone());
         db.open().unwrap();
         Arc::new(db)
     }
@@ -130,9 +320,16 @@ impl RocksDB {
         let mut backup_db_options = Options::default();
         backup_db_options.set_compression_type(rocksdb::DBCompressionType::Lz4);
         backup_db_options.create_if_missing(true);
-
-        let backup_db = DB::open(&backup_db_options, &backup_path)
-            .map_err(|e| RocksdbError::InternalError(e))?;
+        backup_db_options.create_missing_column_families(true);
+
+        let backup_db = DB::open_cf_descriptors(
+            &backup_db_options,
+            &backup_path,
+            ColumnFamily::iter().map(|column_family| {
+                ColumnFamilyDescriptor::new(column_family.name(), Options::default())
+            }),
+        )
+        .map_err(|e| RocksdbError::InternalError(e))?;
         let mut write_options = rocksdb::WriteOptions::default();
         write_options.disable_wal(true); // Significantly faster, WAL doesn't provide benefits for backups
         let mut write_batch = rocksdb::WriteBatch::default();
@@ -141,20 +338,29 @@ impl RocksDB {
             let main_db = db.db();
             let main_db_snapshot = main_db.as_ref().unwrap().snapshot();
 
-            let iterator = main_db_snapshot.iterator(rocksdb::IteratorMode::Start);
             let mut count = 0;
-            for item in iterator {
-                let (key, value) = item.unwrap();
-                write_batch.put(key, value);
-                if write_batch.len() >= 10_000 {
-                    backup_db.write_opt(write_batch, &write_options).unwrap();
-                    write_batch = rocksdb::WriteBatch::default();
-                }
+            for column_family in ColumnFamily::iter() {
+                let main_cf = main_db
+                    .as_ref()
+                    .unwrap()
+                    .cf_handle(column_family.name())
+                    .unwrap();
+                let backup_cf = backup_db.cf_handle(column_family.name()).unwrap();
+
+                let iterator = main_db_snapshot.iterator_cf(&main_cf, rocksdb::IteratorMode::Start);
+                for item in iterator {
+                    let (key, value) = item.unwrap();
+                    write_batch.put_cf(&backup_cf, key, value);
+                    if write_batch.len() >= 10_000 {
+                        backup_db.write_opt(write_batch, &write_options).unwrap();
+                        write_batch = rocksdb::WriteBatch::default();
+                    }
 
-                count += 1;
-                if count % 1_000_000 == 0 {
-                    backup_db.flush().unwrap();
-                    info!("Snapshot backup progress: {}M keys", count / 1_000_000);
+                    count += 1;
+                    if count % 1_000_000 == 0 {
+                        backup_db.flush().unwrap();
+                        info!("Snapshot backup progress: {}M keys", count / 1_000_000);
+                    }
                 }
             }
 
@@ -231,14 +437,41 @@ impl RocksDB {
         // Create RocksDB options
         let mut opts = Options::default();
         opts.create_if_missing(true); // Creates a database if it does not exist
-        opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
+        opts.create_missing_column_families(true);
+        opts.set_compression_type(self.config.compression.to_rocksdb());
+        opts.set_max_background_jobs(self.config.max_background_jobs);
+        if let Some(rate_limit) = self.config.compaction_rate_limit {
+            opts.set_ratelimiter(
+                rate_limit as i64,
+                RATE_LIMITER_REFILL_PERIOD_US,
+                RATE_LIMITER_FAIRNESS,
+            );
+        }
 
         let mut tx_db_opts = rocksdb::TransactionDBOptions::default();
         tx_db_opts.set_default_lock_timeout(5000); // 5 seconds
 
+        let caches = ColumnFamily::iter()
+            .map(|column_family| Cache::new_lru_cache(column_family.block_cache_size(&self.config)))
+            .collect::<Vec<_>>();
+        let descriptors = ColumnFamily::iter()
+            .map(|column_family| {
+                ColumnFamilyDescriptor::new(
+                    column_family.name(),
+                    column_family.options(&self.config, &caches[column_family as usize]),
+                )
+            })
+            .collect::<Vec<_>>();
+
         // Open the database with multi-threaded support
-        let db = rocksdb::TransactionDB::open(&opts, &tx_db_opts, &self.path)?;
+        let db = rocksdb::TransactionDB::open_cf_descriptors(
+            &opts,
+            &tx_db_opts,
+            &self.path,
+            descriptors,
+        )?;
         *db_lock = Some(db);
+        *self.caches.write().unwrap() = caches;
 
         // We put the db in a RwLock to make the compiler happy, but it is strictly not required.
         // We can use unsafe to replace the value directly, and this will work fine, and shave off
@@ -294,11 +527,38 @@ impl RocksDB {
         self.db.read().unwrap()
     }
 
-    pub fn keys_exist(&self, keys: &Vec<Vec<u8>>) -> Vec<bool> {
+    pub fn set_legacy_layout(&self, legacy_layout: bool) {
+        self.legacy_layout.store(legacy_layout, Ordering::SeqCst);
+    }
+
+    pub fn column_family_for_key(&self, key: &[u8]) -> ColumnFamily {
+        if self.legacy_layout.load(Ordering::Relaxed) {
+            ColumnFamily::Default
+        } else {
+            ColumnFamily::for_key(key)
+        }
+    }
+
+    // Handles for all column families, indexed by `ColumnFamily as usize`
+    fn cf_handles(db: &TransactionDB) -> Vec<Arc<BoundColumnFamily<'_>>> {
+        ColumnFamily::iter()
+            .map(|column_family| db.cf_handle(column_family.name()).unwrap())
+            .collect()
+    }
+
+    fn multi_get(&self, keys: &Vec<Vec<u8>>) -> Vec<Result<Option<Vec<u8>>, rocksdb::Error>> {
         let db = self.db();
         let db = db.as_ref().unwrap();
+        let cf_handles = Self::cf_handles(db);
+
+        db.multi_get_cf(
+            keys.iter()
+                .map(|key| (&cf_handles[self.column_family_for_key(key) as usize], key)),
+        )
+    }
 
-        db.multi_get(keys)
+    pub fn keys_exist(&self, keys: &Vec<Vec<u8>>) -> Vec<bool> {
+        self.multi_get(keys)
             .into_iter()
             .map(|r| match r {
                 Ok(Some(_)) => true,
@@ -309,15 +569,17 @@ impl RocksDB {
     }
 
     pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RocksdbError> {
-        self.db()
-            .as_ref()
-            .unwrap()
-            .get(key)
+        let db = self.db();
+        let db = db.as_ref().unwrap();
+        let cf = db
+            .cf_handle(self.column_family_for_key(key).name())
+            .unwrap();
+        db.get_cf(&cf, key)
             .map_err(|e| RocksdbError::InternalError(e))
     }
 
     pub fn get_many(&self, keys: &Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, RocksdbError> {
-        let results = self.db().as_ref().unwrap().multi_get(keys);
+        let results = self.multi_get(keys);
 
         // If any of the results are Errors, return an error
         let results = results.into_iter().collect::<Result<Vec<_>, _>>()?;
@@ -330,18 +592,22 @@ impl RocksDB {
     }
 
     pub fn put(&self, key: &[u8], value: &[u8]) -> Result<(), RocksdbError> {
-        self.db()
-            .as_ref()
-            .unwrap()
-            .put(key, value)
+        let db = self.db();
+        let db = db.as_ref().unwrap();
+        let cf = db
+            .cf_handle(self.column_family_for_key(key).name())
+            .unwrap();
+        db.put_cf(&cf, key, value)
             .map_err(|e| RocksdbError::InternalError(e))
     }
 
     pub fn del(&self, key: &[u8]) -> Result<(), RocksdbError> {
-        self.db()
-            .as_ref()
-            .unwrap()
-            .delete(key)
+        let db = self.db();
+        let db = db.as_ref().unwrap();
+        let cf = db
+            .cf_handle(self.column_family_for_key(key).name())
+            .unwrap();
+        db.delete_cf(&cf, key)
             .map_err(|e| RocksdbError::InternalError(e))
     }
 
@@ -354,13 +620,16 @@ impl RocksDB {
         if db.is_none() {
             return Err(RocksdbError::DbNotOpen);
         }
+        let db = db.as_ref().unwrap();
+        let cf_handles = Self::cf_handles(db);
 
-        let txn = db.as_ref().unwrap().transaction();
+        let txn = db.transaction();
         for (key, value) in batch.batch {
+            let cf = &cf_handles[self.column_family_for_key(&key) as usize];
             if value.is_none() {
-                txn.delete(key)?;
+                txn.delete_cf(cf, key)?;
             } else {
-                txn.put(key, value.unwrap())?;
+                txn.put_cf(cf, key, value.unwrap())?;
             }
         }
 
@@ -412,6 +681,19 @@ impl RocksDB {
         }
     }
 
+    // Iterators are scoped to a single column family, picked from the start prefix (or the stop
+    // prefix if there is no start prefix).
+    fn column_family_for_range(
+        &self,
+        start_prefix: &Option<Vec<u8>>,
+        stop_prefix: &Option<Vec<u8>>,
+    ) -> ColumnFamily {
+        match (start_prefix, stop_prefix) {
+            (Some(prefix), _) | (None, Some(prefix)) => self.column_family_for_key(prefix),
+            (None, None) => ColumnFamily::Default,
+        }
+    }
+
     /**
      * Iterate over all keys with a given prefix.
      * The callback function should return true to stop the iteration, or false to continue.
@@ -426,10 +708,13 @@ impl RocksDB {
     where
         F: FnMut(&[u8], &[u8]) -> Result<bool, HubError>,
     {
+        let column_family = self.column_family_for_range(&start_prefix, &stop_prefix);
         let iter_opts = RocksDB::get_iterator_options(start_prefix, stop_prefix, page_options);
 
         let db = self.db();
-        let mut iter = db.as_ref().unwrap().raw_iterator_opt(iter_opts.opts);
+        let db = db.as_ref().unwrap();
+        let cf = db.cf_handle(column_family.name()).unwrap();
+        let mut iter = db.raw_iterator_cf_opt(&cf, iter_opts.opts);
 
         if iter_opts.reverse {
             iter.seek_to_last();
@@ -502,11 +787,15 @@ impl RocksDB {
             // Iterate over all keys and delete them
             let mut txn = self.txn();
             let db = self.db();
-
-            for item in db.as_ref().unwrap().iterator(rocksdb::IteratorMode::Start) {
-                if let Ok((key, _)) = item {
-                    txn.delete(key.to_vec());
-                    deleted += 1;
+            let db = db.as_ref().unwrap();
+
+            for column_family in ColumnFamily::iter() {
+                let cf = db.cf_handle(column_family.name()).unwrap();
+                for item in db.iterator_cf(&cf, rocksdb::IteratorMode::Start) {
+                    if let Ok((key, _)) = item {
+                        txn.delete(key.to_vec());
+                        deleted += 1;
+                    }
                 }
             }
 
@@ -521,6 +810,95 @@ impl RocksDB {
         Ok(deleted)
     }
 
+    pub fn is_empty(&self) -> bool {
+        let db = self.db();
+        let db = db.as_ref().unwrap();
+
+        ColumnFamily::iter().all(|column_family| {
+            let cf = db.cf_handle(column_family.name()).unwrap();
+            let mut iter = db.raw_iterator_cf(&cf);
+            iter.seek_to_first();
+            !iter.valid()
+        })
+    }
+
+    /**
+     * Move keys written before column families existed out of the default column family and into
+     * the one they are routed to. Processes at most `limit` keys after `start_after`, and returns
+     * the number of keys visited along with the last key, or None once there is nothing left.
+     */
+    pub fn move_to_column_families(
+        &self,
+        start_after: Option<Vec<u8>>,
+        limit: usize,
+    ) -> Result<(u64, Option<Vec<u8>>), RocksdbError> {
+        let db = self.db();
+        let db = db.as_ref().unwrap();
+        let cf_handles = Self::cf_handles(db);
+        let default_cf = &cf_handles[ColumnFamily::Default as usize];
+
+        let mut opts = rocksdb::ReadOptions::default();
+        if let Some(start_after) = start_after {
+            // The smallest key after the cursor. Incrementing it instead would skip keys that
+            // extend it, like the children of a trie node.
+            let mut lower_bound = start_after;
+            lower_bound.push(0);
+            opts.set_iterate_lower_bound(lower_bound);
+        }
+        let mut iter = db.raw_iterator_cf_opt(default_cf, opts);
+        iter.seek_to_first();
+
+        let txn = db.transaction();
+        let mut count = 0;
+        let mut last_key = None;
+        while iter.valid() && count < limit {
+            if let Some((key, value)) = iter.item() {
+                let column_family = ColumnFamily::for_key(key);
+                if column_family != ColumnFamily::Default {
+                    txn.put_cf(&cf_handles[column_family as usize], key, value)?;
+                    txn.delete_cf(default_cf, key)?;
+                }
+                last_key = Some(key.to_vec());
+                count += 1;
+            }
+            iter.next();
+        }
+        txn.commit()?;
+
+        if count < limit {
+            last_key = None;
+        }
+        Ok((count as u64, last_key))
+    }
+
+    /** Live RocksDB properties that apply to the whole db, across all column families */
+    pub fn properties(&self) -> RocksDbProperties {
+        let caches = self.caches.read().unwrap();
+        let db = self.db();
+        let db = db.as_ref().unwrap();
+
+        let int_property = |name: &rocksdb::properties::PropName| {
+            db.property_int_value(name).ok().flatten().unwrap_or(0)
+        };
+
+        RocksDbProperties {
+            block_cache_capacity: ColumnFamily::iter()
+                .map(|column_family| column_family.block_cache_size(&self.config) as u64)
+                .sum(),
+            block_cache_usage: caches.iter().map(|cache| cache.get_usage() as u64).sum(),
+            block_cache_pinned_usage: caches
+                .iter()
+                .map(|cache| cache.get_pinned_usage() as u64)
+                .sum(),
+            num_running_compactions: int_property(rocksdb::properties::NUM_RUNNING_COMPACTIONS),
+            num_running_flushes: int_property(rocksdb::properties::NUM_RUNNING_FLUSHES),
+            num_snapshots: int_property(rocksdb::properties::NUM_SNAPSHOTS),
+            background_errors: int_property(rocksdb::properties::BACKGROUND_ERRORS),
+            actual_delayed_write_rate: int_property(rocksdb::properties::ACTUAL_DELAYED_WRITE_RATE),
+            is_write_stopped: int_property(rocksdb::properties::IS_WRITE_STOPPED) != 0,
+        }
+    }
+
     pub fn approximate_size(&self) -> u64 {
         WalkDir::new(self.location())
             .into_iter()
@@ -542,7 +920,11 @@ impl RocksDB {
         );
 
         let db = self.db();
-        let mut iter = db.as_ref().unwrap().raw_iterator_opt(iter_opts.opts);
+        let db = db.as_ref().unwrap();
+        let cf = db
+            .cf_handle(self.column_family_for_key(&prefix).name())
+            .unwrap();
+        let mut iter = db.raw_iterator_cf_opt(&cf, iter_opts.opts);
 
         let mut count = 0;
         iter.seek_to_first();
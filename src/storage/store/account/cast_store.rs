@@ -1,8 +1,9 @@
 use super::{
-    get_many_messages, make_cast_id_key, make_fid_key, make_message_primary_key, make_user_key,
-    read_fid_key, read_ts_hash,
+    get_many_messages, make_cast_id_key, make_fid_key, make_message_primary_key, make_ts_hash,
+    make_user_key, read_counter, read_fid_key, read_ts_hash,
     store::{Store, StoreDef},
-    MessagesPage, StoreEventHandler, HASH_LENGTH, PAGE_SIZE_MAX, TRUE_VALUE, TS_HASH_LENGTH,
+    update_counter, MessagesPage, StoreEventHandler, HASH_LENGTH, PAGE_SIZE_MAX, TRUE_VALUE,
+    TS_HASH_LENGTH,
 };
 use crate::core::error::HubError;
 use crate::storage::constants::{RootPrefix, UserPostfix};
@@ -43,10 +44,22 @@ type Parent = message::cast_add_body::Parent;
  * 3. fid:set:targetTsHash -> fid:tsHash (Remove Set Index)
  * 4. parentFid:parentTsHash:fid:tsHash -> fid:tsHash (Child Set Index)
  * 5. mentionFid:fid:tsHash -> fid:tsHash (Mentions Set Index)
+ * 6. urlHash:tsHash:fid -> fid:tsHash (Embeds Set Index, only when the cast_embeds index is enabled)
+ * 7. parentFid:parentHash:countType -> count (Cast Counts, also updated by the ReactionStore)
  */
 #[derive(Clone)]
 pub struct CastStoreDef {
+    db: Arc<RocksDB>,
     prune_size_limit: u32,
+    index_embeds: bool,
+}
+
+/** Counts kept per cast */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CastCountType {
+    Likes = 1,
+    Recasts = 2,
+    Replies = 3,
 }
 
 impl StoreDef for CastStoreDef {
@@ -136,7 +149,14 @@ impl StoreDef for CastStoreDef {
                 txn.put(by_mention_key, vec![TRUE_VALUE]);
             }
         }
-        Ok(())
+        if self.index_embeds {
+            if let Ok(Some(by_embed_keys)) = self.by_embed_secondary_index_key(ts_hash, message) {
+                for by_embed_key in by_embed_keys {
+                    txn.put(by_embed_key, vec![TRUE_VALUE]);
+                }
+            }
+        }
+        self.update_reply_count(txn, message, 1)
     }
 
     fn delete_secondary_indices(
@@ -157,7 +177,14 @@ impl StoreDef for CastStoreDef {
             }
         }
 
-        Ok(())
+        // Deleted even when disabled, so entries from when the index was enabled don't linger
+        if let Ok(Some(by_embed_keys)) = self.by_embed_secondary_index_key(ts_hash, message) {
+            for by_embed_key in by_embed_keys {
+                txn.delete(by_embed_key);
+            }
+        }
+
+        self.update_reply_count(txn, message, -1)
     }
 
     fn make_add_key(&self, message: &Message) -> Result<Vec<u8>, HubError> {
@@ -222,6 +249,28 @@ impl StoreDef for CastStoreDef {
 }
 
 impl CastStoreDef {
+    fn update_reply_count(
+        &self,
+        txn: &mut RocksDbTransactionBatch,
+        message: &Message,
+        delta: i64,
+    ) -> Result<(), HubError> {
+        if let Some(message::message_data::Body::CastAddBody(cast_add_body)) =
+            message.data.as_ref().and_then(|data| data.body.as_ref())
+        {
+            if let Some(Parent::ParentCastId(parent)) = &cast_add_body.parent {
+                return CastStore::update_cast_count(
+                    &self.db,
+                    txn,
+                    parent,
+                    CastCountType::Replies,
+                    delta,
+                );
+            }
+        }
+        Ok(())
+    }
+
     fn by_parent_secondary_index_key(
         &self,
         ts_hash: &[u8; TS_HASH_LENGTH],
@@ -344,6 +393,59 @@ impl CastStoreDef {
         key
     }
 
+    fn by_embed_secondary_index_key(
+        &self,
+        ts_hash: &[u8; TS_HASH_LENGTH],
+        message: &Message,
+    ) -> Result<Option<Vec<Vec<u8>>>, HubError> {
+        let cast_body = match message.data.as_ref().unwrap().body.as_ref().unwrap() {
+            message::message_data::Body::CastAddBody(cast_add_body) => cast_add_body,
+            message::message_data::Body::CastRemoveBody(_) => return Ok(None),
+            _ => Err(HubError {
+                code: "bad_request.validation_failure".to_string(),
+                message: "Invalid cast body".to_string(),
+            })?,
+        };
+        let mut result = vec![];
+        for embed in cast_body.embeds.iter() {
+            if let Some(message::embed::Embed::Url(url)) = &embed.embed {
+                let embed_key = Self::make_cast_by_embed_key(
+                    url,
+                    message.data.as_ref().unwrap().fid,
+                    Some(ts_hash),
+                );
+                // The same url can be embedded twice
+                if !result.contains(&embed_key) {
+                    result.push(embed_key);
+                }
+            }
+        }
+        if result.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(result))
+    }
+
+    // Generates unique keys used to store or fetch CastAdd messages in the byEmbed key index. Urls
+    // are hashed so every url has a fixed length prefix, and no url's prefix is another's
+    #[inline]
+    pub fn make_cast_by_embed_key(
+        url: &str,
+        fid: u64,
+        ts_hash: Option<&[u8; TS_HASH_LENGTH]>,
+    ) -> Vec<u8> {
+        let mut key = Vec::with_capacity(1 + HASH_LENGTH + 24 + 4);
+        key.push(RootPrefix::CastsByEmbed as u8); // CastsByEmbed prefix, 1 byte
+        key.extend_from_slice(&blake3::hash(url.as_bytes()).as_bytes()[..HASH_LENGTH]);
+        if let Some(ts_hash_val) = ts_hash {
+            key.extend_from_slice(ts_hash_val);
+        }
+        if fid > 0 {
+            key.extend_from_slice(&make_fid_key(fid));
+        }
+        key
+    }
+
     // Generates unique keys used to store or fetch CastRemove messages in the removes set index
     #[inline]
     pub fn make_cast_removes_key(fid: u64, hash: &Vec<u8>) -> Vec<u8> {
@@ -366,8 +468,61 @@ impl CastStore {
         db: Arc<RocksDB>,
         store_event_handler: Arc<StoreEventHandler>,
         prune_size_limit: u32,
+        index_embeds: bool,
     ) -> Store<CastStoreDef> {
-        Store::new_with_store_def(db, store_event_handler, CastStoreDef { prune_size_limit })
+        Store::new_with_store_def(
+            db.clone(),
+            store_event_handler,
+            CastStoreDef {
+                db,
+                prune_size_limit,
+                index_embeds,
+            },
+        )
+    }
+
+    fn make_cast_count_key(cast_id: &message::CastId, count_type: CastCountType) -> Vec<u8> {
+        let mut key = Vec::with_capacity(1 + 4 + HASH_LENGTH + 1);
+        key.push(RootPrefix::CastCounts as u8);
+        key.extend_from_slice(&make_cast_id_key(cast_id));
+        key.push(count_type as u8);
+        key
+    }
+
+    /** Adds delta to a count for the cast, in the same transaction as the merge that changed it */
+    pub fn update_cast_count(
+        db: &RocksDB,
+        txn: &mut RocksDbTransactionBatch,
+        cast_id: &message::CastId,
+        count_type: CastCountType,
+        delta: i64,
+    ) -> Result<(), HubError> {
+        update_counter(
+            db,
+            txn,
+            Self::make_cast_count_key(cast_id, count_type),
+            delta,
+        )
+    }
+
+    /**
+     * Returns the likes, recasts and replies for a cast from this shard. Reactions and replies are
+     * stored on the shard of their author, so totals are the sum over all shards.
+     */
+    pub fn get_cast_stats(
+        store: &Store<CastStoreDef>,
+        cast_id: &message::CastId,
+    ) -> Result<message::CastStats, HubError> {
+        let count = |count_type| -> Result<u64, HubError> {
+            let key = Self::make_cast_count_key(cast_id, count_type);
+            Ok(read_counter(store.db().get(&key)?))
+        };
+        Ok(message::CastStats {
+            cast_id: Some(cast_id.clone()),
+            likes: count(CastCountType::Likes)?,
+            recasts: count(CastCountType::Recasts)?,
+            replies: count(CastCountType::Replies)?,
+        })
     }
 
     pub fn get_cast_add(
@@ -431,19 +586,32 @@ impl CastStore {
         store.get_removes_by_fid::<fn(&Message) -> bool>(fid, page_options, None)
     }
 
-    pub fn get_casts_by_parent(
+    /**
+     * Pages through a casts secondary index with `prefix:tsHash:fid` keys. Timestamps are
+     * farcaster time and both bounds are inclusive.
+     */
+    fn get_casts_by_index(
         store: &Store<CastStoreDef>,
-        parent: &Parent,
+        prefix: Vec<u8>,
+        start_timestamp: Option<u32>,
+        stop_timestamp: Option<u32>,
         page_options: &PageOptions,
     ) -> Result<MessagesPage, HubError> {
-        let prefix = CastStoreDef::make_cast_by_parent_key(parent, 0, None);
+        let start_prefix = match start_timestamp {
+            Some(start_timestamp) => [prefix.as_slice(), &start_timestamp.to_be_bytes()].concat(),
+            None => prefix.clone(),
+        };
+        let stop_prefix = match stop_timestamp.and_then(|ts| ts.checked_add(1)) {
+            Some(stop_timestamp) => [prefix.as_slice(), &stop_timestamp.to_be_bytes()].concat(),
+            None => increment_vec_u8(&prefix),
+        };
 
         let mut message_keys = vec![];
         let mut last_key = vec![];
 
         store.db().for_each_iterator_by_prefix(
-            Some(prefix.to_vec()),
-            Some(increment_vec_u8(&prefix)),
+            Some(start_prefix),
+            Some(stop_prefix),
             page_options,
             |key, _| {
                 let ts_hash_offset = prefix.len();
@@ -477,49 +645,63 @@ impl CastStore {
         })
     }
 
+    pub fn get_casts_by_parent(
+        store: &Store<CastStoreDef>,
+        parent: &Parent,
+        start_timestamp: Option<u32>,
+        stop_timestamp: Option<u32>,
+        page_options: &PageOptions,
+    ) -> Result<MessagesPage, HubError> {
+        let prefix = CastStoreDef::make_cast_by_parent_key(parent, 0, None);
+        Self::get_casts_by_index(store, prefix, start_timestamp, stop_timestamp, page_options)
+    }
+
+    /** The key a cast has in the casts-by-parent index, usable as a page token to resume after it */
+    pub fn cast_by_parent_cursor(parent: &Parent, message: &Message) -> Result<Vec<u8>, HubError> {
+        let data = message
+            .data
+            .as_ref()
+            .ok_or(HubError::invalid_parameter("missing message data"))?;
+        let ts_hash = make_ts_hash(data.timestamp, &message.hash)?;
+        Ok(CastStoreDef::make_cast_by_parent_key(
+            parent,
+            data.fid,
+            Some(&ts_hash),
+        ))
+    }
+
     pub fn get_casts_by_mention(
         store: &Store<CastStoreDef>,
         mention: u64,
         page_options: &PageOptions,
     ) -> Result<MessagesPage, HubError> {
         let prefix = CastStoreDef::make_cast_by_mention_key(mention, 0, None);
+        Self::get_casts_by_index(store, prefix, None, None, page_options)
+    }
 
-        let mut message_keys = vec![];
-        let mut last_key = vec![];
-
-        store.db().for_each_iterator_by_prefix(
-            Some(prefix.to_vec()),
-            Some(increment_vec_u8(&prefix)),
-            page_options,
-            |key, _| {
-                let ts_hash_offset = prefix.len();
-                let fid_offset = ts_hash_offset + TS_HASH_LENGTH;
-
-                let fid = read_fid_key(key, fid_offset);
-                let ts_hash = read_ts_hash(key, ts_hash_offset);
-                let message_primary_key =
-                    make_message_primary_key(fid, store.postfix(), Some(&ts_hash));
-
-                message_keys.push(message_primary_key.to_vec());
-                if message_keys.len() >= page_options.page_size.unwrap_or(PAGE_SIZE_MAX) {
-                    last_key = key.to_vec();
-                    return Ok(true); // Stop iterating
-                }
-
-                Ok(false) // Continue iterating
-            },
-        )?;
-
-        let messages_bytes = get_many_messages(store.db().borrow(), message_keys)?;
-        let next_page_token = if last_key.len() > 0 {
-            Some(last_key.to_vec())
-        } else {
-            None
-        };
+    /** Only finds casts merged while the embeds index is enabled. */
+    pub fn get_casts_by_embed(
+        store: &Store<CastStoreDef>,
+        url: &str,
+        start_timestamp: Option<u32>,
+        stop_timestamp: Option<u32>,
+        page_options: &PageOptions,
+    ) -> Result<MessagesPage, HubError> {
+        let prefix = CastStoreDef::make_cast_by_embed_key(url, 0, None);
+        Self::get_casts_by_index(store, prefix, start_timestamp, stop_timestamp, page_options)
+    }
 
-        Ok(MessagesPage {
-            messages: messages_bytes,
-            next_page_token,
-        })
+    /** The key a cast has in the casts-by-embed index, usable as a page token to resume after it */
+    pub fn cast_by_embed_cursor(url: &str, message: &Message) -> Result<Vec<u8>, HubError> {
+        let data = message
+            .data
+            .as_ref()
+            .ok_or(HubError::invalid_parameter("missing message data"))?;
+        let ts_hash = make_ts_hash(data.timestamp, &message.hash)?;
+        Ok(CastStoreDef::make_cast_by_embed_key(
+            url,
+            data.fid,
+            Some(&ts_hash),
+        ))
     }
 }
@@ -1,8 +1,8 @@
 use super::{
-    get_many_messages, make_fid_key, make_message_primary_key, make_user_key, read_fid_key,
-    read_ts_hash,
+    get_many_messages, make_fid_key, make_message_primary_key, make_user_key, read_counter,
+    read_fid_key, read_ts_hash,
     store::{Store, StoreDef},
-    MessagesPage, StoreEventHandler, PAGE_SIZE_MAX, TS_HASH_LENGTH,
+    update_counter, MessagesPage, StoreEventHandler, PAGE_SIZE_MAX, TS_HASH_LENGTH,
 };
 use crate::{
     core::error::HubError,
@@ -41,12 +41,26 @@ use std::{borrow::Borrow, sync::Arc};
  * 1. fid:tsHash -> link message
  * 2. fid:set:targetCastTsHash:linkType -> fid:tsHash (Set Index)
  * 3. linkTarget:linkType:targetCastTsHash -> fid:tsHash (Target Index)
+ * 4. fid:direction:linkType -> count (Link Counts, one for the links the fid created and one for
+ *    the links that target it)
  */
 #[derive(Clone)]
 pub struct LinkStore {
+    db: Arc<RocksDB>,
     prune_size_limit: u32,
 }
 
+pub const FOLLOW_LINK_TYPE: &str = "follow";
+
+/** Which side of a link a count is kept for */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LinkCountDirection {
+    /** Links created by the fid, e.g. who it follows */
+    ByFid = 1,
+    /** Links that target the fid, e.g. its followers */
+    ByTarget = 2,
+}
+
 impl LinkStore {
     // Even though fid is 64 bits, we're only using 32 bits for now, to save 4 bytes per key.
     // This is fine until 4 billion users, after which we'll need to do a migration of this key in the DB.
@@ -62,7 +76,77 @@ impl LinkStore {
         store_event_handler: Arc<StoreEventHandler>,
         prune_size_limit: u32,
     ) -> Store<LinkStore> {
-        Store::new_with_store_def(db, store_event_handler, LinkStore { prune_size_limit })
+        let store_def = LinkStore {
+            db: db.clone(),
+            prune_size_limit,
+        };
+        Store::new_with_store_def(db, store_event_handler, store_def)
+    }
+
+    fn link_count_key(fid: u64, direction: LinkCountDirection, link_type: &str) -> Vec<u8> {
+        let mut key = Vec::with_capacity(
+            Self::ROOT_PREFIX_BYTE_SIZE + Self::FID_BYTE_SIZE + 1 + Self::LINK_TYPE_BYTE_SIZE,
+        );
+        key.push(RootPrefix::LinkCounts as u8);
+        key.extend(make_fid_key(fid));
+        key.push(direction as u8);
+        let mut type_bytes = link_type.as_bytes().to_vec();
+        // Pad with zero bytes
+        type_bytes.resize(Self::LINK_TYPE_BYTE_SIZE, 0);
+        key.extend_from_slice(&type_bytes);
+        key
+    }
+
+    /// Returns the number of links of the given type the fid created, or that target the fid.
+    /// Links that target the fid are counted on the shard of the fid that created them, so the
+    /// total for [LinkCountDirection::ByTarget] is the sum over all shards.
+    pub fn get_link_count(
+        store: &Store<LinkStore>,
+        fid: u64,
+        direction: LinkCountDirection,
+        link_type: &str,
+    ) -> Result<u64, HubError> {
+        let key = Self::link_count_key(fid, direction, link_type);
+        Ok(read_counter(store.db().get(&key)?))
+    }
+
+    /// Adds delta to the counts of links from fid to target_fid with the given type.
+    pub fn update_link_counts(
+        db: &RocksDB,
+        txn: &mut RocksDbTransactionBatch,
+        fid: u64,
+        target_fid: u64,
+        link_type: &str,
+        delta: i64,
+    ) -> Result<(), HubError> {
+        let by_fid_key = Self::link_count_key(fid, LinkCountDirection::ByFid, link_type);
+        update_counter(db, txn, by_fid_key, delta)?;
+        let by_target_key =
+            Self::link_count_key(target_fid, LinkCountDirection::ByTarget, link_type);
+        update_counter(db, txn, by_target_key, delta)
+    }
+
+    fn update_link_counts_for_message(
+        &self,
+        txn: &mut RocksDbTransactionBatch,
+        message: &Message,
+        delta: i64,
+    ) -> Result<(), HubError> {
+        if let Some(data) = &message.data {
+            if let Some(Body::LinkBody(link_body)) = &data.body {
+                if let Some(Target::TargetFid(target_fid)) = link_body.target {
+                    return Self::update_link_counts(
+                        &self.db,
+                        txn,
+                        data.fid,
+                        target_fid,
+                        &link_body.r#type,
+                        delta,
+                    );
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Finds a LinkAdd Message by checking the Adds Set index.
@@ -516,7 +600,7 @@ impl StoreDef for LinkStore {
 
         txn.put(by_target_key, rtype);
 
-        Ok(())
+        self.update_link_counts_for_message(txn, message, 1)
     }
 
     #[inline]
@@ -530,7 +614,7 @@ impl StoreDef for LinkStore {
 
         txn.delete(by_target_key);
 
-        Ok(())
+        self.update_link_counts_for_message(txn, message, -1)
     }
 
     fn make_compact_state_add_key(&self, message: &Message) -> Result<Vec<u8>, HubError> {
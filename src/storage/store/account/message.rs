@@ -194,6 +194,29 @@ pub fn get_from_db_or_txn(
     }
 }
 
+/** Reads a counter written by [update_counter], missing counters are 0 */
+pub fn read_counter(value: Option<Vec<u8>>) -> u64 {
+    value
+        .and_then(|value| value.try_into().ok())
+        .map_or(0, u64::from_be_bytes)
+}
+
+/** Adds delta to the counter at key. Counters that drop to 0 are deleted. */
+pub fn update_counter(
+    db: &RocksDB,
+    txn: &mut RocksDbTransactionBatch,
+    key: Vec<u8>,
+    delta: i64,
+) -> Result<(), HubError> {
+    let count = read_counter(get_from_db_or_txn(db, txn, &key)?).saturating_add_signed(delta);
+    if count == 0 {
+        txn.delete(key);
+    } else {
+        txn.put(key, count.to_be_bytes().to_vec());
+    }
+    Ok(())
+}
+
 pub fn get_message_by_key(
     db: &RocksDB,
     txn: &mut RocksDbTransactionBatch,
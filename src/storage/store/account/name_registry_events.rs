@@ -5,7 +5,8 @@ use crate::{
     proto::UserNameProof,
     storage::{
         constants::RootPrefix,
-        db::{RocksDB, RocksDbTransactionBatch},
+        db::{PageOptions, RocksDB, RocksDbTransactionBatch},
+        util::prefix_range_after,
     },
 };
 
@@ -69,6 +70,37 @@ pub fn get_fname_proof_by_fid(db: &RocksDB, fid: u64) -> Result<Option<UserNameP
     }
 }
 
+/**
+ * Fname proofs in name order, for names that start with [prefix] and sort after [after]. Deleted
+ * fnames are included with fid 0, so callers can tell them apart from names they never saw.
+ */
+pub fn get_fname_proofs_by_prefix(
+    db: &RocksDB,
+    prefix: &[u8],
+    after: Option<&[u8]>,
+    limit: usize,
+) -> Result<Vec<UserNameProof>, HubError> {
+    let key_prefix = make_fname_username_proof_key(prefix);
+    let after = after.map(make_fname_username_proof_key);
+    let (start, stop) = prefix_range_after(&key_prefix, after.as_deref());
+
+    let mut proofs = vec![];
+    db.for_each_iterator_by_prefix(
+        Some(start),
+        Some(stop),
+        &PageOptions::default(),
+        |_, value| {
+            let proof = UserNameProof::decode(value).map_err(|_| HubError {
+                code: "internal_error".to_string(),
+                message: "could not decode username proof".to_string(),
+            })?;
+            proofs.push(proof);
+            Ok(proofs.len() >= limit)
+        },
+    )?;
+    Ok(proofs)
+}
+
 #[inline]
 pub fn put_username_proof_transaction(
     txn: &mut RocksDbTransactionBatch,
@@ -4,7 +4,9 @@ mod tests {
     use crate::proto::TierType;
     use crate::storage::db;
     use crate::storage::db::RocksDbTransactionBatch;
-    use crate::storage::store::account::{OnchainEventStore, StorageSlot, StoreEventHandler};
+    use crate::storage::store::account::{
+        tier_purchase_periods, OnchainEventStore, StorageSlot, StoreEventHandler,
+    };
     use crate::utils::factory::{self};
     use std::sync::Arc;
     use tempfile::TempDir;
@@ -232,5 +234,208 @@ mod tests {
                     .incr_by(day_in_secs + 1)
             )
             .unwrap());
+
+        // Later purchases don't count before they were made
+        assert_eq!(
+            store
+                .tier_subscription_exires_at(
+                    TierType::Pro,
+                    10,
+                    Some(&FarcasterTime::from_unix_seconds(
+                        pro_user_event3.block_timestamp - 1
+                    ))
+                )
+                .unwrap(),
+            pro_user_event1.block_timestamp + 2 * day_in_secs
+        );
+        assert_eq!(
+            store
+                .tier_subscription_exires_at(TierType::Pro, 10, None)
+                .unwrap(),
+            pro_user_event3.block_timestamp + day_in_secs
+        );
+    }
+
+    #[test]
+    fn test_tier_purchase_periods() {
+        let day_in_secs = 24 * 60 * 60;
+        let start = 1_700_000_000;
+        let purchases = [
+            factory::events_factory::create_pro_user_event(10, 2, Some(start)),
+            // Bought while active, stacked after the first period
+            factory::events_factory::create_pro_user_event(10, 1, Some(start + day_in_secs)),
+            // Bought after a lapse, starts when purchased
+            factory::events_factory::create_pro_user_event(10, 1, Some(start + 5 * day_in_secs)),
+        ];
+
+        let periods = tier_purchase_periods(&purchases);
+        let start = start as u64;
+        let day_in_secs = day_in_secs as u64;
+        assert_eq!(
+            periods
+                .iter()
+                .map(|period| (period.starts_at, period.expires_at))
+                .collect::<Vec<_>>(),
+            vec![
+                (start, start + 2 * day_in_secs),
+                (start + 2 * day_in_secs, start + 3 * day_in_secs),
+                (start + 5 * day_in_secs, start + 6 * day_in_secs),
+            ]
+        );
+        assert_eq!(periods[1].purchase, Some(purchases[1].clone()));
+    }
+
+    #[test]
+    fn test_tier_status() {
+        let (store, _dir) = store();
+        let day_in_secs = 24 * 60 * 60;
+        let start_time = FarcasterTime::new(100);
+        let start = start_time.to_unix_seconds();
+
+        let status = store
+            .get_tier_status(TierType::Pro, 10, &start_time)
+            .unwrap();
+        assert!(!status.active);
+        assert_eq!(status.expires_at, 0);
+        assert!(status.purchases.is_empty());
+
+        let mut txn = RocksDbTransactionBatch::new();
+        for (for_days, block_timestamp) in [(1, start), (1, start + day_in_secs - 10)] {
+            let event = factory::events_factory::create_pro_user_event(
+                10,
+                for_days,
+                Some(block_timestamp as u32),
+            );
+            store.merge_onchain_event(event, &mut txn).unwrap();
+        }
+        store.db.commit(txn).unwrap();
+
+        let status = store
+            .get_tier_status(TierType::Pro, 10, &start_time)
+            .unwrap();
+        assert_eq!(status.tier_type, TierType::Pro as i32);
+        assert!(status.active);
+        assert_eq!(status.expires_at, start + 2 * day_in_secs);
+        assert_eq!(status.purchases.len(), 2);
+
+        let status = store
+            .get_tier_status(TierType::Pro, 10, &start_time.incr_by(2 * day_in_secs + 1))
+            .unwrap();
+        assert!(!status.active);
+        assert_eq!(status.expires_at, start + 2 * day_in_secs);
+    }
+
+    #[test]
+    fn test_tier_expirations() {
+        let (store, _dir) = store();
+        let day_in_secs = 24 * 60 * 60;
+        let start = 1_700_000_000u64;
+
+        let mut txn = RocksDbTransactionBatch::new();
+        for (fid, for_days) in [(10, 1), (11, 2), (12, 1)] {
+            let event =
+                factory::events_factory::create_pro_user_event(fid, for_days, Some(start as u32));
+            store.merge_onchain_event(event, &mut txn).unwrap();
+        }
+        store.db.commit(txn).unwrap();
+
+        let expired_fids = |from, to, txn: &mut RocksDbTransactionBatch| {
+            store
+                .get_tier_expirations(TierType::Pro, from, to, txn)
+                .unwrap()
+                .iter()
+                .map(|expiration| (expiration.fid, expiration.expires_at))
+                .collect::<Vec<_>>()
+        };
+
+        let mut txn = RocksDbTransactionBatch::new();
+        let one_day = start + day_in_secs;
+        assert_eq!(expired_fids(start, one_day, &mut txn), vec![]);
+        assert_eq!(
+            expired_fids(one_day, one_day + 1, &mut txn),
+            vec![(10, one_day), (12, one_day)]
+        );
+        assert_eq!(
+            expired_fids(start, start + 3 * day_in_secs, &mut txn),
+            vec![(10, one_day), (12, one_day), (11, start + 2 * day_in_secs)]
+        );
+
+        // A renewal in the same transaction moves the expiry
+        let renewal =
+            factory::events_factory::create_pro_user_event(12, 1, Some((one_day - 10) as u32));
+        store.merge_onchain_event(renewal, &mut txn).unwrap();
+        assert_eq!(
+            expired_fids(one_day, one_day + 1, &mut txn),
+            vec![(10, one_day)]
+        );
+        store.db.commit(txn).unwrap();
+
+        let mut txn = RocksDbTransactionBatch::new();
+        assert_eq!(
+            expired_fids(one_day, one_day + day_in_secs + 1, &mut txn),
+            vec![(10, one_day), (12, one_day + day_in_secs)]
+        );
+    }
+
+    #[test]
+    fn test_tier_expiry_with_out_of_order_purchases() {
+        let (store, _dir) = store();
+        let day_in_secs = 24 * 60 * 60;
+        let start = 1_700_000_000u64;
+
+        let first = factory::events_factory::create_pro_user_event(10, 1, Some(start as u32));
+        let second = factory::events_factory::create_pro_user_event(
+            10,
+            1,
+            Some((start + 3 * day_in_secs) as u32),
+        );
+        for event in [second, first] {
+            let mut txn = RocksDbTransactionBatch::new();
+            store.merge_onchain_event(event, &mut txn).unwrap();
+            store.db.commit(txn).unwrap();
+        }
+
+        let mut txn = RocksDbTransactionBatch::new();
+        assert_eq!(
+            store
+                .get_tier_expirations(TierType::Pro, start, start + 5 * day_in_secs, &mut txn)
+                .unwrap()
+                .iter()
+                .map(|expiration| expiration.expires_at)
+                .collect::<Vec<_>>(),
+            vec![start + 4 * day_in_secs]
+        );
+    }
+
+    #[test]
+    fn test_tier_expiry_with_out_of_order_purchases_in_one_txn() {
+        let (store, _dir) = store();
+        let day_in_secs = 24 * 60 * 60;
+        // Small enough that the factory's block numbers follow the timestamps
+        let start = 1_000_000u64;
+
+        let first = factory::events_factory::create_pro_user_event(10, 1, Some(start as u32));
+        let second = factory::events_factory::create_pro_user_event(
+            10,
+            1,
+            Some((start + 3 * day_in_secs) as u32),
+        );
+        // The replay for the earlier purchase has to see the later one, which isn't committed yet
+        let mut txn = RocksDbTransactionBatch::new();
+        for event in [second, first] {
+            store.merge_onchain_event(event, &mut txn).unwrap();
+        }
+        store.db.commit(txn).unwrap();
+
+        let mut txn = RocksDbTransactionBatch::new();
+        assert_eq!(
+            store
+                .get_tier_expirations(TierType::Pro, start, start + 5 * day_in_secs, &mut txn)
+                .unwrap()
+                .iter()
+                .map(|expiration| expiration.expires_at)
+                .collect::<Vec<_>>(),
+            vec![start + 4 * day_in_secs]
+        );
     }
 }
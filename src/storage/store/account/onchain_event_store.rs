@@ -1,13 +1,15 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use prost::{DecodeError, Message};
 
-use super::{get_from_db_or_txn, make_fid_key, StoreEventHandler};
+use super::{get_from_db_or_txn, make_fid_key, read_fid_key, StoreEventHandler};
 use crate::core::error::HubError;
 use crate::core::util::FarcasterTime;
 use crate::proto::{
     self, on_chain_event, IdRegisterEventBody, IdRegisterEventType, OnChainEvent, OnChainEventType,
-    SignerEventBody, SignerEventType, TierType,
+    SignerEventBody, SignerEventType, TierExpiredBody, TierPurchaseBody, TierPurchasePeriod,
+    TierStatus, TierType,
 };
 use crate::proto::{HubEvent, HubEventType, MergeOnChainEventBody};
 use crate::storage::constants::{OnChainEventPostfix, RootPrefix, PAGE_SIZE_MAX};
@@ -20,6 +22,7 @@ static PAGE_SIZE: usize = 1000;
 const LEGACY_STORAGE_UNIT_CUTOFF_TIMESTAMP: u32 = 1724889600;
 const ONE_YEAR_IN_SECONDS: u32 = 365 * 24 * 60 * 60;
 const SUPPORTED_SIGNER_KEY_TYPE: u32 = 1;
+const ONE_DAY_IN_SECONDS: u64 = 24 * 60 * 60;
 
 #[derive(Error, Debug)]
 pub enum OnchainEventStorageError {
@@ -114,6 +117,229 @@ fn make_signer_onchain_event_by_signer_key(fid: u64, key: Vec<u8>) -> Vec<u8> {
     signer_key
 }
 
+fn make_tier_expiry_by_fid_key(tier_type: i32, fid: u64) -> Vec<u8> {
+    let mut tier_expiry_key = vec![
+        RootPrefix::OnChainEvent as u8,
+        OnChainEventPostfix::TierExpiryByFid as u8,
+        tier_type as u8,
+    ];
+    tier_expiry_key.extend(make_fid_key(fid));
+    tier_expiry_key
+}
+
+fn make_tier_expiry_by_timestamp_prefix(tier_type: i32) -> Vec<u8> {
+    vec![
+        RootPrefix::OnChainEvent as u8,
+        OnChainEventPostfix::TierExpiryByTimestamp as u8,
+        tier_type as u8,
+    ]
+}
+
+fn make_tier_expiry_by_timestamp_key(tier_type: i32, expires_at: u64, fid: u64) -> Vec<u8> {
+    let mut tier_expiry_key = make_tier_expiry_by_timestamp_prefix(tier_type);
+    tier_expiry_key.extend(expires_at.to_be_bytes());
+    tier_expiry_key.extend(make_fid_key(fid));
+    tier_expiry_key
+}
+
+/** When a fid's tier expires, after all the purchases up to the latest one */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct TierExpiry {
+    expires_at: u64,
+    last_purchased_at: u64,
+}
+
+impl TierExpiry {
+    fn encode(&self) -> Vec<u8> {
+        let mut value = self.expires_at.to_be_bytes().to_vec();
+        value.extend(self.last_purchased_at.to_be_bytes());
+        value
+    }
+
+    fn decode(value: &[u8]) -> Option<TierExpiry> {
+        if value.len() != 16 {
+            return None;
+        }
+        Some(TierExpiry {
+            expires_at: u64::from_be_bytes(value[..8].try_into().unwrap()),
+            last_purchased_at: u64::from_be_bytes(value[8..].try_into().unwrap()),
+        })
+    }
+
+    // Days bought while the tier is active are stacked after the current expiry
+    fn add_purchase(&self, purchase: &OnChainEvent, body: &TierPurchaseBody) -> TierExpiry {
+        TierExpiry {
+            expires_at: purchase.block_timestamp.max(self.expires_at)
+                + body.for_days * ONE_DAY_IN_SECONDS,
+            last_purchased_at: purchase.block_timestamp.max(self.last_purchased_at),
+        }
+    }
+}
+
+fn tier_purchase_body(onchain_event: &OnChainEvent) -> Option<&TierPurchaseBody> {
+    match &onchain_event.body {
+        Some(on_chain_event::Body::TierPurchaseEventBody(body)) => Some(body),
+        _ => None,
+    }
+}
+
+/** The period each purchase pays for, purchases must be ordered by block */
+pub fn tier_purchase_periods(purchases: &[OnChainEvent]) -> Vec<TierPurchasePeriod> {
+    let mut expiry = TierExpiry::default();
+    purchases
+        .iter()
+        .filter_map(|purchase| {
+            let body = tier_purchase_body(purchase)?;
+            let starts_at = purchase.block_timestamp.max(expiry.expires_at);
+            expiry = expiry.add_purchase(purchase, body);
+            Some(TierPurchasePeriod {
+                purchase: Some(purchase.clone()),
+                starts_at,
+                expires_at: expiry.expires_at,
+            })
+        })
+        .collect()
+}
+
+fn get_tier_purchases(
+    db: &RocksDB,
+    tier_type: i32,
+    fid: u64,
+) -> Result<Vec<OnChainEvent>, OnchainEventStorageError> {
+    let mut purchases = vec![];
+    let mut next_page_token = None;
+    loop {
+        let page = get_onchain_events(
+            db,
+            &PageOptions {
+                page_size: Some(PAGE_SIZE),
+                page_token: next_page_token,
+                reverse: false,
+            },
+            OnChainEventType::EventTypeTierPurchase,
+            Some(fid),
+        )?;
+        purchases.extend(
+            page.onchain_events.into_iter().filter(|event| {
+                tier_purchase_body(event).is_some_and(|b| b.tier_type == tier_type)
+            }),
+        );
+        next_page_token = page.next_page_token;
+        if next_page_token.is_none() {
+            break;
+        }
+    }
+    Ok(purchases)
+}
+
+/** Like [get_tier_purchases], but also sees purchases merged earlier in [txn] that aren't committed yet. Ordered by block. */
+fn get_tier_purchases_in_txn(
+    db: &RocksDB,
+    txn: &RocksDbTransactionBatch,
+    tier_type: i32,
+    fid: u64,
+) -> Result<Vec<OnChainEvent>, OnchainEventStorageError> {
+    let mut purchases: BTreeMap<Vec<u8>, OnChainEvent> = get_tier_purchases(db, tier_type, fid)?
+        .into_iter()
+        .map(|purchase| (make_onchain_event_primary_key(&purchase), purchase))
+        .collect();
+    let mut prefix = make_onchain_event_type_prefix(OnChainEventType::EventTypeTierPurchase);
+    prefix.extend(make_fid_key(fid));
+    for (key, value) in txn.batch.iter() {
+        if !key.starts_with(&prefix) {
+            continue;
+        }
+        match value {
+            Some(value) => {
+                let purchase = OnChainEvent::decode(value.as_slice())?;
+                if tier_purchase_body(&purchase).is_some_and(|b| b.tier_type == tier_type) {
+                    purchases.insert(key.clone(), purchase);
+                }
+            }
+            None => {
+                purchases.remove(key);
+            }
+        }
+    }
+    // Primary keys sort by block number, then log index
+    Ok(purchases.into_values().collect())
+}
+
+fn compute_tier_expiry(purchases: &[OnChainEvent]) -> TierExpiry {
+    purchases
+        .iter()
+        .filter_map(|purchase| Some((purchase, tier_purchase_body(purchase)?)))
+        .fold(TierExpiry::default(), |expiry, (purchase, body)| {
+            expiry.add_purchase(purchase, body)
+        })
+}
+
+fn put_tier_expiry(
+    db: &RocksDB,
+    txn: &mut RocksDbTransactionBatch,
+    tier_type: i32,
+    fid: u64,
+    expiry: &TierExpiry,
+) -> Result<(), OnchainEventStorageError> {
+    let tier_expiry_key = make_tier_expiry_by_fid_key(tier_type, fid);
+    if let Some(existing) =
+        get_from_db_or_txn(db, txn, &tier_expiry_key)?.and_then(|value| TierExpiry::decode(&value))
+    {
+        txn.delete(make_tier_expiry_by_timestamp_key(
+            tier_type,
+            existing.expires_at,
+            fid,
+        ));
+    }
+    txn.put(
+        make_tier_expiry_by_timestamp_key(tier_type, expiry.expires_at, fid),
+        vec![],
+    );
+    txn.put(tier_expiry_key, expiry.encode());
+    Ok(())
+}
+
+/** Recomputes a fid's tier expiry from the purchases already in the db or the txn */
+pub fn rebuild_tier_expiry(
+    db: &RocksDB,
+    txn: &mut RocksDbTransactionBatch,
+    tier_type: i32,
+    fid: u64,
+) -> Result<(), OnchainEventStorageError> {
+    let purchases = get_tier_purchases_in_txn(db, txn, tier_type, fid)?;
+    if purchases.is_empty() {
+        return Ok(());
+    }
+    put_tier_expiry(db, txn, tier_type, fid, &compute_tier_expiry(&purchases))
+}
+
+fn build_secondary_indices_for_tier_purchase(
+    db: &RocksDB,
+    txn: &mut RocksDbTransactionBatch,
+    onchain_event: &OnChainEvent,
+    tier_purchase_body: &TierPurchaseBody,
+) -> Result<(), OnchainEventStorageError> {
+    let tier_type = tier_purchase_body.tier_type;
+    let tier_expiry_key = make_tier_expiry_by_fid_key(tier_type, onchain_event.fid);
+    let existing =
+        get_from_db_or_txn(db, txn, &tier_expiry_key)?.and_then(|value| TierExpiry::decode(&value));
+
+    let expiry = match existing {
+        Some(existing) if onchain_event.block_timestamp >= existing.last_purchased_at => {
+            existing.add_purchase(onchain_event, tier_purchase_body)
+        }
+        // The first purchase, or one that arrived out of order, replay them all. The event itself
+        // is already in the db or the txn.
+        _ => compute_tier_expiry(&get_tier_purchases_in_txn(
+            db,
+            txn,
+            tier_type,
+            onchain_event.fid,
+        )?),
+    };
+    put_tier_expiry(db, txn, tier_type, onchain_event.fid, &expiry)
+}
+
 fn build_secondary_indices_for_id_register(
     db: &RocksDB,
     txn: &mut RocksDbTransactionBatch,
@@ -210,7 +436,7 @@ fn build_secondary_indices_for_signer(
     Ok(())
 }
 
-fn build_secondary_indices(
+pub(crate) fn build_secondary_indices(
     db: &RocksDB,
     txn: &mut RocksDbTransactionBatch,
     onchain_event: &OnChainEvent,
@@ -228,9 +454,16 @@ fn build_secondary_indices(
             on_chain_event::Body::SignerEventBody(signer_event_body) => {
                 build_secondary_indices_for_signer(db, txn, onchain_event, signer_event_body)?
             }
+            on_chain_event::Body::TierPurchaseEventBody(tier_purchase_body) => {
+                build_secondary_indices_for_tier_purchase(
+                    db,
+                    txn,
+                    onchain_event,
+                    tier_purchase_body,
+                )?
+            }
             on_chain_event::Body::SignerMigratedEventBody(_)
-            | on_chain_event::Body::StorageRentEventBody(_)
-            | on_chain_event::Body::TierPurchaseEventBody(_) => {}
+            | on_chain_event::Body::StorageRentEventBody(_) => {}
         }
     };
 
@@ -482,6 +715,15 @@ impl OnchainEventStore {
         signer_event_body.key_type == SUPPORTED_SIGNER_KEY_TYPE
     }
 
+    pub fn get_onchain_events_page(
+        &self,
+        event_type: OnChainEventType,
+        fid: Option<u64>,
+        page_options: &PageOptions,
+    ) -> Result<OnchainEventsPage, OnchainEventStorageError> {
+        get_onchain_events(&self.db, page_options, event_type, fid)
+    }
+
     pub fn get_signers(
         &self,
         fid: Option<u64>,
@@ -555,44 +797,40 @@ impl OnchainEventStore {
         }
         Ok(None)
     }
+    fn get_tier_expiry(
+        &self,
+        tier_type: TierType,
+        fid: u64,
+    ) -> Result<Option<TierExpiry>, OnchainEventStorageError> {
+        let tier_expiry_key = make_tier_expiry_by_fid_key(tier_type as i32, fid);
+        Ok(self
+            .db
+            .get(&tier_expiry_key)?
+            .and_then(|value| TierExpiry::decode(&value)))
+    }
+
+    /** When the tier expires counting purchases made up to `as_of`, or all purchases if unset. 0 if it was never purchased. */
     pub fn tier_subscription_exires_at(
         &self,
         tier_type: TierType,
         fid: u64,
         as_of: Option<&FarcasterTime>,
     ) -> Result<u64, OnchainEventStorageError> {
-        // TODO(aditi): This is pretty expensive, we may want to add caching for fid -> tier expiration to speed up.
-        // Sorted by block timestamp
-        let tier_purchase_events = self
-            .get_onchain_events(OnChainEventType::EventTypeTierPurchase, Some(fid))?
-            .into_iter()
-            .filter(|event| match &event.body {
-                Some(on_chain_event::Body::TierPurchaseEventBody(body)) => {
-                    if body.tier_type == tier_type as i32 {
-                        true
-                    } else {
-                        false
-                    }
-                }
-                _ => false,
-            });
-
-        let mut expires_at = 0;
-        for tier_purchase in tier_purchase_events {
-            match tier_purchase.body.unwrap() {
-                on_chain_event::Body::TierPurchaseEventBody(body) => {
-                    if let Some(as_of) = as_of {
-                        if tier_purchase.block_timestamp > as_of.to_unix_seconds() {
-                            break;
-                        }
-                    };
-                    let extend_by = body.for_days * 24 * 60 * 60;
-                    expires_at = tier_purchase.block_timestamp.max(expires_at) + extend_by;
-                }
-                _ => {}
-            };
+        let Some(expiry) = self.get_tier_expiry(tier_type, fid)? else {
+            return Ok(0);
+        };
+        match as_of.map(|as_of| as_of.to_unix_seconds()) {
+            // Purchased again since, so replay the purchases made up to then
+            Some(as_of) if expiry.last_purchased_at > as_of => {
+                let purchases: Vec<OnChainEvent> =
+                    get_tier_purchases(&self.db, tier_type as i32, fid)?
+                        .into_iter()
+                        .filter(|purchase| purchase.block_timestamp <= as_of)
+                        .collect();
+                Ok(compute_tier_expiry(&purchases).expires_at)
+            }
+            _ => Ok(expiry.expires_at),
         }
-        Ok(expires_at)
     }
 
     pub fn is_tier_subscription_active_at(
@@ -605,6 +843,69 @@ impl OnchainEventStore {
         Ok(expires_at >= timestamp.to_unix_seconds())
     }
 
+    pub fn get_tier_status(
+        &self,
+        tier_type: TierType,
+        fid: u64,
+        as_of: &FarcasterTime,
+    ) -> Result<TierStatus, OnchainEventStorageError> {
+        let purchases =
+            tier_purchase_periods(&get_tier_purchases(&self.db, tier_type as i32, fid)?);
+        Ok(TierStatus {
+            tier_type: tier_type as i32,
+            active: self.is_tier_subscription_active_at(tier_type, fid, as_of)?,
+            expires_at: self.tier_subscription_exires_at(tier_type, fid, None)?,
+            purchases,
+        })
+    }
+
+    /** Tiers that expire at or after `from` and before `to`, in unix seconds. Renewals in the transaction are taken into account. */
+    pub fn get_tier_expirations(
+        &self,
+        tier_type: TierType,
+        from: u64,
+        to: u64,
+        txn: &mut RocksDbTransactionBatch,
+    ) -> Result<Vec<TierExpiredBody>, OnchainEventStorageError> {
+        if from >= to {
+            return Ok(vec![]);
+        }
+
+        let prefix = make_tier_expiry_by_timestamp_prefix(tier_type as i32);
+        let mut start_prefix = prefix.clone();
+        start_prefix.extend(from.to_be_bytes());
+        let mut stop_prefix = prefix.clone();
+        stop_prefix.extend(to.to_be_bytes());
+
+        let mut expirations = vec![];
+        self.db.for_each_iterator_by_prefix(
+            Some(start_prefix),
+            Some(stop_prefix),
+            &PageOptions::default(),
+            |key, _| {
+                expirations.push(TierExpiredBody {
+                    fid: read_fid_key(key, prefix.len() + 8),
+                    tier_type: tier_type as i32,
+                    expires_at: u64::from_be_bytes(
+                        key[prefix.len()..prefix.len() + 8].try_into().unwrap(),
+                    ),
+                });
+                Ok(false)
+            },
+        )?;
+
+        let mut current = vec![];
+        for expiration in expirations {
+            let tier_expiry_key = make_tier_expiry_by_fid_key(tier_type as i32, expiration.fid);
+            let expiry = get_from_db_or_txn(&self.db, txn, &tier_expiry_key)?
+                .and_then(|value| TierExpiry::decode(&value));
+            if expiry.is_some_and(|expiry| expiry.expires_at == expiration.expires_at) {
+                current.push(expiration);
+            }
+        }
+        Ok(current)
+    }
+
     pub fn get_storage_slot_for_fid(
         &self,
         fid: u64,
@@ -2,7 +2,7 @@ use super::{
     get_many_messages, make_cast_id_key, make_fid_key, make_message_primary_key, make_user_key,
     read_fid_key, read_ts_hash,
     store::{Store, StoreDef},
-    MessagesPage, StoreEventHandler, PAGE_SIZE_MAX, TS_HASH_LENGTH,
+    CastCountType, CastStore, MessagesPage, StoreEventHandler, PAGE_SIZE_MAX, TS_HASH_LENGTH,
 };
 use crate::{core::error::HubError, proto::SignatureScheme};
 use crate::{proto::message_data::Body, storage::db::PageOptions};
@@ -22,6 +22,7 @@ use std::{borrow::Borrow, sync::Arc};
 
 #[derive(Clone)]
 pub struct ReactionStoreDef {
+    db: Arc<RocksDB>,
     prune_size_limit: u32,
 }
 
@@ -84,7 +85,7 @@ impl StoreDef for ReactionStoreDef {
 
         txn.put(by_target_key, vec![rtype]);
 
-        Ok(())
+        self.update_cast_count(txn, message, 1)
     }
 
     #[inline]
@@ -98,7 +99,7 @@ impl StoreDef for ReactionStoreDef {
 
         txn.delete(by_target_key);
 
-        Ok(())
+        self.update_cast_count(txn, message, -1)
     }
 
     #[inline]
@@ -189,6 +190,27 @@ impl ReactionStoreDef {
         Ok((by_target_key, reaction_body.r#type as u8))
     }
 
+    fn update_cast_count(
+        &self,
+        txn: &mut RocksDbTransactionBatch,
+        message: &Message,
+        delta: i64,
+    ) -> Result<(), HubError> {
+        if let Some(Body::ReactionBody(reaction_body)) =
+            message.data.as_ref().and_then(|data| data.body.as_ref())
+        {
+            let count_type = match reaction_body.r#type() {
+                ReactionType::Like => CastCountType::Likes,
+                ReactionType::Recast => CastCountType::Recasts,
+                _ => return Ok(()),
+            };
+            if let Some(Target::TargetCastId(cast_id)) = &reaction_body.target {
+                return CastStore::update_cast_count(&self.db, txn, cast_id, count_type, delta);
+            }
+        }
+        Ok(())
+    }
+
     pub fn make_reactions_by_target_key(
         target: &Target,
         fid: u64,
@@ -277,9 +299,12 @@ impl ReactionStore {
         prune_size_limit: u32,
     ) -> Store<ReactionStoreDef> {
         Store::new_with_store_def(
-            db,
+            db.clone(),
             store_event_handler,
-            ReactionStoreDef { prune_size_limit },
+            ReactionStoreDef {
+                db,
+                prune_size_limit,
+            },
         )
     }
 
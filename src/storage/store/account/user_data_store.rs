@@ -1,8 +1,8 @@
 use super::{
     is_message_in_time_range, make_user_key,
     name_registry_events::{
-        delete_username_proof_transaction, get_fname_proof_by_fid, get_username_proof,
-        put_username_proof_transaction,
+        delete_username_proof_transaction, get_fname_proof_by_fid, get_fname_proofs_by_prefix,
+        get_username_proof, put_username_proof_transaction,
     },
     store::{Store, StoreDef},
     MessagesPage, StoreEventHandler,
@@ -241,6 +241,15 @@ impl UserDataStore {
         get_fname_proof_by_fid(&store.db(), fid)
     }
 
+    pub fn get_username_proofs_by_prefix(
+        store: &Store<UserDataStoreDef>,
+        prefix: &[u8],
+        after: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<UserNameProof>, HubError> {
+        get_fname_proofs_by_prefix(&store.db(), prefix, after, limit)
+    }
+
     pub fn merge_username_proof(
         store: &Store<UserDataStoreDef>,
         username_proof: &UserNameProof,
@@ -431,4 +431,39 @@ impl UsernameProofStore {
 
         store.get_add(&partial_message)
     }
+
+    /**
+     * ENS and basename proofs in name order, for names that start with [prefix] and sort after
+     * [after].
+     */
+    pub fn get_username_proofs_by_prefix(
+        store: &Store<UsernameProofStoreDef>,
+        prefix: &[u8],
+        after: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Message>, HubError> {
+        let key_prefix = UsernameProofStoreDef::make_username_proof_by_name_key(&prefix.to_vec());
+        let after = after
+            .map(|name| UsernameProofStoreDef::make_username_proof_by_name_key(&name.to_vec()));
+        let (start, stop) = util::prefix_range_after(&key_prefix, after.as_deref());
+
+        let mut names = vec![];
+        store.db().for_each_iterator_by_prefix(
+            Some(start),
+            Some(stop),
+            &PageOptions::default(),
+            |key, value| {
+                names.push((key[1..].to_vec(), read_fid_key(value, 0)));
+                Ok(names.len() >= limit)
+            },
+        )?;
+
+        let mut messages = vec![];
+        for (name, fid) in names {
+            if let Some(message) = Self::get_username_proof_by_fid_and_name(store, &name, fid)? {
+                messages.push(message);
+            }
+        }
+        Ok(messages)
+    }
 }
@@ -1,5 +1,5 @@
 use super::{
-    make_fid_key, make_user_key,
+    make_fid_key, make_user_key, read_fid_key,
     store::{Store, StoreDef},
     MessagesPage, StoreEventHandler, TS_HASH_LENGTH,
 };
@@ -315,4 +315,27 @@ impl VerificationStore {
     ) -> Result<MessagesPage, HubError> {
         store.get_removes_by_fid::<fn(&Message) -> bool>(fid, page_options, None)
     }
+
+    /**
+     * Looks up the fid that verified an address in the byAddress index. The verification is read
+     * back so addresses verified under another protocol are not returned.
+     */
+    pub fn get_fid_by_verified_address(
+        store: &Store<VerificationStoreDef>,
+        protocol: Protocol,
+        address: &[u8],
+    ) -> Result<Option<u64>, HubError> {
+        let by_address_key = VerificationStoreDef::make_verification_by_address_key(address);
+        let fid = match store.db().get(&by_address_key)? {
+            Some(fid_key) => read_fid_key(&fid_key, 0),
+            None => return Ok(None),
+        };
+        let verification = Self::get_verification_add(store, fid, address)?;
+        let verified =
+            verification.is_some_and(|message| match message.data.and_then(|data| data.body) {
+                Some(Body::VerificationAddAddressBody(body)) => body.protocol == protocol as i32,
+                _ => false,
+            });
+        Ok(if verified { Some(fid) } else { None })
+    }
 }
@@ -14,8 +14,10 @@ use crate::proto::{FarcasterNetwork, HubEvent};
 use crate::proto::{HubEventType, Protocol};
 use crate::proto::{OnChainEvent, OnChainEventType};
 use crate::storage::db::{PageOptions, RocksDB, RocksDbTransactionBatch};
-use crate::storage::store::account::{CastStore, MessagesPage, VerificationStore};
-use crate::storage::store::stores::{StoreLimits, Stores};
+use crate::storage::store::account::{
+    CastStore, HubEventIdGenerator, MessagesPage, VerificationStore,
+};
+use crate::storage::store::stores::{IndexConfig, StoreLimits, Stores};
 use crate::storage::store::BlockStore;
 use crate::storage::trie;
 use crate::storage::trie::merkle_trie;
@@ -38,6 +40,16 @@ use tokio::sync::{broadcast, mpsc};
 use tokio::time::timeout;
 use tracing::{debug, error, info, warn};
 
+// One message type per store, used to prune all of a fid's stores down to its storage limits
+const PRUNABLE_MESSAGE_TYPES: [MessageType; 6] = [
+    MessageType::CastAdd,
+    MessageType::LinkAdd,
+    MessageType::ReactionAdd,
+    MessageType::UserDataAdd,
+    MessageType::VerificationAddEthAddress,
+    MessageType::UsernameProof,
+];
+
 #[derive(Error, Debug)]
 pub enum EngineError {
     #[error(transparent)]
@@ -189,6 +201,7 @@ impl ShardEngine {
         trie: merkle_trie::MerkleTrie,
         shard_id: u32,
         store_limits: StoreLimits,
+        index_config: IndexConfig,
         statsd_client: StatsdClientWrapper,
         max_messages_per_block: u32,
         messages_request_tx: Option<mpsc::Sender<MempoolMessagesRequest>>,
@@ -203,6 +216,7 @@ impl ShardEngine {
                 shard_id,
                 trie,
                 store_limits,
+                index_config,
                 statsd_client.clone(),
             ),
             senders: Senders::new(),
@@ -483,7 +497,15 @@ impl ShardEngine {
                     }
                     None => "".to_string(),
                 };
-            summary += fname_transfer_summary.as_str()
+            summary += fname_transfer_summary.as_str();
+
+            let storage_prune_summary = match &message.storage_prune {
+                Some(storage_prune) => {
+                    format!("message_type: storage_prune, fid: {}\n", storage_prune.fid)
+                }
+                None => "".to_string(),
+            };
+            summary += storage_prune_summary.as_str()
         }
         summary
     }
@@ -724,6 +746,25 @@ impl ShardEngine {
                     }
                 }
             }
+            if let Some(storage_prune) = &msg.storage_prune {
+                if !version.is_enabled(ProtocolFeature::StorageLimitPruning) {
+                    warn!("Saw storage prune while feature isn't active");
+                    continue;
+                }
+                if storage_prune.fid != snapchain_txn.fid {
+                    if source != ProposalSource::Simulate {
+                        warn!(
+                            fid = snapchain_txn.fid,
+                            prune_fid = storage_prune.fid,
+                            "Storage prune fid does not match transaction fid"
+                        );
+                    }
+                    continue;
+                }
+                // Pruned along with the message types merged in this transaction below
+                message_types.extend(PRUNABLE_MESSAGE_TYPES);
+                system_messages_count += 1;
+            }
         }
 
         for key in revoked_signers {
@@ -1172,6 +1213,9 @@ impl ShardEngine {
             Some(proto::hub_event::Body::BlockConfirmedBody(_)) => {
                 // BLOCK_CONFIRMED events don't affect the trie state.
             }
+            Some(proto::hub_event::Body::TierExpiredBody(_)) => {
+                // Tier expiries are derived from onchain events already in the trie
+            }
             &None => {
                 // This should never happen
                 panic!("No body in event");
@@ -1410,6 +1454,7 @@ impl ShardEngine {
     ) {
         let header = shard_chunk.header.as_ref().unwrap();
         let height = header.height.as_ref().unwrap();
+        events.extend(self.tier_expired_events(header, &mut txn));
         let mut event_counts_by_type = Self::compute_event_counts_by_type(&events);
         event_counts_by_type.insert(HubEventType::BlockConfirmed as i32, 1);
         let mut block_confirmed = HubEvent::from(
@@ -1450,6 +1495,57 @@ impl ShardEngine {
         self.time_with_shard("commit_time", elapsed.as_millis() as u64);
     }
 
+    // Tiers that expired between the previous block and this one
+    fn tier_expired_events(
+        &self,
+        header: &proto::ShardHeader,
+        txn: &mut RocksDbTransactionBatch,
+    ) -> Vec<HubEvent> {
+        let timestamp = FarcasterTime::new(header.timestamp);
+        if !self
+            .version_for(&timestamp)
+            .is_enabled(ProtocolFeature::TierExpiryEvents)
+        {
+            return vec![];
+        }
+        let Some(previous_header) = self
+            .get_last_shard_chunk()
+            .and_then(|shard_chunk| shard_chunk.header)
+        else {
+            return vec![];
+        };
+        let expirations = match self.stores.onchain_event_store.get_tier_expirations(
+            proto::TierType::Pro,
+            FarcasterTime::new(previous_header.timestamp).to_unix_seconds(),
+            timestamp.to_unix_seconds(),
+            txn,
+        ) {
+            Ok(expirations) => expirations,
+            Err(err) => {
+                error!("Unable to get tier expirations: {:?}", err);
+                return vec![];
+            }
+        };
+
+        let mut events = vec![];
+        for expiration in expirations {
+            let mut event = HubEvent::from(
+                HubEventType::TierExpired,
+                hub_event::Body::TierExpiredBody(expiration),
+            );
+            match self
+                .stores
+                .event_handler
+                .commit_transaction(txn, &mut event)
+            {
+                Ok(_) => events.push(event),
+                Err(err) => error!("Unable to commit tier expired event: {:?}", err),
+            }
+        }
+        self.count("tier_expired_events", events.len() as u64);
+        events
+    }
+
     fn emit_commit_metrics(&mut self, shard_chunk: &&ShardChunk) -> Result<(), EngineError> {
         self.count("commit.invoked", 1);
 
@@ -1617,6 +1713,70 @@ impl ShardEngine {
         }
     }
 
+    /**
+     * Stores a historical shard chunk without executing it or its transactions. Used by archive
+     * nodes to backfill pruned history.
+     */
+    pub fn backfill_shard_chunk(&mut self, shard_chunk: &ShardChunk) -> bool {
+        let executed_start_height = match self.backfilled_below() {
+            Some(height) => Ok(height.block_number),
+            None => self.first_executed_height(),
+        };
+        let executed_start_height = match executed_start_height {
+            Ok(height) => height,
+            Err(err) => {
+                error!("Unable to find the earliest executed height {}", err);
+                return false;
+            }
+        };
+        match self
+            .stores
+            .shard_store
+            .put_backfilled_shard_chunk(shard_chunk, executed_start_height)
+        {
+            Ok(()) => {
+                self.count("backfill.shard_chunks", 1);
+                true
+            }
+            Err(err) => {
+                error!("Unable to write backfilled shard chunk to store {}", err);
+                false
+            }
+        }
+    }
+
+    /** Chunks below this height were backfilled and have no transactions or events */
+    pub fn backfilled_below(&self) -> Option<Height> {
+        match self.stores.shard_store.get_executed_start_height() {
+            Ok(height) => height.map(|height| Height::new(self.shard_id, height)),
+            Err(err) => {
+                error!("Unable to obtain executed start height {}", err);
+                None
+            }
+        }
+    }
+
+    // The earliest stored chunk, or the earliest stored event if older events were pruned
+    fn first_executed_height(&self) -> Result<u64, HubError> {
+        let first_event = self.stores.get_events(
+            0,
+            None,
+            Some(PageOptions {
+                page_size: Some(1),
+                ..PageOptions::default()
+            }),
+        )?;
+        let first_event_height = first_event
+            .events
+            .first()
+            .map(|event| HubEventIdGenerator::extract_height_and_seq(event.id).0)
+            .unwrap_or(0);
+        Ok(std::cmp::max(
+            self.get_min_height().block_number,
+            first_event_height,
+        ))
+    }
+
     pub fn get_last_shard_chunk(&self) -> Option<ShardChunk> {
         match self.stores.shard_store.get_last_shard_chunk() {
             Ok(shard_chunk) => shard_chunk,
@@ -1842,6 +2002,20 @@ impl BlockEngine {
         }
     }
 
+    /** Stores a historical block without processing it. Used by archive nodes to backfill pruned history. */
+    pub fn backfill_block(&mut self, block: &Block) -> bool {
+        match self.block_store.put_block(block) {
+            Ok(()) => {
+                self.count("backfill.blocks", 1);
+                true
+            }
+            Err(err) => {
+                error!("Failed to store backfilled block: {:?}", err);
+                false
+            }
+        }
+    }
+
     pub fn get_last_block(&self) -> Option<Block> {
         match self.block_store.get_last_block() {
             Ok(block) => block,
@@ -10,7 +10,7 @@ mod tests {
     use crate::storage::db::{PageOptions, RocksDbTransactionBatch};
     use crate::storage::store::account::{HubEventIdGenerator, UserDataStore};
     use crate::storage::store::engine::{MempoolMessage, ShardEngine};
-    use crate::storage::store::stores::StoreLimits;
+    use crate::storage::store::stores::{Limits, StoreLimits};
     use crate::storage::store::test_helper::{
         self, commit_event, commit_event_at, commit_message_at, commit_messages,
         default_custody_address, key_exists_in_trie, EngineOptions, FID3_FOR_TEST,
@@ -223,6 +223,7 @@ mod tests {
             vec![MempoolMessage::ValidatorMessage(ValidatorMessage {
                 on_chain_event: Some(events_factory::create_onchain_event(FID_FOR_TEST)),
                 fname_transfer: None,
+                storage_prune: None,
             })],
             None,
         );
@@ -1307,6 +1308,7 @@ mod tests {
             vec![MempoolMessage::ValidatorMessage(ValidatorMessage {
                 on_chain_event: Some(onchain_event.clone()),
                 fname_transfer: None,
+                storage_prune: None,
             })],
             None,
         );
@@ -1553,6 +1555,135 @@ mod tests {
         assert_eq!(message_exists_in_trie(&mut engine, &cast5), true);
     }
 
+    fn storage_prune(fid: u64) -> MempoolMessage {
+        MempoolMessage::ValidatorMessage(ValidatorMessage {
+            on_chain_event: None,
+            fname_transfer: None,
+            storage_prune: Some(proto::StoragePrune {
+                fid,
+                timestamp: 0,
+                block_number: 0,
+            }),
+        })
+    }
+
+    fn pruned_hashes(events: &[HubEvent]) -> Vec<String> {
+        events
+            .iter()
+            .filter_map(|event| match &event.body {
+                Some(proto::hub_event::Body::PruneMessageBody(body)) => {
+                    Some(to_hex(&body.message.as_ref().unwrap().hash))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_storage_prune_enforces_reduced_limits() {
+        let (mut engine, _first_tmpdir) = test_helper::new_engine();
+        test_helper::register_user(
+            FID_FOR_TEST,
+            test_helper::default_signer(),
+            test_helper::default_custody_address(),
+            &mut engine,
+        )
+        .await;
+        let current_time = factory::time::farcaster_time();
+        let casts = (0..4)
+            .map(|i| {
+                messages_factory::casts::create_cast_add(
+                    FID_FOR_TEST,
+                    &format!("msg{}", i),
+                    Some(current_time + i),
+                    None,
+                )
+            })
+            .collect::<Vec<_>>();
+        commit_messages(&mut engine, casts.clone()).await;
+        assert!(!engine
+            .get_stores()
+            .is_over_storage_limits(FID_FOR_TEST)
+            .unwrap());
+
+        // Simulate the fid's storage shrinking (e.g. rent expiring) by reopening with lower limits
+        let (mut engine, _tmpdir) = test_helper::new_engine_with_options(EngineOptions {
+            limits: Some(StoreLimits {
+                limits: Limits {
+                    casts: 2,
+                    ..test_helper::limits::test()
+                },
+                legacy_limits: test_helper::limits::zero(),
+            }),
+            db: Some(engine.db.clone()),
+            ..EngineOptions::default()
+        });
+        assert!(engine
+            .get_stores()
+            .is_over_storage_limits(FID_FOR_TEST)
+            .unwrap());
+        let (over_limit_fids, _) = engine
+            .get_stores()
+            .get_fids_over_storage_limits(&PageOptions::default())
+            .unwrap();
+        assert_eq!(over_limit_fids, vec![FID_FOR_TEST]);
+
+        // The oldest messages are pruned
+        let state_change = engine.propose_state_change(1, vec![storage_prune(FID_FOR_TEST)], None);
+        test_helper::validate_and_commit_state_change(&mut engine, &state_change);
+        assert_eq!(
+            pruned_hashes(&state_change.events),
+            vec![to_hex(&casts[0].hash), to_hex(&casts[1].hash)]
+        );
+        assert_eq!(message_exists_in_trie(&mut engine, &casts[0]), false);
+        assert_eq!(message_exists_in_trie(&mut engine, &casts[1]), false);
+        assert_eq!(message_exists_in_trie(&mut engine, &casts[2]), true);
+        assert_eq!(message_exists_in_trie(&mut engine, &casts[3]), true);
+        assert!(!engine
+            .get_stores()
+            .is_over_storage_limits(FID_FOR_TEST)
+            .unwrap());
+
+        // Pruning again is a no-op
+        let state_change = engine.propose_state_change(1, vec![storage_prune(FID_FOR_TEST)], None);
+        assert!(pruned_hashes(&state_change.events).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_storage_prune_ignored_before_feature_activation() {
+        let (mut engine, _first_tmpdir) = test_helper::new_engine_with_options(EngineOptions {
+            network: Some(FarcasterNetwork::Testnet),
+            ..EngineOptions::default()
+        });
+        test_helper::register_user(
+            FID_FOR_TEST,
+            test_helper::default_signer(),
+            test_helper::default_custody_address(),
+            &mut engine,
+        )
+        .await;
+        let cast1 = messages_factory::casts::create_cast_add(FID_FOR_TEST, "msg1", None, None);
+        commit_message(&mut engine, &cast1).await;
+
+        let (mut engine, _tmpdir) = test_helper::new_engine_with_options(EngineOptions {
+            limits: Some(StoreLimits {
+                limits: Limits {
+                    casts: 0,
+                    ..test_helper::limits::test()
+                },
+                legacy_limits: test_helper::limits::zero(),
+            }),
+            db: Some(engine.db.clone()),
+            network: Some(FarcasterNetwork::Testnet),
+            ..EngineOptions::default()
+        });
+
+        let state_change = engine.propose_state_change(1, vec![storage_prune(FID_FOR_TEST)], None);
+        assert!(pruned_hashes(&state_change.events).is_empty());
+        test_helper::validate_and_commit_state_change(&mut engine, &state_change);
+        assert_eq!(message_exists_in_trie(&mut engine, &cast1), true);
+    }
+
     #[tokio::test]
     async fn test_messages_partially_merged_with_insufficient_storage() {
         let (mut engine, _tmpdir) = test_helper::new_engine();
@@ -1819,6 +1950,7 @@ mod tests {
             vec![MempoolMessage::ValidatorMessage(ValidatorMessage {
                 on_chain_event: None,
                 fname_transfer: Some(fname_transfer.clone()),
+                storage_prune: None,
             })],
             None,
         );
@@ -1877,6 +2009,7 @@ mod tests {
             vec![MempoolMessage::ValidatorMessage(ValidatorMessage {
                 on_chain_event: None,
                 fname_transfer: Some(fname_transfer.clone()),
+                storage_prune: None,
             })],
             None,
         );
@@ -1914,6 +2047,7 @@ mod tests {
             vec![MempoolMessage::ValidatorMessage(ValidatorMessage {
                 on_chain_event: None,
                 fname_transfer: Some(fname_transfer.clone()),
+                storage_prune: None,
             })],
             None,
         );
@@ -2389,6 +2523,7 @@ mod tests {
             vec![MempoolMessage::ValidatorMessage(proto::ValidatorMessage {
                 on_chain_event: None,
                 fname_transfer: Some(fname_transfer),
+                storage_prune: None,
             })],
             None,
         );
@@ -2917,4 +3052,53 @@ mod tests {
             tokio::time::timeout(std::time::Duration::from_millis(100), event_rx.recv()).await;
         assert!(timeout_result.is_err()); // Should timeout, no more events
     }
+
+    #[tokio::test]
+    async fn test_tier_expired_event() {
+        let (mut engine, _tmpdir) = test_helper::new_engine();
+        let now = FarcasterTime::current();
+        let day_in_secs = 24 * 60 * 60;
+        let pro_event = events_factory::create_pro_user_event(
+            FID_FOR_TEST,
+            1,
+            Some((now.to_unix_seconds() - day_in_secs - 100) as u32),
+        );
+        commit_event_at(&mut engine, &pro_event, &now.decr_by(day_in_secs)).await;
+
+        let mut event_rx = engine.get_senders().events_tx.subscribe();
+        let state_change = engine.propose_state_change(1, vec![], Some(now.clone()));
+        test_helper::validate_and_commit_state_change(&mut engine, &state_change);
+
+        let event = event_rx.recv().await.unwrap();
+        if let Some(proto::hub_event::Body::BlockConfirmedBody(body)) = &event.body {
+            assert_eq!(body.total_events, 2);
+            assert_eq!(
+                body.event_counts_by_type[&(HubEventType::TierExpired as i32)],
+                1
+            );
+        } else {
+            panic!("Expected BlockConfirmedBody");
+        }
+        let event = event_rx.recv().await.unwrap();
+        assert_eq!(event.r#type, HubEventType::TierExpired as i32);
+        assert_eq!(
+            event.body,
+            Some(proto::hub_event::Body::TierExpiredBody(
+                proto::TierExpiredBody {
+                    fid: FID_FOR_TEST,
+                    tier_type: proto::TierType::Pro as i32,
+                    expires_at: pro_event.block_timestamp + day_in_secs,
+                }
+            ))
+        );
+
+        // Only emitted once
+        let state_change = engine.propose_state_change(1, vec![], Some(now.incr_by(1)));
+        test_helper::validate_and_commit_state_change(&mut engine, &state_change);
+        let event = event_rx.recv().await.unwrap();
+        assert_eq!(event.r#type, HubEventType::BlockConfirmed as i32);
+        let timeout_result =
+            tokio::time::timeout(std::time::Duration::from_millis(100), event_rx.recv()).await;
+        assert!(timeout_result.is_err());
+    }
 }
@@ -0,0 +1,626 @@
+use crate::core::error::HubError;
+use crate::proto::{cast_add_body, message_data, reaction_body, MessageType, ReactionType};
+use crate::proto::{on_chain_event, OnChainEvent, OnChainEventType};
+use crate::storage::constants::{OnChainEventPostfix, RootPrefix, UserPostfix};
+use crate::storage::db::{PageOptions, RocksDB, RocksDbTransactionBatch, RocksdbError};
+use crate::storage::store::account::{
+    build_secondary_indices, message_decode, read_fid_key, rebuild_tier_expiry, CastCountType,
+    CastStore, LinkStore, OnchainEventStorageError, FID_BYTES, TS_HASH_LENGTH,
+};
+use crate::storage::util::increment_vec_u8;
+use prost::Message;
+use std::collections::BTreeSet;
+use std::fmt;
+use thiserror::Error;
+use tracing::info;
+
+/** Schema version written to newly created databases. Bump this when adding a migration. */
+pub const LATEST_SCHEMA_VERSION: u32 = 5;
+
+/** First schema version that stores data in column families */
+const COLUMN_FAMILIES_SCHEMA_VERSION: u32 = 2;
+
+const MIGRATION_BATCH_SIZE: usize = 10_000;
+
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error(transparent)]
+    RocksdbError(#[from] RocksdbError),
+
+    #[error(transparent)]
+    HubError(#[from] HubError),
+
+    #[error(transparent)]
+    OnchainEventStorageError(#[from] OnchainEventStorageError),
+
+    #[error(
+        "{db_kind} has schema version {found}, but this node only supports up to version {supported}"
+    )]
+    IncompatibleSchemaVersion {
+        db_kind: DbKind,
+        found: u32,
+        supported: u32,
+    },
+
+    #[error("Invalid schema version record")]
+    InvalidSchemaVersion,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DbKind {
+    Global,
+    Shard(u32),
+}
+
+impl fmt::Display for DbKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbKind::Global => write!(f, "global db"),
+            DbKind::Shard(shard_id) => write!(f, "shard {} db", shard_id),
+        }
+    }
+}
+
+enum SchemaPostfix {
+    Version = 1,
+    MigrationCursor = 2,
+}
+
+fn make_schema_version_key() -> Vec<u8> {
+    vec![
+        RootPrefix::DBSchemaVersion as u8,
+        SchemaPostfix::Version as u8,
+    ]
+}
+
+fn make_migration_cursor_key(version: u32) -> Vec<u8> {
+    let mut key = vec![
+        RootPrefix::DBSchemaVersion as u8,
+        SchemaPostfix::MigrationCursor as u8,
+    ];
+    key.extend_from_slice(&version.to_be_bytes());
+    key
+}
+
+/** The result of running a single batch of a migration */
+pub struct MigrationBatch {
+    pub txn: RocksDbTransactionBatch,
+    pub processed: u64,
+    /** The last key processed, or None if the migration is complete */
+    pub next_cursor: Option<Vec<u8>>,
+}
+
+/**
+ * A migration runs in batches. Each batch is committed together with the cursor it returned, so
+ * a migration that is interrupted (crash, restart) resumes from the last committed batch.
+ */
+pub trait Migration {
+    fn version(&self) -> u32;
+
+    fn description(&self) -> &'static str;
+
+    fn applies_to(&self, db_kind: DbKind) -> bool;
+
+    fn run_batch(
+        &self,
+        db: &RocksDB,
+        cursor: Option<Vec<u8>>,
+    ) -> Result<MigrationBatch, MigrationError>;
+
+    /** Called once the migration has been committed */
+    fn on_complete(&self, _db: &RocksDB) {}
+}
+
+/** All migrations, ordered by version. Versions must be contiguous starting from 1. */
+pub fn migrations() -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(BackfillOnchainEventIndexes {}),
+        Box::new(MoveToColumnFamilies {}),
+        Box::new(BackfillLinkCounts {}),
+        Box::new(BackfillCastCounts {}),
+        Box::new(BackfillTierExpiries {}),
+    ]
+}
+
+pub fn get_schema_version(db: &RocksDB) -> Result<Option<u32>, MigrationError> {
+    match db.get(&make_schema_version_key())? {
+        Some(value) => {
+            let bytes: [u8; 4] = value
+                .as_slice()
+                .try_into()
+                .map_err(|_| MigrationError::InvalidSchemaVersion)?;
+            Ok(Some(u32::from_be_bytes(bytes)))
+        }
+        None => Ok(None),
+    }
+}
+
+fn put_schema_version(txn: &mut RocksDbTransactionBatch, version: u32) {
+    txn.put(make_schema_version_key(), version.to_be_bytes().to_vec());
+}
+
+/**
+ * Bring the db up to [LATEST_SCHEMA_VERSION]. New databases are stamped with the latest version,
+ * databases created before versioning was introduced are treated as version 0. Databases written
+ * by a newer node are refused.
+ */
+pub fn run_migrations(db: &RocksDB, db_kind: DbKind) -> Result<(), MigrationError> {
+    let current_version = match get_schema_version(db)? {
+        Some(version) => version,
+        None => {
+            if db.is_empty() {
+                let mut txn = RocksDbTransactionBatch::new();
+                put_schema_version(&mut txn, LATEST_SCHEMA_VERSION);
+                db.commit(txn)?;
+                info!(
+                    %db_kind,
+                    version = LATEST_SCHEMA_VERSION,
+                    "Initialized db schema version"
+                );
+                return Ok(());
+            }
+            0
+        }
+    };
+
+    if current_version > LATEST_SCHEMA_VERSION {
+        return Err(MigrationError::IncompatibleSchemaVersion {
+            db_kind,
+            found: current_version,
+            supported: LATEST_SCHEMA_VERSION,
+        });
+    }
+
+    if current_version == LATEST_SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    // Until the data has been moved into column families, everything lives in the default one
+    db.set_legacy_layout(current_version < COLUMN_FAMILIES_SCHEMA_VERSION);
+
+    info!(
+        %db_kind,
+        from = current_version,
+        to = LATEST_SCHEMA_VERSION,
+        "Migrating db schema"
+    );
+
+    for migration in migrations()
+        .iter()
+        .filter(|migration| migration.version() > current_version)
+    {
+        run_migration(db, db_kind, migration.as_ref())?;
+    }
+
+    Ok(())
+}
+
+fn run_migration(
+    db: &RocksDB,
+    db_kind: DbKind,
+    migration: &dyn Migration,
+) -> Result<(), MigrationError> {
+    let version = migration.version();
+    let cursor_key = make_migration_cursor_key(version);
+    let start = std::time::Instant::now();
+
+    if !migration.applies_to(db_kind) {
+        let mut txn = RocksDbTransactionBatch::new();
+        put_schema_version(&mut txn, version);
+        db.commit(txn)?;
+        migration.on_complete(db);
+        return Ok(());
+    }
+
+    let mut cursor = db.get(&cursor_key)?;
+    if cursor.is_some() {
+        info!(
+            %db_kind,
+            version,
+            description = migration.description(),
+            "Resuming db migration"
+        );
+    } else {
+        info!(
+            %db_kind,
+            version,
+            description = migration.description(),
+            "Starting db migration"
+        );
+    }
+
+    let mut processed = 0;
+    loop {
+        let batch = migration.run_batch(db, cursor)?;
+        let mut txn = batch.txn;
+        processed += batch.processed;
+
+        match batch.next_cursor {
+            Some(next_cursor) => {
+                txn.put(cursor_key.clone(), next_cursor.clone());
+                db.commit(txn)?;
+                cursor = Some(next_cursor);
+                info!(%db_kind, version, processed, "Db migration progress");
+            }
+            None => {
+                txn.delete(cursor_key.clone());
+                put_schema_version(&mut txn, version);
+                db.commit(txn)?;
+                break;
+            }
+        }
+    }
+    migration.on_complete(db);
+
+    info!(
+        %db_kind,
+        version,
+        processed,
+        time_taken = ?start.elapsed(),
+        "Completed db migration"
+    );
+    Ok(())
+}
+
+/**
+ * Rebuild the IdRegisterByFid and SignerByFid secondary indexes from the stored onchain events.
+ * Merging is idempotent, so this is safe to run on dbs that already have the indexes populated.
+ */
+pub struct BackfillOnchainEventIndexes {}
+
+impl Migration for BackfillOnchainEventIndexes {
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn description(&self) -> &'static str {
+        "Backfill onchain event secondary indexes"
+    }
+
+    fn applies_to(&self, db_kind: DbKind) -> bool {
+        match db_kind {
+            DbKind::Global => false,
+            DbKind::Shard(shard_id) => shard_id != 0,
+        }
+    }
+
+    fn run_batch(
+        &self,
+        db: &RocksDB,
+        cursor: Option<Vec<u8>>,
+    ) -> Result<MigrationBatch, MigrationError> {
+        let start_prefix = vec![
+            RootPrefix::OnChainEvent as u8,
+            OnChainEventPostfix::OnChainEvents as u8,
+        ];
+        let stop_prefix = increment_vec_u8(&start_prefix);
+
+        let mut txn = RocksDbTransactionBatch::new();
+        let mut processed = 0;
+        let mut last_key = None;
+        let mut onchain_events = vec![];
+        db.for_each_iterator_by_prefix_paged(
+            Some(start_prefix),
+            Some(stop_prefix),
+            &PageOptions {
+                page_size: Some(MIGRATION_BATCH_SIZE),
+                page_token: cursor,
+                reverse: false,
+            },
+            |key, value| {
+                let onchain_event = OnChainEvent::decode(value).map_err(|e| HubError::from(e))?;
+                onchain_events.push(onchain_event);
+                last_key = Some(key.to_vec());
+                Ok(false)
+            },
+        )?;
+
+        for onchain_event in onchain_events.iter() {
+            match &onchain_event.body {
+                Some(on_chain_event::Body::IdRegisterEventBody(_))
+                | Some(on_chain_event::Body::SignerEventBody(_)) => {
+                    build_secondary_indices(db, &mut txn, onchain_event)?;
+                }
+                _ => {}
+            }
+            processed += 1;
+        }
+
+        let next_cursor = if onchain_events.len() < MIGRATION_BATCH_SIZE {
+            None
+        } else {
+            last_key
+        };
+
+        Ok(MigrationBatch {
+            txn,
+            processed,
+            next_cursor,
+        })
+    }
+}
+
+/**
+ * Move data written before column families existed out of the default column family. Each key is
+ * copied and deleted in one transaction, so re-running a partially completed batch is harmless.
+ */
+pub struct MoveToColumnFamilies {}
+
+impl Migration for MoveToColumnFamilies {
+    fn version(&self) -> u32 {
+        COLUMN_FAMILIES_SCHEMA_VERSION
+    }
+
+    fn description(&self) -> &'static str {
+        "Move data into column families"
+    }
+
+    fn applies_to(&self, _db_kind: DbKind) -> bool {
+        true
+    }
+
+    fn run_batch(
+        &self,
+        db: &RocksDB,
+        cursor: Option<Vec<u8>>,
+    ) -> Result<MigrationBatch, MigrationError> {
+        let (processed, next_cursor) = db.move_to_column_families(cursor, MIGRATION_BATCH_SIZE)?;
+        Ok(MigrationBatch {
+            txn: RocksDbTransactionBatch::new(),
+            processed,
+            next_cursor,
+        })
+    }
+
+    fn on_complete(&self, db: &RocksDB) {
+        db.set_legacy_layout(false);
+    }
+}
+
+/**
+ * Count the links that were merged before link counts were kept, from the links by target index.
+ * Counts are only written by this migration's batches until it completes, so each link is counted
+ * exactly once.
+ */
+pub struct BackfillLinkCounts {}
+
+impl Migration for BackfillLinkCounts {
+    fn version(&self) -> u32 {
+        3
+    }
+
+    fn description(&self) -> &'static str {
+        "Backfill link counts"
+    }
+
+    fn applies_to(&self, db_kind: DbKind) -> bool {
+        match db_kind {
+            DbKind::Global => false,
+            DbKind::Shard(shard_id) => shard_id != 0,
+        }
+    }
+
+    fn run_batch(
+        &self,
+        db: &RocksDB,
+        cursor: Option<Vec<u8>>,
+    ) -> Result<MigrationBatch, MigrationError> {
+        let start_prefix = vec![RootPrefix::LinksByTarget as u8];
+        let stop_prefix = increment_vec_u8(&start_prefix);
+
+        let mut links = vec![];
+        let mut last_key = None;
+        db.for_each_iterator_by_prefix_paged(
+            Some(start_prefix),
+            Some(stop_prefix),
+            &PageOptions {
+                page_size: Some(MIGRATION_BATCH_SIZE),
+                page_token: cursor,
+                reverse: false,
+            },
+            |key, value| {
+                // <RootPrefix>:<target fid>:<tsHash>:<fid> -> link type
+                let target_fid = read_fid_key(key, 1);
+                let fid = read_fid_key(key, 1 + FID_BYTES + TS_HASH_LENGTH);
+                links.push((fid, target_fid, String::from_utf8_lossy(value).to_string()));
+                last_key = Some(key.to_vec());
+                Ok(false)
+            },
+        )?;
+
+        let mut txn = RocksDbTransactionBatch::new();
+        for (fid, target_fid, link_type) in links.iter() {
+            LinkStore::update_link_counts(db, &mut txn, *fid, *target_fid, link_type, 1)?;
+        }
+
+        let next_cursor = if links.len() < MIGRATION_BATCH_SIZE {
+            None
+        } else {
+            last_key
+        };
+
+        Ok(MigrationBatch {
+            txn,
+            processed: links.len() as u64,
+            next_cursor,
+        })
+    }
+}
+
+/**
+ * Count the likes, recasts and replies merged before cast counts were kept. Only messages in the
+ * add sets are stored, so every reaction add and cast add found is counted.
+ */
+pub struct BackfillCastCounts {}
+
+impl Migration for BackfillCastCounts {
+    fn version(&self) -> u32 {
+        4
+    }
+
+    fn description(&self) -> &'static str {
+        "Backfill cast counts"
+    }
+
+    fn applies_to(&self, db_kind: DbKind) -> bool {
+        match db_kind {
+            DbKind::Global => false,
+            DbKind::Shard(shard_id) => shard_id != 0,
+        }
+    }
+
+    fn run_batch(
+        &self,
+        db: &RocksDB,
+        cursor: Option<Vec<u8>>,
+    ) -> Result<MigrationBatch, MigrationError> {
+        let start_prefix = vec![RootPrefix::User as u8];
+        let stop_prefix = increment_vec_u8(&start_prefix);
+        // <RootPrefix>:<fid>:<UserPostfix>:<tsHash>
+        let message_key_length = 1 + FID_BYTES + 1 + TS_HASH_LENGTH;
+
+        let mut scanned = 0;
+        let mut messages = vec![];
+        let mut last_key = None;
+        db.for_each_iterator_by_prefix_paged(
+            Some(start_prefix),
+            Some(stop_prefix),
+            &PageOptions {
+                page_size: Some(MIGRATION_BATCH_SIZE),
+                page_token: cursor,
+                reverse: false,
+            },
+            |key, value| {
+                scanned += 1;
+                last_key = Some(key.to_vec());
+                if key.len() == message_key_length
+                    && (key[1 + FID_BYTES] == UserPostfix::CastMessage as u8
+                        || key[1 + FID_BYTES] == UserPostfix::ReactionMessage as u8)
+                {
+                    messages.push(message_decode(value)?);
+                }
+                Ok(false)
+            },
+        )?;
+
+        let mut txn = RocksDbTransactionBatch::new();
+        let mut processed = 0;
+        for message in messages.iter() {
+            // Removes are stored in the same key range, they are not counted
+            if message.msg_type() != MessageType::CastAdd
+                && message.msg_type() != MessageType::ReactionAdd
+            {
+                continue;
+            }
+            let (cast_id, count_type) = match message.data.as_ref().and_then(|d| d.body.as_ref()) {
+                Some(message_data::Body::CastAddBody(body)) => match &body.parent {
+                    Some(cast_add_body::Parent::ParentCastId(parent)) => {
+                        (parent, CastCountType::Replies)
+                    }
+                    _ => continue,
+                },
+                Some(message_data::Body::ReactionBody(body)) => {
+                    let count_type = match body.r#type() {
+                        ReactionType::Like => CastCountType::Likes,
+                        ReactionType::Recast => CastCountType::Recasts,
+                        _ => continue,
+                    };
+                    match &body.target {
+                        Some(reaction_body::Target::TargetCastId(cast_id)) => (cast_id, count_type),
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+            CastStore::update_cast_count(db, &mut txn, cast_id, count_type, 1)?;
+            processed += 1;
+        }
+
+        let next_cursor = if scanned < MIGRATION_BATCH_SIZE {
+            None
+        } else {
+            last_key
+        };
+
+        Ok(MigrationBatch {
+            txn,
+            processed,
+            next_cursor,
+        })
+    }
+}
+
+/**
+ * Compute the tier expiry of every fid that purchased a tier before expiries were tracked. Expiries
+ * are recomputed from all of a fid's purchases, so fids spanning batches are handled.
+ */
+pub struct BackfillTierExpiries {}
+
+impl Migration for BackfillTierExpiries {
+    fn version(&self) -> u32 {
+        5
+    }
+
+    fn description(&self) -> &'static str {
+        "Backfill tier expiries"
+    }
+
+    fn applies_to(&self, db_kind: DbKind) -> bool {
+        match db_kind {
+            DbKind::Global => false,
+            DbKind::Shard(shard_id) => shard_id != 0,
+        }
+    }
+
+    fn run_batch(
+        &self,
+        db: &RocksDB,
+        cursor: Option<Vec<u8>>,
+    ) -> Result<MigrationBatch, MigrationError> {
+        let start_prefix = vec![
+            RootPrefix::OnChainEvent as u8,
+            OnChainEventPostfix::OnChainEvents as u8,
+            OnChainEventType::EventTypeTierPurchase as u8,
+        ];
+        let stop_prefix = increment_vec_u8(&start_prefix);
+
+        let mut scanned = 0;
+        let mut last_key = None;
+        let mut tiers = BTreeSet::new();
+        db.for_each_iterator_by_prefix_paged(
+            Some(start_prefix),
+            Some(stop_prefix),
+            &PageOptions {
+                page_size: Some(MIGRATION_BATCH_SIZE),
+                page_token: cursor,
+                reverse: false,
+            },
+            |key, value| {
+                scanned += 1;
+                last_key = Some(key.to_vec());
+                let onchain_event = OnChainEvent::decode(value).map_err(|e| HubError::from(e))?;
+                if let Some(on_chain_event::Body::TierPurchaseEventBody(body)) = &onchain_event.body
+                {
+                    tiers.insert((body.tier_type, onchain_event.fid));
+                }
+                Ok(false)
+            },
+        )?;
+
+        let mut txn = RocksDbTransactionBatch::new();
+        for (tier_type, fid) in tiers.iter() {
+            rebuild_tier_expiry(db, &mut txn, *tier_type, *fid)?;
+        }
+
+        let next_cursor = if scanned < MIGRATION_BATCH_SIZE {
+            None
+        } else {
+            last_key
+        };
+        Ok(MigrationBatch {
+            txn,
+            processed: tiers.len() as u64,
+            next_cursor,
+        })
+    }
+}
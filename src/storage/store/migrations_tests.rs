@@ -0,0 +1,352 @@
+#[cfg(test)]
+mod tests {
+    use crate::proto::{self, ReactionType, SignerEventType, TierType};
+    use crate::storage::constants::{OnChainEventPostfix, RootPrefix};
+    use crate::storage::db::{self, RocksDB, RocksDbTransactionBatch};
+    use crate::storage::store::account::{
+        CastStore, LinkCountDirection, LinkStore, OnchainEventStore, ReactionStore,
+        StoreEventHandler,
+    };
+    use crate::storage::store::migrations::{
+        get_schema_version, migrations, run_migrations, DbKind, MigrationError,
+        LATEST_SCHEMA_VERSION,
+    };
+    use crate::utils::factory::{events_factory, messages_factory, signers};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    fn db() -> (Arc<RocksDB>, TempDir) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("a.db");
+
+        let db = db::RocksDB::new(db_path.to_str().unwrap());
+        db.open().unwrap();
+
+        (Arc::new(db), dir)
+    }
+
+    fn is_onchain_index_key(key: &Vec<u8>) -> bool {
+        key.len() > 1
+            && key[0] == RootPrefix::OnChainEvent as u8
+            && (key[1] == OnChainEventPostfix::SignerByFid as u8
+                || key[1] == OnChainEventPostfix::IdRegisterByFid as u8)
+    }
+
+    #[test]
+    fn test_migration_versions_are_contiguous() {
+        let migrations = migrations();
+        for (i, migration) in migrations.iter().enumerate() {
+            assert_eq!(migration.version(), i as u32 + 1);
+        }
+        assert_eq!(
+            migrations.last().map(|m| m.version()).unwrap_or(0),
+            LATEST_SCHEMA_VERSION
+        );
+    }
+
+    #[test]
+    fn test_new_db_is_stamped_with_latest_version() {
+        let (db, _dir) = db();
+        assert_eq!(get_schema_version(&db).unwrap(), None);
+
+        run_migrations(&db, DbKind::Shard(1)).unwrap();
+        assert_eq!(
+            get_schema_version(&db).unwrap(),
+            Some(LATEST_SCHEMA_VERSION)
+        );
+
+        // Running again is a no-op
+        run_migrations(&db, DbKind::Shard(1)).unwrap();
+        assert_eq!(
+            get_schema_version(&db).unwrap(),
+            Some(LATEST_SCHEMA_VERSION)
+        );
+    }
+
+    #[test]
+    fn test_newer_schema_version_is_refused() {
+        let (db, _dir) = db();
+        db.put(
+            &[RootPrefix::DBSchemaVersion as u8, 1],
+            &(LATEST_SCHEMA_VERSION + 1).to_be_bytes(),
+        )
+        .unwrap();
+
+        let result = run_migrations(&db, DbKind::Global);
+        assert!(matches!(
+            result,
+            Err(MigrationError::IncompatibleSchemaVersion { found, .. }) if found == LATEST_SCHEMA_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn test_legacy_db_backfills_onchain_event_indexes() {
+        let (db, _dir) = db();
+        // Dbs written before versioning also predate column families
+        db.set_legacy_layout(true);
+        let store = OnchainEventStore::new(db.clone(), StoreEventHandler::new());
+
+        let signer = signers::generate_signer();
+        let id_register_event = events_factory::create_id_register_event(
+            10,
+            crate::proto::IdRegisterEventType::Register,
+            vec![1, 2, 3],
+            None,
+        );
+        let signer_event = events_factory::create_signer_event(
+            10,
+            signer.clone(),
+            SignerEventType::Add,
+            None,
+            None,
+        );
+
+        // Simulate a db written before the indexes existed by dropping the index keys
+        let mut txn = RocksDbTransactionBatch::new();
+        store
+            .merge_onchain_event(id_register_event.clone(), &mut txn)
+            .unwrap();
+        store
+            .merge_onchain_event(signer_event.clone(), &mut txn)
+            .unwrap();
+        txn.batch.retain(|key, _| !is_onchain_index_key(key));
+        db.commit(txn).unwrap();
+
+        let signer_key = signer.verifying_key().as_bytes().to_vec();
+        assert!(store
+            .get_active_signer(10, signer_key.clone())
+            .unwrap()
+            .is_none());
+        assert!(store.get_id_register_event_by_fid(10).unwrap().is_none());
+
+        run_migrations(&db, DbKind::Shard(1)).unwrap();
+
+        assert_eq!(
+            get_schema_version(&db).unwrap(),
+            Some(LATEST_SCHEMA_VERSION)
+        );
+        assert_eq!(
+            store.get_active_signer(10, signer_key).unwrap(),
+            Some(signer_event)
+        );
+        assert_eq!(
+            store.get_id_register_event_by_fid(10).unwrap(),
+            Some(id_register_event)
+        );
+    }
+
+    #[test]
+    fn test_legacy_db_is_moved_to_column_families() {
+        let (db, _dir) = db();
+        let user_key = vec![RootPrefix::User as u8, 1, 2, 3];
+        let event_key = vec![RootPrefix::HubEvents as u8, 4, 5, 6];
+        let local_state_key = vec![RootPrefix::NodeLocalState as u8, 1];
+
+        db.set_legacy_layout(true);
+        db.put(&user_key, b"user").unwrap();
+        db.put(&event_key, b"event").unwrap();
+        db.put(&local_state_key, b"state").unwrap();
+        db.set_legacy_layout(false);
+
+        // Not visible in their column families yet
+        assert_eq!(db.get(&user_key).unwrap(), None);
+        assert_eq!(db.get(&event_key).unwrap(), None);
+
+        run_migrations(&db, DbKind::Global).unwrap();
+
+        assert_eq!(
+            get_schema_version(&db).unwrap(),
+            Some(LATEST_SCHEMA_VERSION)
+        );
+        assert_eq!(db.get(&user_key).unwrap(), Some(b"user".to_vec()));
+        assert_eq!(db.get(&event_key).unwrap(), Some(b"event".to_vec()));
+        assert_eq!(db.get(&local_state_key).unwrap(), Some(b"state".to_vec()));
+    }
+
+    #[test]
+    fn test_column_family_move_resumes_at_keys_extending_the_cursor() {
+        let (db, _dir) = db();
+        let node_key = vec![RootPrefix::SyncMerkleTrieNode as u8, 1];
+        let child_key = vec![RootPrefix::SyncMerkleTrieNode as u8, 1, 2];
+        let sibling_key = vec![RootPrefix::SyncMerkleTrieNode as u8, 2];
+
+        db.set_legacy_layout(true);
+        db.put(&node_key, b"node").unwrap();
+        db.put(&child_key, b"child").unwrap();
+        db.put(&sibling_key, b"sibling").unwrap();
+        db.set_legacy_layout(false);
+
+        // Each batch ends on a key that the next key extends
+        let (processed, cursor) = db.move_to_column_families(None, 1).unwrap();
+        assert_eq!((processed, cursor.clone()), (1, Some(node_key.clone())));
+        let (processed, cursor) = db.move_to_column_families(cursor, 1).unwrap();
+        assert_eq!((processed, cursor.clone()), (1, Some(child_key.clone())));
+        let (_, cursor) = db.move_to_column_families(cursor, 1).unwrap();
+        db.move_to_column_families(cursor, 1).unwrap();
+
+        assert_eq!(db.get(&node_key).unwrap(), Some(b"node".to_vec()));
+        assert_eq!(db.get(&child_key).unwrap(), Some(b"child".to_vec()));
+        assert_eq!(db.get(&sibling_key).unwrap(), Some(b"sibling".to_vec()));
+    }
+
+    #[test]
+    fn test_link_counts_are_backfilled() {
+        let (db, _dir) = db();
+        // A db at the version before link counts were kept
+        db.put(&[RootPrefix::DBSchemaVersion as u8, 1], &2u32.to_be_bytes())
+            .unwrap();
+        let store = LinkStore::new(db.clone(), StoreEventHandler::new(), 100);
+
+        let mut txn = RocksDbTransactionBatch::new();
+        for (fid, target_fid) in [(1, 10), (2, 10), (3, 10), (1, 11)] {
+            let link =
+                messages_factory::links::create_link_add(fid, "follow", target_fid, None, None);
+            store.merge(&link, &mut txn).unwrap();
+        }
+        txn.batch
+            .retain(|key, _| key[0] != RootPrefix::LinkCounts as u8);
+        db.commit(txn).unwrap();
+
+        let count =
+            |fid, direction| LinkStore::get_link_count(&store, fid, direction, "follow").unwrap();
+        assert_eq!(count(10, LinkCountDirection::ByTarget), 0);
+
+        run_migrations(&db, DbKind::Shard(1)).unwrap();
+
+        assert_eq!(
+            get_schema_version(&db).unwrap(),
+            Some(LATEST_SCHEMA_VERSION)
+        );
+        assert_eq!(count(10, LinkCountDirection::ByTarget), 3);
+        assert_eq!(count(11, LinkCountDirection::ByTarget), 1);
+        assert_eq!(count(1, LinkCountDirection::ByFid), 2);
+        assert_eq!(count(2, LinkCountDirection::ByFid), 1);
+        assert_eq!(count(10, LinkCountDirection::ByFid), 0);
+    }
+
+    #[test]
+    fn test_cast_counts_are_backfilled() {
+        let (db, _dir) = db();
+        // A db at the version before cast counts were kept
+        db.put(&[RootPrefix::DBSchemaVersion as u8, 1], &3u32.to_be_bytes())
+            .unwrap();
+        let cast_store = CastStore::new(db.clone(), StoreEventHandler::new(), 100, false);
+        let reaction_store = ReactionStore::new(db.clone(), StoreEventHandler::new(), 100);
+
+        let cast = messages_factory::casts::create_cast_add(1, "test", None, None);
+        let timestamp = cast.data.as_ref().unwrap().timestamp;
+        let cast_id = proto::CastId {
+            fid: 1,
+            hash: cast.hash.clone(),
+        };
+        let reaction = |fid, message_type, reaction_type| {
+            messages_factory::reactions::create_cast_reaction(
+                fid,
+                message_type,
+                reaction_type,
+                cast_id.clone(),
+                Some(timestamp + 1),
+                None,
+            )
+        };
+
+        let mut txn = RocksDbTransactionBatch::new();
+        cast_store.merge(&cast, &mut txn).unwrap();
+        for fid in [2, 3] {
+            let reply = messages_factory::casts::create_cast_with_parent(
+                fid, "reply", 1, &cast.hash, None, None,
+            );
+            cast_store.merge(&reply, &mut txn).unwrap();
+        }
+        for (fid, message_type, reaction_type) in [
+            (2, proto::MessageType::ReactionAdd, ReactionType::Like),
+            (3, proto::MessageType::ReactionAdd, ReactionType::Like),
+            (3, proto::MessageType::ReactionAdd, ReactionType::Recast),
+            // Removes are not counted
+            (4, proto::MessageType::ReactionRemove, ReactionType::Like),
+        ] {
+            reaction_store
+                .merge(&reaction(fid, message_type, reaction_type), &mut txn)
+                .unwrap();
+        }
+        txn.batch
+            .retain(|key, _| key[0] != RootPrefix::CastCounts as u8);
+        db.commit(txn).unwrap();
+        assert_eq!(
+            CastStore::get_cast_stats(&cast_store, &cast_id)
+                .unwrap()
+                .likes,
+            0
+        );
+
+        run_migrations(&db, DbKind::Shard(1)).unwrap();
+
+        assert_eq!(
+            get_schema_version(&db).unwrap(),
+            Some(LATEST_SCHEMA_VERSION)
+        );
+        assert_eq!(
+            CastStore::get_cast_stats(&cast_store, &cast_id).unwrap(),
+            proto::CastStats {
+                cast_id: Some(cast_id.clone()),
+                likes: 2,
+                recasts: 1,
+                replies: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_tier_expiries_are_backfilled() {
+        let (db, _dir) = db();
+        // A db at the version before tier expiries were kept
+        db.put(&[RootPrefix::DBSchemaVersion as u8, 1], &4u32.to_be_bytes())
+            .unwrap();
+        let store = OnchainEventStore::new(db.clone(), StoreEventHandler::new());
+        let day_in_secs = 24 * 60 * 60;
+        let start = 1_700_000_000u64;
+
+        let mut txn = RocksDbTransactionBatch::new();
+        for (fid, for_days, block_timestamp) in [
+            (10, 1, start),
+            (10, 1, start + day_in_secs / 2),
+            (11, 1, start),
+        ] {
+            let event =
+                events_factory::create_pro_user_event(fid, for_days, Some(block_timestamp as u32));
+            store.merge_onchain_event(event, &mut txn).unwrap();
+        }
+        txn.batch.retain(|key, _| {
+            !(key[0] == RootPrefix::OnChainEvent as u8
+                && (key[1] == OnChainEventPostfix::TierExpiryByFid as u8
+                    || key[1] == OnChainEventPostfix::TierExpiryByTimestamp as u8))
+        });
+        db.commit(txn).unwrap();
+
+        let expirations = || {
+            store
+                .get_tier_expirations(
+                    TierType::Pro,
+                    start,
+                    start + 3 * day_in_secs,
+                    &mut RocksDbTransactionBatch::new(),
+                )
+                .unwrap()
+                .iter()
+                .map(|expiration| (expiration.fid, expiration.expires_at))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(expirations(), vec![]);
+
+        run_migrations(&db, DbKind::Shard(1)).unwrap();
+
+        assert_eq!(
+            get_schema_version(&db).unwrap(),
+            Some(LATEST_SCHEMA_VERSION)
+        );
+        assert_eq!(
+            expirations(),
+            vec![(11, start + day_in_secs), (10, start + 2 * day_in_secs)]
+        );
+    }
+}
@@ -3,6 +3,7 @@ pub use self::block::*;
 pub mod account;
 pub mod block;
 pub mod engine;
+pub mod migrations;
 pub mod node_local_state;
 pub mod shard;
 pub mod stores;
@@ -13,6 +14,8 @@ pub mod test_helper;
 #[cfg(test)]
 mod engine_tests;
 #[cfg(test)]
+mod migrations_tests;
+#[cfg(test)]
 mod node_local_state_tests;
 #[cfg(test)]
 mod stores_test;
@@ -1,10 +1,13 @@
 use std::sync::Arc;
 
 use crate::core::error::HubError;
+use crate::proto::EnsResolution;
+use crate::proto::FinalizedBlock;
 use crate::proto::FnameState;
 use crate::proto::FullProposal;
 use crate::proto::Height;
 use crate::proto::OnChainEventState;
+use crate::proto::QuarantinedFnameTransfer;
 use crate::storage::constants::RootPrefix;
 use crate::storage::db::PageOptions;
 use crate::storage::db::RocksDB;
@@ -37,12 +40,29 @@ pub enum DataType {
     OptimismOnchainEvent = 1,
     FnameTransfer = 2,
     BaseOnchainEvent = 3,
+    QuarantinedFnameTransfer = 4,
+    EnsResolution = 5,
+    MissingFnameTransfer = 6,
+    ChainOnchainEvent = 7,
 }
 
-#[derive(Clone, Copy, strum_macros::Display)]
-pub enum Chain {
-    Optimism = 1,
-    Base = 2,
+/** A chain onchain events are indexed from, keyed by its chain id */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Chain(pub u32);
+
+impl Chain {
+    pub const OPTIMISM: Chain = Chain(10);
+    pub const BASE: Chain = Chain(8453);
+}
+
+impl std::fmt::Display for Chain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Chain::OPTIMISM => write!(f, "Optimism"),
+            Chain::BASE => write!(f, "Base"),
+            Chain(chain_id) => write!(f, "chain_{}", chain_id),
+        }
+    }
 }
 
 impl LocalStateStore {
@@ -51,13 +71,25 @@ impl LocalStateStore {
     }
 
     fn make_onchain_event_primary_key(chain: Chain) -> Vec<u8> {
-        vec![
-            RootPrefix::NodeLocalState as u8,
-            match chain {
-                Chain::Optimism => DataType::OptimismOnchainEvent as u8,
-                Chain::Base => DataType::BaseOnchainEvent as u8,
-            },
-        ]
+        // Optimism and Base mainnet keep the keys they were stored under before chains were configurable
+        match chain {
+            Chain::OPTIMISM => vec![
+                RootPrefix::NodeLocalState as u8,
+                DataType::OptimismOnchainEvent as u8,
+            ],
+            Chain::BASE => vec![
+                RootPrefix::NodeLocalState as u8,
+                DataType::BaseOnchainEvent as u8,
+            ],
+            Chain(chain_id) => {
+                let mut key = vec![
+                    RootPrefix::NodeLocalState as u8,
+                    DataType::ChainOnchainEvent as u8,
+                ];
+                key.extend_from_slice(&chain_id.to_be_bytes());
+                key
+            }
+        }
     }
 
     fn make_proposal_key(shard_index: u32, height: u64, round: i64) -> Vec<u8> {
@@ -121,25 +153,35 @@ impl LocalStateStore {
         }
     }
 
-    pub fn set_latest_block_number(
+    pub fn set_finalized_block(
         &self,
         chain: Chain,
-        block_number: u64,
+        finalized_block: FinalizedBlock,
     ) -> Result<(), LocalStateError> {
         Ok(self.db.put(
             &Self::make_onchain_event_primary_key(chain),
             &OnChainEventState {
-                last_l2_block: block_number,
+                // Still written so that older nodes can resume from the same block
+                last_l2_block: finalized_block.block_number,
+                finalized_block: Some(finalized_block),
             }
             .encode_to_vec(),
         )?)
     }
 
-    pub fn get_latest_block_number(&self, chain: Chain) -> Result<Option<u64>, LocalStateError> {
+    /** Returns the last block whose events have been submitted. State written before block hashes were tracked has an empty hash. */
+    pub fn get_finalized_block(
+        &self,
+        chain: Chain,
+    ) -> Result<Option<FinalizedBlock>, LocalStateError> {
         match self.db.get(&Self::make_onchain_event_primary_key(chain))? {
-            Some(state) => Ok(Some(
-                OnChainEventState::decode(state.as_slice())?.last_l2_block,
-            )),
+            Some(state) => {
+                let state = OnChainEventState::decode(state.as_slice())?;
+                Ok(Some(state.finalized_block.unwrap_or(FinalizedBlock {
+                    block_number: state.last_l2_block,
+                    block_hash: vec![],
+                })))
+            }
             None => Ok(None),
         }
     }
@@ -167,4 +209,136 @@ impl LocalStateStore {
             None => Ok(None),
         }
     }
+
+    fn make_quarantined_fname_transfer_key(transfer_id: u64) -> Vec<u8> {
+        let mut key = vec![
+            RootPrefix::NodeLocalState as u8,
+            DataType::QuarantinedFnameTransfer as u8,
+        ];
+        key.extend_from_slice(&transfer_id.to_be_bytes());
+        key
+    }
+
+    /** Keeps a transfer that failed verification in the connector, so it can be inspected without being submitted */
+    pub fn put_quarantined_fname_transfer(
+        &self,
+        quarantined: &QuarantinedFnameTransfer,
+    ) -> Result<(), LocalStateError> {
+        let transfer_id = quarantined
+            .transfer
+            .as_ref()
+            .map_or(0, |transfer| transfer.id);
+        Ok(self.db.put(
+            &Self::make_quarantined_fname_transfer_key(transfer_id),
+            &quarantined.encode_to_vec(),
+        )?)
+    }
+
+    pub fn get_quarantined_fname_transfers(
+        &self,
+    ) -> Result<Vec<QuarantinedFnameTransfer>, LocalStateError> {
+        let start_prefix = vec![
+            RootPrefix::NodeLocalState as u8,
+            DataType::QuarantinedFnameTransfer as u8,
+        ];
+        let stop_prefix = increment_vec_u8(&start_prefix);
+        let mut quarantined = vec![];
+        self.db.for_each_iterator_by_prefix(
+            Some(start_prefix),
+            Some(stop_prefix),
+            &PageOptions::default(),
+            |_, value| {
+                quarantined.push(QuarantinedFnameTransfer::decode(value)?);
+                Ok(false)
+            },
+        )?;
+        Ok(quarantined)
+    }
+
+    fn make_missing_fname_transfer_key(transfer_id: u64) -> Vec<u8> {
+        let mut key = vec![
+            RootPrefix::NodeLocalState as u8,
+            DataType::MissingFnameTransfer as u8,
+        ];
+        key.extend_from_slice(&transfer_id.to_be_bytes());
+        key
+    }
+
+    /** Records transfer ids that no source returned, so they can be fetched again later */
+    pub fn put_missing_fname_transfer(&self, transfer_id: u64) -> Result<(), LocalStateError> {
+        Ok(self
+            .db
+            .put(&Self::make_missing_fname_transfer_key(transfer_id), &[])?)
+    }
+
+    pub fn delete_missing_fname_transfer(&self, transfer_id: u64) -> Result<(), LocalStateError> {
+        Ok(self
+            .db
+            .del(&Self::make_missing_fname_transfer_key(transfer_id))?)
+    }
+
+    /** Missing transfer ids in ascending order */
+    pub fn get_missing_fname_transfers(&self) -> Result<Vec<u64>, LocalStateError> {
+        let start_prefix = vec![
+            RootPrefix::NodeLocalState as u8,
+            DataType::MissingFnameTransfer as u8,
+        ];
+        let stop_prefix = increment_vec_u8(&start_prefix);
+        let mut transfer_ids = vec![];
+        self.db.for_each_iterator_by_prefix(
+            Some(start_prefix),
+            Some(stop_prefix),
+            &PageOptions::default(),
+            |key, _| {
+                let id_bytes: [u8; 8] = key[2..].try_into().map_err(|_| {
+                    HubError::invalid_internal_state("invalid missing fname transfer key")
+                })?;
+                transfer_ids.push(u64::from_be_bytes(id_bytes));
+                Ok(false)
+            },
+        )?;
+        Ok(transfer_ids)
+    }
+
+    fn make_ens_resolution_key(chain_id: u32, name: &str) -> Vec<u8> {
+        let mut key = vec![
+            RootPrefix::NodeLocalState as u8,
+            DataType::EnsResolution as u8,
+        ];
+        key.extend_from_slice(&chain_id.to_be_bytes());
+        key.extend_from_slice(name.as_bytes());
+        key
+    }
+
+    pub fn put_ens_resolution(
+        &self,
+        chain_id: u32,
+        name: &str,
+        resolution: &EnsResolution,
+    ) -> Result<(), LocalStateError> {
+        Ok(self.db.put(
+            &Self::make_ens_resolution_key(chain_id, name),
+            &resolution.encode_to_vec(),
+        )?)
+    }
+
+    pub fn get_ens_resolution(
+        &self,
+        chain_id: u32,
+        name: &str,
+    ) -> Result<Option<EnsResolution>, LocalStateError> {
+        match self
+            .db
+            .get(&Self::make_ens_resolution_key(chain_id, name))?
+        {
+            None => Ok(None),
+            Some(value) => Ok(Some(EnsResolution::decode(value.as_slice())?)),
+        }
+    }
+
+    pub fn delete_ens_resolution(&self, chain_id: u32, name: &str) -> Result<(), LocalStateError> {
+        Ok(self
+            .db
+            .del(&Self::make_ens_resolution_key(chain_id, name))?)
+    }
 }
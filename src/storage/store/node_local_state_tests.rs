@@ -1,8 +1,10 @@
 #[cfg(test)]
 mod tests {
-    use crate::proto::{FullProposal, Height};
+    use crate::proto::{EnsResolution, FinalizedBlock, FullProposal, Height, OnChainEventState};
+    use crate::storage::constants::RootPrefix;
     use crate::storage::db;
-    use crate::storage::store::node_local_state::LocalStateStore;
+    use crate::storage::store::node_local_state::{Chain, DataType, LocalStateStore};
+    use prost::Message;
     use std::sync::Arc;
 
     fn store() -> LocalStateStore {
@@ -79,4 +81,96 @@ mod tests {
         assert_proposal_exists(&store, &proposal3);
         assert_proposal_exists(&store, &proposal4);
     }
+
+    #[test]
+    fn test_finalized_block() {
+        let store = store();
+        assert!(store
+            .get_finalized_block(Chain::OPTIMISM)
+            .unwrap()
+            .is_none());
+
+        let finalized_block = FinalizedBlock {
+            block_number: 100,
+            block_hash: vec![1; 32],
+        };
+        store
+            .set_finalized_block(Chain::OPTIMISM, finalized_block.clone())
+            .unwrap();
+        assert_eq!(
+            store.get_finalized_block(Chain::OPTIMISM).unwrap(),
+            Some(finalized_block)
+        );
+        assert!(store.get_finalized_block(Chain::BASE).unwrap().is_none());
+
+        // Other chains are keyed by their chain id
+        let sepolia = Chain(11155420);
+        assert!(store.get_finalized_block(sepolia).unwrap().is_none());
+        store
+            .set_finalized_block(sepolia, finalized_block.clone())
+            .unwrap();
+        assert_eq!(
+            store.get_finalized_block(sepolia).unwrap(),
+            Some(finalized_block)
+        );
+        assert!(store.get_finalized_block(Chain(84532)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_finalized_block_from_legacy_state() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = db::RocksDB::new(dir.path().join("a.db").to_str().unwrap());
+        db.open().unwrap();
+        let db = Arc::new(db);
+
+        db.put(
+            &[
+                RootPrefix::NodeLocalState as u8,
+                DataType::OptimismOnchainEvent as u8,
+            ],
+            &OnChainEventState {
+                last_l2_block: 42,
+                finalized_block: None,
+            }
+            .encode_to_vec(),
+        )
+        .unwrap();
+
+        let store = LocalStateStore::new(db);
+        assert_eq!(
+            store.get_finalized_block(Chain::OPTIMISM).unwrap(),
+            Some(FinalizedBlock {
+                block_number: 42,
+                block_hash: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn test_ens_resolutions() {
+        let store = store();
+        let resolution = EnsResolution {
+            address: vec![1; 20],
+            resolved_at: 100,
+        };
+        store
+            .put_ens_resolution(1, "alice.eth", &resolution)
+            .unwrap();
+
+        assert_eq!(
+            store.get_ens_resolution(1, "alice.eth").unwrap(),
+            Some(resolution)
+        );
+        assert!(store
+            .get_ens_resolution(8453, "alice.eth")
+            .unwrap()
+            .is_none());
+        assert!(store
+            .get_ens_resolution(1, "alice.eth.x")
+            .unwrap()
+            .is_none());
+
+        store.delete_ens_resolution(1, "alice.eth").unwrap();
+        assert!(store.get_ens_resolution(1, "alice.eth").unwrap().is_none());
+    }
 }
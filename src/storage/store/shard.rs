@@ -53,6 +53,10 @@ fn make_shard_key(block_number: u64) -> Vec<u8> {
     key
 }
 
+fn make_executed_start_height_key() -> Vec<u8> {
+    vec![RootPrefix::ExecutedStartHeight as u8]
+}
+
 fn make_block_timestamp_index(shard_index: u32, timestamp: u64) -> Vec<u8> {
     let mut key = vec![RootPrefix::BlockIndex as u8];
     key.extend_from_slice(&shard_index.to_be_bytes());
@@ -202,6 +206,42 @@ impl ShardStore {
         put_shard_chunk(&self.db, shard_chunk)
     }
 
+    /**
+     * Stores a chunk from below the earliest stored height. Nothing in the signed header commits
+     * to the transactions, so they can't be checked without executing the chunk and only the
+     * header and commits are kept. The first backfill records `executed_start_height`, the
+     * earliest height that still has its transactions and events.
+     */
+    pub fn put_backfilled_shard_chunk(
+        &self,
+        shard_chunk: &ShardChunk,
+        executed_start_height: u64,
+    ) -> Result<(), ShardStorageError> {
+        if self.get_executed_start_height()?.is_none() {
+            self.db.put(
+                &make_executed_start_height_key(),
+                &executed_start_height.to_be_bytes(),
+            )?;
+        }
+        put_shard_chunk(
+            &self.db,
+            &ShardChunk {
+                transactions: vec![],
+                ..shard_chunk.clone()
+            },
+        )
+    }
+
+    /** The earliest height that has its transactions and events, or None if nothing was backfilled */
+    pub fn get_executed_start_height(&self) -> Result<Option<u64>, ShardStorageError> {
+        match self.db.get(&make_executed_start_height_key())? {
+            None => Ok(None),
+            Some(bytes) => Ok(Some(u64::from_be_bytes(bytes.try_into().map_err(
+                |_| HubError::internal_db_error("invalid executed start height"),
+            )?))),
+        }
+    }
+
     pub fn get_first_shard_chunk(&self) -> Result<Option<ShardChunk>, ShardStorageError> {
         get_first_or_last_shard_chunk(&self.db, FirstOrLast::First)
     }
@@ -16,10 +16,11 @@ use crate::storage::store::account::{
     CastStore, CastStoreDef, IntoU8, LinkStore, OnchainEventStorageError, OnchainEventStore, Store,
     StoreEventHandler, UsernameProofStore, UsernameProofStoreDef,
 };
-use crate::storage::store::shard::ShardStore;
+use crate::storage::store::shard::{ShardStorageError, ShardStore};
 use crate::storage::trie::merkle_trie;
 use crate::storage::trie::merkle_trie::TrieKey;
 use crate::utils::statsd_wrapper::StatsdClientWrapper;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
@@ -31,6 +32,9 @@ pub enum StoresError {
     #[error(transparent)]
     OnchainEventError(#[from] OnchainEventStorageError),
 
+    #[error(transparent)]
+    ShardStorageError(#[from] ShardStorageError),
+
     #[error("unsupported message type")]
     UnsupportedMessageType(MessageType),
 
@@ -54,6 +58,7 @@ pub struct Stores {
     pub(crate) db: Arc<RocksDB>,
     pub(crate) trie: merkle_trie::MerkleTrie,
     pub store_limits: StoreLimits,
+    pub index_config: IndexConfig,
     pub event_handler: Arc<StoreEventHandler>,
     pub shard_id: u32,
     pub statsd: StatsdClientWrapper,
@@ -148,6 +153,13 @@ impl Limits {
     }
 }
 
+/** Optional indexes, off by default since they cost disk space and merge time */
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct IndexConfig {
+    /** Index casts by the urls they embed. Only casts merged while enabled are indexed */
+    pub cast_embeds: bool,
+}
+
 #[derive(Clone, Debug)]
 pub struct StoreLimits {
     pub limits: Limits,
@@ -176,13 +188,19 @@ impl Stores {
         shard_id: u32,
         mut trie: merkle_trie::MerkleTrie,
         store_limits: StoreLimits,
+        index_config: IndexConfig,
         statsd: StatsdClientWrapper,
     ) -> Stores {
         trie.initialize(&db).unwrap();
 
         let event_handler = StoreEventHandler::new();
         let shard_store = ShardStore::new(db.clone(), shard_id);
-        let cast_store = CastStore::new(db.clone(), event_handler.clone(), 100);
+        let cast_store = CastStore::new(
+            db.clone(),
+            event_handler.clone(),
+            100,
+            index_config.cast_embeds,
+        );
         let link_store = LinkStore::new(db.clone(), event_handler.clone(), 100);
         let reaction_store = ReactionStore::new(db.clone(), event_handler.clone(), 100);
         let user_data_store = UserDataStore::new(db.clone(), event_handler.clone(), 100);
@@ -202,6 +220,7 @@ impl Stores {
             username_proof_store,
             db: db.clone(),
             store_limits,
+            index_config,
             event_handler,
             statsd,
             prune_lock: Arc::new(RwLock::new(false)),
@@ -323,6 +342,29 @@ impl Stores {
         Ok(response)
     }
 
+    pub fn is_over_storage_limits(&self, fid: u64) -> Result<bool, StoresError> {
+        let storage_limits = self.get_storage_limits(fid)?;
+        Ok(storage_limits
+            .limits
+            .iter()
+            .any(|limit| limit.used > limit.limit))
+    }
+
+    /** Returns a page of fids that have more messages than their storage limits allow */
+    pub fn get_fids_over_storage_limits(
+        &self,
+        page_options: &PageOptions,
+    ) -> Result<(Vec<u64>, Option<Vec<u8>>), StoresError> {
+        let (fids, next_page_token) = self.onchain_event_store.get_fids(page_options)?;
+        let mut over_limit_fids = vec![];
+        for fid in fids {
+            if self.is_over_storage_limits(fid)? {
+                over_limit_fids.push(fid);
+            }
+        }
+        Ok((over_limit_fids, next_page_token))
+    }
+
     pub fn revoke_messages(
         &self,
         fid: u64,
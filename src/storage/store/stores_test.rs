@@ -23,7 +23,14 @@ mod tests {
             legacy_limits: test_helper::limits::zero(),
         };
 
-        stores::Stores::new(Arc::new(db), 1, trie, limits, test_helper::statsd_client())
+        stores::Stores::new(
+            Arc::new(db),
+            1,
+            trie,
+            limits,
+            stores::IndexConfig::default(),
+            test_helper::statsd_client(),
+        )
     }
 
     pub fn create_events(stores: &stores::Stores) {
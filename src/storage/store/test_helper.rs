@@ -2,7 +2,7 @@ use crate::core::types::{Address, Vote};
 use crate::mempool::mempool::MempoolMessagesRequest;
 use crate::storage::db::{self, RocksDB};
 use crate::storage::store::engine::ShardEngine;
-use crate::storage::store::stores::StoreLimits;
+use crate::storage::store::stores::{IndexConfig, StoreLimits};
 use crate::storage::trie::merkle_trie;
 use crate::utils::statsd_wrapper::StatsdClientWrapper;
 use ed25519_dalek::{SecretKey, SigningKey};
@@ -119,6 +119,7 @@ pub struct EngineOptions {
     pub network: Option<proto::FarcasterNetwork>,
     pub fname_signer_address: Option<alloy_primitives::Address>,
     pub shard_id: u32,
+    pub index_config: IndexConfig,
 }
 
 impl Default for EngineOptions {
@@ -130,6 +131,7 @@ impl Default for EngineOptions {
             network: None,
             fname_signer_address: None,
             shard_id: 1,
+            index_config: IndexConfig::default(),
         }
     }
 }
@@ -168,6 +170,7 @@ pub fn new_engine_with_options(options: EngineOptions) -> (ShardEngine, tempfile
             merkle_trie::MerkleTrie::new(16).unwrap(),
             options.shard_id,
             test_limits,
+            options.index_config,
             statsd_client,
             256,
             options.messages_request_tx,
@@ -188,6 +191,7 @@ pub async fn commit_event(engine: &mut ShardEngine, event: &OnChainEvent) -> Sha
         vec![MempoolMessage::ValidatorMessage(proto::ValidatorMessage {
             on_chain_event: Some(event.clone()),
             fname_transfer: None,
+            storage_prune: None,
         })],
         None,
     );
@@ -205,6 +209,7 @@ pub async fn commit_event_at(
         vec![MempoolMessage::ValidatorMessage(proto::ValidatorMessage {
             on_chain_event: Some(event.clone()),
             fname_transfer: None,
+            storage_prune: None,
         })],
         Some(timestamp.clone()),
     );
@@ -407,6 +412,7 @@ pub async fn commit_fname_transfer(engine: &mut ShardEngine, transfer: &FnameTra
         vec![MempoolMessage::ValidatorMessage(proto::ValidatorMessage {
             on_chain_event: None,
             fname_transfer: Some(transfer.clone()),
+            storage_prune: None,
         })],
         None,
     );
@@ -445,6 +451,7 @@ pub async fn register_fname(
         vec![MempoolMessage::ValidatorMessage(proto::ValidatorMessage {
             on_chain_event: None,
             fname_transfer: Some(fname_transfer.clone()),
+            storage_prune: None,
         })],
         None,
     );
@@ -29,6 +29,19 @@ pub fn bytes_compare(a: &[u8], b: &[u8]) -> i8 {
     }
 }
 
+/**
+ * Iterator bounds for the keys starting with [prefix] that sort after the key [after]. Unlike
+ * incrementing [after], this doesn't skip longer keys that extend it, so it works for variable
+ * length keys such as names.
+ */
+pub fn prefix_range_after(prefix: &Vec<u8>, after: Option<&[u8]>) -> (Vec<u8>, Vec<u8>) {
+    let start = match after {
+        Some(after) if after > prefix.as_slice() => [after, &[0]].concat(),
+        _ => prefix.clone(),
+    };
+    (start, increment_vec_u8(prefix))
+}
+
 /** Increment the bytes of a Vec<u8> as if it were a big-endian number */
 pub fn increment_vec_u8(vec: &Vec<u8>) -> Vec<u8> {
     let mut result = vec.clone(); // Clone the input vector to create a new one for the result
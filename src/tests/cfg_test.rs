@@ -32,9 +32,13 @@ impl Drop for EnvRestorer {
 mod tests {
     use super::*;
     use crate::cfg::load_and_merge_config;
+    use crate::connectors::onchain_events::ens_cache::MissPolicy;
+    use crate::connectors::onchain_events::{configured_chains, ContractKind, SubscribeError};
+    use crate::storage::db::CompressionType;
     use serial_test::serial; // for setting env vars
     use std::fs::File;
     use std::io::Write;
+    use std::time::Duration;
     use tempfile::{tempdir, TempDir};
 
     fn run_test<T>(envs: Vec<Env>, test: T)
@@ -205,6 +209,159 @@ mod tests {
         )
     }
 
+    #[test]
+    #[serial]
+    fn test_rocksdb_config() {
+        run_test(
+            vec![set("SNAPCHAIN_ROCKSDB__MAX_BACKGROUND_JOBS", "8")],
+            || {
+                let (_tmpdir, file_path) = write_config_file(
+                    r#"
+                [rocksdb]
+                block_cache_size = 1073741824
+                compression = "zstd"
+                compaction_rate_limit = 104857600
+
+                [indexes]
+                cast_embeds = true
+            "#,
+                );
+
+                let args = vec![
+                    "test_binary".to_string(),
+                    "--config-path".to_string(),
+                    file_path.to_string(),
+                ];
+
+                let config = load_and_merge_config(args).expect("Failed to load config");
+                assert_eq!(config.rocksdb.block_cache_size, 1024 * 1024 * 1024);
+                assert_eq!(config.rocksdb.compression, CompressionType::Zstd);
+                assert_eq!(
+                    config.rocksdb.compaction_rate_limit,
+                    Some(100 * 1024 * 1024)
+                );
+                assert_eq!(config.rocksdb.max_background_jobs, 8);
+                // Unset values keep their defaults
+                assert_eq!(config.rocksdb.write_buffer_size, 64 * 1024 * 1024);
+                assert_eq!(config.rocksdb.cold_compression, CompressionType::Zstd);
+                assert!(config.indexes.cast_embeds);
+            },
+        )
+    }
+
+    #[test]
+    #[serial]
+    fn test_onchain_events_contracts_config() {
+        run_test(vec![], || {
+            let (_tmpdir, file_path) = write_config_file(
+                r#"
+                [base_onchain_events]
+                rpc_url = "http://localhost:8545"
+                fallback_rpc_urls = ["http://localhost:8546"]
+                chain_id = 84532
+
+                [[base_onchain_events.contracts]]
+                address = "0x00000000fc84484d585C3cF48d213424DFDE43FD"
+                kind = "tier_registry"
+                start_block = 1000
+
+                [[chains]]
+                rpc_url = "http://localhost:9545"
+                chain_id = 11155420
+
+                [[chains.contracts]]
+                address = "0x00000000Fc6c5F01Fc30151999387Bb99A9f489b"
+                kind = "id_registry"
+                start_block = 2000
+            "#,
+            );
+
+            let args = vec![
+                "test_binary".to_string(),
+                "--config-path".to_string(),
+                file_path.to_string(),
+            ];
+
+            let config = load_and_merge_config(args).expect("Failed to load config");
+            let base = &config.base_onchain_events;
+            assert_eq!(base.chain_id, Some(84532));
+            assert_eq!(base.fallback_rpc_urls, vec!["http://localhost:8546"]);
+            assert_eq!(base.contracts.len(), 1);
+            assert_eq!(base.contracts[0].kind, ContractKind::TierRegistry);
+            assert_eq!(base.contracts[0].start_block, 1000);
+            // Unconfigured chains index the mainnet deployments
+            assert!(config.onchain_events.contracts.is_empty());
+            assert_eq!(config.onchain_events.chain_id, Some(10));
+
+            assert_eq!(config.chains.len(), 1);
+            assert_eq!(config.chains[0].chain_id, Some(11155420));
+            assert_eq!(config.chains[0].contracts[0].kind, ContractKind::IdRegistry);
+            // Chains without an rpc url aren't indexed
+            let chains = configured_chains(&config).unwrap();
+            assert_eq!(
+                chains
+                    .iter()
+                    .map(|chain| chain.chain_id)
+                    .collect::<Vec<_>>(),
+                vec![Some(84532), Some(11155420)]
+            );
+        })
+    }
+
+    #[test]
+    #[serial]
+    fn test_duplicate_chains_config() {
+        run_test(vec![], || {
+            let (_tmpdir, file_path) = write_config_file(
+                r#"
+                [onchain_events]
+                rpc_url = "http://localhost:8545"
+
+                [[chains]]
+                rpc_url = "http://localhost:9545"
+                chain_id = 10
+            "#,
+            );
+
+            let args = vec![
+                "test_binary".to_string(),
+                "--config-path".to_string(),
+                file_path.to_string(),
+            ];
+
+            let config = load_and_merge_config(args).expect("Failed to load config");
+            assert!(matches!(
+                configured_chains(&config),
+                Err(SubscribeError::DuplicateChain(10))
+            ));
+        })
+    }
+
+    #[test]
+    #[serial]
+    fn test_ens_cache_config() {
+        run_test(vec![], || {
+            let (_tmpdir, file_path) = write_config_file(
+                r#"
+                [ens_cache]
+                ttl = "30m"
+                policy = "lenient"
+            "#,
+            );
+
+            let args = vec![
+                "test_binary".to_string(),
+                "--config-path".to_string(),
+                file_path.to_string(),
+            ];
+
+            let config = load_and_merge_config(args).expect("Failed to load config");
+            assert!(config.ens_cache.enabled);
+            assert_eq!(config.ens_cache.ttl, Duration::from_secs(30 * 60));
+            assert_eq!(config.ens_cache.policy, MissPolicy::Lenient);
+        })
+    }
+
     #[test]
     #[serial]
     fn test_missing_config_file() {
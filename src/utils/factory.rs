@@ -353,6 +353,33 @@ pub mod messages_factory {
             )
         }
 
+        pub fn create_cast_with_parent_url(
+            fid: u64,
+            text: &str,
+            parent_url: &str,
+            timestamp: Option<u32>,
+            private_key: Option<&SigningKey>,
+        ) -> message::Message {
+            let cast_add = CastAddBody {
+                text: text.to_string(),
+                embeds: vec![],
+                embeds_deprecated: vec![],
+                mentions: vec![],
+                mentions_positions: vec![],
+                parent: Some(proto::cast_add_body::Parent::ParentUrl(
+                    parent_url.to_string(),
+                )),
+                r#type: CastType::Cast as i32,
+            };
+            create_message_with_data(
+                fid,
+                MessageType::CastAdd,
+                message::message_data::Body::CastAddBody(cast_add),
+                timestamp,
+                private_key,
+            )
+        }
+
         pub fn create_cast_remove(
             fid: u64,
             target_hash: &Vec<u8>,
@@ -485,6 +512,27 @@ pub mod messages_factory {
                 private_key,
             )
         }
+
+        pub fn create_cast_reaction(
+            fid: u64,
+            message_type: MessageType,
+            reaction_type: ReactionType,
+            target_cast_id: message::CastId,
+            timestamp: Option<u32>,
+            private_key: Option<&SigningKey>,
+        ) -> message::Message {
+            let reaction_body = ReactionBody {
+                r#type: reaction_type as i32,
+                target: Some(Target::TargetCastId(target_cast_id)),
+            };
+            create_message_with_data(
+                fid,
+                message_type,
+                message::message_data::Body::ReactionBody(reaction_body),
+                timestamp,
+                private_key,
+            )
+        }
     }
     pub mod user_data {
         use message::{UserDataBody, UserDataType};
@@ -13,6 +13,7 @@ pub enum EngineVersion {
     V3 = 3,
     V4 = 4,
     V5 = 5,
+    V6 = 6,
 }
 
 pub enum ProtocolFeature {
@@ -23,6 +24,9 @@ pub enum ProtocolFeature {
     MessageLengthCheckFix,
     PrimaryAddresses,
     UsernameShardRoutingFix,
+    StorageLimitPruning, // Validators prune fids that are over their storage limits
+    SolanaAccountVerifications, // Solana verifications signed by the members of a multisig or program derived account
+    TierExpiryEvents,           // Emit an event with the first block after a tier expires
 }
 
 pub struct VersionSchedule {
@@ -72,7 +76,7 @@ const ENGINE_VERSION_SCHEDULE_TESTNET: &[VersionSchedule] = [
 
 const ENGINE_VERSION_SCHEDULE_DEVNET: &[VersionSchedule] = [VersionSchedule {
     active_at: 0,
-    version: EngineVersion::V5,
+    version: EngineVersion::V6,
 }]
 .as_slice();
 
@@ -112,6 +116,9 @@ impl EngineVersion {
             | ProtocolFeature::MessageLengthCheckFix
             | ProtocolFeature::UsernameShardRoutingFix
             | ProtocolFeature::PrimaryAddresses => self >= &EngineVersion::V5,
+            ProtocolFeature::StorageLimitPruning
+            | ProtocolFeature::SolanaAccountVerifications
+            | ProtocolFeature::TierExpiryEvents => self >= &EngineVersion::V6,
         }
     }
 
@@ -122,7 +129,7 @@ impl EngineVersion {
             | EngineVersion::V2
             | EngineVersion::V3
             | EngineVersion::V4 => 1,
-            EngineVersion::V5 => LATEST_PROTOCOL_VERSION,
+            EngineVersion::V5 | EngineVersion::V6 => LATEST_PROTOCOL_VERSION,
         }
     }
 
@@ -230,13 +237,13 @@ mod version_test {
         let time = FarcasterTime::new(0);
         assert_eq!(
             EngineVersion::version_for(&time, FarcasterNetwork::Devnet),
-            EngineVersion::V5
+            EngineVersion::V6
         );
 
         let time = FarcasterTime::current();
         assert_eq!(
             EngineVersion::version_for(&time, FarcasterNetwork::Devnet),
-            EngineVersion::V5
+            EngineVersion::V6
         );
     }
 
@@ -266,7 +273,7 @@ mod version_test {
 
     #[test]
     fn test_latest() {
-        assert_eq!(EngineVersion::latest(), EngineVersion::V5);
+        assert_eq!(EngineVersion::latest(), EngineVersion::V6);
         assert_eq!(
             EngineVersion::version_for(&FarcasterTime::current(), FarcasterNetwork::Devnet),
             EngineVersion::latest()
@@ -17,11 +17,11 @@ use snapchain::node::snapchain_read_node::SnapchainReadNode;
 use snapchain::proto::hub_service_server::HubServiceServer;
 use snapchain::proto::{self, Height};
 use snapchain::proto::{Block, FarcasterNetwork, IdRegisterEventType, SignerEventType};
-use snapchain::storage::db::{PageOptions, RocksDB, RocksDbTransactionBatch};
+use snapchain::storage::db::{self, PageOptions, RocksDB, RocksDbTransactionBatch};
 use snapchain::storage::store::account::{CastStore, OnchainEventStore, UserDataStore};
 use snapchain::storage::store::engine::MempoolMessage;
 use snapchain::storage::store::node_local_state::LocalStateStore;
-use snapchain::storage::store::stores::Stores;
+use snapchain::storage::store::stores::{IndexConfig, Stores};
 use snapchain::storage::store::BlockStore;
 use snapchain::utils::factory::{self, messages_factory};
 use snapchain::utils::statsd_wrapper::StatsdClientWrapper;
@@ -237,6 +237,7 @@ impl ReadNodeForTest {
             &config,
             system_tx.clone(),
             false,
+            false,
             fc_network,
             statsd_client.clone(),
         )
@@ -261,12 +262,16 @@ impl ReadNodeForTest {
             messages_request_tx,
             block_store.clone(),
             make_tmp_path(),
+            &db::Config::default(),
+            &IndexConfig::default(),
             statsd_client.clone(),
             16,
             fc_network,
+            false,
             registry,
         )
-        .await;
+        .await
+        .unwrap();
 
         let mut join_handles = Vec::new();
 
@@ -332,6 +337,7 @@ impl NodeForTest {
             &config,
             system_tx.clone(),
             false,
+            false,
             fc_network,
             statsd_client.clone(),
         )
@@ -347,7 +353,7 @@ impl NodeForTest {
         let db = Arc::new(RocksDB::new(data_dir));
         db.open().unwrap();
         let block_store = BlockStore::new(db.clone());
-        let global_db = RocksDB::open_global_db(&data_dir);
+        let global_db = RocksDB::open_global_db(&data_dir, &db::Config::default());
         let node_local_store = LocalStateStore::new(global_db);
         let (messages_request_tx, messages_request_rx) = mpsc::channel(100);
         let (shard_decision_tx, shard_decision_rx) = broadcast::channel(100);
@@ -362,12 +368,15 @@ impl NodeForTest {
             block_store.clone(),
             node_local_store,
             make_tmp_path(),
+            &db::Config::default(),
+            &IndexConfig::default(),
             statsd_client.clone(),
             16,
             fc_network,
             registry,
         )
-        .await;
+        .await
+        .unwrap();
 
         let node_id = node.id();
         let assert_valid_block = move |block: &Block| {
@@ -437,6 +446,7 @@ impl NodeForTest {
             },
             "".to_string(),
             "".to_string(),
+            false,
         );
 
         let handle = tokio::spawn(async move {
@@ -611,6 +621,7 @@ impl TestNetwork {
                     MempoolMessage::ValidatorMessage(proto::ValidatorMessage {
                         on_chain_event: Some(event),
                         fname_transfer: None,
+                        storage_prune: None,
                     }),
                     MempoolSource::Local,
                     None,
@@ -962,7 +973,8 @@ async fn test_cross_shard_interactions() {
                 signature: hex::decode("050b42fdda7b0a7309a1fb8a2cbc9a5f4bbf241aec74f53191f9665d9b9f572d4f452ac807911af7b6980219482d6f7fda7f99f23ab19c961b4701b9934fa2f91b").unwrap(),
                 r#type: proto::UserNameType::UsernameTypeFname as i32,
             }),
-        })
+        }),
+        storage_prune: None,
     };
 
     let transfer2 = proto::ValidatorMessage {
@@ -978,7 +990,8 @@ async fn test_cross_shard_interactions() {
                 signature: hex::decode("00c3601c515edffe208e7128f47f89c2fb7b8e0beaaf615158305ddf02818a71679a8e7062503be59a19d241bd0b47396a3c294cfafd0d5478db1ae8249463bd1c").unwrap(),
                 r#type: proto::UserNameType::UsernameTypeFname as i32,
             }),
-        })
+        }),
+        storage_prune: None,
     };
 
     let node = &network.nodes[0];